@@ -0,0 +1,50 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use vchain_plus::acc::{AccPublicKey, AccSecretKey, AccValue, Set, SubgroupBatch};
+
+/// 集合元素必须小于公钥的 q，而公钥大小随 q 平方增长（q = 1 万时已无法在普通机器上生成），
+/// 因此最大的集合取 1000 个元素
+const SET_SIZES: &[u64] = &[10, 100, 1_000];
+
+fn bench_from_set(c: &mut Criterion) {
+    let max_size = *SET_SIZES.iter().max().unwrap_or(&1);
+    let sk = AccSecretKey::from_seed([0u8; 32]).into();
+    let pk = AccPublicKey::gen_key(&sk, max_size + 1);
+
+    let mut group = c.benchmark_group("acc_from_set");
+    group.sample_size(10);
+    for &size in SET_SIZES {
+        let set: Set = (1..=size).collect();
+        group.bench_with_input(BenchmarkId::from_parameter(size), &set, |b, set| {
+            b.iter(|| AccValue::from_set(set, &pk))
+        });
+    }
+    group.finish();
+}
+
+/// 验证一个含有 100 个累加器值的 VO 之前的子群检查：逐个检查与随机组合后一次检查
+fn bench_subgroup_check(c: &mut Criterion) {
+    let sk = AccSecretKey::from_seed([0u8; 32]).into();
+    let pk = AccPublicKey::gen_key(&sk, 101);
+    let accs: Vec<AccValue> = (1..=100u64)
+        .map(|i| AccValue::from_set(&(1..=i).collect(), &pk))
+        .collect();
+
+    let mut group = c.benchmark_group("acc_subgroup_check_100");
+    group.sample_size(10);
+    group.bench_function("eager", |b| {
+        b.iter(|| assert!(accs.iter().all(AccValue::in_prime_subgroup)))
+    });
+    group.bench_function("deferred", |b| {
+        b.iter(|| {
+            let mut batch = SubgroupBatch::new();
+            for acc in &accs {
+                batch.add(acc);
+            }
+            batch.verify().expect("subgroup check")
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_from_set, bench_subgroup_check);
+criterion_main!(benches);
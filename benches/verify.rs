@@ -0,0 +1,118 @@
+//! `verify` 的性能基准：在临时目录中构建一条 10 个区块的链，分别对单关键字、
+//! 三个关键字求交集与跨越 10 个区块的范围查询生成 VO，只对验证过程计时。
+//! 验证中按边权确定子节点顺序（每个运算节点一次 `find_edge`）的开销也包含在内。
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde_json::json;
+use std::collections::HashSet;
+use vchain_plus::{
+    acc::{AccPublicKey, AccSecretKey},
+    chain::{
+        block::{build::build_block, BlockContent, Height},
+        object::Object,
+        query::{query, query_param::QueryParam},
+        traits::WriteInterface,
+        verify::verify,
+        Parameter,
+    },
+    digest::Digestible,
+    SimChain,
+};
+
+const BLK_NUM: u32 = 10;
+const OBJ_NUM_PER_BLK: usize = 20;
+const KEYWORDS: &[&str] = &["a", "b", "c", "d"];
+
+fn build_chain(path: &std::path::Path, pk: &AccPublicKey) -> SimChain {
+    let param = Parameter {
+        time_win_sizes: vec![4, 16],
+        id_tree_fanout: 4,
+        max_id_num: 256,
+        bplus_tree_fanout: 4,
+        num_dim: 2,
+    };
+    let mut chain = SimChain::create(path, param.clone()).expect("create chain");
+    chain.set_parameter(&param).expect("set parameter");
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut prev_hash = BlockContent::genesis(&param).0.to_digest();
+    for height in 1..=BLK_NUM {
+        let objs = (0..OBJ_NUM_PER_BLK)
+            .map(|_| {
+                let num_data = vec![rng.gen_range(0..1000u32), rng.gen_range(0..1000u32)];
+                let keywords: HashSet<String> = KEYWORDS
+                    .iter()
+                    .filter(|_| rng.gen_bool(0.6))
+                    .map(|k| k.to_string())
+                    .collect();
+                Object::new(Height(height), num_data, keywords)
+            })
+            .collect();
+        let (blk_head, _) = build_block(Height(height), prev_hash, objs, &mut chain, &param, pk)
+            .expect("build block");
+        prev_hash = blk_head.to_digest();
+    }
+    chain
+}
+
+fn bench_verify(c: &mut Criterion) {
+    let sk = AccSecretKey::from_seed([0u8; 32]).into();
+    let pk = AccPublicKey::gen_key(&sk, 257);
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let chain = build_chain(dir.path(), &pk);
+
+    let cases = vec![
+        (
+            "single_keyword",
+            json!({
+                "start_blk": 1,
+                "end_blk": 4,
+                "range": [],
+                "keyword_exp": {"input": "a"},
+            }),
+        ),
+        (
+            "intersec_3",
+            json!({
+                "start_blk": 1,
+                "end_blk": 4,
+                "range": [],
+                "keyword_exp": {"and": [
+                    {"input": "a"},
+                    {"and": [{"input": "b"}, {"input": "c"}]},
+                ]},
+            }),
+        ),
+        (
+            "range_10_blocks",
+            json!({
+                "start_blk": 1,
+                "end_blk": BLK_NUM,
+                "range": [(100, 500), (0, 1000)],
+                "keyword_exp": null,
+            }),
+        ),
+    ];
+
+    let mut group = c.benchmark_group("verify");
+    group.sample_size(10);
+    for (name, query_param) in cases {
+        let query_param: QueryParam<u32> =
+            serde_json::from_value(query_param).expect("parse query param");
+        let (results, dag, _) = query(false, false, &chain, query_param, &pk).expect("query");
+        let info = verify(&chain, &results, &dag, &pk).expect("verify");
+        println!(
+            "{}: {} DAG nodes, VO size {}",
+            name,
+            dag.node_count(),
+            info.vo_size
+        );
+        group.bench_function(name, |b| {
+            b.iter(|| verify(&chain, &results, &dag, &pk).expect("verify"))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_verify);
+criterion_main!(benches);
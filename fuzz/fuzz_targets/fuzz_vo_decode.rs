@@ -0,0 +1,12 @@
+//! 以任意字节解码 VO：畸形输入只应返回错误，任何 panic（包括分配失败与栈溢出）都会被记录为崩溃
+//!
+//! 运行：`cargo fuzz run fuzz_vo_decode`，`corpus/fuzz_vo_decode` 中保存了曾导致 panic 的输入
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use vchain_plus::chain::verify::vo::VO;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = VO::<u32>::decode(data);
+});
@@ -1,6 +1,8 @@
 //! Ref: https://user.eng.umd.edu/~cpap/published/accumEUROSP2017.pdf
 
 pub mod acc_value;
+pub mod batch;
+pub mod cache;
 pub mod keys;
 pub mod ops;
 pub mod poly;
@@ -8,6 +10,7 @@ pub mod serde_impl;
 pub mod set;
 pub mod utils;
 
+pub use cache::AccCache;
 pub use ops::Op;
 pub use set::Set;
 
@@ -18,6 +21,8 @@ pub type AccPublicKey = keys::AccPublicKey<Curve>;
 pub type AccValue = acc_value::AccValue<Curve>;
 pub type IntermediateProof = ops::IntermediateProof<Curve>;
 pub type FinalProof = ops::FinalProof<Curve>;
+pub type PairingBatch = batch::PairingBatch<Curve>;
+pub type SubgroupBatch = batch::SubgroupBatch<Curve>;
 
 #[inline(always)]
 pub fn compute_set_operation_intermediate(
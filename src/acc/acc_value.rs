@@ -1,32 +1,95 @@
 use super::{
-    keys::{AccPublicKey, AccSecretKeyWithPowCache},
+    keys::{AccPublicKey, AccSecretKeyWithPowCache, KeyFingerprint},
     set::Set,
 };
 use crate::digest::{Digest, Digestible};
+use anyhow::{ensure, Result};
 use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
-use ark_ff::{PrimeField, Zero};
+use ark_ff::{FpParameters, One, PrimeField, Zero};
 use core::{
+    fmt,
     marker::PhantomData,
-    ops::{Add, Sub},
+    ops::{Add, Neg, Sub},
 };
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 
-/// 计算公钥相关的累加器值。使用并行迭代（par_iter）对集合set中的每个元素i应用函数f 得到群元素 
-/// 然后在射影坐标下累加（fold和reduce使用零元素作为初始值），最后转换为仿射坐标。用于高效并行计算群元素的和
+/// 乘以群的阶之后为单位元的点位于素数阶子群中
+pub(crate) fn in_prime_subgroup<G: AffineCurve>(p: &G) -> bool {
+    p.mul(<G::ScalarField as PrimeField>::Params::MODULUS)
+        .is_zero()
+}
+
+/// 并行计算累加器值时，每个 rayon 任务负责累加的元素个数
+#[cfg(feature = "parallel")]
+const ACC_CHUNK_SIZE: usize = 1024;
+
+/// 计算公钥相关的累加器值。使用并行迭代（par_iter）对集合set中的每个元素i应用函数f 得到群元素
+/// 然后按 `ACC_CHUNK_SIZE` 分块并行累加，最后转换为仿射坐标。用于高效并行计算群元素的和
+///
+/// 未开启 `parallel` feature 时使用串行实现。群加法满足交换律与结合律，两种实现的结果完全一致。
+#[cfg(feature = "parallel")]
 #[inline]
 pub(crate) fn cal_acc_pk<G, F>(set: &Set, f: F) -> G
 where
     G: AffineCurve,
     F: Fn(u64) -> G + Sync,
 {
-    set.par_iter()
+    cal_acc_pk_chunked(set, f, ACC_CHUNK_SIZE)
+}
+
+/// 分块累加：块内对仿射点做 mixed addition，得到各块的射影坐标小计；
+/// 再通过 `batch_normalization`（一次批量求逆）把小计统一转为仿射坐标，最后用 mixed addition 汇总。
+/// 相比 fold + reduce，避免了 rayon 切分产生的大量射影点之间的完全加法（projective + projective）。
+#[cfg(feature = "parallel")]
+fn cal_acc_pk_chunked<G, F>(set: &Set, f: F, chunk_size: usize) -> G
+where
+    G: AffineCurve,
+    F: Fn(u64) -> G + Sync,
+{
+    let points: Vec<G> = set.par_iter().map(|i| f(i.get() as u64)).collect();
+    let mut subtotals: Vec<G::Projective> = points
+        .par_chunks(chunk_size)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .fold(G::Projective::zero(), |a, b| a.add_mixed(b))
+        })
+        .collect();
+    G::Projective::batch_normalization(&mut subtotals);
+    subtotals
+        .iter()
+        .fold(G::Projective::zero(), |a, b| a.add_mixed(&b.into_affine()))
+        .into_affine()
+}
+
+#[cfg(not(feature = "parallel"))]
+#[inline]
+pub(crate) fn cal_acc_pk<G, F>(set: &Set, f: F) -> G
+where
+    G: AffineCurve,
+    F: Fn(u64) -> G + Sync,
+{
+    cal_acc_pk_serial(set, f)
+}
+
+/// `cal_acc_pk` 的串行实现
+#[cfg_attr(all(feature = "parallel", not(test)), allow(dead_code))]
+#[inline]
+fn cal_acc_pk_serial<G, F>(set: &Set, f: F) -> G
+where
+    G: AffineCurve,
+    F: Fn(u64) -> G,
+{
+    set.iter()
         .map(|i| f(i.get() as u64))
-        .fold(G::Projective::zero, |a, b| a.add_mixed(&b))
-        .reduce(G::Projective::zero, |a, b| a + b)
+        .fold(G::Projective::zero(), |a, b| a.add_mixed(&b))
         .into_affine()
 }
+
 /// 计算私钥相关的标量值
+#[cfg(feature = "parallel")]
 #[inline]
 pub(crate) fn cal_acc_scalar_sk<Fr, F>(set: &Set, f: F) -> Fr
 where
@@ -38,9 +101,32 @@ where
         .reduce(Fr::zero, |a, b| a + b)
 }
 
+#[cfg(not(feature = "parallel"))]
+#[inline]
+pub(crate) fn cal_acc_scalar_sk<Fr, F>(set: &Set, f: F) -> Fr
+where
+    Fr: PrimeField,
+    F: Fn(u64) -> Fr + Sync,
+{
+    cal_acc_scalar_sk_serial(set, f)
+}
+
+/// `cal_acc_scalar_sk` 的串行实现
+#[cfg_attr(all(feature = "parallel", not(test)), allow(dead_code))]
+#[inline]
+fn cal_acc_scalar_sk_serial<Fr, F>(set: &Set, f: F) -> Fr
+where
+    Fr: PrimeField,
+    F: Fn(u64) -> Fr,
+{
+    set.iter()
+        .map(|i| f(i.get() as u64))
+        .fold(Fr::zero(), |a, b| a + b)
+}
+
 /// An accumulative value consists of both [`LeftAccValue`] and [`RightAccValue`].
 /// 累加器值，包含公钥和私钥相关的累加器值。由四个群元素组成
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 pub struct AccValue<E: PairingEngine> {
     /// g^{\sum s^i}
     #[serde(with = "super::serde_impl::unchecked")]
@@ -54,8 +140,22 @@ pub struct AccValue<E: PairingEngine> {
     /// h^{\sum r^i \cdot s^{q - i}}
     #[serde(with = "super::serde_impl::unchecked")]
     pub(crate) h_r_s: E::G2Affine,
+    /// 计算该累加器值所用密钥的指纹，不参与序列化与比较；反序列化得到的值为 None
+    #[serde(skip)]
+    key_fp: Option<KeyFingerprint>,
     _marker: PhantomData<E>,
 }
+
+impl<E: PairingEngine> PartialEq for AccValue<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.g_s == other.g_s
+            && self.g_r == other.g_r
+            && self.h_s_r == other.h_s_r
+            && self.h_r_s == other.h_r_s
+    }
+}
+
+impl<E: PairingEngine> Eq for AccValue<E> {}
 /// 实现了累加器值的摘要计算。将累加器值的四个群元素转换为字节数组，然后计算摘要。
 /// 累加器值的摘要计算
 impl<E: PairingEngine> Digestible for AccValue<E> {
@@ -65,9 +165,15 @@ impl<E: PairingEngine> Digestible for AccValue<E> {
             .to_digest()
     }
 }
+/// 以指纹显示累加器值，避免在日志中输出完整的四个群元素
+impl<E: PairingEngine> fmt::Display for AccValue<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.fingerprint())
+    }
+}
 /// 实现了累加器值的加法和减法操作。
 /// 加法：将两个累加器值的对应群元素相加，得到新的累加器值。
-/// 减法：将两个累加器值的对应群元素相减，得到新的累加器值。
+/// 减法：加上减数的相反数，即 `a - b == a + (-b)`。
 impl<E: PairingEngine> Add for AccValue<E> {
     type Output = Self;
 
@@ -77,6 +183,7 @@ impl<E: PairingEngine> Add for AccValue<E> {
             g_r: self.g_r + rhs.g_r,
             h_s_r: self.h_s_r + rhs.h_s_r,
             h_r_s: self.h_r_s + rhs.h_r_s,
+            key_fp: self.key_fp.or(rhs.key_fp),
             _marker: PhantomData,
         }
     }
@@ -86,11 +193,21 @@ impl<E: PairingEngine> Sub for AccValue<E> {
     type Output = Self;
 
     fn sub(self, rhs: Self) -> Self {
+        self + (-rhs)
+    }
+}
+
+/// 相反数：四个群元素分别取负，保留密钥指纹
+impl<E: PairingEngine> Neg for AccValue<E> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
         Self {
-            g_s: self.g_s + (-rhs.g_s),
-            g_r: self.g_r + (-rhs.g_r),
-            h_s_r: self.h_s_r + (-rhs.h_s_r),
-            h_r_s: self.h_r_s + (-rhs.h_r_s),
+            g_s: -self.g_s,
+            g_r: -self.g_r,
+            h_s_r: -self.h_s_r,
+            h_r_s: -self.h_r_s,
+            key_fp: self.key_fp,
             _marker: PhantomData,
         }
     }
@@ -109,15 +226,47 @@ impl<E: PairingEngine> AccValue<E> {
             g_r,
             h_s_r,
             h_r_s,
+            key_fp: None,
             _marker: PhantomData,
         }
     }
+    /// 累加器值摘要的前 8 个十六进制字符，用于在日志中区分不同的累加器值
+    pub fn fingerprint(&self) -> String {
+        let mut hex = self.to_digest().to_string();
+        hex.truncate(8);
+        hex
+    }
+    /// 检查两个累加器值是否在同一密钥下计算；任一方的密钥未知时视为兼容
+    fn check_key(&self, rhs: &Self) -> Result<()> {
+        if let (Some(l), Some(r)) = (self.key_fp, rhs.key_fp) {
+            ensure!(
+                l == r,
+                "Cannot combine acc values computed under different keys"
+            );
+        }
+        Ok(())
+    }
+    /// 检查密钥一致性的加法，密钥不一致时返回错误
+    pub fn add_checked(self, rhs: Self) -> Result<Self> {
+        self.check_key(&rhs)?;
+        Ok(self + rhs)
+    }
+    /// 检查密钥一致性的减法，密钥不一致时返回错误
+    pub fn sub_checked(self, rhs: Self) -> Result<Self> {
+        self.check_key(&rhs)?;
+        Ok(self - rhs)
+    }
     /// 从集合和公钥计算累加器值
     /// 计算累加器值的过程如下：
     /// 1. 计算公钥相关的累加器值 g_s, g_r, h_s_r, h_r_s
     /// 2. 返回累加器值
     /// Compute accumulative value from set using public key.
     pub fn from_set(set: &Set, pk: &AccPublicKey<E>) -> Self {
+        debug_assert!(
+            set.iter().all(|i| i.get() as u64 <= pk.max_element()),
+            "set element exceeds max element {} of the public key",
+            pk.max_element()
+        );
         let g_s = cal_acc_pk(set, |i| pk.get_g_s_i(i));
         let g_r = cal_acc_pk(set, |i| pk.get_g_r_i(i));
         let h_s_r = cal_acc_pk(set, |i| pk.get_h_s_r_i(i));
@@ -128,9 +277,38 @@ impl<E: PairingEngine> AccValue<E> {
             g_r,
             h_s_r,
             h_r_s,
+            key_fp: Some(pk.fingerprint()),
             _marker: PhantomData,
         }
     }
+    /// 检查累加器值的四个群元素是否自洽，用于尽早发现损坏的累加器。
+    ///
+    /// 对于一般的集合，g_s、g_r、h_s_r、h_r_s 之间不存在不依赖额外证据的双线性等式
+    /// （例如 e(g_s, h_r_s) == e(g_r, h_s_r) 只在特殊情况下成立），完整的关系需要
+    /// `IntermediateProof` 中的 Z 证据才能验证。这里只检查可以独立验证的部分：
+    /// 1. 四个群元素都位于素数阶子群中；
+    /// 2. e(g_s, h)、e(g_r, h)、e(g, h_s_r)、e(g, h_r_s) 要么全为单位元（空集），要么全不为单位元。
+    pub fn check_pairing_invariant(&self, pk: &AccPublicKey<E>) -> bool {
+        if !self.in_prime_subgroup() {
+            return false;
+        }
+
+        let degenerate = [
+            E::pairing(self.g_s, pk.h).is_one(),
+            E::pairing(self.g_r, pk.h).is_one(),
+            E::pairing(pk.g, self.h_s_r).is_one(),
+            E::pairing(pk.g, self.h_r_s).is_one(),
+        ];
+        degenerate.iter().all(|&d| d) || degenerate.iter().all(|&d| !d)
+    }
+    /// 四个群元素是否都位于素数阶子群中。反序列化累加器值时不做该检查，
+    /// 验证不可信的累加器值之前逐个检查，或用 `SubgroupBatch` 一次检查多个
+    pub fn in_prime_subgroup(&self) -> bool {
+        in_prime_subgroup(&self.g_s)
+            && in_prime_subgroup(&self.g_r)
+            && in_prime_subgroup(&self.h_s_r)
+            && in_prime_subgroup(&self.h_r_s)
+    }
     /// 从集合和私钥计算累加器值
     /// 计算累加器值的过程如下：
     /// 1. 计算私钥相关的标量值 s^i, r^i, s^i \cdot r^{q - i}, r^i \cdot s^{q - i}
@@ -171,6 +349,7 @@ impl<E: PairingEngine> AccValue<E> {
             g_r,
             h_s_r,
             h_r_s,
+            key_fp: Some(sk.fingerprint(q)),
             _marker: PhantomData,
         }
     }
@@ -195,6 +374,20 @@ mod tests {
         assert_eq!(acc1, acc2);
     }
 
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "exceeds max element")]
+    fn test_from_set_out_of_range() {
+        let mut rng = rand::thread_rng();
+        let q = 5;
+        let sk = AccSecretKey::<Bn254>::rand(&mut rng).into();
+        let pk = AccPublicKey::<Bn254>::gen_key(&sk, q);
+        assert_eq!(pk.q(), q);
+        assert_eq!(pk.max_element(), 4);
+        AccValue::<Bn254>::from_set(&set! {4}, &pk);
+        AccValue::<Bn254>::from_set(&set! {7}, &pk);
+    }
+
     #[test]
     fn test_update_acc() {
         let mut rng = rand::thread_rng();
@@ -207,4 +400,158 @@ mod tests {
         assert_eq!(acc1, acc2 + acc3);
         assert_eq!(acc1 - acc2, acc3);
     }
+
+    #[test]
+    fn test_neg_acc() {
+        use rand::Rng;
+
+        let mut rng = rand::thread_rng();
+        let q = 40;
+        let sk = AccSecretKey::<Bn254>::rand(&mut rng).into();
+        let pk = AccPublicKey::<Bn254>::gen_key(&sk, q);
+        let mut rand_acc = || {
+            let s: Set = (0..rng.gen_range(0..8))
+                .map(|_| rng.gen_range(1..q as u16))
+                .collect();
+            AccValue::<Bn254>::from_set(&s, &pk)
+        };
+        let empty = AccValue::<Bn254>::from_set(&Set::new(), &pk);
+        for _ in 0..10 {
+            let a = rand_acc();
+            let b = rand_acc();
+            assert_eq!(a - b, a + (-b));
+            assert_eq!(-(-a), a);
+            assert_eq!(a + (-a), empty);
+        }
+    }
+
+    #[test]
+    fn test_checked_ops_key_mismatch() {
+        let mut rng = rand::thread_rng();
+        let q = 5;
+        let sk1 = AccSecretKey::<Bn254>::rand(&mut rng).into();
+        let sk2 = AccSecretKey::<Bn254>::rand(&mut rng).into();
+        let pk1 = AccPublicKey::<Bn254>::gen_key(&sk1, q);
+        let pk2 = AccPublicKey::<Bn254>::gen_key(&sk2, q);
+        assert_eq!(pk1.fingerprint(), sk1.fingerprint(q));
+        assert_ne!(pk1.fingerprint(), pk2.fingerprint());
+
+        let acc1 = AccValue::<Bn254>::from_set(&set! {1, 2, 3}, &pk1);
+        let acc2 = AccValue::<Bn254>::from_set(&set! {1, 2}, &pk1);
+        let acc3 = AccValue::<Bn254>::from_set_sk(&set! {3}, &sk1, q);
+        assert_eq!(acc2.add_checked(acc3).unwrap(), acc2 + acc3);
+        assert_eq!(acc2.add_checked(acc3).unwrap(), acc1);
+        assert_eq!(acc1.sub_checked(acc2).unwrap(), acc1 - acc2);
+
+        let other = AccValue::<Bn254>::from_set(&set! {3}, &pk2);
+        assert!(acc2.add_checked(other).is_err());
+        assert!(acc1.sub_checked(other).is_err());
+        // 不同 q 生成的公钥同样不兼容
+        let pk3 = AccPublicKey::<Bn254>::gen_key(&sk1, q + 1);
+        let other = AccValue::<Bn254>::from_set(&set! {3}, &pk3);
+        assert!(acc2.add_checked(other).is_err());
+        // 反序列化得到的值不携带指纹，按兼容处理
+        let de: AccValue<Bn254> =
+            bincode::deserialize(&bincode::serialize(&other).unwrap()).unwrap();
+        assert_eq!(de, other);
+        assert!(acc2.add_checked(de).is_ok());
+    }
+
+    #[test]
+    fn test_check_pairing_invariant() {
+        use crate::chain::tests::PUB_KEY;
+        use ark_bn254::{Fq, G1Affine};
+
+        let pk = &*PUB_KEY;
+        let acc = AccValue::from_set(&set! {1, 2, 3, 5, 8}, pk);
+        assert!(acc.check_pairing_invariant(pk));
+        assert!(AccValue::from_set(&Set::new(), pk).check_pairing_invariant(pk));
+
+        let mut zeroed = acc;
+        zeroed.g_s = G1Affine::zero();
+        assert!(!zeroed.check_pairing_invariant(pk));
+
+        let mut off_curve = acc;
+        off_curve.g_s = G1Affine::new(acc.g_s.x + Fq::one(), acc.g_s.y, false);
+        assert!(!off_curve.check_pairing_invariant(pk));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_chunked_acc_pk() {
+        use crate::chain::tests::PUB_KEY;
+
+        let pk = &*PUB_KEY;
+        let sets: Vec<Set> = vec![
+            Set::new(),
+            set! {7},
+            (1..=10u16).collect(),
+            (1..=39u16).collect(),
+            (3..=39u16).step_by(3).collect(),
+        ];
+        for s in &sets {
+            let expect_g = cal_acc_pk_serial(s, |i| pk.get_g_s_i(i));
+            let expect_h = cal_acc_pk_serial(s, |i| pk.get_h_s_r_i(i));
+            for chunk_size in [1, 2, 3, 7, 64] {
+                assert_eq!(
+                    cal_acc_pk_chunked(s, |i| pk.get_g_s_i(i), chunk_size),
+                    expect_g
+                );
+                assert_eq!(
+                    cal_acc_pk_chunked(s, |i| pk.get_h_s_r_i(i), chunk_size),
+                    expect_h
+                );
+            }
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_serial_matches_parallel() {
+        use crate::chain::tests::PUB_KEY;
+        use ark_bn254::Fr;
+
+        let s: Set = (1..=39u16).collect();
+        let pk = &*PUB_KEY;
+        assert_eq!(
+            cal_acc_pk(&s, |i| pk.get_g_s_i(i)),
+            cal_acc_pk_serial(&s, |i| pk.get_g_s_i(i))
+        );
+        assert_eq!(
+            cal_acc_pk(&s, |i| pk.get_h_r_s_i(i)),
+            cal_acc_pk_serial(&s, |i| pk.get_h_r_s_i(i))
+        );
+        assert_eq!(
+            cal_acc_scalar_sk(&s, |i| Fr::from(i * i)),
+            cal_acc_scalar_sk_serial(&s, |i| Fr::from(i * i))
+        );
+
+        let empty = Set::new();
+        assert_eq!(
+            cal_acc_pk(&empty, |i| pk.get_g_r_i(i)),
+            cal_acc_pk_serial(&empty, |i| pk.get_g_r_i(i))
+        );
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        use crate::chain::tests::PUB_KEY;
+
+        let acc1 = AccValue::from_set(&set! {1, 2, 3}, &PUB_KEY);
+        let acc2 = AccValue::from_set(&set! {1, 2, 4}, &PUB_KEY);
+        let fp = acc1.fingerprint();
+        assert_eq!(fp.len(), 8);
+        assert!(fp.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(
+            fp,
+            AccValue::from_set(&set! {3, 2, 1}, &PUB_KEY).fingerprint()
+        );
+        assert!(acc1.to_digest().to_string().starts_with(&fp));
+        assert_ne!(fp, acc2.fingerprint());
+        assert_eq!(acc1.to_string(), fp);
+        assert_eq!(
+            format!("{} != {}", acc1, acc2),
+            format!("{} != {}", fp, acc2.fingerprint())
+        );
+    }
 }
@@ -0,0 +1,227 @@
+//! 配对等式的批量验证
+//!
+//! 每个形如 `∏ e(a_i, b_i) == ∏ e(c_j, d_j)` 的等式先改写为 `∏ e(a_i, b_i) · ∏ e(-c_j, d_j) == 1`，
+//! 再乘以独立的随机数 `r_k` 后合并为一个等式 `∏_k (∏ e(r_k·a_i, b_i) · ∏ e(-r_k·c_j, d_j)) == 1`。
+//! 任何一个等式不成立时，合并后的等式只以可忽略的概率（约 `1/|Fr|`）成立。
+//! 合并时按 G2 元素归并 G1 一侧的点，因此最终的配对次数等于不同 G2 元素的个数，
+//! 而大部分等式都使用公钥中的少数几个 G2 元素。
+//!
+//! 累加器值的子群检查同样可以合并：`SubgroupBatch` 把各群元素乘以独立的随机数后按群求和，
+//! 只对两个和各做一次乘以群阶的检查。这种合并的可靠性受余因子限制，远弱于逐个检查，见 `SubgroupBatch`。
+
+use super::acc_value::AccValue;
+use anyhow::{ensure, Result};
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{FpParameters, One, PrimeField, UniformRand, Zero};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::collections::HashMap;
+
+pub struct PairingBatch<E: PairingEngine> {
+    /// 以 G2 元素为键，累加乘以随机数之后的 G1 元素
+    terms: HashMap<E::G2Affine, E::G1Projective>,
+    /// 已加入的等式个数
+    check_num: usize,
+    rng: StdRng,
+}
+
+impl<E: PairingEngine> Default for PairingBatch<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E: PairingEngine> PairingBatch<E> {
+    pub fn new() -> Self {
+        Self {
+            terms: HashMap::new(),
+            check_num: 0,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// 加入等式 `∏ e(lhs) == ∏ e(rhs)`，只在 `verify` 时检查
+    pub fn add_check(
+        &mut self,
+        lhs: &[(E::G1Affine, E::G2Affine)],
+        rhs: &[(E::G1Affine, E::G2Affine)],
+    ) {
+        let mut r = E::Fr::rand(&mut self.rng);
+        while r.is_zero() {
+            r = E::Fr::rand(&mut self.rng);
+        }
+        for (a, b) in lhs {
+            *self.terms.entry(*b).or_insert_with(Zero::zero) += &a.mul(r);
+        }
+        for (c, d) in rhs {
+            *self.terms.entry(*d).or_insert_with(Zero::zero) += &(-c.mul(r));
+        }
+        self.check_num += 1;
+    }
+
+    /// 已加入的等式个数
+    pub fn len(&self) -> usize {
+        self.check_num
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.check_num == 0
+    }
+
+    /// 一次性检查所有加入的等式
+    pub fn verify(self) -> Result<()> {
+        if self.terms.is_empty() {
+            return Ok(());
+        }
+        let (g2s, g1s): (Vec<E::G2Affine>, Vec<E::G1Projective>) = self.terms.into_iter().unzip();
+        let g1s = E::G1Projective::batch_normalization_into_affine(&g1s);
+        let pairs: Vec<_> = g1s
+            .into_iter()
+            .zip(g2s)
+            .map(|(a, b)| (a.into(), b.into()))
+            .collect();
+        ensure!(
+            E::product_of_pairings(&pairs).is_one(),
+            "Batched pairing verification failed ({} checks)",
+            self.check_num
+        );
+        Ok(())
+    }
+}
+
+/// 批量检查累加器值的群元素是否位于素数阶子群。某个元素含有子群之外的分量时，
+/// 只有该分量的随机系数恰好为其阶的倍数时才会漏检，概率不超过 `1/p`，`p` 为余因子的最小素因子。
+/// BN254 上 G2 余因子的最小素因子为 10069，即每次检查约有 1/10069 的概率漏检；
+/// G1 余因子为 1，曲线上的点总在子群中，G1 一侧的求和不提供额外保证。
+/// 验证不可信的输入时应逐个调用 `AccValue::in_prime_subgroup`
+pub struct SubgroupBatch<E: PairingEngine> {
+    g1_sum: E::G1Projective,
+    g2_sum: E::G2Projective,
+    /// 已加入的累加器值个数
+    acc_num: usize,
+    rng: StdRng,
+}
+
+impl<E: PairingEngine> Default for SubgroupBatch<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E: PairingEngine> SubgroupBatch<E> {
+    pub fn new() -> Self {
+        Self {
+            g1_sum: Zero::zero(),
+            g2_sum: Zero::zero(),
+            acc_num: 0,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// 加入一个累加器值的四个群元素，只在 `verify` 时检查
+    pub fn add(&mut self, acc: &AccValue<E>) {
+        let rng = &mut self.rng;
+        let mut r = || E::Fr::from(rng.gen::<u128>());
+        self.g1_sum += &acc.g_s.mul(r());
+        self.g1_sum += &acc.g_r.mul(r());
+        self.g2_sum += &acc.h_s_r.mul(r());
+        self.g2_sum += &acc.h_r_s.mul(r());
+        self.acc_num += 1;
+    }
+
+    /// 已加入的累加器值个数
+    pub fn len(&self) -> usize {
+        self.acc_num
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.acc_num == 0
+    }
+
+    /// 一次性检查所有加入的群元素
+    pub fn verify(self) -> Result<()> {
+        let modulus = <E::Fr as PrimeField>::Params::MODULUS;
+        ensure!(
+            self.g1_sum.mul(modulus).is_zero() && self.g2_sum.mul(modulus).is_zero(),
+            "Acc value is not in the prime order subgroup ({} acc values checked)",
+            self.acc_num
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn off_subgroup_g2() -> ark_bn254::G2Affine {
+    use ark_bn254::{Fq2, G2Affine};
+
+    let mut rng = StdRng::seed_from_u64(0);
+    loop {
+        if let Some(p) = G2Affine::get_point_from_x(Fq2::rand(&mut rng), false) {
+            if !p.is_in_correct_subgroup_assuming_on_curve() {
+                return p;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bn254::{Bn254, Fr, G1Affine, G2Affine};
+    use ark_ec::AffineCurve;
+
+    #[test]
+    fn test_pairing_batch() {
+        let mut rng = rand::thread_rng();
+        let g = G1Affine::prime_subgroup_generator();
+        let h = G2Affine::prime_subgroup_generator();
+        let x = Fr::rand(&mut rng);
+        let y = Fr::rand(&mut rng);
+        let g_x = g.mul(x).into_affine();
+        let h_x = h.mul(x).into_affine();
+        let g_xy = g.mul(x * y).into_affine();
+        let h_y = h.mul(y).into_affine();
+
+        let mut batch = PairingBatch::<Bn254>::new();
+        assert!(batch.is_empty());
+        assert!(PairingBatch::<Bn254>::new().verify().is_ok());
+        // e(g^x, h) == e(g, h^x)
+        batch.add_check(&[(g_x, h)], &[(g, h_x)]);
+        // e(g^{xy}, h) == e(g^x, h^y)
+        batch.add_check(&[(g_xy, h)], &[(g_x, h_y)]);
+        assert_eq!(batch.len(), 2);
+        assert!(batch.verify().is_ok());
+
+        let mut batch = PairingBatch::<Bn254>::new();
+        batch.add_check(&[(g_x, h)], &[(g, h_x)]);
+        batch.add_check(&[(g_xy, h)], &[(g, h_y)]);
+        assert!(batch.verify().is_err());
+    }
+
+    #[test]
+    fn test_subgroup_batch() {
+        use crate::{acc::Set, chain::tests::PUB_KEY, set};
+
+        let pk = &*PUB_KEY;
+        let accs: Vec<_> = (1..=5u16)
+            .map(|i| AccValue::from_set(&(1..=i).collect::<Set>(), pk))
+            .collect();
+        let mut batch = SubgroupBatch::<Bn254>::new();
+        assert!(batch.is_empty());
+        assert!(SubgroupBatch::<Bn254>::new().verify().is_ok());
+        for acc in &accs {
+            assert!(acc.in_prime_subgroup());
+            batch.add(acc);
+        }
+        assert_eq!(batch.len(), 5);
+        assert!(batch.verify().is_ok());
+
+        let mut bad = AccValue::from_set(&set! {1, 2}, pk);
+        bad.h_r_s = bad.h_r_s + off_subgroup_g2();
+        assert!(!bad.in_prime_subgroup());
+        let mut batch = SubgroupBatch::<Bn254>::new();
+        for acc in accs.iter().chain(std::iter::once(&bad)) {
+            batch.add(acc);
+        }
+        assert!(batch.verify().is_err());
+    }
+}
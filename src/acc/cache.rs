@@ -0,0 +1,79 @@
+//! 构建区块时复用的累加器值
+//!
+//! 构建区块时，同一个对象要插入每个时间窗口的 trie 与各维度的 B+ 树，
+//! 每次插入都要由只含该对象 id 的集合计算累加器值，删除过期对象时同样如此。
+//! `AccCache` 以集合的摘要（`Set::to_digest`）为键保存计算结果，内容相同的集合只计算一次。
+
+use super::{keys::KeyFingerprint, AccPublicKey, AccValue, Set};
+use crate::digest::{Digest, Digestible};
+use std::{collections::HashMap, sync::RwLock};
+
+/// 默认最多保存的累加器值个数
+const DEFAULT_CAPACITY: usize = 1 << 16;
+
+/// 只对创建时使用的公钥有效；以其他公钥调用时直接计算，不读写缓存
+#[derive(Debug)]
+pub struct AccCache {
+    key_fp: KeyFingerprint,
+    accs: RwLock<HashMap<Digest, AccValue>>,
+    capacity: usize,
+}
+
+impl AccCache {
+    pub fn new(pk: &AccPublicKey) -> Self {
+        Self::with_capacity(pk, DEFAULT_CAPACITY)
+    }
+
+    /// 最多保存 `capacity` 个累加器值，达到上限后不再加入新的值
+    pub fn with_capacity(pk: &AccPublicKey, capacity: usize) -> Self {
+        Self {
+            key_fp: pk.fingerprint(),
+            accs: RwLock::new(HashMap::new()),
+            capacity,
+        }
+    }
+
+    /// 集合 `set` 在公钥 `pk` 下的累加器值
+    pub fn acc_of(&self, set: &Set, pk: &AccPublicKey) -> AccValue {
+        if pk.fingerprint() != self.key_fp {
+            return AccValue::from_set(set, pk);
+        }
+        let key = set.to_digest();
+        if let Some(acc) = self.accs.read().expect("poisoned lock").get(&key) {
+            return *acc;
+        }
+        let acc = AccValue::from_set(set, pk);
+        let mut accs = self.accs.write().expect("poisoned lock");
+        if accs.len() < self.capacity {
+            accs.insert(key, acc);
+        }
+        acc
+    }
+
+    /// 已保存的累加器值个数
+    pub fn len(&self) -> usize {
+        self.accs.read().expect("poisoned lock").len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chain::tests::PUB_KEY, set};
+
+    #[test]
+    fn test_acc_cache() {
+        let cache = AccCache::new(&PUB_KEY);
+        let acc1 = cache.acc_of(&set! {1, 2, 3}, &PUB_KEY);
+        let acc2 = cache.acc_of(&set! {3, 2, 1}, &PUB_KEY);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(acc1, acc2);
+        assert_eq!(acc1, AccValue::from_set(&set! {1, 2, 3}, &PUB_KEY));
+        cache.acc_of(&set! {1, 2}, &PUB_KEY);
+        assert_eq!(cache.len(), 2);
+    }
+}
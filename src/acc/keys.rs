@@ -1,10 +1,22 @@
 use super::utils::{FixedBaseCurvePow, FixedBaseScalarPow};
+use crate::{
+    digest::{Digest, Digestible},
+    utils::{binary_decode, binary_encode},
+};
+use anyhow::{ensure, Context, Result};
 use ark_ec::{PairingEngine, ProjectiveCurve};
 use ark_ff::UniformRand;
 use core::marker::PhantomData;
-use rand::{CryptoRng, RngCore};
+use rand::{CryptoRng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::Path,
+    sync::atomic::{AtomicU64, Ordering},
+};
 /// 累加器的私钥
 /// 包含了累加器的四个标量值 s, r, beta, gamma, delta
 /// 以及用于计算公钥相关的群元素的缓存 g_pow, h_pow, s_pow, r_pow
@@ -46,6 +58,12 @@ impl<E: PairingEngine> AccSecretKey<E> {
             _marker: PhantomData,
         }
     }
+
+    /// 由 32 字节种子确定性地生成私钥（内部使用 `ChaCha20Rng`），
+    /// 相同的种子总是得到相同的私钥与公钥，用于可复现的测试与基准
+    pub fn from_seed(seed: [u8; 32]) -> Self {
+        Self::rand(ChaCha20Rng::from_seed(seed))
+    }
 }
 /// 累加器的私钥的缓存
 /// 包含了累加器的私钥和用于计算公钥相关的群元素的缓存
@@ -66,6 +84,10 @@ pub struct AccSecretKeyWithPowCache<E: PairingEngine> {
     pub(crate) s_pow: FixedBaseScalarPow<E::Fr>,
     /// Used to compute r^x.
     pub(crate) r_pow: FixedBaseScalarPow<E::Fr>,
+    /// h^s，用于计算密钥指纹
+    pub(crate) h_s: E::G2Affine,
+    /// h^r，用于计算密钥指纹
+    pub(crate) h_r: E::G2Affine,
     _marker: PhantomData<E>,
 }
 /// 累加器的私钥的缓存的转换
@@ -78,6 +100,10 @@ impl<E: PairingEngine> From<AccSecretKey<E>> for AccSecretKeyWithPowCache<E> {
         let g = <E::G1Projective as ProjectiveCurve>::prime_subgroup_generator();
         let h = <E::G2Projective as ProjectiveCurve>::prime_subgroup_generator();
 
+        let h_pow = FixedBaseCurvePow::build(&h);
+        let h_s = h_pow.apply(&sk.s).into_affine();
+        let h_r = h_pow.apply(&sk.r).into_affine();
+
         Self {
             s: sk.s,
             r: sk.r,
@@ -85,13 +111,37 @@ impl<E: PairingEngine> From<AccSecretKey<E>> for AccSecretKeyWithPowCache<E> {
             gamma: sk.gamma,
             delta: sk.delta,
             g_pow: FixedBaseCurvePow::build(&g),
-            h_pow: FixedBaseCurvePow::build(&h),
+            h_pow,
             s_pow: FixedBaseScalarPow::build(&sk.s),
             r_pow: FixedBaseScalarPow::build(&sk.r),
+            h_s,
+            h_r,
             _marker: PhantomData,
         }
     }
 }
+/// 累加器密钥的指纹，由 q、h^s、h^r 计算得到。
+/// 同一密钥对的公钥与私钥得到相同的指纹，用于发现不同密钥下计算的累加器值被混合运算。
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct KeyFingerprint(Digest);
+
+impl KeyFingerprint {
+    fn new<E: PairingEngine>(q: u64, h_s: &E::G2Affine, h_r: &E::G2Affine) -> Self {
+        Self(
+            ark_ff::to_bytes!(q, h_s, h_r)
+                .expect("failed to convert key to bytes")
+                .to_digest(),
+        )
+    }
+}
+
+impl<E: PairingEngine> AccSecretKeyWithPowCache<E> {
+    /// 使用参数 q 生成的公钥的指纹
+    pub fn fingerprint(&self, q: u64) -> KeyFingerprint {
+        KeyFingerprint::new::<E>(q, &self.h_s, &self.h_r)
+    }
+}
+
 /// 累加器的公钥
 /// 包含了累加器的公钥相关的群元素，用于验证累加器值的有效性
 /// 公钥的长度为 32 字节，每个群元素的长度为 32 字节
@@ -169,6 +219,43 @@ pub struct AccPublicKey<E: PairingEngine> {
 /// 生成的公钥的序列化和反序列化使用了自定义的 serde 实现，确保公钥的安全性和正确性
 impl<E: PairingEngine> AccPublicKey<E> {
     pub fn gen_key(sk: &AccSecretKeyWithPowCache<E>, q: u64) -> Self {
+        Self::gen_key_with_progress(sk, q, None)
+    }
+
+    /// 在最多 `max_threads` 个线程的线程池中生成公钥，结果与 `gen_key` 相同
+    pub fn gen_key_bounded(
+        sk: &AccSecretKeyWithPowCache<E>,
+        q: u64,
+        max_threads: usize,
+    ) -> Result<Self> {
+        ensure!(q > 0, "q must be positive");
+        ensure!(max_threads > 0, "max_threads must be positive");
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(max_threads)
+            .build()?;
+        Ok(pool.install(|| Self::gen_key(sk, q)))
+    }
+
+    /// 公钥包含的群元素个数（不含生成元 g、h），即生成进度的总量
+    pub fn num_elements(q: u64) -> Result<u64> {
+        ensure!(q > 0, "q must be positive");
+        let q_1 = q - 1;
+        q_1.checked_mul(2)
+            .and_then(|n| n.checked_mul(n))
+            .and_then(|n| n.checked_mul(2))
+            .and_then(|n| n.checked_add(q_1.checked_mul(8)?))
+            .and_then(|n| n.checked_add(7))
+            .with_context(|| format!("q {} is too large", q))
+    }
+
+    /// 生成公钥，每生成 `PROGRESS_STEP` 个群元素以及全部生成后，以已生成的个数调用 `progress`。
+    /// `progress` 可能在多个线程中被并发调用
+    pub fn gen_key_with_progress(
+        sk: &AccSecretKeyWithPowCache<E>,
+        q: u64,
+        progress: Option<&(dyn Fn(u64) + Sync)>,
+    ) -> Self {
+        let progress = GenProgress::new(progress);
         let q_usize = q as usize;
         let q_fr = E::Fr::from(q);
 
@@ -180,6 +267,7 @@ impl<E: PairingEngine> AccPublicKey<E> {
         let h_r = sk.h_pow.apply(&sk.r).into_affine();
         let h_s_q = sk.h_pow.apply(&sk.s_pow.apply(&q_fr)).into_affine();
         let h_r_q = sk.h_pow.apply(&sk.r_pow.apply(&q_fr)).into_affine();
+        progress.add(7);
 
         let mut s_i_list = Vec::with_capacity(q_usize - 1);
         (1..q_usize)
@@ -208,34 +296,36 @@ impl<E: PairingEngine> AccPublicKey<E> {
         let mut g_s_i = Vec::with_capacity(q_usize - 1);
         (1..q_usize)
             .into_par_iter()
-            .map(|i| sk.g_pow.apply(&s_i_list[i - 1]).into_affine())
+            .map(|i| progress.tick(sk.g_pow.apply(&s_i_list[i - 1]).into_affine()))
             .collect_into_vec(&mut g_s_i);
 
         let mut g_r_i = Vec::with_capacity(q_usize - 1);
         (1..q_usize)
             .into_par_iter()
-            .map(|i| sk.g_pow.apply(&r_i_list[i - 1]).into_affine())
+            .map(|i| progress.tick(sk.g_pow.apply(&r_i_list[i - 1]).into_affine()))
             .collect_into_vec(&mut g_r_i);
 
         let mut g_beta_s_i = Vec::with_capacity(q_usize - 1);
         (1..q_usize)
             .into_par_iter()
-            .map(|i| sk.g_pow.apply(&(sk.beta * s_i_list[i - 1])).into_affine())
+            .map(|i| progress.tick(sk.g_pow.apply(&(sk.beta * s_i_list[i - 1])).into_affine()))
             .collect_into_vec(&mut g_beta_s_i);
 
         let mut g_beta_r_i = Vec::with_capacity(q_usize - 1);
         (1..q_usize)
             .into_par_iter()
-            .map(|i| sk.g_pow.apply(&(sk.beta * r_i_list[i - 1])).into_affine())
+            .map(|i| progress.tick(sk.g_pow.apply(&(sk.beta * r_i_list[i - 1])).into_affine()))
             .collect_into_vec(&mut g_beta_r_i);
 
         let mut h_r_s_i = Vec::with_capacity(q_usize - 1);
         (1..q_usize)
             .into_par_iter()
             .map(|i| {
-                sk.h_pow
-                    .apply(&(r_i_list[i - 1] * s_q_i_list[i - 1]))
-                    .into_affine()
+                progress.tick(
+                    sk.h_pow
+                        .apply(&(r_i_list[i - 1] * s_q_i_list[i - 1]))
+                        .into_affine(),
+                )
             })
             .collect_into_vec(&mut h_r_s_i);
 
@@ -243,9 +333,11 @@ impl<E: PairingEngine> AccPublicKey<E> {
         (1..q_usize)
             .into_par_iter()
             .map(|i| {
-                sk.h_pow
-                    .apply(&(s_i_list[i - 1] * r_q_i_list[i - 1]))
-                    .into_affine()
+                progress.tick(
+                    sk.h_pow
+                        .apply(&(s_i_list[i - 1] * r_q_i_list[i - 1]))
+                        .into_affine(),
+                )
             })
             .collect_into_vec(&mut h_s_r_i);
 
@@ -253,9 +345,11 @@ impl<E: PairingEngine> AccPublicKey<E> {
         (1..q_usize)
             .into_par_iter()
             .map(|i| {
-                sk.g_pow
-                    .apply(&(sk.gamma * r_i_list[i - 1] * s_q_i_list[i - 1]))
-                    .into_affine()
+                progress.tick(
+                    sk.g_pow
+                        .apply(&(sk.gamma * r_i_list[i - 1] * s_q_i_list[i - 1]))
+                        .into_affine(),
+                )
             })
             .collect_into_vec(&mut g_gamma_r_s_i);
 
@@ -263,9 +357,11 @@ impl<E: PairingEngine> AccPublicKey<E> {
         (1..q_usize)
             .into_par_iter()
             .map(|i| {
-                sk.g_pow
-                    .apply(&(sk.gamma * s_i_list[i - 1] * r_q_i_list[i - 1]))
-                    .into_affine()
+                progress.tick(
+                    sk.g_pow
+                        .apply(&(sk.gamma * s_i_list[i - 1] * r_q_i_list[i - 1]))
+                        .into_affine(),
+                )
             })
             .collect_into_vec(&mut g_gamma_s_r_i);
 
@@ -290,15 +386,17 @@ impl<E: PairingEngine> AccPublicKey<E> {
         let mut g_r_i_s_j = Vec::with_capacity((2 * q_usize - 2) * (2 * q_usize - 2));
         r_i_s_j_list
             .par_iter()
-            .map(|r_i_s_j| sk.g_pow.apply(r_i_s_j).into_affine())
+            .map(|r_i_s_j| progress.tick(sk.g_pow.apply(r_i_s_j).into_affine()))
             .collect_into_vec(&mut g_r_i_s_j);
 
         let mut g_delta_r_i_s_j = Vec::with_capacity((2 * q_usize - 2) * (2 * q_usize - 2));
         r_i_s_j_list
             .par_iter()
-            .map(|r_i_s_j| sk.g_pow.apply(&(sk.delta * r_i_s_j)).into_affine())
+            .map(|r_i_s_j| progress.tick(sk.g_pow.apply(&(sk.delta * r_i_s_j)).into_affine()))
             .collect_into_vec(&mut g_delta_r_i_s_j);
 
+        progress.finish();
+
         Self {
             q,
             g: <E::G1Projective as ProjectiveCurve>::prime_subgroup_generator().into_affine(),
@@ -329,6 +427,59 @@ impl<E: PairingEngine> AccPublicKey<E> {
         self.q
     }
 
+    /// Return q
+    pub fn q(&self) -> u64 {
+        self.q
+    }
+
+    /// 该公钥支持的最大集合元素，合法元素的范围为 [1, q-1]
+    pub fn max_element(&self) -> u64 {
+        self.q.saturating_sub(1)
+    }
+
+    /// 公钥的指纹
+    pub fn fingerprint(&self) -> KeyFingerprint {
+        KeyFingerprint::new::<E>(self.q, &self.h_s, &self.h_r)
+    }
+
+    /// 将公钥保存到文件：先写入未压缩的文件头（曲线标识与 q），再写入 `binary_encode` 编码的公钥
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let header = PubKeyFileHeader {
+            curve: curve_id::<E>(),
+            q: self.q,
+        };
+        let mut writer = BufWriter::new(
+            File::create(path).with_context(|| format!("failed to create {}", path.display()))?,
+        );
+        bincode::serialize_into(&mut writer, &header)?;
+        writer.write_all(&binary_encode(self)?)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// 从 `save` 写出的文件加载公钥，文件头中的曲线必须与 `E` 一致
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+        let mut reader = &data[..];
+        let header: PubKeyFileHeader = bincode::deserialize_from(&mut reader)?;
+        let curve = curve_id::<E>();
+        ensure!(
+            header.curve == curve,
+            "Public key in {} is for curve {}, expected {}",
+            path.display(),
+            header.curve,
+            curve
+        );
+        let pk: Self = binary_decode(reader)?;
+        ensure!(
+            pk.q == header.q,
+            "Public key q mismatch: header {}, key {}",
+            header.q,
+            pk.q
+        );
+        Ok(pk)
+    }
+
     /// Return g^{s^i} i \in [q-1]
     pub(crate) fn try_get_g_s_i(&self, i: u64) -> Option<E::G1Affine> {
         self.g_s_i.get(map_i_to_index(i, self.q)?).copied()
@@ -469,6 +620,58 @@ fn map_i_j_to_index(i: u64, j: u64, q: u64) -> Option<usize> {
     let _j = if j > q { j - 2 } else { j - 1 };
     Some((_i * (2 * q - 2) + _j) as usize)
 }
+/// 生成公钥时报告进度的间隔（群元素个数）
+const PROGRESS_STEP: u64 = 4096;
+
+/// 生成公钥时的进度计数
+struct GenProgress<'a> {
+    done: AtomicU64,
+    callback: Option<&'a (dyn Fn(u64) + Sync)>,
+}
+
+impl<'a> GenProgress<'a> {
+    fn new(callback: Option<&'a (dyn Fn(u64) + Sync)>) -> Self {
+        Self {
+            done: AtomicU64::new(0),
+            callback,
+        }
+    }
+
+    /// 记录新生成了 `n` 个群元素，跨过 `PROGRESS_STEP` 的整数倍时报告
+    fn add(&self, n: u64) {
+        if let Some(callback) = self.callback {
+            let done = self.done.fetch_add(n, Ordering::Relaxed) + n;
+            if done / PROGRESS_STEP != (done - n) / PROGRESS_STEP {
+                callback(done);
+            }
+        }
+    }
+
+    /// 记录生成了群元素 `elem` 并将其返回
+    fn tick<T>(&self, elem: T) -> T {
+        self.add(1);
+        elem
+    }
+
+    fn finish(&self) {
+        if let Some(callback) = self.callback {
+            callback(self.done.load(Ordering::Relaxed));
+        }
+    }
+}
+
+/// 公钥文件的文件头
+#[derive(Debug, Serialize, Deserialize)]
+struct PubKeyFileHeader {
+    curve: String,
+    q: u64,
+}
+
+/// 曲线标识，使用配对引擎的类型名
+fn curve_id<E: PairingEngine>() -> String {
+    std::any::type_name::<E>().to_owned()
+}
+
 /// 累加器公钥的测试
 #[cfg(test)]
 mod tests {
@@ -477,6 +680,32 @@ mod tests {
     use ark_ec::AffineCurve;
     use ark_ff::{Field, PrimeField};
 
+    #[test]
+    fn test_from_seed() {
+        use crate::{acc::acc_value::AccValue, set};
+
+        let q = 5;
+        let sk1 = AccSecretKey::<Bn254>::from_seed([1u8; 32]);
+        let sk2 = AccSecretKey::<Bn254>::from_seed([1u8; 32]);
+        let sk3 = AccSecretKey::<Bn254>::from_seed([2u8; 32]);
+        assert_eq!(sk1, sk2);
+        assert_ne!(sk1, sk3);
+
+        let pk1 = AccPublicKey::<Bn254>::gen_key(&sk1.into(), q);
+        let pk2 = AccPublicKey::<Bn254>::gen_key(&sk2.into(), q);
+        let pk3 = AccPublicKey::<Bn254>::gen_key(&sk3.into(), q);
+        assert_eq!(pk1.fingerprint(), pk2.fingerprint());
+        let s = set! {1, 2, 3};
+        assert_eq!(
+            AccValue::<Bn254>::from_set(&s, &pk1),
+            AccValue::<Bn254>::from_set(&s, &pk2)
+        );
+        assert_ne!(
+            AccValue::<Bn254>::from_set(&s, &pk1),
+            AccValue::<Bn254>::from_set(&s, &pk3)
+        );
+    }
+
     #[test]
     fn test_key_gen() {
         let mut rng = rand::thread_rng();
@@ -544,4 +773,57 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_save_load_pub_key() {
+        use crate::{acc::acc_value::AccValue, set};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pk");
+        let mut rng = rand::thread_rng();
+        let q = 10;
+        let sk = AccSecretKey::<Bn254>::rand(&mut rng).into();
+        let pk = AccPublicKey::<Bn254>::gen_key(&sk, q);
+        pk.save(&path).unwrap();
+
+        let loaded = AccPublicKey::<Bn254>::load(&path).unwrap();
+        assert_eq!(loaded, pk);
+        assert_eq!(loaded.fingerprint(), pk.fingerprint());
+        let s = set! {1, 3, 5, 9};
+        assert_eq!(AccValue::from_set(&s, &loaded), AccValue::from_set(&s, &pk));
+
+        let bad_path = dir.path().join("bad_pk");
+        let header = PubKeyFileHeader {
+            curve: "OtherCurve".to_owned(),
+            q,
+        };
+        let mut data = bincode::serialize(&header).unwrap();
+        data.extend(binary_encode(&pk).unwrap());
+        fs::write(&bad_path, data).unwrap();
+        assert!(AccPublicKey::<Bn254>::load(&bad_path).is_err());
+        assert!(AccPublicKey::<Bn254>::load(&dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn test_gen_key_bounded() {
+        use std::sync::Mutex;
+
+        let q = 5;
+        let sk = AccSecretKey::<Bn254>::from_seed([3u8; 32]).into();
+        let pk = AccPublicKey::<Bn254>::gen_key(&sk, q);
+        assert_eq!(AccPublicKey::gen_key_bounded(&sk, q, 1).unwrap(), pk);
+        assert!(AccPublicKey::gen_key_bounded(&sk, q, 0).is_err());
+        assert!(AccPublicKey::gen_key_bounded(&sk, 0, 1).is_err());
+        assert!(AccPublicKey::<Bn254>::num_elements(0).is_err());
+        assert!(AccPublicKey::<Bn254>::num_elements(u64::MAX).is_err());
+
+        let reported = Mutex::new(Vec::new());
+        let progress = |n| reported.lock().unwrap().push(n);
+        let pk2 = AccPublicKey::<Bn254>::gen_key_with_progress(&sk, q, Some(&progress));
+        assert_eq!(pk2, pk);
+        assert_eq!(
+            reported.into_inner().unwrap().last(),
+            Some(&AccPublicKey::<Bn254>::num_elements(q).unwrap())
+        );
+    }
 }
@@ -1,8 +1,9 @@
 use super::{
     acc_value::{cal_acc_pk, AccValue},
+    batch::PairingBatch,
     keys::AccPublicKey,
     poly::{poly_a, poly_b, poly_variable_minus_one, Poly, Variable, R, S},
-    set::Set,
+    set::{Set, SetOpWitness},
 };
 use anyhow::{ensure, Context as _, Result};
 use ark_ec::{msm::VariableBaseMSM, PairingEngine, ProjectiveCurve};
@@ -139,6 +140,25 @@ impl<E: PairingEngine> IntersectionProof<E> {
         );
         Ok(())
     }
+
+    /// 与 `verify` 检查相同的配对等式，但只将其加入 `batch`
+    #[allow(clippy::too_many_arguments)]
+    fn add_checks(
+        &self,
+        lhs_acc: E::G1Affine,
+        rhs_acc: E::G2Affine,
+        h: E::G2Affine,
+        h_y_q: E::G2Affine,
+        h_beta: E::G2Affine,
+        h_delta: E::G2Affine,
+        h_x: E::G2Affine,
+        batch: &mut PairingBatch<E>,
+    ) {
+        batch.add_check(&[(lhs_acc, rhs_acc)], &[(self.g_x, h_y_q), (self.q_x_y, h)]);
+        batch.add_check(&[(self.g_x, h_beta)], &[(self.g_x_beta, h)]);
+        batch.add_check(&[(self.q_x_y, h_delta)], &[(self.q_x_y_delta, h)]);
+        batch.add_check(&[(self.g_x, h)], &[(self.l_x, h_x)]);
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -159,6 +179,11 @@ pub struct IntermediateProof<E: PairingEngine> {
 }
 
 impl<E: PairingEngine> IntermediateProof<E> {
+    /// 证明所针对的集合运算，由证明方给出，验证方须与查询要求的运算比较
+    pub fn op(&self) -> Op {
+        self.op
+    }
+
     pub fn verify(
         &self,
         lhs_acc: &AccValue<E>,
@@ -193,7 +218,92 @@ impl<E: PairingEngine> IntermediateProof<E> {
 
         verify_inner_proof_r.context("failed to verify the inner_proof_r.")?;
         verify_inner_proof_s.context("failed to verify the inner_proof_s.")?;
+        self.verify_result_g1(lhs_acc, rhs_acc, result_acc)?;
 
+        ensure!(
+            E::pairing(pk.g_gamma, result_acc.h_r_s) == E::pairing(self.result_acc_r_s_gamma, pk.h),
+            "e(g^{{gamma}}, R_{{r,s}}) != e(R_{{r,s,gamma}}, h)"
+        );
+        ensure!(
+            E::pairing(pk.g_gamma, result_acc.h_s_r) == E::pairing(self.result_acc_s_r_gamma, pk.h),
+            "e(g^{{gamma}}, R_{{s,r}}) != e(R_{{s,r,gamma}}, h)"
+        );
+
+        ensure!(
+            E::product_of_pairings(&[
+                (result_acc.g_r.into(), pk.h.into()),
+                (pk.g.into(), (-result_acc.h_r_s).into())
+            ]) == E::pairing(self.z_s_r, pk.h_s + (-pk.h)),
+            "e(R_{{r}}, h) * e(g, 1/R_{{r,s}}) != e(Z_{{s,r}}, h^{{s-1}})"
+        );
+        ensure!(
+            E::product_of_pairings(&[
+                (result_acc.g_s.into(), pk.h.into()),
+                (pk.g.into(), (-result_acc.h_s_r).into())
+            ]) == E::pairing(self.z_r_s, pk.h_r + (-pk.h)),
+            "e(R_{{s}}, h) * e(g, 1/R_{{s,r}}) != e(Z_{{r,s}}, h^{{r-1}})"
+        );
+
+        Ok(())
+    }
+
+    /// 与 `verify` 相同，但配对等式只加入 `batch`，由调用方最后调用 `PairingBatch::verify` 统一检查；
+    /// 不涉及配对的检查仍然立即进行
+    pub fn verify_batched(
+        &self,
+        lhs_acc: &AccValue<E>,
+        rhs_acc: &AccValue<E>,
+        result_acc: &AccValue<E>,
+        pk: &AccPublicKey<E>,
+        batch: &mut PairingBatch<E>,
+    ) -> Result<()> {
+        self.verify_result_g1(lhs_acc, rhs_acc, result_acc)?;
+        self.inner_proof_r.add_checks(
+            lhs_acc.g_s,
+            rhs_acc.h_r_s,
+            pk.h,
+            pk.h_s_q,
+            pk.h_beta,
+            pk.h_delta,
+            pk.h_r,
+            batch,
+        );
+        self.inner_proof_s.add_checks(
+            lhs_acc.g_r,
+            rhs_acc.h_s_r,
+            pk.h,
+            pk.h_r_q,
+            pk.h_beta,
+            pk.h_delta,
+            pk.h_s,
+            batch,
+        );
+        batch.add_check(
+            &[(pk.g_gamma, result_acc.h_r_s)],
+            &[(self.result_acc_r_s_gamma, pk.h)],
+        );
+        batch.add_check(
+            &[(pk.g_gamma, result_acc.h_s_r)],
+            &[(self.result_acc_s_r_gamma, pk.h)],
+        );
+        batch.add_check(
+            &[(result_acc.g_r, pk.h), (pk.g, -result_acc.h_r_s)],
+            &[(self.z_s_r, pk.h_s + (-pk.h))],
+        );
+        batch.add_check(
+            &[(result_acc.g_s, pk.h), (pk.g, -result_acc.h_s_r)],
+            &[(self.z_r_s, pk.h_r + (-pk.h))],
+        );
+        Ok(())
+    }
+
+    // 检查结果累加器的 G1 部分与两个内部证明给出的交集一致
+    fn verify_result_g1(
+        &self,
+        lhs_acc: &AccValue<E>,
+        rhs_acc: &AccValue<E>,
+        result_acc: &AccValue<E>,
+    ) -> Result<()> {
         match self.op {
             Op::Intersection => {
                 ensure!(
@@ -226,31 +336,6 @@ impl<E: PairingEngine> IntermediateProof<E> {
                 );
             }
         }
-
-        ensure!(
-            E::pairing(pk.g_gamma, result_acc.h_r_s) == E::pairing(self.result_acc_r_s_gamma, pk.h),
-            "e(g^{{gamma}}, R_{{r,s}}) != e(R_{{r,s,gamma}}, h)"
-        );
-        ensure!(
-            E::pairing(pk.g_gamma, result_acc.h_s_r) == E::pairing(self.result_acc_s_r_gamma, pk.h),
-            "e(g^{{gamma}}, R_{{s,r}}) != e(R_{{s,r,gamma}}, h)"
-        );
-
-        ensure!(
-            E::product_of_pairings(&[
-                (result_acc.g_r.into(), pk.h.into()),
-                (pk.g.into(), (-result_acc.h_r_s).into())
-            ]) == E::pairing(self.z_s_r, pk.h_s + (-pk.h)),
-            "e(R_{{r}}, h) * e(g, 1/R_{{r,s}}) != e(Z_{{s,r}}, h^{{s-1}})"
-        );
-        ensure!(
-            E::product_of_pairings(&[
-                (result_acc.g_s.into(), pk.h.into()),
-                (pk.g.into(), (-result_acc.h_s_r).into())
-            ]) == E::pairing(self.z_r_s, pk.h_r + (-pk.h)),
-            "e(R_{{s}}, h) * e(g, 1/R_{{s,r}}) != e(Z_{{r,s}}, h^{{r-1}})"
-        );
-
         Ok(())
     }
 }
@@ -263,15 +348,16 @@ pub fn compute_set_operation_intermediate<E: PairingEngine>(
     rhs_acc: &AccValue<E>,
     pk: &AccPublicKey<E>,
 ) -> (Set, AccValue<E>, IntermediateProof<E>) {
-    let intersection_set = lhs_set & rhs_set;
+    let witness = SetOpWitness::new(lhs_set, rhs_set);
+    let intersection_set = &witness.common;
     let lhs_poly: Poly<E::Fr> = poly_a(lhs_set, S);
     let rhs_poly: Poly<E::Fr> = poly_b(rhs_set, R, S, pk.q);
     let mut q_poly = &lhs_poly * &rhs_poly;
-    q_poly.remove_intersected_term(S, pk.q, &intersection_set);
+    q_poly.remove_intersected_term(S, pk.q, intersection_set);
     let (inner_proof_r, inner_proof_s) = rayon::join(
         || {
             IntersectionProof::<E>::new(
-                &intersection_set,
+                intersection_set,
                 &q_poly,
                 R,
                 S,
@@ -284,7 +370,7 @@ pub fn compute_set_operation_intermediate<E: PairingEngine>(
         },
         || {
             IntersectionProof::<E>::new(
-                &intersection_set,
+                intersection_set,
                 &q_poly,
                 S,
                 R,
@@ -298,9 +384,9 @@ pub fn compute_set_operation_intermediate<E: PairingEngine>(
     );
 
     let result_set = match op {
-        Op::Intersection => intersection_set,
-        Op::Union => lhs_set | rhs_set,
-        Op::Difference => lhs_set / &intersection_set,
+        Op::Intersection => witness.intersection(),
+        Op::Union => witness.union(),
+        Op::Difference => witness.difference(),
     };
     let result_acc = match op {
         Op::Intersection => AccValue::<E>::new(
@@ -396,6 +482,11 @@ pub struct FinalProof<E: PairingEngine> {
 }
 
 impl<E: PairingEngine> FinalProof<E> {
+    /// 证明所针对的集合运算，由证明方给出，验证方须与查询要求的运算比较
+    pub fn op(&self) -> Op {
+        self.op
+    }
+
     pub fn verify(
         &self,
         lhs_acc: &AccValue<E>,
@@ -414,6 +505,39 @@ impl<E: PairingEngine> FinalProof<E> {
                 pk.h_r,
             )
             .context("failed to verify the inner_proof.")?;
+        self.verify_result_set(lhs_acc, rhs_acc, result_set, pk)
+    }
+
+    /// 与 `verify` 相同，但配对等式只加入 `batch`
+    pub fn verify_batched(
+        &self,
+        lhs_acc: &AccValue<E>,
+        rhs_acc: &AccValue<E>,
+        result_set: &Set,
+        pk: &AccPublicKey<E>,
+        batch: &mut PairingBatch<E>,
+    ) -> Result<()> {
+        self.verify_result_set(lhs_acc, rhs_acc, result_set, pk)?;
+        self.inner_proof.add_checks(
+            lhs_acc.g_s,
+            rhs_acc.h_r_s,
+            pk.h,
+            pk.h_s_q,
+            pk.h_beta,
+            pk.h_delta,
+            pk.h_r,
+            batch,
+        );
+        Ok(())
+    }
+
+    fn verify_result_set(
+        &self,
+        lhs_acc: &AccValue<E>,
+        rhs_acc: &AccValue<E>,
+        result_set: &Set,
+        pk: &AccPublicKey<E>,
+    ) -> Result<()> {
         let result_acc = match self.op {
             Op::Intersection => self.inner_proof.g_x,
             Op::Union => lhs_acc.g_r + rhs_acc.g_r + (-self.inner_proof.g_x),
@@ -431,13 +555,14 @@ pub fn compute_set_operation_final<E: PairingEngine>(
     rhs_set: &Set,
     pk: &AccPublicKey<E>,
 ) -> (Set, FinalProof<E>) {
-    let intersection_set = lhs_set & rhs_set;
+    let witness = SetOpWitness::new(lhs_set, rhs_set);
+    let intersection_set = &witness.common;
     let lhs_poly: Poly<E::Fr> = poly_a(lhs_set, S);
     let rhs_poly: Poly<E::Fr> = poly_b(rhs_set, R, S, pk.q);
     let mut q_poly = &lhs_poly * &rhs_poly;
-    q_poly.remove_intersected_term(S, pk.q, &intersection_set);
+    q_poly.remove_intersected_term(S, pk.q, intersection_set);
     let inner_proof = IntersectionProof::new(
-        &intersection_set,
+        intersection_set,
         &q_poly,
         R,
         S,
@@ -449,9 +574,9 @@ pub fn compute_set_operation_final<E: PairingEngine>(
     );
     let proof = FinalProof { op, inner_proof };
     let result = match op {
-        Op::Intersection => intersection_set,
-        Op::Union => lhs_set | rhs_set,
-        Op::Difference => lhs_set / &intersection_set,
+        Op::Intersection => witness.intersection(),
+        Op::Union => witness.union(),
+        Op::Difference => witness.difference(),
     };
     (result, proof)
 }
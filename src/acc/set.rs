@@ -1,15 +1,107 @@
+use crate::digest::{blake2, Digest, Digestible};
 use core::{
+    convert::TryFrom,
     iter::FromIterator,
     num::NonZeroU16,
     ops::{BitAnd, BitOr, Deref, DerefMut, Div},
 };
-use serde::{Deserialize, Serialize};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::HashSet;
 
 /// A set of elements.
-#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+///
+/// 二进制编码（如 bincode）时按密度选择表示：元素较稠密时编码为位图，否则按升序列出元素；
+/// JSON 等可读格式中始终为升序的元素列表
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct Set(HashSet<NonZeroU16>);
 
+/// bincode 中枚举变体编号的字节数
+const VARIANT_TAG_LEN: usize = 4;
+/// bincode 中 `Vec` 长度前缀的字节数
+const SEQ_LEN_PREFIX: usize = 8;
+
+#[derive(Serialize, Deserialize)]
+enum SetRepr {
+    Sparse(Vec<NonZeroU16>),
+    /// 第 `i` 位为 1 表示元素 `start + i` 属于集合
+    Bitmap {
+        start: NonZeroU16,
+        bits: Vec<u8>,
+    },
+}
+
+/// 使用位图时的起始元素与位图字节数；集合为空或位图不比元素列表小时返回 `None`
+// `usize::div_ceil` 需要 Rust 1.73，crate 未声明 MSRV，这里手写向上取整
+#[allow(clippy::manual_div_ceil)]
+fn bitmap_layout(set: &HashSet<NonZeroU16>) -> Option<(NonZeroU16, usize)> {
+    let min = *set.iter().min()?;
+    let max = *set.iter().max()?;
+    let bit_len = usize::from(max.get() - min.get()) + 1;
+    let byte_len = (bit_len + 7) / 8;
+    let sparse_len = SEQ_LEN_PREFIX + 2 * set.len();
+    if 2 + SEQ_LEN_PREFIX + byte_len < sparse_len {
+        Some((min, byte_len))
+    } else {
+        None
+    }
+}
+
+impl Serialize for Set {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let layout = if serializer.is_human_readable() {
+            None
+        } else {
+            bitmap_layout(&self.0)
+        };
+        match layout {
+            Some((start, byte_len)) => {
+                let mut bits = vec![0u8; byte_len];
+                for v in self.iter() {
+                    let i = usize::from(v.get() - start.get());
+                    bits[i / 8] |= 1 << (i % 8);
+                }
+                SetRepr::Bitmap { start, bits }.serialize(serializer)
+            }
+            None => {
+                let mut elems: Vec<NonZeroU16> = self.iter().copied().collect();
+                elems.sort_unstable();
+                if serializer.is_human_readable() {
+                    elems.serialize(serializer)
+                } else {
+                    SetRepr::Sparse(elems).serialize(serializer)
+                }
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Set {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            return Ok(Vec::<NonZeroU16>::deserialize(deserializer)?
+                .into_iter()
+                .collect());
+        }
+        match SetRepr::deserialize(deserializer)? {
+            SetRepr::Sparse(elems) => Ok(elems.into_iter().collect()),
+            SetRepr::Bitmap { start, bits } => {
+                let mut set = Set::new();
+                for (i, byte) in bits.iter().enumerate() {
+                    for j in (0..8).filter(|j| byte & (1 << j) != 0) {
+                        let v = u16::try_from(i * 8 + j)
+                            .ok()
+                            .and_then(|offset| start.get().checked_add(offset))
+                            .and_then(NonZeroU16::new)
+                            .ok_or_else(|| D::Error::custom("set bitmap element out of range"))?;
+                        set.insert(v);
+                    }
+                }
+                Ok(set)
+            }
+        }
+    }
+}
+
 impl Set {
     pub fn new() -> Self {
         Self::default()
@@ -19,6 +111,16 @@ impl Set {
         Self(HashSet::with_capacity(cap))
     }
 
+    /// 预先分配 `cap` 个元素的空间后从迭代器批量构造集合，避免逐个插入时反复扩容
+    pub fn from_iter_with_capacity<T, I: IntoIterator<Item = T>>(iter: I, cap: usize) -> Self
+    where
+        Self: Extend<T>,
+    {
+        let mut set = Self::with_capacity(cap);
+        set.extend(iter);
+        set
+    }
+
     pub fn from_single_element(elm: NonZeroU16) -> Self {
         let mut set = Set::with_capacity(1);
         set.insert(elm);
@@ -57,6 +159,96 @@ impl Set {
     pub fn is_subset_of(&self, rhs: &Self) -> bool {
         self.iter().all(|v| rhs.contains(v))
     }
+
+    /// 相对于全集 `universe` 的补集，即 `universe` 中不属于 `self` 的元素。`self` 须为 `universe` 的子集
+    #[must_use]
+    pub fn complement_within(&self, universe: &Self) -> Self {
+        debug_assert!(
+            self.is_subset_of(universe),
+            "complement_within: set is not a subset of the universe"
+        );
+        universe.set_difference(self)
+    }
+
+    /// 集合经 bincode 编码后的字节数，与实际选择的表示（位图或元素列表）一致
+    pub fn encoded_size_hint(&self) -> usize {
+        let repr_len = match bitmap_layout(&self.0) {
+            Some((_, byte_len)) => 2 + SEQ_LEN_PREFIX + byte_len,
+            None => SEQ_LEN_PREFIX + 2 * self.len(),
+        };
+        VARIANT_TAG_LEN + repr_len
+    }
+
+    /// 求并集，同时返回记录元素来源的见证
+    pub fn union_with_witness(&self, rhs: &Self) -> (Self, SetOpWitness) {
+        let witness = SetOpWitness::new(self, rhs);
+        (witness.union(), witness)
+    }
+
+    /// 求交集，同时返回记录元素来源的见证
+    pub fn intersection_with_witness(&self, rhs: &Self) -> (Self, SetOpWitness) {
+        let witness = SetOpWitness::new(self, rhs);
+        (witness.intersection(), witness)
+    }
+
+    /// 求差集，同时返回记录元素来源的见证
+    pub fn difference_with_witness(&self, rhs: &Self) -> (Self, SetOpWitness) {
+        let witness = SetOpWitness::new(self, rhs);
+        (witness.difference(), witness)
+    }
+}
+
+/// 集合运算的见证：把两个操作数的元素划分为互不相交的三部分——仅属于左操作数、仅属于右操作数、两者共有。
+/// 证明方构造 `IntermediateProof` / `FinalProof` 时直接从见证取交集与运算结果，无需重新计算元素归属。
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SetOpWitness {
+    pub lhs_only: Set,
+    pub rhs_only: Set,
+    pub common: Set,
+}
+
+impl SetOpWitness {
+    pub fn new(lhs: &Set, rhs: &Set) -> Self {
+        let mut lhs_only = Set::with_capacity(lhs.len());
+        let mut common = Set::new();
+        for &v in lhs.iter() {
+            if rhs.contains(&v) {
+                common.insert(v);
+            } else {
+                lhs_only.insert(v);
+            }
+        }
+        let rhs_only = rhs
+            .iter()
+            .filter(|v| !common.contains(v))
+            .copied()
+            .collect();
+        Self {
+            lhs_only,
+            rhs_only,
+            common,
+        }
+    }
+
+    /// 由见证重建并集
+    pub fn union(&self) -> Set {
+        let mut set =
+            Set::with_capacity(self.lhs_only.len() + self.rhs_only.len() + self.common.len());
+        set.extend(self.lhs_only.iter().copied());
+        set.extend(self.rhs_only.iter().copied());
+        set.extend(self.common.iter().copied());
+        set
+    }
+
+    /// 由见证重建交集
+    pub fn intersection(&self) -> Set {
+        self.common.clone()
+    }
+
+    /// 由见证重建差集（左操作数减去右操作数）
+    pub fn difference(&self) -> Set {
+        self.lhs_only.clone()
+    }
 }
 
 pub fn in_place_set_intersection(lhs: Set, rhs: Set) -> Set {
@@ -93,6 +285,23 @@ pub fn in_place_set_difference(mut lhs: Set, rhs: &Set) -> Set {
     lhs
 }
 
+/// 集合摘要的域分隔标签（blake2b personalization，不超过 16 字节），
+/// 使集合的摘要不会与相同字节序列的其他摘要相同
+const SET_DOMAIN: &[u8] = b"vchain+/set";
+
+/// 按元素升序计算摘要，与集合内部的迭代顺序无关；可作为以集合内容为键的缓存的键
+impl Digestible for Set {
+    fn to_digest(&self) -> Digest {
+        let mut elems: Vec<u16> = self.iter().map(|v| v.get()).collect();
+        elems.sort_unstable();
+        let mut state = blake2().personal(SET_DOMAIN).to_state();
+        for v in elems {
+            state.update(&v.to_le_bytes());
+        }
+        Digest::from(state.finalize())
+    }
+}
+
 impl Deref for Set {
     type Target = HashSet<NonZeroU16>;
 
@@ -129,9 +338,24 @@ impl FromIterator<u16> for Set {
 
 impl FromIterator<u64> for Set {
     fn from_iter<T: IntoIterator<Item = u64>>(iter: T) -> Self {
-        iter.into_iter()
-            .map(|v| NonZeroU16::new(v as u16).expect("set element cannot be zero."))
-            .collect()
+        let iter = iter.into_iter();
+        let cap = iter.size_hint().0;
+        Self::from_iter_with_capacity(iter, cap)
+    }
+}
+
+impl Extend<NonZeroU16> for Set {
+    fn extend<T: IntoIterator<Item = NonZeroU16>>(&mut self, iter: T) {
+        self.0.extend(iter);
+    }
+}
+
+impl Extend<u64> for Set {
+    fn extend<T: IntoIterator<Item = u64>>(&mut self, iter: T) {
+        self.0.extend(
+            iter.into_iter()
+                .map(|v| NonZeroU16::new(v as u16).expect("set element cannot be zero.")),
+        );
     }
 }
 
@@ -206,7 +430,20 @@ macro_rules! set {
 mod tests {
     use std::iter::FromIterator;
 
-    use crate::acc::Set;
+    use crate::{acc::Set, digest::Digestible};
+
+    #[test]
+    fn test_to_digest() {
+        let a = set! {1, 2, 3};
+        let mut b = Set::new();
+        for v in [3u16, 1, 2] {
+            b.insert(core::num::NonZeroU16::new(v).unwrap());
+        }
+        assert_eq!(a.to_digest(), b.to_digest());
+        assert_ne!(a.to_digest(), set! {1, 2}.to_digest());
+        assert_ne!(a.to_digest(), set! {1, 2, 4}.to_digest());
+        assert_ne!(Set::new().to_digest(), set! {1}.to_digest());
+    }
 
     #[test]
     fn test_intersection() {
@@ -247,6 +484,35 @@ mod tests {
         assert_eq!(actual2, expect);
     }
 
+    #[test]
+    fn test_witness() {
+        let a = set! {1, 2, 3, 6};
+        let b = set! {2, 3, 4, 5};
+
+        let (union, w) = a.union_with_witness(&b);
+        assert_eq!(w.lhs_only, set! {1, 6});
+        assert_eq!(w.rhs_only, set! {4, 5});
+        assert_eq!(w.common, set! {2, 3});
+        assert!(w.lhs_only.is_disjoint(&w.rhs_only));
+        assert!(w.lhs_only.is_disjoint(&w.common));
+        assert!(w.rhs_only.is_disjoint(&w.common));
+        assert_eq!(union, &a | &b);
+        assert_eq!(w.union(), &a | &b);
+
+        let (inter, w2) = a.intersection_with_witness(&b);
+        assert_eq!(w2, w);
+        assert_eq!(inter, &a & &b);
+
+        let (diff, w3) = a.difference_with_witness(&b);
+        assert_eq!(w3, w);
+        assert_eq!(diff, &a / &b);
+
+        let (union, w) = a.union_with_witness(&Set::new());
+        assert_eq!(union, a);
+        assert_eq!(w.lhs_only, a);
+        assert!(w.rhs_only.is_empty() && w.common.is_empty());
+    }
+
     #[test]
     fn test_is_subset_of() {
         let a = set! {1, 2, 3};
@@ -259,6 +525,21 @@ mod tests {
         assert!(c.is_subset_of(&b));
     }
 
+    #[test]
+    fn test_complement_within() {
+        let universe = set! {1, 2, 3, 4, 5};
+        assert_eq!(set! {2, 4}.complement_within(&universe), set! {1, 3, 5});
+        assert_eq!(Set::new().complement_within(&universe), universe);
+        assert!(universe.complement_within(&universe).is_empty());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "not a subset of the universe")]
+    fn test_complement_within_not_subset() {
+        let _ = set! {1, 6}.complement_within(&set! {1, 2, 3});
+    }
+
     #[test]
     fn test_from_iter() {
         let mut v = Vec::<u16>::new();
@@ -268,4 +549,49 @@ mod tests {
         let b = Set::from_iter(v.into_iter());
         assert_eq!(b, set! {1,2,3})
     }
+
+    #[test]
+    fn test_from_iter_with_capacity() {
+        let mut expect = Set::new();
+        for v in 1..10000u64 {
+            expect.insert(core::num::NonZeroU16::new(v as u16).unwrap());
+        }
+        assert_eq!(Set::from_iter(1..10000u64), expect);
+        assert_eq!(Set::from_iter_with_capacity(1..10000u64, 0), expect);
+        assert_eq!(Set::from_iter_with_capacity(1..10000u64, 16), expect);
+        assert_eq!(Set::from_iter_with_capacity(1..10000u64, 100_000), expect);
+
+        let mut set = Set::from_iter_with_capacity(1..5000u64, 5000);
+        set.extend(5000..10000u64);
+        assert_eq!(set, expect);
+        set.extend(1..10u64);
+        assert_eq!(set, expect);
+    }
+
+    #[test]
+    fn test_serde_repr() {
+        // 连续 id 组成的稠密集合编码为位图
+        let dense: Set = (1..=1000u16).collect();
+        let bytes = bincode::serialize(&dense).unwrap();
+        assert_eq!(bytes.len(), dense.encoded_size_hint());
+        assert_eq!(bytes.len(), 4 + 2 + 8 + 125);
+        assert!(bytes.len() * 10 < 8 + 2 * dense.len());
+        assert_eq!(bincode::deserialize::<Set>(&bytes).unwrap(), dense);
+
+        // 稀疏集合仍按元素列表编码
+        let sparse = set! {3, 1000, 40000, 65535};
+        let bytes = bincode::serialize(&sparse).unwrap();
+        assert_eq!(bytes.len(), sparse.encoded_size_hint());
+        assert_eq!(bytes.len(), 4 + 8 + 2 * 4);
+        assert_eq!(bincode::deserialize::<Set>(&bytes).unwrap(), sparse);
+
+        let empty = Set::new();
+        let bytes = bincode::serialize(&empty).unwrap();
+        assert_eq!(bytes.len(), empty.encoded_size_hint());
+        assert_eq!(bincode::deserialize::<Set>(&bytes).unwrap(), empty);
+
+        let json = serde_json::to_string(&set! {3, 1, 2}).unwrap();
+        assert_eq!(json, "[1,2,3]");
+        assert_eq!(serde_json::from_str::<Set>(&json).unwrap(), set! {1, 2, 3});
+    }
 }
@@ -11,12 +11,12 @@ use structopt::StructOpt;
 use vchain_plus::utils::{init_tracing_subscriber, KeyPair};
 use vchain_plus::{
     chain::{
-        block::{build::build_block, Height},
+        block::{build::build_block, BlockContent, Height},
         object::Object,
         traits::WriteInterface,
         Parameter,
     },
-    digest::{Digest, Digestible},
+    digest::Digestible,
     utils::{load_raw_obj_from_file, Time},
     SimChain,
 };
@@ -79,7 +79,7 @@ fn build_chain(
     fs::create_dir_all(db_path)?;
     let mut chain = SimChain::create(db_path, param.clone())?;
     chain.set_parameter(param)?;
-    let mut prev_hash = Digest::zero();
+    let mut prev_hash = BlockContent::genesis(param).0.to_digest();
     let raw_objs: BTreeMap<Height, Vec<Object<u32>>> = load_raw_obj_from_file(data_path)?;
     let timer = howlong::ProcessCPUTimer::new();
     let pk = KeyPair::load(key_path)?.pk;
@@ -110,13 +110,13 @@ fn build_chain(
 fn main() -> Result<()> {
     init_tracing_subscriber("info")?;
     let opts = Opt::from_args();
-    let param = Parameter {
-        time_win_sizes: opts.time_win_sizes,
-        id_tree_fanout: opts.id_fanout,
-        max_id_num: opts.max_id,
-        bplus_tree_fanout: opts.bplus_fanout,
-        num_dim: opts.dim,
-    };
+    let param = Parameter::builder()
+        .time_win_sizes(opts.time_win_sizes)
+        .id_tree_fanout(opts.id_fanout)
+        .max_id_num(opts.max_id)
+        .bplus_tree_fanout(opts.bplus_fanout)
+        .num_dim(opts.dim)
+        .build()?;
     build_chain(
         &opts.input,
         &opts.key_path,
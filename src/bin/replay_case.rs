@@ -0,0 +1,19 @@
+use anyhow::Result;
+use std::path::PathBuf;
+use structopt::StructOpt;
+use vchain_plus::chain::verify::case::VerifyCase;
+
+#[derive(StructOpt, Debug)]
+struct Opt {
+    /// Verify case path, written by `VerifyCase::capture`
+    #[structopt(parse(from_os_str))]
+    case: PathBuf,
+}
+
+fn main() -> Result<()> {
+    let opt = Opt::from_args();
+    let case = VerifyCase::<u32>::load(&opt.case)?;
+    let info = case.run()?;
+    println!("{}", serde_json::to_string_pretty(&info)?);
+    Ok(())
+}
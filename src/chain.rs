@@ -1,12 +1,15 @@
+use anyhow::{ensure, Result};
 use serde::{Deserialize, Serialize};
 
 pub mod block;
 pub mod bplus_tree;
 pub mod hash;
 pub mod id_tree;
+pub mod mmr;
 pub mod object;
 pub mod query;
 pub mod range;
+pub mod schema;
 pub mod traits;
 pub mod trie_tree;
 pub mod verify;
@@ -24,5 +27,122 @@ pub struct Parameter {
     pub num_dim: u8,
 }
 
+impl Parameter {
+    /// 与单元测试数据规模相当的参数：两个时间窗口、扇出 4，每个窗口最多 32 个对象
+    pub fn default_for_testing() -> Self {
+        Self {
+            time_win_sizes: vec![2, 4],
+            id_tree_fanout: 4,
+            max_id_num: 32,
+            bplus_tree_fanout: 4,
+            num_dim: 2,
+        }
+    }
+
+    /// 推荐的生产环境参数：时间窗口 2 至 32、扇出 4（README 中的建议值），
+    /// MaxID 为 4095，生成公钥时的 q 至少取 4096；`num_dim` 需按数据集的数值维度修改
+    pub fn recommended() -> Self {
+        Self {
+            time_win_sizes: vec![2, 4, 8, 16, 32],
+            id_tree_fanout: 4,
+            max_id_num: 4095,
+            bplus_tree_fanout: 4,
+            num_dim: 1,
+        }
+    }
+
+    /// 以全零的参数开始构造，`build` 时检查参数是否合法
+    pub fn builder() -> ParameterBuilder {
+        ParameterBuilder::default()
+    }
+
+    /// 检查参数是否合法：时间窗口非空、非零且严格递增，扇出至少为 2，
+    /// MaxID 与数值维度至少为 1
+    pub fn validate(&self) -> Result<()> {
+        ensure!(
+            !self.time_win_sizes.is_empty(),
+            "At least one time window size is required"
+        );
+        ensure!(
+            self.time_win_sizes[0] > 0,
+            "Time window sizes must be positive"
+        );
+        ensure!(
+            self.time_win_sizes.windows(2).all(|w| w[0] < w[1]),
+            "Time window sizes must be strictly ascending: {:?}",
+            self.time_win_sizes
+        );
+        ensure!(
+            self.id_tree_fanout >= 2,
+            "Id tree fanout must be at least 2, got {}",
+            self.id_tree_fanout
+        );
+        ensure!(
+            self.bplus_tree_fanout >= 2,
+            "B+ tree fanout must be at least 2, got {}",
+            self.bplus_tree_fanout
+        );
+        ensure!(self.max_id_num > 0, "Max id num must be positive");
+        ensure!(self.num_dim > 0, "Number of dimensions must be positive");
+        Ok(())
+    }
+
+    /// 由参数得出的模式：维度 `0..num_dim`，关键字总是建立 trie 索引
+    pub fn schema(&self) -> schema::Schema {
+        schema::Schema {
+            dims: (0..self.num_dim)
+                .map(|dim| schema::DimInfo { dim })
+                .collect(),
+            keywords_indexed: true,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ParameterBuilder {
+    param: Parameter,
+}
+
+impl ParameterBuilder {
+    pub fn time_win_sizes(mut self, time_win_sizes: Vec<u16>) -> Self {
+        self.param.time_win_sizes = time_win_sizes;
+        self
+    }
+
+    pub fn id_tree_fanout(mut self, id_tree_fanout: u8) -> Self {
+        self.param.id_tree_fanout = id_tree_fanout;
+        self
+    }
+
+    pub fn max_id_num(mut self, max_id_num: u16) -> Self {
+        self.param.max_id_num = max_id_num;
+        self
+    }
+
+    pub fn bplus_tree_fanout(mut self, bplus_tree_fanout: u8) -> Self {
+        self.param.bplus_tree_fanout = bplus_tree_fanout;
+        self
+    }
+
+    pub fn num_dim(mut self, num_dim: u8) -> Self {
+        self.param.num_dim = num_dim;
+        self
+    }
+
+    pub fn build(self) -> Result<Parameter> {
+        self.param.validate()?;
+        Ok(self.param)
+    }
+}
+
+impl From<Parameter> for ParameterBuilder {
+    fn from(param: Parameter) -> Self {
+        Self { param }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod testutil;
+
 #[cfg(test)]
 pub(crate) mod tests;
@@ -4,14 +4,38 @@ pub mod build;
 pub mod hash;
 
 use crate::{
-    chain::id_tree::IdTreeRoot,
+    acc::{AccPublicKey, AccValue, Set},
+    chain::{bplus_tree::BPlusTreeRoot, id_tree::IdTreeRoot, trie_tree::TrieRoot, Parameter},
     digest::{Digest, Digestible},
+    utils::{binary_decode, binary_encode},
 };
-use block_ads::BlockMultiADS;
-use block_ads_root::BlockADSComponents;
-use hash::block_head_hash;
+use anyhow::{bail, ensure, Context, Result};
+use block_ads::{BlockADS, BlockMultiADS};
+use block_ads_root::{BlockADSComponents, ComponentKind};
+use hash::{block_head_hash, height_hash, obj_id_nums_hash, obj_root_hash};
 use serde::{Deserialize, Serialize};
-use std::num::NonZeroU16;
+use std::{
+    io::{Read, Write},
+    num::NonZeroU16,
+};
+
+/// 区块快照文件的标识
+const SNAPSHOT_MAGIC: [u8; 4] = *b"VCBS";
+/// 区块快照格式的版本
+const SNAPSHOT_VERSION: u16 = 1;
+
+/// 区块快照的文件头，其后紧跟 `binary_encode` 编码的 `BlockContent`
+#[derive(Debug, Serialize, Deserialize)]
+struct SnapshotHeader {
+    magic: [u8; 4],
+    version: u16,
+    /// `ads_components.compute_root()`，导入时重新计算并比较
+    expected_root: Digest,
+    /// 编码后区块内容的校验和
+    checksum: Digest,
+    /// 编码后区块内容的字节数
+    len: u64,
+}
 /// 区块头（轻节点存储）
 ///
 /// BlockHead 包含区块的关键元数据和承诺根，轻节点通过同步区块头
@@ -41,6 +65,24 @@ use std::num::NonZeroU16;
 )]
 pub struct Height(pub u32);
 
+impl Height {
+    /// 下一个区块的高度，已是 `u32::MAX` 时返回 `None`
+    pub fn next(self) -> Option<Height> {
+        self.0.checked_add(1).map(Height)
+    }
+
+    /// 上一个区块的高度，高度 0 没有前驱，返回 `None`
+    pub fn prev(self) -> Option<Height> {
+        self.0.checked_sub(1).map(Height)
+    }
+}
+
+impl Digestible for Height {
+    fn to_digest(&self) -> Digest {
+        height_hash(*self)
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
 pub struct BlockContent {
     pub blk_height: Height,
@@ -66,6 +108,32 @@ impl BlockContent {
         }
     }
 
+    /// 创世区块：高度 0、`prev_hash` 为 `Digest::default()`，不含对象，
+    /// 参数中的每个时间窗口对应一个空的 ADS。返回的区块头承诺了区块内容的 ads_components
+    pub fn genesis(param: &Parameter) -> (BlockHead, BlockContent) {
+        let mut content = Self::new(Height(0), Digest::default());
+        for &k in &param.time_win_sizes {
+            let ads = BlockADS {
+                bplus_tree_roots: vec![BPlusTreeRoot::default(); param.num_dim as usize],
+                trie_root: TrieRoot::default(),
+            };
+            // 重复的窗口大小对应同一个空 ADS，忽略重复即可
+            let _ = content.ads.add_window(k, ads);
+        }
+        content.set_ads_components(BlockADSComponents::new(
+            obj_id_nums_hash(content.obj_id_nums.iter()),
+            content.id_tree_root.to_digest(),
+            content.ads.to_digest(),
+        ));
+        let head = BlockHead {
+            blk_height: content.blk_height,
+            prev_hash: content.prev_hash,
+            ads_root: content.ads_components.compute_root(),
+            obj_root_hash: obj_root_hash(content.obj_hashes.iter()),
+        };
+        (head, content)
+    }
+
     pub fn set_id_tree_root(&mut self, new_id_tree_root: IdTreeRoot) {
         self.id_tree_root = new_id_tree_root;
     }
@@ -82,10 +150,46 @@ impl BlockContent {
         self.obj_id_nums = new_id_nums;
     }
 
+    /// 检查后设置对象 id：id 个数与每个 id 都不能超过参数中的 `max_id_num`，
+    /// 否则 id 会回绕并覆盖同一区块中的对象，直到验证时才会暴露
+    pub fn try_set_obj_id_nums(
+        &mut self,
+        new_id_nums: Vec<NonZeroU16>,
+        max_id_num: u16,
+    ) -> Result<()> {
+        ensure!(
+            new_id_nums.len() <= max_id_num as usize,
+            "Block {} has {} objects, exceeding max_id_num {}",
+            self.blk_height,
+            new_id_nums.len(),
+            max_id_num
+        );
+        if let Some(id) = new_id_nums.iter().find(|id| id.get() > max_id_num) {
+            bail!(
+                "Object id {} in block {} exceeds max_id_num {}",
+                id,
+                self.blk_height,
+                max_id_num
+            );
+        }
+        self.obj_id_nums = new_id_nums;
+        Ok(())
+    }
+
     pub fn read_obj_id_nums(&self) -> Vec<NonZeroU16> {
         self.obj_id_nums.clone()
     }
 
+    /// 区块中对象 id 组成的集合，其承诺为 `ads_components.id_set_root_hash`
+    pub fn id_set(&self) -> Set {
+        Set::from_iter_with_capacity(self.obj_id_nums.iter().copied(), self.obj_id_nums.len())
+    }
+
+    /// 区块对象 id 集合的累加器值，用于验证以全集为被减数的差集（即补集）
+    pub fn id_set_acc(&self, pk: &AccPublicKey) -> AccValue {
+        AccValue::from_set(&self.id_set(), pk)
+    }
+
     /// 【创新点1】设置 BlockADSComponents
     pub fn set_ads_components(&mut self, components: BlockADSComponents) {
         self.ads_components = components;
@@ -95,6 +199,122 @@ impl BlockContent {
     pub fn get_ads_components(&self) -> &BlockADSComponents {
         &self.ads_components
     }
+
+    /// 重新索引区块时只更新一个组件，返回重新计算的统一承诺根，
+    /// 调用方需用它更新区块头中的 `ads_root`
+    pub fn update_component(&mut self, which: ComponentKind, new_hash: Digest) -> Digest {
+        self.ads_components.set_component(which, new_hash);
+        self.ads_components.compute_root()
+    }
+
+    /// 导出区块快照，供归档节点迁移到其他节点。快照只包含区块内容本身（各 ADS 的根与组件），
+    /// 树节点与对象仍需按 id 另行迁移
+    pub fn export(&self, mut w: impl Write) -> Result<()> {
+        let payload = binary_encode(self)?;
+        let header = SnapshotHeader {
+            magic: SNAPSHOT_MAGIC,
+            version: SNAPSHOT_VERSION,
+            expected_root: self.ads_components.compute_root(),
+            checksum: payload.to_digest(),
+            len: payload.len() as u64,
+        };
+        bincode::serialize_into(&mut w, &header)?;
+        w.write_all(&payload)?;
+        w.flush()?;
+        Ok(())
+    }
+
+    /// 导入 `export` 写出的区块快照：检查校验和，重新计算 `ads_components` 的统一承诺根
+    /// 并与文件头中的根比较，同时检查各组件与区块内容一致
+    pub fn import(mut r: impl Read) -> Result<Self> {
+        let mut data = Vec::new();
+        r.read_to_end(&mut data)?;
+        let mut reader = &data[..];
+        let header: SnapshotHeader =
+            bincode::deserialize_from(&mut reader).context("Invalid block snapshot header")?;
+        ensure!(header.magic == SNAPSHOT_MAGIC, "Not a block snapshot");
+        ensure!(
+            header.version == SNAPSHOT_VERSION,
+            "Unsupported block snapshot version {}",
+            header.version
+        );
+        ensure!(
+            reader.len() as u64 == header.len,
+            "Block snapshot truncated: expect {} bytes, got {}",
+            header.len,
+            reader.len()
+        );
+        ensure!(
+            reader.to_digest().ct_eq(&header.checksum),
+            "Block snapshot checksum mismatched"
+        );
+        let content: Self = binary_decode(reader)?;
+        let components = &content.ads_components;
+        ensure!(
+            components.compute_root().ct_eq(&header.expected_root),
+            "Block snapshot ads root mismatched for block {}",
+            content.blk_height
+        );
+        content.validate_shape()?;
+        ensure!(
+            components.multi_ads_hash.ct_eq(&content.ads.to_digest()),
+            "Block snapshot ads components do not match the content of block {}",
+            content.blk_height
+        );
+        Ok(content)
+    }
+
+    /// 对照区块头检查区块内容：高度一致，各 ADS 组件与区块内容一致，
+    /// 且组件的统一承诺根与区块头中的 `ads_root` 一致
+    pub fn verify_ads_root_against(&self, blk_head: &BlockHead) -> Result<()> {
+        ensure!(
+            self.blk_height == blk_head.blk_height,
+            "Block content at height {} does not belong to the block head at height {}",
+            self.blk_height,
+            blk_head.blk_height
+        );
+        self.validate_shape()?;
+        ensure!(
+            self.ads_components.multi_ads_hash == self.ads.to_digest(),
+            "Multi ads of block {} does not match its ads components",
+            self.blk_height
+        );
+        ensure!(
+            blk_head.verify_ads_components(&self.ads_components),
+            "Ads components of block {} do not match the ads_root of its head",
+            self.blk_height
+        );
+        Ok(())
+    }
+
+    /// 检查区块内容的形状：每个对象恰有一个哈希与一个互不相同的 id，
+    /// 且 `ads_components` 中的 id 集合与 id 树组件与区块内容一致。
+    /// 用于导入或从存储加载区块后，避免对象 Merkle 树与 id 计数错位
+    pub fn validate_shape(&self) -> Result<()> {
+        ensure!(
+            self.obj_hashes.len() == self.obj_id_nums.len(),
+            "Block {} has {} object hashes but {} object ids",
+            self.blk_height,
+            self.obj_hashes.len(),
+            self.obj_id_nums.len()
+        );
+        ensure!(
+            self.id_set().len() == self.obj_id_nums.len(),
+            "Block {} has duplicated object ids",
+            self.blk_height
+        );
+        ensure!(
+            self.ads_components.id_set_root_hash == obj_id_nums_hash(self.obj_id_nums.iter()),
+            "Id set of block {} does not match its ads components",
+            self.blk_height
+        );
+        ensure!(
+            self.ads_components.id_tree_root_hash == self.id_tree_root.to_digest(),
+            "Id tree root of block {} does not match its ads components",
+            self.blk_height
+        );
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
@@ -128,14 +348,24 @@ impl BlockHead {
         self.obj_root_hash = new_hash;
     }
 
+    /// 是否为 `BlockContent::genesis` 构造的创世区块头：高度 0 且 `prev_hash` 为默认值
+    pub fn is_genesis(&self) -> bool {
+        self.blk_height == Height(0) && self.prev_hash == Digest::default()
+    }
+
     /// 【创新点1】获取 BlockADSRoot（统一承诺）
     pub fn get_ads_root(&self) -> Digest {
         self.ads_root
     }
 
+    /// 【创新点1】验证全节点提供的 BlockADSComponents 能否还原区块头中的统一承诺
+    pub fn verify_ads_components(&self, components: &BlockADSComponents) -> bool {
+        components.compute_root().ct_eq(&self.ads_root)
+    }
+
     /// 【兼容性】保留原方法名，内部调用新方法
     #[deprecated(note = "请使用 get_ads_root() 代替")]
     pub(crate) fn get_ads_root_hash(&self) -> Digest {
         self.ads_root
     }
-}
\ No newline at end of file
+}
@@ -7,14 +7,14 @@ use crate::{
     },
     digest::Digest,
 };
-use anyhow::{Context, Result};
+use anyhow::{ensure, Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
 pub struct BlockADS {
-    pub bplus_tree_roots: Vec<BPlusTreeRoot>,// B+树根（多维度）
-    pub trie_root: TrieRoot,// Trie树根
+    pub bplus_tree_roots: Vec<BPlusTreeRoot>, // B+树根（多维度）
+    pub trie_root: TrieRoot,                  // Trie树根
 }
 
 impl Digestible for BlockADS {
@@ -34,7 +34,7 @@ impl BlockADS {
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
-pub struct BlockMultiADS(BTreeMap<u16, BlockADS>);// 按时间窗口组织
+pub struct BlockMultiADS(BTreeMap<u16, BlockADS>); // 按时间窗口组织
 
 impl Digestible for BlockMultiADS {
     fn to_digest(&self) -> Digest {
@@ -43,10 +43,37 @@ impl Digestible for BlockMultiADS {
 }
 
 impl BlockMultiADS {
+    /// 加入一个新的时间窗口的 ADS，各窗口的 B+ 树与 trie 互不影响。
+    /// 窗口以 `BTreeMap` 保存，无论加入顺序如何，`multi_ads_hash` 总是按窗口大小升序折叠
+    pub fn add_window(&mut self, window: u16, ads: BlockADS) -> Result<()> {
+        ensure!(
+            !self.0.contains_key(&window),
+            "Time window {} already exists in the block ADS",
+            window
+        );
+        self.0.insert(window, ads);
+        Ok(())
+    }
+
+    /// 当前的 multi_ads_hash，即 `BlockADSComponents` 中的 multi_ads_hash 组件
+    pub fn multi_ads_hash(&self) -> Digest {
+        self.to_digest()
+    }
+
     pub(crate) fn read_adses(&self) -> &BTreeMap<u16, BlockADS> {
         &self.0
     }
 
+    /// 时间窗口 `window` 中第 `dim` 维 B+ 树根的哈希，不存在时返回 `None`；
+    /// 用于不经过查询直接比较本地计算的 B+ 树根与区块承诺的 B+ 树根
+    pub fn bplus_root(&self, window: u16, dim: u8) -> Option<Digest> {
+        self.0
+            .get(&window)?
+            .bplus_tree_roots
+            .get(dim as usize)
+            .map(|root| root.bplus_tree_root_hash)
+    }
+
     pub(crate) fn read_bplus_root(&self, time_win: u16, dim: u8) -> Result<BPlusTreeRoot> {
         let blk_ads = self.0.get(&time_win);
         let ads =
@@ -8,6 +8,18 @@
 
 use crate::digest::{blake2, Digest, Digestible};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// `compute_root` 的域标签，区分 BlockADSRoot 与其他同样由若干摘要拼接得到的哈希
+const BLOCK_ADS_ROOT_DOMAIN: &[u8] = b"vchain-plus/BlockADSRoot/v1";
+
+/// `BlockADSComponents` 中的定长组件，重新索引时用于指定需要更新的组件
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum ComponentKind {
+    IdSetRoot,
+    IdTreeRoot,
+    MultiAds,
+}
 
 /// BlockADSRoot 的组成部分
 ///
@@ -26,6 +38,11 @@ pub struct BlockADSComponents {
     /// BlockMultiADS 的哈希（包含各时间窗口的 B+树根和 Trie 根）
     /// 用于范围查询和关键词查询的验证
     pub multi_ads_hash: Digest,
+
+    /// 扩展组件：新增的索引或摘要以键值对的形式加入承诺，默认为空。
+    /// 目前验证时由 VO 重建的组件不含扩展项，使用前需同时在 `MerkleProof` 中携带
+    #[serde(default)]
+    pub extra: BTreeMap<String, Vec<u8>>,
 }
 
 impl BlockADSComponents {
@@ -39,22 +56,74 @@ impl BlockADSComponents {
             id_set_root_hash,
             id_tree_root_hash,
             multi_ads_hash,
+            extra: BTreeMap::new(),
         }
     }
 
+    /// 只替换一个组件的哈希，其余组件（包括扩展组件）保持不变
+    pub fn set_component(&mut self, which: ComponentKind, new_hash: Digest) {
+        let field = match which {
+            ComponentKind::IdSetRoot => &mut self.id_set_root_hash,
+            ComponentKind::IdTreeRoot => &mut self.id_tree_root_hash,
+            ComponentKind::MultiAds => &mut self.multi_ads_hash,
+        };
+        *field = new_hash;
+    }
+
+    /// 列出与 `other` 不同的定长组件，顺序与 `compute_root` 中的拼接顺序一致。
+    /// 扩展组件不在比较之列，需要时直接比较 `extra`
+    pub fn diff(&self, other: &Self) -> Vec<ComponentKind> {
+        [
+            (
+                ComponentKind::IdSetRoot,
+                self.id_set_root_hash,
+                other.id_set_root_hash,
+            ),
+            (
+                ComponentKind::IdTreeRoot,
+                self.id_tree_root_hash,
+                other.id_tree_root_hash,
+            ),
+            (
+                ComponentKind::MultiAds,
+                self.multi_ads_hash,
+                other.multi_ads_hash,
+            ),
+        ]
+        .iter()
+        .filter(|(_, lhs, rhs)| lhs != rhs)
+        .map(|(kind, _, _)| *kind)
+        .collect()
+    }
+
     /// 计算组件的统一承诺根
     ///
-    /// 使用 Blake2b 哈希函数，按确定顺序拼接各组件进行承诺：
-    /// root = Blake2b(id_set_root_hash || id_tree_root_hash || multi_ads_hash)
+    /// 使用 Blake2b 哈希函数，按确定顺序拼接域标签与各组件进行承诺：
+    /// root = Blake2b(len(tag) || tag || id_set_root_hash || id_tree_root_hash || multi_ads_hash
+    ///                || len(extra) || (len(key) || key || len(value) || value)*)
+    ///
+    /// 定长的摘要直接拼接，变长的字段均以 8 字节小端长度为前缀，
+    /// 因此不同的组件不会因字段边界的移动而拼接出相同的字节串
     pub fn compute_root(&self) -> Digest {
         let mut state = blake2().to_state();
+        update_with_len(&mut state, BLOCK_ADS_ROOT_DOMAIN);
         state.update(self.id_set_root_hash.as_bytes());
         state.update(self.id_tree_root_hash.as_bytes());
         state.update(self.multi_ads_hash.as_bytes());
+        state.update(&(self.extra.len() as u64).to_le_bytes());
+        for (key, value) in &self.extra {
+            update_with_len(&mut state, key.as_bytes());
+            update_with_len(&mut state, value);
+        }
         Digest::from(state.finalize())
     }
 }
 
+fn update_with_len(state: &mut blake2b_simd::State, data: &[u8]) {
+    state.update(&(data.len() as u64).to_le_bytes());
+    state.update(data);
+}
+
 impl Digestible for BlockADSComponents {
     fn to_digest(&self) -> Digest {
         self.compute_root()
@@ -71,19 +140,30 @@ impl Digestible for BlockADSComponents {
 pub struct BlockADSRoot {
     /// 统一的 32 字节承诺
     root: Digest,
+    /// 生成 root 的组件（全节点持有；仅由 root 构建时为默认值）
+    components: BlockADSComponents,
 }
 
 impl BlockADSRoot {
-    /// 从组件构建 BlockADSRoot
-    pub fn from_components(components: &BlockADSComponents) -> Self {
+    /// 由根与组件直接构建（用于从存储加载），不检查二者是否一致
+    pub fn new(root: Digest, components: BlockADSComponents) -> Self {
+        Self { root, components }
+    }
+
+    /// 从组件构建 BlockADSRoot，并保留组件
+    pub fn from_components(components: BlockADSComponents) -> Self {
         Self {
             root: components.compute_root(),
+            components,
         }
     }
 
-    /// 从已计算的根哈希创建（用于从存储加载）
+    /// 从已计算的根哈希创建（轻节点），components 为默认值
     pub fn from_digest(root: Digest) -> Self {
-        Self { root }
+        Self {
+            root,
+            components: BlockADSComponents::default(),
+        }
     }
 
     /// 获取统一承诺根（轻节点只需存储这个）
@@ -91,6 +171,11 @@ impl BlockADSRoot {
         &self.root
     }
 
+    /// 获取生成根的组件
+    pub fn components(&self) -> &BlockADSComponents {
+        &self.components
+    }
+
     /// 获取根的 Digest 值
     pub fn to_digest_value(&self) -> Digest {
         self.root
@@ -101,7 +186,12 @@ impl BlockADSRoot {
     /// 轻节点持有 root，全节点提供 components，
     /// 通过此方法验证 components 确实能生成 root
     pub fn verify_components(&self, components: &BlockADSComponents) -> bool {
-        components.compute_root() == self.root
+        components.compute_root().ct_eq(&self.root)
+    }
+
+    /// 验证自身携带的组件能否生成 root
+    pub fn verify_self(&self) -> bool {
+        self.verify_components(&self.components)
     }
 }
 
@@ -125,6 +215,12 @@ impl From<BlockADSRoot> for Digest {
 
 impl From<&BlockADSComponents> for BlockADSRoot {
     fn from(components: &BlockADSComponents) -> Self {
+        Self::from_components(components.clone())
+    }
+}
+
+impl From<BlockADSComponents> for BlockADSRoot {
+    fn from(components: BlockADSComponents) -> Self {
         Self::from_components(components)
     }
 }
@@ -143,29 +239,25 @@ mod tests {
         let components = BlockADSComponents::new(id_set_hash, id_tree_hash, multi_ads_hash);
 
         // 从组件创建 BlockADSRoot
-        let ads_root = BlockADSRoot::from_components(&components);
+        let ads_root = BlockADSRoot::from_components(components.clone());
 
         // 验证组件
         assert!(ads_root.verify_components(&components));
+        assert!(ads_root.verify_self());
+        assert_eq!(ads_root.components(), &components);
     }
 
     #[test]
     fn test_block_ads_root_deterministic() {
         // 相同的组件应该产生相同的根
-        let components1 = BlockADSComponents::new(
-            Digest::default(),
-            Digest::default(),
-            Digest::default(),
-        );
+        let components1 =
+            BlockADSComponents::new(Digest::default(), Digest::default(), Digest::default());
 
-        let components2 = BlockADSComponents::new(
-            Digest::default(),
-            Digest::default(),
-            Digest::default(),
-        );
+        let components2 =
+            BlockADSComponents::new(Digest::default(), Digest::default(), Digest::default());
 
-        let root1 = BlockADSRoot::from_components(&components1);
-        let root2 = BlockADSRoot::from_components(&components2);
+        let root1 = BlockADSRoot::from_components(components1);
+        let root2 = BlockADSRoot::from_components(components2);
 
         assert_eq!(root1.root(), root2.root());
     }
@@ -173,49 +265,37 @@ mod tests {
     #[test]
     fn test_block_ads_root_different_components() {
         // 不同的组件应该产生不同的根
-        let components1 = BlockADSComponents::new(
-            Digest::default(),
-            Digest::default(),
-            Digest::default(),
-        );
+        let components1 =
+            BlockADSComponents::new(Digest::default(), Digest::default(), Digest::default());
 
         // 创建一个非默认的 Digest
         let mut different_bytes = [0u8; 32];
         different_bytes[0] = 1;
         let different_digest = Digest::from(different_bytes);
 
-        let components2 = BlockADSComponents::new(
-            different_digest,
-            Digest::default(),
-            Digest::default(),
-        );
+        let components2 =
+            BlockADSComponents::new(different_digest, Digest::default(), Digest::default());
 
-        let root1 = BlockADSRoot::from_components(&components1);
-        let root2 = BlockADSRoot::from_components(&components2);
+        let root1 = BlockADSRoot::from_components(components1);
+        let root2 = BlockADSRoot::from_components(components2);
 
         assert_ne!(root1.root(), root2.root());
     }
 
     #[test]
     fn test_verify_components_failure() {
-        let components1 = BlockADSComponents::new(
-            Digest::default(),
-            Digest::default(),
-            Digest::default(),
-        );
+        let components1 =
+            BlockADSComponents::new(Digest::default(), Digest::default(), Digest::default());
 
-        let root = BlockADSRoot::from_components(&components1);
+        let root = BlockADSRoot::from_components(components1);
 
         // 创建不同的组件
         let mut different_bytes = [0u8; 32];
         different_bytes[0] = 1;
         let different_digest = Digest::from(different_bytes);
 
-        let components2 = BlockADSComponents::new(
-            different_digest,
-            Digest::default(),
-            Digest::default(),
-        );
+        let components2 =
+            BlockADSComponents::new(different_digest, Digest::default(), Digest::default());
 
         // 验证应该失败
         assert!(!root.verify_components(&components2));
@@ -223,16 +303,104 @@ mod tests {
 
     #[test]
     fn test_digestible_trait() {
-        let components = BlockADSComponents::new(
-            Digest::default(),
-            Digest::default(),
-            Digest::default(),
-        );
+        let components =
+            BlockADSComponents::new(Digest::default(), Digest::default(), Digest::default());
 
-        let root = BlockADSRoot::from_components(&components);
+        let root = BlockADSRoot::from_components(components.clone());
 
         // Digestible trait 应该返回相同的值
         assert_eq!(root.to_digest(), *root.root());
         assert_eq!(components.to_digest(), *root.root());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_verify_self() {
+        let components = BlockADSComponents::new(
+            "id_set".to_digest(),
+            "id_tree".to_digest(),
+            "multi_ads".to_digest(),
+        );
+        let root = BlockADSRoot::new(components.compute_root(), components.clone());
+        assert!(root.verify_self());
+
+        // root 与组件不一致
+        let forged = BlockADSRoot::new(Digest::zero(), components.clone());
+        assert!(!forged.verify_self());
+
+        // 仅由 root 构建时 components 为默认值，无法通过自校验
+        let light = BlockADSRoot::from_digest(components.compute_root());
+        assert_eq!(light.components(), &BlockADSComponents::default());
+        assert!(!light.verify_self());
+        assert!(light.verify_components(&components));
+    }
+
+    #[test]
+    fn test_components_diff() {
+        let base = BlockADSComponents::new(
+            "id_set".to_digest(),
+            "id_tree".to_digest(),
+            "multi_ads".to_digest(),
+        );
+        assert!(base.diff(&base.clone()).is_empty());
+        for kind in [
+            ComponentKind::IdSetRoot,
+            ComponentKind::IdTreeRoot,
+            ComponentKind::MultiAds,
+        ] {
+            let mut other = base.clone();
+            other.set_component(kind, "peer".to_digest());
+            assert_eq!(base.diff(&other), vec![kind]);
+            assert_eq!(other.diff(&base), vec![kind]);
+        }
+        let other = BlockADSComponents::new(
+            "id_set".to_digest(),
+            "other_id_tree".to_digest(),
+            "other_multi_ads".to_digest(),
+        );
+        assert_eq!(
+            base.diff(&other),
+            vec![ComponentKind::IdTreeRoot, ComponentKind::MultiAds]
+        );
+    }
+
+    #[test]
+    fn test_extra_boundary_ambiguity() {
+        let base = BlockADSComponents::new(
+            "id_set".to_digest(),
+            "id_tree".to_digest(),
+            "multi_ads".to_digest(),
+        );
+        // 直接拼接键值时两者都得到 "ab" || "c"
+        let mut components1 = base.clone();
+        components1.extra.insert("ab".to_string(), b"c".to_vec());
+        let mut components2 = base.clone();
+        components2.extra.insert("a".to_string(), b"bc".to_vec());
+        // 直接拼接所有键值时两者都得到 "a" || "b" || "c"
+        let mut components3 = base.clone();
+        components3.extra.insert("a".to_string(), b"bc".to_vec());
+        components3.extra.insert("b".to_string(), Vec::new());
+        let mut components4 = base.clone();
+        components4.extra.insert("a".to_string(), b"b".to_vec());
+        components4.extra.insert("b".to_string(), b"c".to_vec());
+
+        let roots: Vec<Digest> = [
+            &base,
+            &components1,
+            &components2,
+            &components3,
+            &components4,
+        ]
+        .iter()
+        .map(|c| c.compute_root())
+        .collect();
+        for i in 0..roots.len() {
+            for j in (i + 1)..roots.len() {
+                assert_ne!(roots[i], roots[j], "components {} and {} collide", i, j);
+            }
+        }
+
+        let root = BlockADSRoot::from_components(components1.clone());
+        assert!(root.verify_self());
+        assert!(!root.verify_components(&components2));
+    }
+}
@@ -1,5 +1,5 @@
 use crate::{
-    acc::AccPublicKey,
+    acc::{AccCache, AccPublicKey},
     chain::{
         block::{
             block_ads::BlockMultiADS,
@@ -21,14 +21,49 @@ use howlong::ProcessDuration;
 use smol_str::SmolStr;
 use std::{collections::HashMap, num::NonZeroU16};
 
+/// 区块构建过程中各阶段完成时上报的进度
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildProgress {
+    /// 所有对象已插入 id 树
+    IdTreeBuilt { obj_num: usize, node_num: usize },
+    /// 时间窗口 `time_win` 的 trie 已构建完成
+    TrieBuilt { time_win: u16, node_num: usize },
+    /// 时间窗口 `time_win` 中第 `dim` 维的 B+ 树已构建完成
+    BPlusTreeBuilt {
+        time_win: u16,
+        dim: u8,
+        node_num: usize,
+    },
+    /// 区块的 ads_root 已计算完成
+    AdsRootComputed { blk_height: Height },
+}
+
 pub fn build_block<K: Num, T: ReadInterface<K = K> + WriteInterface<K = K>>(
+    blk_height: Height,
+    prev_hash: Digest,
+    raw_objs: Vec<Object<K>>,
+    chain: T,
+    param: &Parameter,
+    pk: &AccPublicKey,
+) -> Result<(BlockHead, ProcessDuration)> {
+    build_block_with_progress(blk_height, prev_hash, raw_objs, chain, param, pk, None)
+}
+
+/// 与 `build_block` 相同，并在每个构建阶段结束时调用 `progress`
+pub fn build_block_with_progress<K: Num, T: ReadInterface<K = K> + WriteInterface<K = K>>(
     blk_height: Height,
     prev_hash: Digest,
     raw_objs: Vec<Object<K>>,
     mut chain: T,
     param: &Parameter,
     pk: &AccPublicKey,
+    progress: Option<&dyn Fn(BuildProgress)>,
 ) -> Result<(BlockHead, ProcessDuration)> {
+    let report = |p: BuildProgress| {
+        if let Some(f) = progress {
+            f(p);
+        }
+    };
     info!("Building block {}...", blk_height);
     let timer = howlong::ProcessCPUTimer::new();
     let mut block_head = BlockHead {
@@ -39,18 +74,20 @@ pub fn build_block<K: Num, T: ReadInterface<K = K> + WriteInterface<K = K>>(
     let mut block_content = BlockContent::new(blk_height, prev_hash);
     let max_id_num = param.max_id_num;
     let mut blk_multi_ads: BlockMultiADS = BlockMultiADS::default();
-    let pre_blk_content = if blk_height.0 > 1 {
-        chain.read_block_content(Height(blk_height.0 - 1))?
-    } else {
-        BlockContent::default()
+    let pre_blk_content = match blk_height.prev() {
+        Some(prev_height) if prev_height.0 > 0 => chain.read_block_content(prev_height)?,
+        _ => BlockContent::default(),
     };
 
     let multi_ads = pre_blk_content.ads.read_adses();
     let time_wins = &param.time_win_sizes;
+    // 同一对象插入各时间窗口的 trie 与 B+ 树时使用相同的单元素集合，其累加器值只计算一次
+    let acc_cache = AccCache::new(pk);
 
     // id tree ctx
     let id_tree_root = pre_blk_content.id_tree_root;
     let mut id_tree_ctx = id_tree::write::WriteContext::new(&chain, id_tree_root);
+    id_tree_ctx.set_fanout(param.id_tree_fanout)?;
     // trie ctxes
     let mut trie_ctxes = Vec::<(u16, trie_tree::write::WriteContext<T>)>::new();
     // bplus tree
@@ -72,6 +109,7 @@ pub fn build_block<K: Num, T: ReadInterface<K = K> + WriteInterface<K = K>>(
             TrieRoot::default()
         };
         let mut trie_ctx = trie_tree::write::WriteContext::new(&chain, trie_root);
+        trie_ctx.set_acc_cache(&acc_cache);
         for (idx, obj_hash) in pre_k_blk_obj_hashes.iter().enumerate() {
             let raw_obj = chain.read_object(*obj_hash)?;
             let obj_id_num = pre_k_blk_obj_id_nums
@@ -100,6 +138,7 @@ pub fn build_block<K: Num, T: ReadInterface<K = K> + WriteInterface<K = K>>(
                 BPlusTreeRoot::default()
             };
             let mut bplus_ctx = bplus_tree::write::WriteContext::new(&chain, bplus_tree_root);
+            bplus_ctx.set_acc_cache(&acc_cache);
             for (idx, obj_hash) in pre_k_blk_obj_hashes.iter().enumerate() {
                 let raw_obj = chain.read_object(*obj_hash)?;
                 let obj_id_num = pre_k_blk_obj_id_nums
@@ -138,9 +177,15 @@ pub fn build_block<K: Num, T: ReadInterface<K = K> + WriteInterface<K = K>>(
         obj_hashes.push(obj_hash);
         obj_id_nums.push(obj_id.0);
     }
+    let id_set_root_hash = obj_id_nums_hash(obj_id_nums.iter());
+    block_content.try_set_obj_id_nums(obj_id_nums, max_id_num)?;
 
     // handle id tree changes
     let id_tree_changes = id_tree_ctx.changes();
+    report(BuildProgress::IdTreeBuilt {
+        obj_num: obj_hashes.len(),
+        node_num: id_tree_changes.nodes.len(),
+    });
 
     // handle trie changes
     let mut new_trie_nodes = Vec::<HashMap<TrieNodeId, TrieNode>>::new();
@@ -148,6 +193,10 @@ pub fn build_block<K: Num, T: ReadInterface<K = K> + WriteInterface<K = K>>(
     for (k, trie_ctx) in trie_ctxes {
         let trie_changes = trie_ctx.changes();
         new_trie_roots.push((k, trie_changes.root));
+        report(BuildProgress::TrieBuilt {
+            time_win: k,
+            node_num: trie_changes.nodes.len(),
+        });
         new_trie_nodes.push(trie_changes.nodes);
     }
     blk_multi_ads.set_multi_trie_roots(new_trie_roots.iter());
@@ -157,9 +206,14 @@ pub fn build_block<K: Num, T: ReadInterface<K = K> + WriteInterface<K = K>>(
     let mut new_bplus_nodes = Vec::<HashMap<BPlusTreeNodeId, BPlusTreeNode<K>>>::new();
     for (k, bplus_ctx_vec) in bplus_ctxes {
         let mut new_bplus_roots_dim = Vec::<BPlusTreeRoot>::new();
-        for bplus_ctx in bplus_ctx_vec {
+        for (dim, bplus_ctx) in bplus_ctx_vec.into_iter().enumerate() {
             let bplus_tree_changes = bplus_ctx.changes();
             new_bplus_roots_dim.push(bplus_tree_changes.root);
+            report(BuildProgress::BPlusTreeBuilt {
+                time_win: k,
+                dim: dim as u8,
+                node_num: bplus_tree_changes.nodes.len(),
+            });
             new_bplus_nodes.push(bplus_tree_changes.nodes);
         }
         new_bplus_roots.push((k, new_bplus_roots_dim));
@@ -187,22 +241,19 @@ pub fn build_block<K: Num, T: ReadInterface<K = K> + WriteInterface<K = K>>(
     }
 
     let obj_root_hash = obj_root_hash(obj_hashes.iter());
-    let id_set_root_hash = obj_id_nums_hash(obj_id_nums.iter());
     let multi_ads_hash = blk_multi_ads.to_digest();
     let id_tree_root_hash = id_tree_changes.root.to_digest();
 
     // 【创新点1】构建一体化的 BlockADSRoot
     // 先构建组件
-    let ads_components = BlockADSComponents::new(
-        id_set_root_hash,
-        id_tree_root_hash,
-        multi_ads_hash,
-    );
-    
+    let ads_components =
+        BlockADSComponents::new(id_set_root_hash, id_tree_root_hash, multi_ads_hash);
+
     // 从组件构建完整的 BlockADSRoot（体现一体化承诺的设计理念）
     use crate::chain::block::block_ads_root::BlockADSRoot;
     let block_ads_root = BlockADSRoot::from_components(ads_components);
-    
+    report(BuildProgress::AdsRootComputed { blk_height });
+
     // 验证内部一致性（调试模式下）
     #[cfg(debug_assertions)]
     {
@@ -221,7 +272,6 @@ pub fn build_block<K: Num, T: ReadInterface<K = K> + WriteInterface<K = K>>(
     // BlockContent 存储完整组件（全节点保存，用于验证时展开）
     block_content.set_multi_ads(blk_multi_ads);
     block_content.set_obj_hashes(obj_hashes);
-    block_content.set_obj_id_nums(obj_id_nums);
     block_content.set_id_tree_root(id_tree_changes.root);
     block_content.set_ads_components(block_ads_root.components().clone());
 
@@ -231,4 +281,4 @@ pub fn build_block<K: Num, T: ReadInterface<K = K> + WriteInterface<K = K>>(
     info!("Time elapsed : {}.", time);
 
     Ok((block_head, time))
-}
\ No newline at end of file
+}
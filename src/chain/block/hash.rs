@@ -9,6 +9,17 @@ use crate::{
     digest::{blake2, concat_digest_ref, Digest, Digestible},
 };
 
+/// 区块高度摘要的域分隔标签（blake2b personalization，不超过 16 字节），
+/// 使高度的摘要不会与同值 `u32` 的摘要相同
+const HEIGHT_DOMAIN: &[u8] = b"vchain+/height";
+
+#[inline]
+pub(crate) fn height_hash(blk_height: Height) -> Digest {
+    let mut state = blake2().personal(HEIGHT_DOMAIN).to_state();
+    state.update(&blk_height.0.to_le_bytes());
+    Digest::from(state.finalize())
+}
+
 #[inline]
 pub(crate) fn block_head_hash(
     blk_height: Height,
@@ -17,7 +28,7 @@ pub(crate) fn block_head_hash(
     obj_root_hash: &Digest,
 ) -> Digest {
     let mut state = blake2().to_state();
-    state.update(&blk_height.to_le_bytes());
+    state.update(blk_height.to_digest().as_bytes());
     state.update(prev_hash.as_bytes());
     state.update(ads_hash.as_bytes());
     state.update(obj_root_hash.as_bytes());
@@ -68,10 +79,58 @@ pub(crate) fn obj_root_hash<'a>(obj_hashes: impl Iterator<Item = &'a Digest>) ->
     Digest::from(state.finalize())
 }
 
+/// 逐个加入对象哈希、增量计算 `obj_root_hash`，用于流式构建区块。
+///
+/// obj_root 是全部对象哈希依次拼接后的哈希，blake2b 的内部状态就是继续追加所需的全部信息，
+/// 因此 `push` 与 `root` 都不必从头计算；对同样的哈希序列，`root` 与 `obj_root_hash` 的结果相同
+#[derive(Debug, Clone)]
+pub struct ObjMerkleBuilder {
+    state: blake2b_simd::State,
+    len: usize,
+}
+
+impl Default for ObjMerkleBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ObjMerkleBuilder {
+    pub fn new() -> Self {
+        Self {
+            state: blake2().to_state(),
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, obj_hash: &Digest) {
+        self.state.update(obj_hash.as_bytes());
+        self.len += 1;
+    }
+
+    /// 已加入的对象哈希个数
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 当前已加入的所有对象哈希的 obj_root，不影响之后继续 `push`
+    pub fn root(&self) -> Digest {
+        let concat_hash = Digest::from(self.state.finalize());
+        let mut state = blake2().to_state();
+        state.update(concat_hash.as_bytes());
+        Digest::from(state.finalize())
+    }
+}
+
 /// 【已废弃】计算 ADS 根哈希
 ///
 /// 请使用 `BlockADSComponents::compute_root()` 代替。
-/// 此函数保留用于向后兼容，将在未来版本中移除。
+/// 此函数保留用于向后兼容，将在未来版本中移除；
+/// 其结果不带域标签与扩展组件，与 `compute_root()` 不同。
 #[inline]
 #[deprecated(
     since = "0.2.0",
@@ -87,4 +146,4 @@ pub(crate) fn ads_root_hash(
     state.update(id_tree_root_hash.as_bytes());
     state.update(ads_hash.as_bytes());
     Digest::from(state.finalize())
-}
\ No newline at end of file
+}
@@ -1,6 +1,6 @@
 use crate::{
     acc::{AccPublicKey, AccValue},
-    chain::{range::Range, traits::Num},
+    chain::{range::RangeBound, traits::Num},
     digest::{Digest, Digestible},
 };
 use serde::{Deserialize, Serialize};
@@ -29,9 +29,10 @@ impl<K: Num> Proof<K> {
         }
     }
 
+    /// 结果为 `intervals` 各区间结果的并集；`range_query` 的证明只有一个区间
     pub(crate) fn verify(
         &self,
-        query_range: Range<K>,
+        intervals: &[RangeBound<K>],
         acc_val: AccValue,
         pk: &AccPublicKey,
     ) -> Result<Digest> {
@@ -39,7 +40,7 @@ impl<K: Num> Proof<K> {
             .root
             .as_ref()
             .ok_or_else(|| anyhow!("Cannot find subproof!"))?
-            .value_acc_completeness(query_range, pk)?
+            .value_acc_completeness(intervals, pk)?
             != acc_val
         {
             bail!("B plus tree verification: acc value not matched");
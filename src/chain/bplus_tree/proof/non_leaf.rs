@@ -15,6 +15,7 @@ use smallvec::SmallVec;
 pub(crate) struct BPlusTreeNonLeaf<K: Num> {
     pub(crate) range: Range<K>,
     pub(crate) acc_hash: Digest,
+    #[serde(deserialize_with = "crate::utils::deserialize_nested")]
     pub(crate) children: SmallVec<[Option<Box<SubProof<K>>>; MAX_INLINE_BTREE_FANOUT]>,
 }
 
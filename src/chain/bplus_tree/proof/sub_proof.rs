@@ -5,7 +5,7 @@ use crate::{
             leaf::BPlusTreeLeaf, non_leaf::BPlusTreeNonLeaf, res_sub_tree::BPlusTreeResSubTree,
             sub_tree::BPlusTreeSubTree,
         },
-        range::Range,
+        range::{Range, RangeBound},
         traits::Num,
     },
     digest::{Digest, Digestible},
@@ -52,7 +52,7 @@ impl<K: Num> SubProof<K> {
 
     pub(crate) fn value_acc_completeness(
         &self,
-        range: Range<K>,
+        intervals: &[RangeBound<K>],
         pk: &AccPublicKey,
     ) -> Result<AccValue> {
         let mut res_acc_val: AccValue = AccValue::from_set(&Set::new(), pk);
@@ -64,12 +64,12 @@ impl<K: Num> SubProof<K> {
         while let Some(cur_proof) = queue.pop_front() {
             match *cur_proof {
                 SubProof::Hash(n) => {
-                    if !n.range.has_no_intersection(range) {
+                    if !intervals.iter().all(|r| r.is_disjoint(n.range)) {
                         completeness = false;
                     }
                 }
                 SubProof::Leaf(n) => {
-                    if !range.is_in_range(n.num) {
+                    if !intervals.iter().any(|r| r.contains(n.num)) {
                         completeness = false;
                     }
                     res_acc_val = res_acc_val + n.acc_val;
@@ -80,7 +80,7 @@ impl<K: Num> SubProof<K> {
                     }
                 }
                 SubProof::ResSubTree(n) => {
-                    if !n.range.is_covered(range) {
+                    if !intervals.iter().any(|r| r.covers(n.range)) {
                         completeness = false;
                     }
                     res_acc_val = res_acc_val + n.acc_val;
@@ -5,7 +5,7 @@ use crate::{
             proof::{sub_proof::SubProof, Proof},
             BPlusTreeNode, BPlusTreeNodeId, BPlusTreeNodeLoader,
         },
-        range::Range,
+        range::{Range, RangeBound},
         traits::Num,
         MAX_INLINE_BTREE_FANOUT,
     },
@@ -18,21 +18,37 @@ use std::collections::VecDeque;
 pub fn range_query<K: Num>(
     node_loader: &impl BPlusTreeNodeLoader<K>,
     root_id: Option<BPlusTreeNodeId>,
-    range: Range<K>,
+    range: RangeBound<K>,
     pk: &AccPublicKey,
 ) -> Result<(Set, AccValue, Proof<K>)> {
     let bplus_tree_root_id = match root_id {
         Some(id) => id,
         None => bail!("The BPlus tree is empty"),
     };
-    let (res, acc, p) = inner_range_query(node_loader, bplus_tree_root_id, range, pk)?;
+    let (res, acc, p) = inner_range_query(node_loader, bplus_tree_root_id, &[range], pk)?;
+    Ok((res, acc, Proof::from_subproof(p)))
+}
+
+/// 同一维度上多个互不相交区间的并集，只生成一个证明：各区间共用树中的路径，
+/// 完全落在某个区间内的子树只给出一次累加器值
+pub fn multi_range_query<K: Num>(
+    node_loader: &impl BPlusTreeNodeLoader<K>,
+    root_id: Option<BPlusTreeNodeId>,
+    intervals: &[RangeBound<K>],
+    pk: &AccPublicKey,
+) -> Result<(Set, AccValue, Proof<K>)> {
+    let bplus_tree_root_id = match root_id {
+        Some(id) => id,
+        None => bail!("The BPlus tree is empty"),
+    };
+    let (res, acc, p) = inner_range_query(node_loader, bplus_tree_root_id, intervals, pk)?;
     Ok((res, acc, Proof::from_subproof(p)))
 }
 
 fn inner_range_query<K: Num>(
     node_loader: &impl BPlusTreeNodeLoader<K>,
     root_id: BPlusTreeNodeId,
-    range: Range<K>,
+    intervals: &[RangeBound<K>],
     pk: &AccPublicKey,
 ) -> Result<(Set, AccValue, SubProof<K>)> {
     use crate::chain::bplus_tree::proof::{
@@ -41,9 +57,8 @@ fn inner_range_query<K: Num>(
 
     let mut query_res = Set::new();
     let mut res_acc_val: AccValue = AccValue::from_set(&query_res, pk);
-    let mut query_proof = SubProof::from_hash(range, Digest::zero());
-
     let root_node = node_loader.load_node(root_id)?;
+    let mut query_proof = SubProof::from_hash(root_node.get_range(), Digest::zero());
     let cur_proof = &mut query_proof as *mut _;
 
     let mut queue: VecDeque<(BPlusTreeNode<K>, *mut SubProof<K>)> = VecDeque::new();
@@ -52,7 +67,7 @@ fn inner_range_query<K: Num>(
     while let Some((cur_node, cur_proof_ptr)) = queue.pop_front() {
         match cur_node {
             BPlusTreeNode::Leaf(n) => {
-                if range.is_in_range(n.num) {
+                if intervals.iter().any(|r| r.contains(n.num)) {
                     // leaf
                     query_res = (&query_res) | (&n.data_set);
                     res_acc_val = res_acc_val + n.data_set_acc;
@@ -69,7 +84,7 @@ fn inner_range_query<K: Num>(
                 }
             }
             BPlusTreeNode::NonLeaf(n) => {
-                if n.range.is_covered(range) {
+                if intervals.iter().any(|r| r.covers(n.range)) {
                     //res_node
                     query_res = (&query_res) | (&n.data_set);
                     res_acc_val = res_acc_val + n.data_set_acc;
@@ -80,12 +95,12 @@ fn inner_range_query<K: Num>(
                             n.to_digest(),
                         ));
                     }
-                } else if n.range.has_no_intersection(range) {
+                } else if intervals.iter().all(|r| r.is_disjoint(n.range)) {
                     // hash(sub_tree)
                     unsafe {
                         *cur_proof_ptr = SubProof::from_hash(n.range, n.to_digest());
                     }
-                } else if n.range.intersects(range) {
+                } else {
                     // non_leaf
                     let mut cur_proof_children =
                         SmallVec::<[Option<Box<SubProof<K>>>; MAX_INLINE_BTREE_FANOUT]>::new();
@@ -113,7 +128,6 @@ fn inner_range_query<K: Num>(
                             cur_proof_children,
                         ));
                     }
-                } else {
                 }
             }
         }
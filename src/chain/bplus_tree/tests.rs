@@ -1,14 +1,17 @@
 use super::{
     super::tests::PUB_KEY,
     proof::sub_proof::SubProof,
-    read::range_query,
+    read::{multi_range_query, range_query},
     write::{Apply, WriteContext},
     BPlusTreeNode, BPlusTreeNodeId, BPlusTreeNodeLoader,
 };
 use crate::chain::id_tree::ObjId;
 use crate::{chain::bplus_tree::BPlusTreeRoot, utils::init_tracing_subscriber};
 use crate::{
-    chain::{range::Range, traits::Num},
+    chain::{
+        range::{Range, RangeBound},
+        traits::Num,
+    },
     digest::{Digest, Digestible},
 };
 use anyhow::{bail, Result};
@@ -129,32 +132,177 @@ fn test_read() {
         .unwrap()
         .to_digest();
 
-    let query_range = Range::new(1, 4);
+    let query_range = RangeBound::new(0, 1, 4).unwrap();
     let (_v, acc, p) =
         range_query(&test_b_tree, test_b_tree.root_id, query_range, &PUB_KEY).unwrap();
 
-    let res_digest = p.verify(query_range, acc, &PUB_KEY).unwrap();
+    let res_digest = p.verify(&[query_range], acc, &PUB_KEY).unwrap();
     assert_eq!(root_digest, res_digest);
 
-    let query_range = Range::new(3, 10);
+    let query_range = RangeBound::new(0, 3, 10).unwrap();
     let (_v, acc, p) =
         range_query(&test_b_tree, test_b_tree.root_id, query_range, &PUB_KEY).unwrap();
-    let res_digest = p.verify(query_range, acc, &PUB_KEY).unwrap();
+    let res_digest = p.verify(&[query_range], acc, &PUB_KEY).unwrap();
     assert_eq!(root_digest, res_digest);
 
-    let query_range = Range::new(5, 30);
+    let query_range = RangeBound::new(0, 5, 30).unwrap();
     let (_v, acc, p) =
         range_query(&test_b_tree, test_b_tree.root_id, query_range, &PUB_KEY).unwrap();
-    let res_digest = p.verify(query_range, acc, &PUB_KEY).unwrap();
+    let res_digest = p.verify(&[query_range], acc, &PUB_KEY).unwrap();
     assert_eq!(root_digest, res_digest);
 
-    let query_range = Range::new(31, 40);
+    let query_range = RangeBound::new(0, 31, 40).unwrap();
     let (_v, acc, p) =
         range_query(&test_b_tree, test_b_tree.root_id, query_range, &PUB_KEY).unwrap();
-    let res_digest = p.verify(query_range, acc, &PUB_KEY).unwrap();
+    let res_digest = p.verify(&[query_range], acc, &PUB_KEY).unwrap();
     assert_eq!(root_digest, res_digest);
 }
 
+#[test]
+fn test_read_open_bounds() {
+    use crate::acc::Set;
+    use std::ops::Bound;
+
+    let mut test_b_tree = TestBPlusTree::<u32>::new();
+    let mut test_b_tree_root = BPlusTreeRoot::default();
+    set_root_id(&mut test_b_tree_root, test_b_tree.root_id);
+    let mut ctx = WriteContext::new(&mut test_b_tree, test_b_tree_root);
+    let (keys, ids) = get_dataset();
+    for (key, id) in keys.iter().zip(ids.iter()) {
+        ctx.insert(*key, ObjId(*id), FANOUT, &PUB_KEY).unwrap();
+    }
+    let changes = ctx.changes();
+    test_b_tree.apply(changes);
+    let root_digest = test_b_tree
+        .load_node(test_b_tree.root_id.unwrap())
+        .unwrap()
+        .to_digest();
+
+    let cases = vec![
+        // >= 20
+        (Bound::Included(20), Bound::Unbounded),
+        // < 10
+        (Bound::Unbounded, Bound::Excluded(10)),
+        // (4, 11]
+        (Bound::Excluded(4), Bound::Included(11)),
+        (Bound::Unbounded, Bound::Unbounded),
+    ];
+    for (low, high) in cases {
+        let query_range = RangeBound::with_bounds(0, low, high).unwrap();
+        let (v, acc, p) =
+            range_query(&test_b_tree, test_b_tree.root_id, query_range, &PUB_KEY).unwrap();
+        let expect: Set = keys
+            .iter()
+            .zip(ids.iter())
+            .filter(|(k, _)| query_range.contains(**k))
+            .map(|(_, id)| *id)
+            .collect();
+        assert_eq!(v, expect);
+        let res_digest = p.verify(&[query_range], acc, &PUB_KEY).unwrap();
+        assert_eq!(root_digest, res_digest);
+    }
+
+    // 按 < 10 生成的证明不能通过 <= 10 的验证
+    let lt = RangeBound::with_bounds(0, Bound::Unbounded, Bound::Excluded(10)).unwrap();
+    let le = RangeBound::with_bounds(0, Bound::Unbounded, Bound::Included(10)).unwrap();
+    let (_v, acc, p) = range_query(&test_b_tree, test_b_tree.root_id, lt, &PUB_KEY).unwrap();
+    assert!(p.verify(&[le], acc, &PUB_KEY).is_err());
+}
+
+#[test]
+fn test_read_multi_interval() {
+    use crate::acc::Set;
+
+    let mut test_b_tree = TestBPlusTree::<u32>::new();
+    let mut test_b_tree_root = BPlusTreeRoot::default();
+    set_root_id(&mut test_b_tree_root, test_b_tree.root_id);
+    let mut ctx = WriteContext::new(&test_b_tree, test_b_tree_root);
+    let (keys, ids) = get_dataset();
+    for (key, id) in keys.iter().zip(ids.iter()) {
+        ctx.insert(*key, ObjId(*id), FANOUT, &PUB_KEY).unwrap();
+    }
+    let changes = ctx.changes();
+    test_b_tree.apply(changes);
+    let root_digest = test_b_tree
+        .load_node(test_b_tree.root_id.unwrap())
+        .unwrap()
+        .to_digest();
+
+    let intervals = vec![
+        RangeBound::new(0, 2, 5).unwrap(),
+        RangeBound::new(0, 11, 14).unwrap(),
+        RangeBound::new(0, 20, 22).unwrap(),
+    ];
+    let (v, acc, p) =
+        multi_range_query(&test_b_tree, test_b_tree.root_id, &intervals, &PUB_KEY).unwrap();
+    let expect: Set = keys
+        .iter()
+        .zip(ids.iter())
+        .filter(|(k, _)| intervals.iter().any(|r| r.contains(**k)))
+        .map(|(_, id)| *id)
+        .collect();
+    assert_eq!(v, expect);
+    assert_eq!(p.verify(&intervals, acc, &PUB_KEY).unwrap(), root_digest);
+    // 少了一个区间时，证明中该区间的结果不满足完整性
+    assert!(p.verify(&intervals[..2], acc, &PUB_KEY).is_err());
+}
+
+#[test]
+fn test_read_signed() {
+    use crate::{acc::Set, set};
+    use std::ops::Bound;
+
+    // 有符号的键按 `Ord` 排序，负数在正数之前，包括类型的最小值与最大值
+    let mut test_b_tree = TestBPlusTree::<i64>::new();
+    let test_b_tree_root = BPlusTreeRoot::default();
+    let mut ctx = WriteContext::new(&test_b_tree, test_b_tree_root);
+    let keys: Vec<i64> = vec![-5, -1, 0, 3, i64::MIN, 100, -100, i64::MAX, -3, 5, 7, -7];
+    let ids = get_dataset().1;
+    for (key, id) in keys.iter().zip(ids.iter()) {
+        ctx.insert(*key, ObjId(*id), FANOUT, &PUB_KEY).unwrap();
+    }
+    let changes = ctx.changes();
+    test_b_tree.apply(changes);
+    let root_digest = test_b_tree
+        .load_node(test_b_tree.root_id.unwrap())
+        .unwrap()
+        .to_digest();
+
+    let query_range = RangeBound::new(0, -2, 2).unwrap();
+    let (v, acc, p) =
+        range_query(&test_b_tree, test_b_tree.root_id, query_range, &PUB_KEY).unwrap();
+    // -1 与 0 对应的 id
+    assert_eq!(v, set! {2, 3});
+    assert_eq!(
+        p.verify(&[query_range], acc, &PUB_KEY).unwrap(),
+        root_digest
+    );
+
+    let cases = vec![
+        (Bound::Included(-2), Bound::Excluded(2)),
+        (Bound::Unbounded, Bound::Excluded(0)),
+        (Bound::Included(i64::MIN), Bound::Included(-5)),
+        (Bound::Excluded(-100), Bound::Unbounded),
+        (Bound::Included(4), Bound::Included(i64::MAX)),
+    ];
+    for (low, high) in cases {
+        let query_range = RangeBound::with_bounds(0, low, high).unwrap();
+        let (v, acc, p) =
+            range_query(&test_b_tree, test_b_tree.root_id, query_range, &PUB_KEY).unwrap();
+        let expect: Set = keys
+            .iter()
+            .zip(ids.iter())
+            .filter(|(k, _)| query_range.contains(**k))
+            .map(|(_, id)| *id)
+            .collect();
+        assert_eq!(v, expect);
+        assert_eq!(
+            p.verify(&[query_range], acc, &PUB_KEY).unwrap(),
+            root_digest
+        );
+    }
+}
+
 #[test]
 fn test_insert_rich() {
     let mut test_b_tree = TestBPlusTree::<u32>::new();
@@ -1,5 +1,5 @@
 use crate::{
-    acc::{AccPublicKey, AccValue, Set},
+    acc::{AccCache, AccPublicKey, AccValue, Set},
     chain::{
         bplus_tree::{
             BPlusTreeLeafNode, BPlusTreeNode, BPlusTreeNodeId, BPlusTreeNodeLoader,
@@ -29,6 +29,7 @@ pub struct WriteContext<'a, K: Num, L: BPlusTreeNodeLoader<K>> {
     node_loader: &'a L,
     apply: Apply<K>,
     outdated: HashSet<BPlusTreeNodeId>,
+    acc_cache: Option<&'a AccCache>,
 }
 
 impl<'a, K: Num, L: BPlusTreeNodeLoader<K>> WriteContext<'a, K, L> {
@@ -40,6 +41,19 @@ impl<'a, K: Num, L: BPlusTreeNodeLoader<K>> WriteContext<'a, K, L> {
                 nodes: HashMap::new(),
             },
             outdated: HashSet::new(),
+            acc_cache: None,
+        }
+    }
+
+    /// 插入、删除时由集合计算的累加器值从 `cache` 中读取，多个上下文可共享同一个 `cache`
+    pub fn set_acc_cache(&mut self, cache: &'a AccCache) {
+        self.acc_cache = Some(cache);
+    }
+
+    fn acc_of(&self, set: &Set, pk: &AccPublicKey) -> AccValue {
+        match self.acc_cache {
+            Some(cache) => cache.acc_of(set, pk),
+            None => AccValue::from_set(set, pk),
         }
     }
 
@@ -76,7 +90,7 @@ impl<'a, K: Num, L: BPlusTreeNodeLoader<K>> WriteContext<'a, K, L> {
 
     pub fn insert(&mut self, key: K, obj_id: ObjId, fanout: u8, pk: &AccPublicKey) -> Result<()> {
         let set = Set::from_single_element(obj_id.0);
-        let new_acc = AccValue::from_set(&set, pk);
+        let new_acc = self.acc_of(&set, pk);
 
         let mut cur_id_opt = self.apply.root.bplus_tree_root_id;
         let mut insert_flag = false;
@@ -443,7 +457,7 @@ impl<'a, K: Num, L: BPlusTreeNodeLoader<K>> WriteContext<'a, K, L> {
 
     pub fn delete(&mut self, key: K, obj_id: ObjId, fanout: u8, pk: &AccPublicKey) -> Result<()> {
         let set = Set::from_single_element(obj_id.0);
-        let delta_acc = AccValue::from_set(&set, pk);
+        let delta_acc = self.acc_of(&set, pk);
         let mut cur_id_opt = self.apply.root.bplus_tree_root_id;
 
         #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -3,7 +3,7 @@ use crate::{
     create_id_type_by_u16, create_id_type_by_u32,
     digest::{Digest, Digestible},
 };
-use anyhow::Result;
+use anyhow::{ensure, Result};
 use hash::{id_tree_leaf_hash, id_tree_non_leaf_hash, id_tree_root_hash};
 use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
@@ -50,6 +50,35 @@ impl ObjId {
     fn from_internal_id(id: IdTreeInternalId) -> Self {
         Self(unsafe { NonZeroU16::new_unchecked(id.0 + 1) })
     }
+
+    /// 由内部 id 构造 `ObjId`，要求 `internal < max_id_num` 且 `max_id_num` 不超过 `u16::MAX`
+    pub fn new_checked(internal: u64, max_id_num: u64) -> Result<Self> {
+        ensure!(
+            max_id_num <= u64::from(u16::MAX),
+            "max_id_num {} exceeds the limit {}",
+            max_id_num,
+            u16::MAX
+        );
+        ensure!(
+            internal < max_id_num,
+            "Object internal id {} out of range [0, {})",
+            internal,
+            max_id_num
+        );
+        Ok(Self::from_internal_id(IdTreeInternalId(internal as u16)))
+    }
+
+    /// 转换为内部 id，并检查其小于 `max_id_num`
+    pub fn to_internal_id_checked(self, max_id_num: u64) -> Result<IdTreeInternalId> {
+        let id = self.to_internal_id();
+        ensure!(
+            u64::from(id.0) < max_id_num,
+            "Object id {} out of range [1, {}]",
+            self.0,
+            max_id_num
+        );
+        Ok(id)
+    }
 }
 
 impl Default for ObjId {
@@ -63,11 +92,17 @@ pub struct IdTreeRoot {
     id_tree_root_id: Option<IdTreeNodeId>,
     id_tree_root_hash: Digest,
     cur_obj_id: ObjId,
+    /// 构建 id 树所用的扇出，尚未设置时为 0；纳入根哈希，使验证时的扇出必须与构建时一致
+    fanout: u8,
 }
 
 impl Digestible for IdTreeRoot {
     fn to_digest(&self) -> Digest {
-        id_tree_root_hash(&self.cur_obj_id.to_digest(), &self.id_tree_root_hash)
+        id_tree_root_hash(
+            &self.cur_obj_id.to_digest(),
+            self.fanout,
+            &self.id_tree_root_hash,
+        )
     }
 }
 
@@ -79,6 +114,11 @@ impl IdTreeRoot {
     pub(crate) fn get_cur_obj_id(&self) -> ObjId {
         self.cur_obj_id
     }
+
+    /// id 树根节点的哈希，即 `Proof::root_hash` 由证明重建出的值
+    pub fn get_id_tree_root_hash(&self) -> Digest {
+        self.id_tree_root_hash
+    }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -44,9 +44,14 @@ pub(crate) fn id_tree_non_leaf_proof_hash(
 }
 
 #[inline]
-pub(crate) fn id_tree_root_hash(cur_obj_id_hash: &Digest, id_tree_root_hash: &Digest) -> Digest {
+pub(crate) fn id_tree_root_hash(
+    cur_obj_id_hash: &Digest,
+    fanout: u8,
+    id_tree_root_hash: &Digest,
+) -> Digest {
     let mut state = blake2().to_state();
     state.update(cur_obj_id_hash.as_bytes());
+    state.update(&fanout.to_le_bytes());
     state.update(id_tree_root_hash.as_bytes());
     Digest::from(state.finalize())
 }
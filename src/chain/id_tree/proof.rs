@@ -1,5 +1,8 @@
 use crate::{
-    chain::id_tree::{write::fanout_nary_rev, IdTreeNodeId, ObjId},
+    chain::id_tree::{
+        write::{fanout_nary_rev, id_tree_depth},
+        IdTreeNodeId, ObjId,
+    },
     digest::{Digest, Digestible},
 };
 use anyhow::{ensure, Result};
@@ -41,7 +44,7 @@ impl Proof {
     }
 
     fn value_hash(&self, obj_id: ObjId, max_id_num: u16, fanout: u8) -> Digest {
-        let depth = (max_id_num as f64).log(fanout as f64).floor() as usize;
+        let depth = id_tree_depth(max_id_num, fanout);
         let mut cur_path_rev = fanout_nary_rev(obj_id.to_internal_id().0, fanout, depth);
         match self.root.as_ref() {
             None => Digest::zero(),
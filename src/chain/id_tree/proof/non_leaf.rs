@@ -11,6 +11,7 @@ use smallvec::SmallVec;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct IdTreeNonLeaf {
+    #[serde(deserialize_with = "crate::utils::deserialize_nested")]
     pub(crate) children: SmallVec<[Option<Box<SubProof>>; MAX_ININE_ID_FANOUT]>,
 }
 
@@ -1,7 +1,7 @@
 use crate::{
     chain::id_tree::{
         proof::{sub_proof::SubProof, Proof},
-        write::fanout_nary_rev,
+        write::{fanout_nary_rev, id_tree_depth},
         Digest, IdTreeInternalId, IdTreeNode, IdTreeNodeId, IdTreeNodeLoader, ObjId,
     },
     digest::Digestible,
@@ -10,7 +10,7 @@ use anyhow::{anyhow, bail, Context, Result};
 
 pub fn query_without_proof(
     max_id_num: u16,
-    node_loader: impl IdTreeNodeLoader,
+    node_loader: &impl IdTreeNodeLoader,
     root_id: IdTreeNodeId,
     obj_id: IdTreeInternalId,
     fanout: u8,
@@ -18,7 +18,7 @@ pub fn query_without_proof(
     let root_node = node_loader.load_node(root_id)?;
 
     let mut cur_node = root_node;
-    let depth = (max_id_num as f64).log(fanout as f64).floor() as usize;
+    let depth = id_tree_depth(max_id_num, fanout);
     let mut cur_path_rev = fanout_nary_rev(obj_id.0, fanout, depth);
 
     let value = loop {
@@ -120,7 +120,7 @@ pub fn query_id_tree(
         None => bail!("The id tree is empty"),
     };
     let root_node = node_loader.load_node(id_tree_root_id)?;
-    let depth = (max_id_num as f64).log(fanout as f64).floor() as usize;
+    let depth = id_tree_depth(max_id_num, fanout);
     let mut cur_path_rev = fanout_nary_rev(obj_id.0, fanout, depth);
     let (v, p) = inner_query_id_tree(
         node_loader,
@@ -182,7 +182,7 @@ impl<'a, L: IdTreeNodeLoader> ReadContext<'a, L> {
         let internal_id: IdTreeInternalId = obj_id.to_internal_id();
         let value = match self.proof.root.as_mut() {
             Some(root) => {
-                let depth = (max_id_num as f64).log(fanout as f64).floor() as usize;
+                let depth = id_tree_depth(max_id_num, fanout);
                 let mut cur_path_rev = fanout_nary_rev(internal_id.0, fanout, depth);
 
                 match root.search_prefix(internal_id, &mut cur_path_rev) {
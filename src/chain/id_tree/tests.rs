@@ -767,3 +767,20 @@ fn test_proof_size() {
 
     assert_eq!(4, leaf1_size - leaf2_size);
 }
+
+#[test]
+fn test_obj_id_new_checked() {
+    let id = ObjId::new_checked(0, 32).unwrap();
+    assert_eq!(id, ObjId(NonZeroU16::new(1).unwrap()));
+    let id = ObjId::new_checked(31, 32).unwrap();
+    assert_eq!(id, ObjId(NonZeroU16::new(32).unwrap()));
+    assert_eq!(id.to_internal_id_checked(32).unwrap(), IdTreeInternalId(31));
+    assert!(id.to_internal_id_checked(31).is_err());
+
+    assert!(ObjId::new_checked(32, 32).is_err());
+    assert!(ObjId::new_checked(u64::MAX, 32).is_err());
+    assert!(ObjId::new_checked(0, 0).is_err());
+    assert!(ObjId::new_checked(0, u64::from(u16::MAX) + 1).is_err());
+    let id = ObjId::new_checked(u64::from(u16::MAX) - 1, u64::from(u16::MAX)).unwrap();
+    assert_eq!(id, ObjId(NonZeroU16::new(u16::MAX).unwrap()));
+}
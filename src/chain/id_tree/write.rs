@@ -2,7 +2,7 @@ use crate::chain::id_tree::{
     Digest, Digestible, IdTreeInternalId, IdTreeLeafNode, IdTreeNode, IdTreeNodeId,
     IdTreeNodeLoader, IdTreeNonLeafNode, IdTreeRoot, ObjId,
 };
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, ensure, Result};
 use serde::{Deserialize, Serialize};
 use std::{
     borrow::Cow,
@@ -64,13 +64,29 @@ impl<'a, L: IdTreeNodeLoader> WriteContext<'a, L> {
         })
     }
 
+    /// 设置 id 树的扇出；已有的 id 树必须使用相同的扇出继续构建
+    pub fn set_fanout(&mut self, fanout: u8) -> Result<()> {
+        ensure!(fanout >= 2, "Invalid id tree fanout {}", fanout);
+        match self.apply.root.fanout {
+            0 => self.apply.root.fanout = fanout,
+            f => ensure!(
+                f == fanout,
+                "Id tree fanout mismatched: the tree was built with fanout {}, but {} is given",
+                f,
+                fanout
+            ),
+        }
+        Ok(())
+    }
+
     pub fn insert(&mut self, obj_hash: Digest, max_id_num: u16, fanout: u8) -> Result<ObjId> {
+        self.set_fanout(fanout)?;
         let cur_id = self.apply.root.cur_obj_id;
-        let internal_id = cur_id.to_internal_id();
-        let next_internal_id = IdTreeInternalId((internal_id.0 + 1) % max_id_num);
-        self.apply.root.cur_obj_id = ObjId::from_internal_id(next_internal_id);
+        let internal_id = cur_id.to_internal_id_checked(max_id_num.into())?;
+        let next_internal_id = (u64::from(internal_id.0) + 1) % u64::from(max_id_num);
+        self.apply.root.cur_obj_id = ObjId::new_checked(next_internal_id, max_id_num.into())?;
         let mut cur_id_opt = self.apply.root.id_tree_root_id;
-        let depth = (max_id_num as f64).log(fanout as f64).floor() as usize;
+        let depth = id_tree_depth(max_id_num, fanout);
         let mut cur_path_rev = fanout_nary_rev(internal_id.0, fanout, depth);
 
         struct Leaf {
@@ -178,6 +194,17 @@ impl<'a, L: IdTreeNodeLoader> WriteContext<'a, L> {
     }
 }
 
+/// 容纳 `max_id_num` 个对象所需的 id 树深度，即满足 `fanout^depth >= max_id_num` 的最小 `depth`
+pub fn id_tree_depth(max_id_num: u16, fanout: u8) -> usize {
+    let mut depth = 0;
+    let mut capacity = 1u32;
+    while fanout > 1 && capacity < u32::from(max_id_num) {
+        capacity *= u32::from(fanout);
+        depth += 1;
+    }
+    depth
+}
+
 pub fn fanout_nary_rev(obj_id: u16, fanout: u8, depth: usize) -> Vec<usize> {
     let mut path: Vec<usize> = vec![0; depth];
     let mut num = obj_id;
@@ -208,4 +235,17 @@ mod tests {
         let v_two_2: Vec<usize> = fanout_nary_rev(1025, 2, 12);
         assert_eq!(v_two_2, expect_two_2);
     }
+
+    #[test]
+    fn test_id_tree_depth() {
+        use super::id_tree_depth;
+
+        assert_eq!(id_tree_depth(3, 3), 1);
+        assert_eq!(id_tree_depth(9, 3), 2);
+        assert_eq!(id_tree_depth(32, 4), 3);
+        assert_eq!(id_tree_depth(32, 8), 2);
+        assert_eq!(id_tree_depth(1024, 3), 7);
+        assert_eq!(id_tree_depth(1024, 4), 5);
+        assert_eq!(id_tree_depth(u16::MAX, 2), 16);
+    }
 }
@@ -1 +1,20 @@
-//核心适配文件，让 MMR 能用 累加器 的 Digest
\ No newline at end of file
+//核心适配文件，让 MMR 能用 累加器 的 Digest
+use crate::{
+    chain::mmr::{error::Result, merge::Merge},
+    digest::{blake2, Digest},
+};
+
+/// 以 BlockADSRoot 的 Digest 作为 MMR 叶子，父节点为左右子节点拼接后的 blake2 哈希
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BlockADSMerge;
+
+impl Merge for BlockADSMerge {
+    type Item = Digest;
+
+    fn merge(left: &Digest, right: &Digest) -> Result<Digest> {
+        let mut state = blake2().to_state();
+        state.update(left.as_bytes());
+        state.update(right.as_bytes());
+        Ok(Digest::from(state.finalize()))
+    }
+}
@@ -13,6 +13,10 @@ pub enum Error {
     GenProofForInvalidLeaves,
     /// The two nodes couldn't merge into one.
     MergeError(String),
+    /// The leaf count is larger than the leaves in the MMR
+    LeafCountOutOfRange(u64),
+    /// The mmr_size does not correspond to any MMR
+    InvalidMMRSize(u64),
 }
 
 impl core::fmt::Display for Error {
@@ -26,9 +30,11 @@ impl core::fmt::Display for Error {
             NodeProofsNotSupported => write!(f, "Tried to verify membership of a non-leaf")?,
             GenProofForInvalidLeaves => write!(f, "Generate proof for invalid leaves")?,
             MergeError(msg) => write!(f, "Merge error {}", msg)?,
+            LeafCountOutOfRange(n) => write!(f, "Leaf count {} out of range", n)?,
+            InvalidMMRSize(n) => write!(f, "Invalid mmr size {}", n)?,
         }
         Ok(())
     }
 }
 
-impl std::error::Error for Error {}
\ No newline at end of file
+impl std::error::Error for Error {}
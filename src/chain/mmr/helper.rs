@@ -1,21 +1,117 @@
 use std::vec;
 use std::vec::Vec;
 
+/// 将叶子序号（第几个叶子，从 0 开始）转换为该叶子在 MMR 中的位置（position，从 0 开始）。
+///
+/// 位置按节点写入顺序编号，叶子之间穿插着内部节点，因此二者通常不相等，
+/// 生成/验证证明时传入的都应是位置而不是叶子序号。
+///
+/// ```
+/// use vchain_plus::chain::mmr::helper::leaf_index_to_pos;
+///
+/// assert_eq!(leaf_index_to_pos(0), 0);
+/// assert_eq!(leaf_index_to_pos(1), 1);
+/// assert_eq!(leaf_index_to_pos(2), 3);
+/// assert_eq!(leaf_index_to_pos(4), 7);
+/// ```
 pub fn leaf_index_to_pos(index: u64) -> u64 {
     // mmr_size - H - 1, H is the height(intervals) of last peak
     leaf_index_to_mmr_size(index) - (index + 1).trailing_zeros() as u64 - 1
 }
 
+/// 支持的最大叶子序号：共 2^63 个叶子时 mmr_size 恰为 `u64::MAX`，再多一个叶子就会溢出
+pub const MAX_LEAF_INDEX: u64 = u64::MAX >> 1;
+
+/// 叶子序号为 `index` 的叶子加入后 MMR 的节点总数。
+///
+/// `index` 超过 [`MAX_LEAF_INDEX`] 时 panic，来自外部的输入应使用
+/// [`checked_leaf_index_to_mmr_size`]。
 pub fn leaf_index_to_mmr_size(index: u64) -> u64 {
+    checked_leaf_index_to_mmr_size(index).expect("leaf index exceeds MAX_LEAF_INDEX")
+}
+
+/// 同 [`leaf_index_to_mmr_size`]，`index` 超过 [`MAX_LEAF_INDEX`] 时返回 `None`。
+///
+/// ```
+/// use vchain_plus::chain::mmr::helper::{checked_leaf_index_to_mmr_size, MAX_LEAF_INDEX};
+///
+/// assert_eq!(checked_leaf_index_to_mmr_size(2), Some(4));
+/// assert_eq!(checked_leaf_index_to_mmr_size(MAX_LEAF_INDEX), Some(u64::MAX));
+/// assert_eq!(checked_leaf_index_to_mmr_size(MAX_LEAF_INDEX + 1), None);
+/// ```
+pub fn checked_leaf_index_to_mmr_size(index: u64) -> Option<u64> {
     // leaf index start with 0
-    let leaves_count = index + 1;
+    let leaves_count = index.checked_add(1)?;
 
     // the peak count(k) is actually the count of 1 in leaves count's binary representation
     let peak_count = leaves_count.count_ones() as u64;
 
-    2 * leaves_count - peak_count
+    // 2 * leaves_count - peak_count, 先做减法以免中间结果溢出
+    leaves_count.checked_add(leaves_count - peak_count)
+}
+
+/// 含 `leaf_count` 个叶子的 MMR 的节点总数（即 mmr_size）。
+///
+/// `leaf_count` 超过 `MAX_LEAF_INDEX + 1` 时 panic。
+pub fn leaf_count_to_mmr_size(leaf_count: u64) -> u64 {
+    match leaf_count.checked_sub(1) {
+        Some(index) => leaf_index_to_mmr_size(index),
+        None => 0,
+    }
+}
+
+/// `mmr_size` 对应的叶子数，`leaf_index_to_mmr_size` 的逆运算。
+///
+/// 若不存在叶子数恰好构成该 mmr_size（如 2、5、6），返回 `None`。
+///
+/// ```
+/// use vchain_plus::chain::mmr::helper::mmr_size_to_leaf_count;
+///
+/// assert_eq!(mmr_size_to_leaf_count(0), Some(0));
+/// assert_eq!(mmr_size_to_leaf_count(4), Some(3));
+/// assert_eq!(mmr_size_to_leaf_count(11), Some(7));
+/// assert_eq!(mmr_size_to_leaf_count(5), None);
+/// ```
+pub fn mmr_size_to_leaf_count(mmr_size: u64) -> Option<u64> {
+    let leaf_count = get_peak_map(mmr_size);
+    let expect_size = match leaf_count.checked_sub(1) {
+        Some(index) => checked_leaf_index_to_mmr_size(index)?,
+        None => 0,
+    };
+    if expect_size == mmr_size {
+        Some(leaf_count)
+    } else {
+        None
+    }
+}
+
+/// `mmr_size` 是否可能是某个 MMR 的节点总数。
+///
+/// 非法的 mmr_size 会让 `get_peaks` 等函数给出无意义的峰，
+/// 因此来自外部（如证明中携带）的 mmr_size 必须先经过该检查。
+///
+/// ```
+/// use vchain_plus::chain::mmr::helper::is_valid_mmr_size;
+///
+/// assert!(is_valid_mmr_size(11));
+/// assert!(!is_valid_mmr_size(5));
+/// ```
+pub fn is_valid_mmr_size(mmr_size: u64) -> bool {
+    mmr_size_to_leaf_count(mmr_size).is_some()
 }
 
+/// 位置 `pos` 处节点在其所在子树中的高度，叶子高度为 0。
+///
+/// 参数是位置而非叶子序号，叶子序号需先经 [`leaf_index_to_pos`] 转换。
+///
+/// ```
+/// use vchain_plus::chain::mmr::helper::pos_height_in_tree;
+///
+/// // 位置 0、1、3、4 为叶子，2、5 为高度 1 的节点，6 为高度 2 的节点
+/// assert_eq!(pos_height_in_tree(3), 0);
+/// assert_eq!(pos_height_in_tree(5), 1);
+/// assert_eq!(pos_height_in_tree(6), 2);
+/// ```
 pub fn pos_height_in_tree(mut pos: u64) -> u8 {
     if pos == 0 {
         return 0;
@@ -39,6 +135,17 @@ pub fn sibling_offset(height: u8) -> u64 {
     (2 << height) - 1
 }
 
+/// 峰位图：第 h 位为 1 表示存在高度为 h 的峰。
+///
+/// 对合法的 `mmr_size`，其数值恰好等于叶子数。
+///
+/// ```
+/// use vchain_plus::chain::mmr::helper::get_peak_map;
+///
+/// // 7 个叶子（mmr_size = 11）：高度 2、1、0 各一个峰
+/// assert_eq!(get_peak_map(11), 0b111);
+/// assert_eq!(get_peak_map(8), 0b101);
+/// ```
 pub fn get_peak_map(mmr_size: u64) -> u64 {
     if mmr_size == 0 {
         return 0;
@@ -59,6 +166,14 @@ pub fn get_peak_map(mmr_size: u64) -> u64 {
     peak_map
 }
 
+/// 按从左到右（高度递减）的顺序返回所有峰的位置。
+///
+/// ```
+/// use vchain_plus::chain::mmr::helper::get_peaks;
+///
+/// assert_eq!(get_peaks(4), vec![2, 3]);
+/// assert_eq!(get_peaks(11), vec![6, 9, 10]);
+/// ```
 pub fn get_peaks(mmr_size: u64) -> Vec<u64> {
     if mmr_size == 0 {
         return vec![];
@@ -78,4 +193,4 @@ pub fn get_peaks(mmr_size: u64) -> Vec<u64> {
         peak_size >>= 1;
     }
     peaks
-}
\ No newline at end of file
+}
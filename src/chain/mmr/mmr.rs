@@ -8,8 +8,8 @@ use std::vec::Vec;
 
 use crate::chain::mmr::error::{Error, Result};
 use crate::chain::mmr::helper::{
-    get_peak_map, get_peaks, leaf_index_to_mmr_size, leaf_index_to_pos, parent_offset,
-    pos_height_in_tree, sibling_offset,
+    get_peak_map, get_peaks, is_valid_mmr_size, leaf_count_to_mmr_size, leaf_index_to_mmr_size,
+    leaf_index_to_pos, parent_offset, pos_height_in_tree, sibling_offset,
 };
 use crate::chain::mmr::merge::Merge;
 use crate::chain::mmr::mmr_store::{MMRBatch, MMRStoreReadOps, MMRStoreWriteOps};
@@ -83,12 +83,31 @@ impl<T: Clone + PartialEq, M: Merge<Item = T>, S: MMRStoreReadOps<T>> MMR<T, M,
 
     /// get_root
     pub fn get_root(&self) -> Result<T> {
-        if self.mmr_size == 0 {
+        self.root_of_size(self.mmr_size)
+    }
+
+    /// 计算仅包含前 `leaf_count` 个叶子时的历史根，不修改当前 MMR
+    pub fn root_at(&self, leaf_count: u64) -> Result<T> {
+        let mmr_size = self.checked_size_of(leaf_count)?;
+        self.root_of_size(mmr_size)
+    }
+
+    // mmr_size of the first `leaf_count` leaves, which must not exceed current leaves
+    fn checked_size_of(&self, leaf_count: u64) -> Result<u64> {
+        check_mmr_size(self.mmr_size)?;
+        if leaf_count > get_peak_map(self.mmr_size) {
+            return Err(Error::LeafCountOutOfRange(leaf_count));
+        }
+        Ok(leaf_count_to_mmr_size(leaf_count))
+    }
+
+    fn root_of_size(&self, mmr_size: u64) -> Result<T> {
+        if mmr_size == 0 {
             return Err(Error::GetRootOnEmpty);
-        } else if self.mmr_size == 1 {
+        } else if mmr_size == 1 {
             return self.batch.get_elem(0)?.ok_or(Error::InconsistentStore);
         }
-        let peaks: Vec<T> = get_peaks(self.mmr_size)
+        let peaks: Vec<T> = get_peaks(mmr_size)
             .into_iter()
             .map(|peak_pos| {
                 self.batch
@@ -183,6 +202,7 @@ impl<T: Clone + PartialEq, M: Merge<Item = T>, S: MMRStoreReadOps<T>> MMR<T, M,
     /// 2. push merkle proof to proof by peak from left to right
     /// 3. push bagged right hand side root
     pub fn gen_proof(&self, mut pos_list: Vec<u64>) -> Result<MerkleProof<T, M>> {
+        check_mmr_size(self.mmr_size)?;
         if pos_list.is_empty() {
             return Err(Error::GenProofForInvalidLeaves);
         }
@@ -223,6 +243,76 @@ impl<T: Clone + PartialEq, M: Merge<Item = T>, S: MMRStoreReadOps<T>> MMR<T, M,
     }
 }
 
+impl<T: Clone + PartialEq, M: Merge<Item = T>, S: MMRStoreReadOps<T>> MMR<T, M, S> {
+    /// 回滚到仅包含前 `leaf_count` 个叶子的状态（用于链重组）
+    ///
+    /// 新的峰由 `get_peaks` 按缩小后的 mmr_size 重新确定；
+    /// 位置不小于新 mmr_size 的节点均为孤立节点，从内存批次中丢弃，
+    /// 已提交到存储中的旧节点不可再被访问，并会在后续 push 时被覆盖。
+    pub fn truncate(&mut self, leaf_count: u64) -> Result<()> {
+        let mmr_size = self.checked_size_of(leaf_count)?;
+        self.batch.truncate(mmr_size);
+        self.mmr_size = mmr_size;
+        Ok(())
+    }
+}
+
+impl<T: Clone + PartialEq, M: Merge<Item = T>, S: MMRStoreReadOps<T>> MMR<T, M, S> {
+    /// 生成一致性证明：证明含 `new_leaf_count` 个叶子的 MMR 是含 `old_leaf_count`
+    /// 个叶子的 MMR 的追加扩展（历史未被改写），类似 RFC 6962 的一致性证明。
+    ///
+    /// 证明包含旧 MMR 的所有峰，以及把这些旧峰逐级合并到新峰所需的兄弟节点；
+    /// 不包含任何旧峰的新峰直接给出其哈希。
+    pub fn gen_consistency_proof(
+        &self,
+        old_leaf_count: u64,
+        new_leaf_count: u64,
+    ) -> Result<ConsistencyProof<T, M>> {
+        if old_leaf_count == 0 || old_leaf_count > new_leaf_count {
+            return Err(Error::LeafCountOutOfRange(old_leaf_count));
+        }
+        let old_size = self.checked_size_of(old_leaf_count)?;
+        let new_size = self.checked_size_of(new_leaf_count)?;
+
+        let mut old_peaks = get_peaks(old_size)
+            .into_iter()
+            .map(|pos| {
+                let elem = self.batch.get_elem(pos)?.ok_or(Error::InconsistentStore)?;
+                Ok((pos, elem))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let old_peak_hashes = old_peaks.iter().map(|(_, elem)| elem.clone()).collect();
+
+        let mut proof: Vec<T> = Vec::new();
+        for peak_pos in get_peaks(new_size) {
+            let nodes = take_while_vec(&mut old_peaks, |(pos, _)| *pos <= peak_pos);
+            if nodes.is_empty() {
+                proof.push(
+                    self.batch
+                        .get_elem(peak_pos)?
+                        .ok_or(Error::InconsistentStore)?,
+                );
+            } else {
+                climb_to_peak::<_, M, _>(nodes, peak_pos, |sib_pos| {
+                    let elem = self
+                        .batch
+                        .get_elem(sib_pos)?
+                        .ok_or(Error::InconsistentStore)?;
+                    proof.push(elem.clone());
+                    Ok(elem)
+                })?;
+            }
+        }
+
+        Ok(ConsistencyProof::new(
+            old_size,
+            new_size,
+            old_peak_hashes,
+            proof,
+        ))
+    }
+}
+
 impl<T, M, S: MMRStoreWriteOps<T>> MMR<T, M, S> {
     pub fn commit(&mut self) -> Result<()> {
         self.batch.commit()
@@ -300,6 +390,7 @@ impl<T: Clone + PartialEq, M: Merge<Item = T>> MerkleProof<T, M> {
     /// - The MMR, which could generate the old root, appends all incremental leaves, becomes the
     ///   current MMR.
     pub fn verify_incremental(&self, root: T, prev_root: T, incremental: Vec<T>) -> Result<bool> {
+        check_mmr_size(self.mmr_size)?;
         let current_leaves_count = get_peak_map(self.mmr_size);
         if current_leaves_count <= incremental.len() as u64 {
             return Err(Error::CorruptedProof);
@@ -347,6 +438,192 @@ impl<T: Clone + PartialEq, M: Merge<Item = T>> MerkleProof<T, M> {
     }
 }
 
+/// MMR 两个版本之间的一致性证明，由 [`MMR::gen_consistency_proof`] 生成
+#[derive(Debug)]
+pub struct ConsistencyProof<T, M> {
+    old_mmr_size: u64,
+    new_mmr_size: u64,
+    old_peaks: Vec<T>,
+    proof: Vec<T>,
+    merge: PhantomData<M>,
+}
+
+impl<T: Clone + PartialEq, M: Merge<Item = T>> ConsistencyProof<T, M> {
+    pub fn new(old_mmr_size: u64, new_mmr_size: u64, old_peaks: Vec<T>, proof: Vec<T>) -> Self {
+        ConsistencyProof {
+            old_mmr_size,
+            new_mmr_size,
+            old_peaks,
+            proof,
+            merge: PhantomData,
+        }
+    }
+
+    pub fn old_mmr_size(&self) -> u64 {
+        self.old_mmr_size
+    }
+
+    pub fn new_mmr_size(&self) -> u64 {
+        self.new_mmr_size
+    }
+
+    pub fn old_peaks(&self) -> &[T] {
+        &self.old_peaks
+    }
+
+    pub fn proof_items(&self) -> &[T] {
+        &self.proof
+    }
+
+    /// 由旧峰与证明计算新 MMR 的各个峰
+    fn calculate_new_peaks(&self) -> Result<Vec<T>> {
+        check_mmr_size(self.old_mmr_size)?;
+        check_mmr_size(self.new_mmr_size)?;
+        if self.old_mmr_size == 0 || self.old_mmr_size > self.new_mmr_size {
+            return Err(Error::CorruptedProof);
+        }
+        let old_peaks_pos = get_peaks(self.old_mmr_size);
+        if old_peaks_pos.len() != self.old_peaks.len() {
+            return Err(Error::CorruptedProof);
+        }
+        let mut old_peaks: Vec<(u64, T)> = old_peaks_pos
+            .into_iter()
+            .zip(self.old_peaks.iter().cloned())
+            .collect();
+
+        let mut proof_iter = self.proof.iter();
+        let mut new_peaks = Vec::new();
+        for peak_pos in get_peaks(self.new_mmr_size) {
+            let nodes = take_while_vec(&mut old_peaks, |(pos, _)| *pos <= peak_pos);
+            let peak = if nodes.is_empty() {
+                proof_iter.next().cloned().ok_or(Error::CorruptedProof)?
+            } else {
+                climb_to_peak::<_, M, _>(nodes, peak_pos, |_| {
+                    proof_iter.next().cloned().ok_or(Error::CorruptedProof)
+                })?
+            };
+            new_peaks.push(peak);
+        }
+
+        // ensure all old peaks and proof items are consumed
+        if !old_peaks.is_empty() || proof_iter.next().is_some() {
+            return Err(Error::CorruptedProof);
+        }
+        Ok(new_peaks)
+    }
+
+    /// 验证 `new_root` 对应的 MMR 是 `old_root` 对应 MMR 的追加扩展
+    pub fn verify(&self, old_root: T, new_root: T) -> Result<bool> {
+        let new_peaks = self.calculate_new_peaks()?;
+        let calculated_old_root = bagging_peaks_hashes::<T, M>(self.old_peaks.clone())?;
+        if calculated_old_root != old_root {
+            return Ok(false);
+        }
+        let calculated_new_root = bagging_peaks_hashes::<T, M>(new_peaks)?;
+        Ok(calculated_new_root == new_root)
+    }
+}
+
+/// 将多个 MMR 的根（如各分片链的根）按给定顺序聚合成一个承诺，
+/// 与单个 MMR 装袋峰值的方式相同：从右向左依次 `merge_peaks(right, left)`
+pub fn bag_roots<T: Clone, M: Merge<Item = T>>(roots: &[T]) -> Result<T> {
+    if roots.is_empty() {
+        return Err(Error::GetRootOnEmpty);
+    }
+    bagging_peaks_hashes::<T, M>(roots.to_vec())
+}
+
+/// 某个根包含在 [`bag_roots`] 聚合结果中的证明
+#[derive(Debug)]
+pub struct RootsProof<T, M> {
+    /// 该根左侧的所有根，按原顺序排列
+    lhs_roots: Vec<T>,
+    /// 该根右侧所有根装袋后的结果，该根位于最右侧时为 `None`
+    rhs_bag: Option<T>,
+    merge: PhantomData<M>,
+}
+
+impl<T: Clone + PartialEq, M: Merge<Item = T>> RootsProof<T, M> {
+    /// 生成 `roots[index]` 包含在 `bag_roots(roots)` 中的证明
+    pub fn gen(roots: &[T], index: usize) -> Result<Self> {
+        if index >= roots.len() {
+            return Err(Error::GenProofForInvalidLeaves);
+        }
+        let rhs_roots = &roots[index + 1..];
+        let rhs_bag = if rhs_roots.is_empty() {
+            None
+        } else {
+            Some(bag_roots::<T, M>(rhs_roots)?)
+        };
+        Ok(RootsProof {
+            lhs_roots: roots[..index].to_vec(),
+            rhs_bag,
+            merge: PhantomData,
+        })
+    }
+
+    /// 被证明的根在所有根中的下标
+    pub fn index(&self) -> usize {
+        self.lhs_roots.len()
+    }
+
+    pub fn calculate_root(&self, root: T) -> Result<T> {
+        let mut bag = match &self.rhs_bag {
+            Some(rhs_bag) => M::merge_peaks(rhs_bag, &root)?,
+            None => root,
+        };
+        for lhs_root in self.lhs_roots.iter().rev() {
+            bag = M::merge_peaks(&bag, lhs_root)?;
+        }
+        Ok(bag)
+    }
+
+    /// 验证 `root` 包含在聚合承诺 `bagged_root` 中
+    pub fn verify(&self, bagged_root: T, root: T) -> Result<bool> {
+        self.calculate_root(root)
+            .map(|calculated_root| calculated_root == bagged_root)
+    }
+}
+
+/// 将同一新峰下、按位置升序排列的若干节点逐级合并到新峰 `peak_pos`，
+/// 缺少的兄弟节点由 `sibling` 按需提供。
+fn climb_to_peak<T, M: Merge<Item = T>, F: FnMut(u64) -> Result<T>>(
+    mut nodes: Vec<(u64, T)>,
+    peak_pos: u64,
+    mut sibling: F,
+) -> Result<T> {
+    let (mut pos, mut item) = nodes.pop().ok_or(Error::CorruptedProof)?;
+    let mut height = pos_height_in_tree(pos);
+    while pos != peak_pos {
+        if pos > peak_pos {
+            return Err(Error::CorruptedProof);
+        }
+        let next_height = pos_height_in_tree(pos + 1);
+        if next_height > height {
+            // implies pos is right sibling
+            let sib_pos = pos - sibling_offset(height);
+            let sibling_item = match nodes.last() {
+                Some((p, _)) if *p == sib_pos => {
+                    nodes.pop().map(|(_, item)| item).expect("checked")
+                }
+                _ => sibling(sib_pos)?,
+            };
+            item = M::merge(&sibling_item, &item)?;
+            pos += 1;
+        } else {
+            // pos is left sibling
+            let sibling_item = sibling(pos + sibling_offset(height))?;
+            item = M::merge(&item, &sibling_item)?;
+            pos += parent_offset(height);
+        }
+        height += 1;
+    }
+    if !nodes.is_empty() {
+        return Err(Error::CorruptedProof);
+    }
+    Ok(item)
+}
+
 fn calculate_peak_root<'a, T: 'a, M: Merge<Item = T>, I: Iterator<Item = &'a T>>(
     leaves: Vec<(u64, T)>,
     peak_pos: u64,
@@ -379,7 +656,10 @@ fn calculate_peak_root<'a, T: 'a, M: Merge<Item = T>, I: Iterator<Item = &'a T>>
                 let sib_pos = pos - sibling_offset;
                 let parent_pos = pos + 1;
                 let parent_item = if Some(&sib_pos) == queue.front().map(|(pos, _, _)| pos) {
-                    let sibling_item = queue.pop_front().map(|(_, item, _)| item).unwrap();
+                    let sibling_item = queue
+                        .pop_front()
+                        .map(|(_, item, _)| item)
+                        .ok_or(Error::CorruptedProof)?;
                     M::merge(&sibling_item, &item)?
                 } else {
                     let sibling_item = proof_iter.next().ok_or(Error::CorruptedProof)?;
@@ -391,7 +671,10 @@ fn calculate_peak_root<'a, T: 'a, M: Merge<Item = T>, I: Iterator<Item = &'a T>>
                 let sib_pos = pos + sibling_offset;
                 let parent_pos = pos + parent_offset(height);
                 let parent_item = if Some(&sib_pos) == queue.front().map(|(pos, _, _)| pos) {
-                    let sibling_item = queue.pop_front().map(|(_, item, _)| item).unwrap();
+                    let sibling_item = queue
+                        .pop_front()
+                        .map(|(_, item, _)| item)
+                        .ok_or(Error::CorruptedProof)?;
                     M::merge(&item, &sibling_item)?
                 } else {
                     let sibling_item = proof_iter.next().ok_or(Error::CorruptedProof)?;
@@ -415,6 +698,7 @@ fn calculate_peaks_hashes<'a, T: 'a + Clone, M: Merge<Item = T>, I: Iterator<Ite
     mmr_size: u64,
     mut proof_iter: I,
 ) -> Result<Vec<T>> {
+    check_mmr_size(mmr_size)?;
     if leaves.iter().any(|(pos, _)| pos_height_in_tree(*pos) > 0) {
         return Err(Error::NodeProofsNotSupported);
     }
@@ -489,6 +773,14 @@ fn calculate_root<'a, T: 'a + Clone, M: Merge<Item = T>, I: Iterator<Item = &'a
     bagging_peaks_hashes::<_, M>(peaks_hashes)
 }
 
+fn check_mmr_size(mmr_size: u64) -> Result<()> {
+    if is_valid_mmr_size(mmr_size) {
+        Ok(())
+    } else {
+        Err(Error::InvalidMMRSize(mmr_size))
+    }
+}
+
 fn take_while_vec<T, P: Fn(&T) -> bool>(v: &mut Vec<T>, p: P) -> Vec<T> {
     for i in 0..v.len() {
         if !p(&v[i]) {
@@ -22,6 +22,14 @@ impl<Elem, Store> MMRBatch<Elem, Store> {
     pub fn store(&self) -> &Store {
         &self.store
     }
+
+    /// drop all elements whose position >= `mmr_size`
+    pub fn truncate(&mut self, mmr_size: u64) {
+        self.memory_batch.retain(|(pos, _)| *pos < mmr_size);
+        for (pos, elems) in self.memory_batch.iter_mut() {
+            elems.truncate((mmr_size - *pos) as usize);
+        }
+    }
 }
 
 impl<Elem: Clone, Store: MMRStoreReadOps<Elem>> MMRBatch<Elem, Store> {
@@ -63,4 +71,4 @@ pub trait MMRStoreReadOps<Elem> {
 
 pub trait MMRStoreWriteOps<Elem> {
     fn append(&mut self, pos: u64, elems: Vec<Elem>) -> Result<()>;
-}
\ No newline at end of file
+}
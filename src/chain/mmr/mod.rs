@@ -1 +1,18 @@
-//模块入口，组织导出
\ No newline at end of file
+//模块入口，组织导出
+pub mod block_ads_merge;
+pub mod error;
+pub mod helper;
+pub mod merge;
+#[allow(clippy::module_inception)]
+pub mod mmr;
+pub mod mmr_store;
+pub mod util;
+
+pub use block_ads_merge::BlockADSMerge;
+pub use error::{Error, Result};
+pub use merge::Merge;
+pub use mmr::{bag_roots, ConsistencyProof, MerkleProof, RootsProof, MMR};
+pub use mmr_store::{MMRBatch, MMRStoreReadOps, MMRStoreWriteOps};
+
+#[cfg(test)]
+mod tests;
@@ -1 +1 @@
-//核心适配文件，让 MMR 能用 RocksDB
\ No newline at end of file
+//核心适配文件，让 MMR 能用 RocksDB
@@ -0,0 +1,247 @@
+use super::{
+    bag_roots,
+    helper::{
+        checked_leaf_index_to_mmr_size, is_valid_mmr_size, leaf_count_to_mmr_size,
+        leaf_index_to_mmr_size, leaf_index_to_pos, mmr_size_to_leaf_count, MAX_LEAF_INDEX,
+    },
+    util::{MemMMR, MemStore},
+    BlockADSMerge, ConsistencyProof, Error, RootsProof,
+};
+use crate::digest::{Digest, Digestible};
+
+fn leaf(i: u32) -> Digest {
+    i.to_digest()
+}
+
+fn build_mmr(store: &MemStore<Digest>, leaf_count: u32) -> MemMMR<'_, Digest, BlockADSMerge> {
+    let mut mmr = MemMMR::<_, BlockADSMerge>::new(0, store);
+    for i in 0..leaf_count {
+        mmr.push(leaf(i)).unwrap();
+    }
+    mmr
+}
+
+#[test]
+fn test_truncate() {
+    let store = MemStore::default();
+    let mut mmr = build_mmr(&store, 10);
+    let expect_store = MemStore::default();
+    let expect = build_mmr(&expect_store, 6);
+
+    mmr.truncate(6).unwrap();
+    assert_eq!(mmr.mmr_size(), expect.mmr_size());
+    assert_eq!(mmr.get_root().unwrap(), expect.get_root().unwrap());
+
+    // 回滚后继续追加，结果与重新构建一致
+    mmr.push(leaf(100)).unwrap();
+    let mut expect = expect;
+    expect.push(leaf(100)).unwrap();
+    assert_eq!(mmr.get_root().unwrap(), expect.get_root().unwrap());
+}
+
+#[test]
+fn test_truncate_after_commit() {
+    let store = MemStore::default();
+    let mut mmr = build_mmr(&store, 10);
+    mmr.commit().unwrap();
+    for n in (0..=10u32).rev() {
+        mmr.truncate(n as u64).unwrap();
+        if n == 0 {
+            assert!(mmr.is_empty());
+            assert_eq!(mmr.get_root(), Err(Error::GetRootOnEmpty));
+        } else {
+            let expect_store = MemStore::default();
+            let expect = build_mmr(&expect_store, n);
+            assert_eq!(mmr.get_root().unwrap(), expect.get_root().unwrap());
+        }
+    }
+    assert_eq!(mmr.truncate(1), Err(Error::LeafCountOutOfRange(1)));
+}
+
+#[test]
+fn test_root_at() {
+    let store = MemStore::default();
+    let mmr = build_mmr(&store, 10);
+    for n in 1..=10 {
+        let expect_store = MemStore::default();
+        let expect = build_mmr(&expect_store, n);
+        assert_eq!(mmr.root_at(n as u64).unwrap(), expect.get_root().unwrap());
+    }
+    assert_eq!(
+        mmr.mmr_size(),
+        build_mmr(&MemStore::default(), 10).mmr_size()
+    );
+    assert_eq!(mmr.root_at(0), Err(Error::GetRootOnEmpty));
+    assert_eq!(mmr.root_at(11), Err(Error::LeafCountOutOfRange(11)));
+}
+
+#[test]
+fn test_consistency_proof() {
+    let store = MemStore::default();
+    let mmr = build_mmr(&store, 11);
+    let old_root = mmr.root_at(5).unwrap();
+    let new_root = mmr.get_root().unwrap();
+
+    let proof = mmr.gen_consistency_proof(5, 11).unwrap();
+    assert!(proof.verify(old_root, new_root).unwrap());
+    assert!(!proof.verify(leaf(0), new_root).unwrap());
+    assert!(!proof.verify(old_root, leaf(0)).unwrap());
+
+    for old in 1..=11 {
+        for new in old..=11 {
+            let proof = mmr.gen_consistency_proof(old, new).unwrap();
+            let old_root = mmr.root_at(old).unwrap();
+            let new_root = mmr.root_at(new).unwrap();
+            assert!(proof.verify(old_root, new_root).unwrap());
+        }
+    }
+    assert!(mmr.gen_consistency_proof(6, 5).is_err());
+    assert!(mmr.gen_consistency_proof(5, 12).is_err());
+}
+
+#[test]
+fn test_consistency_proof_forged() {
+    let store = MemStore::default();
+    let mmr = build_mmr(&store, 11);
+    let old_root = mmr.root_at(5).unwrap();
+
+    // 改写中间的叶子 2 后生成的证明不能与原有旧根匹配
+    let forged_store = MemStore::default();
+    let mut forged = MemMMR::<_, BlockADSMerge>::new(0, &forged_store);
+    for i in 0..11 {
+        let elem = if i == 2 { leaf(1000) } else { leaf(i) };
+        forged.push(elem).unwrap();
+    }
+    let forged_root = forged.get_root().unwrap();
+    let proof = forged.gen_consistency_proof(5, 11).unwrap();
+    assert!(!proof.verify(old_root, forged_root).unwrap());
+
+    // 篡改证明中的旧峰同样无法通过验证
+    let proof = mmr.gen_consistency_proof(5, 11).unwrap();
+    let mut old_peaks = proof.old_peaks().to_vec();
+    old_peaks[0] = leaf(1000);
+    let tampered = ConsistencyProof::<_, BlockADSMerge>::new(
+        proof.old_mmr_size(),
+        proof.new_mmr_size(),
+        old_peaks,
+        proof.proof_items().to_vec(),
+    );
+    assert!(!tampered.verify(old_root, mmr.get_root().unwrap()).unwrap());
+
+    // 证明项数量不符时报错
+    let mut items = proof.proof_items().to_vec();
+    items.push(leaf(0));
+    let corrupted = ConsistencyProof::<_, BlockADSMerge>::new(
+        proof.old_mmr_size(),
+        proof.new_mmr_size(),
+        proof.old_peaks().to_vec(),
+        items,
+    );
+    assert_eq!(
+        corrupted.verify(old_root, mmr.get_root().unwrap()),
+        Err(Error::CorruptedProof)
+    );
+}
+
+#[test]
+fn test_mmr_size_to_leaf_count() {
+    for i in 0..10000u64 {
+        assert_eq!(
+            mmr_size_to_leaf_count(leaf_index_to_mmr_size(i)),
+            Some(i + 1)
+        );
+    }
+    assert_eq!(mmr_size_to_leaf_count(0), Some(0));
+    for invalid in [2u64, 5, 6, 9, 12, 13, 14] {
+        assert_eq!(mmr_size_to_leaf_count(invalid), None);
+    }
+}
+
+#[test]
+fn test_leaf_index_to_mmr_size_boundary() {
+    assert_eq!(
+        checked_leaf_index_to_mmr_size(MAX_LEAF_INDEX - 1),
+        Some(u64::MAX - 64)
+    );
+    assert_eq!(leaf_index_to_mmr_size(MAX_LEAF_INDEX), u64::MAX);
+    assert_eq!(leaf_index_to_pos(MAX_LEAF_INDEX), u64::MAX - 64);
+    assert_eq!(checked_leaf_index_to_mmr_size(MAX_LEAF_INDEX + 1), None);
+    assert_eq!(checked_leaf_index_to_mmr_size(u64::MAX), None);
+    assert_eq!(mmr_size_to_leaf_count(u64::MAX), Some(MAX_LEAF_INDEX + 1));
+    assert_eq!(mmr_size_to_leaf_count(u64::MAX - 1), None);
+}
+
+#[test]
+fn test_is_valid_mmr_size() {
+    let valid_sizes: Vec<u64> = (0..=64).map(leaf_count_to_mmr_size).collect();
+    for size in 0..=leaf_count_to_mmr_size(64) {
+        assert_eq!(is_valid_mmr_size(size), valid_sizes.contains(&size));
+    }
+
+    // mmr_size = 5 不对应任何 MMR
+    assert!(!is_valid_mmr_size(5));
+    let store = MemStore::default();
+    let mut mmr = build_mmr(&store, 3);
+    mmr.commit().unwrap();
+    let bad_mmr = MemMMR::<_, BlockADSMerge>::new(5, &store);
+    assert_eq!(
+        bad_mmr.gen_proof(vec![0]).err(),
+        Some(Error::InvalidMMRSize(5))
+    );
+    assert_eq!(
+        bad_mmr.gen_consistency_proof(1, 2).err(),
+        Some(Error::InvalidMMRSize(5))
+    );
+
+    let proof = mmr.gen_proof(vec![0]).unwrap();
+    let forged = super::MerkleProof::<_, BlockADSMerge>::new(5, proof.proof_items().to_vec());
+    assert_eq!(
+        forged.verify(mmr.get_root().unwrap(), vec![(0, leaf(0))]),
+        Err(Error::InvalidMMRSize(5))
+    );
+
+    let proof = mmr.gen_consistency_proof(1, 3).unwrap();
+    let forged = ConsistencyProof::<_, BlockADSMerge>::new(
+        1,
+        5,
+        proof.old_peaks().to_vec(),
+        proof.proof_items().to_vec(),
+    );
+    assert_eq!(
+        forged.verify(mmr.root_at(1).unwrap(), mmr.get_root().unwrap()),
+        Err(Error::InvalidMMRSize(5))
+    );
+}
+
+#[test]
+fn test_bag_roots() {
+    // 三个分片链各自的 MMR 根
+    let stores: Vec<MemStore<Digest>> = (0..3).map(|_| MemStore::default()).collect();
+    let roots: Vec<Digest> = stores
+        .iter()
+        .zip(&[3u32, 5, 8])
+        .map(|(store, &n)| build_mmr(store, n).get_root().unwrap())
+        .collect();
+    let bagged = bag_roots::<_, BlockADSMerge>(&roots).unwrap();
+    assert_eq!(bagged, bag_roots::<_, BlockADSMerge>(&roots).unwrap());
+    assert_eq!(
+        bag_roots::<Digest, BlockADSMerge>(&[]),
+        Err(Error::GetRootOnEmpty)
+    );
+
+    // 证明第二个分片的根包含在聚合承诺中
+    let proof = RootsProof::<_, BlockADSMerge>::gen(&roots, 1).unwrap();
+    assert_eq!(proof.index(), 1);
+    assert!(proof.verify(bagged, roots[1]).unwrap());
+    assert!(!proof.verify(bagged, roots[0]).unwrap());
+    assert!(!proof.verify(bagged, leaf(100)).unwrap());
+
+    for i in 0..roots.len() {
+        let proof = RootsProof::<_, BlockADSMerge>::gen(&roots, i).unwrap();
+        assert!(proof.verify(bagged, roots[i]).unwrap());
+    }
+    assert_eq!(
+        RootsProof::<_, BlockADSMerge>::gen(&roots, 3).err(),
+        Some(Error::GenProofForInvalidLeaves)
+    );
+}
@@ -0,0 +1,40 @@
+use crate::chain::mmr::{
+    error::Result,
+    mmr::MMR,
+    mmr_store::{MMRStoreReadOps, MMRStoreWriteOps},
+};
+use std::{cell::RefCell, collections::HashMap};
+
+/// 基于内存 HashMap 的 MMR 存储，主要用于测试与轻节点
+#[derive(Debug, Clone)]
+pub struct MemStore<T>(RefCell<HashMap<u64, T>>);
+
+impl<T> Default for MemStore<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> MemStore<T> {
+    pub fn new() -> Self {
+        MemStore(RefCell::new(HashMap::new()))
+    }
+}
+
+impl<T: Clone> MMRStoreReadOps<T> for &MemStore<T> {
+    fn get_elem(&self, pos: u64) -> Result<Option<T>> {
+        Ok(self.0.borrow().get(&pos).cloned())
+    }
+}
+
+impl<T> MMRStoreWriteOps<T> for &MemStore<T> {
+    fn append(&mut self, pos: u64, elems: Vec<T>) -> Result<()> {
+        let mut store = self.0.borrow_mut();
+        for (i, elem) in elems.into_iter().enumerate() {
+            store.insert(pos + i as u64, elem);
+        }
+        Ok(())
+    }
+}
+
+pub type MemMMR<'a, T, M> = MMR<T, M, &'a MemStore<T>>;
@@ -3,7 +3,7 @@ use crate::{
     digest::{Digest, Digestible},
 };
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Object<K: Num> {
@@ -20,6 +20,57 @@ impl<K: Num> Object<K> {
             keyword_data,
         }
     }
+
+    /// 对象内容的摘要，与对象 id 无关；内容相同的对象摘要相同，可用于去重。
+    /// 与 id 树中使用的 `obj_hash(obj, id)` 不同，后者还包含对象 id
+    pub fn content_digest(&self) -> Digest {
+        self.to_digest()
+    }
+
+    /// 只保留 `fields` 中的字段，用于客户端展示。投影不改变对象哈希的计算方式，
+    /// 验证仍需完整对象，因此既不减少 VO 大小，也不能代替完整对象参与验证
+    pub fn project(&self, fields: &[FieldId]) -> ProjectedObject<K> {
+        let mut projected = ProjectedObject::default();
+        for field in fields {
+            match *field {
+                FieldId::BlkHeight => projected.blk_height = Some(self.blk_height),
+                FieldId::Num(dim) => {
+                    if let Some(v) = self.num_data.get(dim as usize) {
+                        projected.num_data.insert(dim, *v);
+                    }
+                }
+                FieldId::Keywords => projected.keyword_data = Some(self.keyword_data.clone()),
+            }
+        }
+        projected
+    }
+}
+
+/// 对象中可被投影的字段
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum FieldId {
+    BlkHeight,
+    /// 第 `dim` 维数值属性
+    Num(u8),
+    Keywords,
+}
+
+/// `Object::project` 的结果，未被选中的字段为空
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ProjectedObject<K: Num> {
+    pub blk_height: Option<Height>,
+    pub num_data: BTreeMap<u8, K>,
+    pub keyword_data: Option<HashSet<String>>,
+}
+
+impl<K: Num> Default for ProjectedObject<K> {
+    fn default() -> Self {
+        Self {
+            blk_height: None,
+            num_data: BTreeMap::new(),
+            keyword_data: None,
+        }
+    }
 }
 
 impl<K: Num> Digestible for Object<K> {
@@ -27,3 +78,27 @@ impl<K: Num> Digestible for Object<K> {
         object_hash(self.blk_height, &self.num_data, &self.keyword_data)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::{id_tree::ObjId, verify::hash::obj_hash};
+
+    #[test]
+    fn test_content_digest() {
+        let keywords: HashSet<String> = ["a", "b"].iter().map(|k| k.to_string()).collect();
+        let obj1 = Object::new(Height(1), vec![1u32, 2], keywords.clone());
+        let obj2 = Object::new(Height(1), vec![1u32, 2], keywords.clone());
+        assert_eq!(obj1, obj2);
+        assert_eq!(obj1.content_digest(), obj2.content_digest());
+
+        let id1 = ObjId::new_checked(0, 32).unwrap();
+        let id2 = ObjId::new_checked(1, 32).unwrap();
+        assert_eq!(obj_hash(&obj1, &id1), obj_hash(&obj2, &id1));
+        assert_ne!(obj_hash(&obj1, &id1), obj_hash(&obj2, &id2));
+
+        let obj3 = Object::new(Height(2), vec![1u32, 2], keywords);
+        assert_ne!(obj1, obj3);
+        assert_ne!(obj1.content_digest(), obj3.content_digest());
+    }
+}
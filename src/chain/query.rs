@@ -1,28 +1,38 @@
 pub mod egg_qp;
+pub mod explain;
+pub mod parser;
 pub mod query_dag;
 pub mod query_param;
 pub mod query_plan;
 
 use self::{
-    query_dag::DagNode,
+    query_dag::{identity_child, DagNode},
     query_param::{param_to_qp, Node},
 };
 use crate::{
     acc::{
-        compute_set_operation_final, compute_set_operation_intermediate, ops::Op, AccPublicKey, Set,
+        compute_set_operation_final, compute_set_operation_intermediate, ops::Op, AccPublicKey,
+        AccValue, FinalProof, IntermediateProof, Set,
     },
     chain::{
         block::{hash::obj_id_nums_hash, Height},
         bplus_tree,
         id_tree::{self, ObjId},
-        object::Object,
+        object::{FieldId, Object, ProjectedObject},
         query::{egg_qp::egg_optimize, query_dag::gen_parallel_query_dag, query_plan::QPNode},
         range::Range,
         traits::{Num, ReadInterface},
         trie_tree,
-        verify::vo::{
-            MerkleProof, VOBlkRtNode, VOFinalDiff, VOFinalIntersec, VOFinalUnion, VOInterDiff,
-            VOInterIntersec, VOInterUnion, VOKeywordNode, VONode, VORangeNode, VoDagContent, VO,
+        verify::{
+            cache::VerifyCache,
+            cal_vo_size, inner_verify, pre_verify,
+            vo::{
+                time_win_heights, IdSetProof, MerkleProof, VOBlkRtNode, VOFinalDiff,
+                VOFinalIntersec, VOFinalUnion, VOHeightFilter, VOIdentity, VOInterDiff,
+                VOInterIntersec, VOInterUnion, VOKeywordConjunction, VOKeywordNode,
+                VOKeywordPrefix, VONode, VORangeNode, VoDagContent, VO,
+            },
+            VerifyInfo, VerifyOptions,
         },
     },
     digest::{Digest, Digestible},
@@ -35,8 +45,9 @@ use petgraph::{graph::NodeIndex, EdgeDirection::Outgoing, Graph};
 use query_param::QueryParam;
 use query_plan::QueryPlan;
 use rayon::prelude::*;
+use serde::Serialize;
 use smol_str::SmolStr;
-use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::collections::{hash_map::Entry, BTreeMap, HashMap, HashSet, VecDeque};
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub struct TimeWin {
@@ -62,6 +73,51 @@ pub struct QueryContent<K: Num> {
     pub keyword_exp: Option<Node>,
 }
 
+/// 单个时间窗口的查询结果：结果对象、VO 以及生成该 VO 所用的查询 DAG，
+/// 三者始终放在一起，避免验证时把 VO 与其他查询的结果或 DAG 混用
+pub struct QueryResult<K: Num> {
+    pub objects: HashMap<ObjId, Object<K>>,
+    pub vo: VO<K>,
+    pub dag: Graph<DagNode<K>, bool>,
+}
+
+impl<K: Num + Serialize> QueryResult<K> {
+    /// 使用结果自带的 DAG 验证 VO 与结果对象
+    pub fn verify<T: ReadInterface<K = K>>(
+        &self,
+        chain: T,
+        pk: &AccPublicKey,
+    ) -> Result<VerifyInfo> {
+        let timer = howlong::ProcessCPUTimer::new();
+        pre_verify(&[&self.vo], &self.dag, &VerifyOptions::default())?;
+        let cache = VerifyCache::new(pk);
+        inner_verify(
+            &chain,
+            Some(&self.objects),
+            &self.vo,
+            &self.dag,
+            &cache,
+            &mut HashMap::new(),
+            None,
+            None,
+            pk,
+        )?;
+        let time = Time::from(timer.elapsed());
+        Ok(VerifyInfo {
+            vo_size: cal_vo_size(&self.vo)?,
+            verify_time: time,
+        })
+    }
+
+    /// 结果对象按 `fields` 投影后的视图，仅用于展示；验证仍使用完整的 `objects`
+    pub fn projected_objects(&self, fields: &[FieldId]) -> HashMap<ObjId, ProjectedObject<K>> {
+        self.objects
+            .iter()
+            .map(|(id, obj)| (*id, obj.project(fields)))
+            .collect()
+    }
+}
+
 pub struct QueryResInfo<K: Num> {
     stage1: ProcessDuration,
     stage2: ProcessDuration,
@@ -70,6 +126,75 @@ pub struct QueryResInfo<K: Num> {
     res: (HashMap<ObjId, Object<K>>, VO<K>),
 }
 
+// 按顺序对 `sets` 逐个执行集合运算 `op`，返回结果集合以及每一步的中间证明；
+// 若为输出节点，最后一步生成 `FinalProof`
+#[allow(clippy::type_complexity)]
+fn fold_set_operation(
+    op: Op,
+    sets: &[Set],
+    accs: &[AccValue],
+    is_output: bool,
+    pk: &AccPublicKey,
+) -> Result<(Set, Vec<(AccValue, IntermediateProof)>, Option<FinalProof>)> {
+    let mut set = sets.first().context("Empty operand list")?.clone();
+    let mut acc = *accs.first().context("Empty operand list")?;
+    let mut proofs = Vec::with_capacity(accs.len().saturating_sub(1));
+    let mut final_proof = None;
+    for (i, (s, a)) in sets.iter().zip(accs.iter()).enumerate().skip(1) {
+        if i + 1 == sets.len() && is_output {
+            let (res_set, proof) = compute_set_operation_final(op, &set, s, pk);
+            set = res_set;
+            final_proof = Some(proof);
+        } else {
+            let (res_set, res_acc, proof) =
+                compute_set_operation_intermediate(op, &set, &acc, s, a, pk);
+            set = res_set;
+            acc = res_acc;
+            proofs.push((res_acc, proof));
+        }
+    }
+    Ok((set, proofs, final_proof))
+}
+
+// 区块 `blk_height` 处使用的时间窗口大小：查询窗口终点为 `e_win_size`，起点的前一个区块为 `s_win_size`
+fn win_size_at(
+    time_win: &TimeWin,
+    s_win_size: Option<u16>,
+    e_win_size: u16,
+    blk_height: Height,
+) -> Result<u16> {
+    if blk_height.0 == time_win.get_end() {
+        Ok(e_win_size)
+    } else if blk_height.0 == time_win.get_start() - 1 {
+        s_win_size.context("hight = start time_win height but start win_size is None")
+    } else {
+        bail!("invalid blk height");
+    }
+}
+
+// 取出区块 `height` 处的 trie 读取上下文，不存在时按 `win_size` 对应的 trie 根创建，
+// 使同一区块上的多个关键字节点共享一个 trie 证明
+fn trie_ctx_for<'a, 'b, K: Num, T: ReadInterface<K = K>>(
+    chain: &'a T,
+    trie_ctxes: &'b mut HashMap<Height, trie_tree::read::ReadContext<'a, T>>,
+    height: Height,
+    win_size: u16,
+) -> Result<&'b mut trie_tree::read::ReadContext<'a, T>> {
+    Ok(match trie_ctxes.entry(height) {
+        Entry::Occupied(e) => e.into_mut(),
+        Entry::Vacant(e) => {
+            let trie_root = chain
+                .read_block_content(height)?
+                .ads
+                .read_trie_root(win_size)?;
+            e.insert(trie_tree::read::ReadContext::new(
+                chain,
+                trie_root.trie_root_id,
+            ))
+        }
+    })
+}
+
 #[allow(clippy::too_many_arguments)]
 #[allow(clippy::type_complexity)]
 fn query_final<K: Num, T: ReadInterface<K = K>>(
@@ -105,12 +230,13 @@ fn query_final<K: Num, T: ReadInterface<K = K>>(
     for idx in qp_inputs {
         if let Some(dag_node) = query_dag.node_weight(idx) {
             match dag_node {
-                query_dag::DagNode::Range(node) => {
+                query_dag::DagNode::Range(_) | query_dag::DagNode::MultiInterval(_) => {
+                    let (dim, intervals) = dag_node.bplus_query()?;
                     let set;
                     let acc;
                     let proof;
                     if let Some(QPNode::Range(n)) = qp_dag_content.remove(&idx) {
-                        height_dims.push((n.blk_height, node.dim as u8));
+                        height_dims.push((n.blk_height, dim));
                         let blk_height = n.blk_height;
                         let win_size = if blk_height.0 == time_win.get_end() {
                             e_win_size
@@ -130,11 +256,11 @@ fn query_final<K: Num, T: ReadInterface<K = K>>(
                             let bplus_root = chain
                                 .read_block_content(blk_height)?
                                 .ads
-                                .read_bplus_root(win_size, node.dim)?;
-                            let (s, a, p) = bplus_tree::read::range_query(
+                                .read_bplus_root(win_size, dim)?;
+                            let (s, a, p) = bplus_tree::read::multi_range_query(
                                 chain,
                                 bplus_root.bplus_tree_root_id,
-                                node.range,
+                                &intervals,
                                 pk,
                             )?;
                             set = s;
@@ -195,6 +321,65 @@ fn query_final<K: Num, T: ReadInterface<K = K>>(
                         set_map.insert(idx, set);
                     }
                 }
+                query_dag::DagNode::KeywordSet(node) => {
+                    if let Some(QPNode::Keyword(n)) = qp_dag_content.remove(&idx) {
+                        let blk_height = n.blk_height;
+                        let win_size = win_size_at(time_win, s_win_size, e_win_size, blk_height)?;
+                        time_win_map.insert(blk_height, win_size);
+                        let trie_ctx = trie_ctx_for(chain, &mut trie_ctxes, blk_height, win_size)?;
+                        let (sets, accs): (Vec<Set>, Vec<AccValue>) = trie_ctx
+                            .query_keywords(&node.keywords, pk)?
+                            .into_iter()
+                            .unzip();
+                        let (set, proofs, final_proof) = fold_set_operation(
+                            Op::Intersection,
+                            &sets,
+                            &accs,
+                            outputs.contains(&idx),
+                            pk,
+                        )?;
+                        let vo_keyword_conj = VOKeywordConjunction {
+                            blk_height,
+                            win_size,
+                            accs,
+                            proofs,
+                            final_proof,
+                        };
+                        vo_dag_content.insert(idx, VONode::KeywordConjunction(vo_keyword_conj));
+                        set_map.insert(idx, set);
+                    }
+                }
+                query_dag::DagNode::KeywordPrefix(node) => {
+                    if let Some(QPNode::Keyword(n)) = qp_dag_content.remove(&idx) {
+                        let blk_height = n.blk_height;
+                        let win_size = win_size_at(time_win, s_win_size, e_win_size, blk_height)?;
+                        time_win_map.insert(blk_height, win_size);
+                        let trie_ctx = trie_ctx_for(chain, &mut trie_ctxes, blk_height, win_size)?;
+                        let (mut sets, mut accs): (Vec<Set>, Vec<AccValue>) =
+                            trie_ctx.query_prefix(&node.prefix, pk)?.into_iter().unzip();
+                        if sets.is_empty() {
+                            let empty_set = Set::new();
+                            accs.push(AccValue::from_set(&empty_set, pk));
+                            sets.push(empty_set);
+                        }
+                        let (set, proofs, final_proof) = fold_set_operation(
+                            Op::Union,
+                            &sets,
+                            &accs,
+                            outputs.contains(&idx),
+                            pk,
+                        )?;
+                        let vo_keyword_prefix = VOKeywordPrefix {
+                            blk_height,
+                            win_size,
+                            accs,
+                            proofs,
+                            final_proof,
+                        };
+                        vo_dag_content.insert(idx, VONode::KeywordPrefix(vo_keyword_prefix));
+                        set_map.insert(idx, set);
+                    }
+                }
                 query_dag::DagNode::BlkRt(_) => {
                     let set;
                     let acc;
@@ -223,10 +408,16 @@ fn query_final<K: Num, T: ReadInterface<K = K>>(
                             set = bplus_root_node.get_set().clone();
                             acc = bplus_root_node.get_node_acc();
                         }
+                        let mut id_set_proofs = BTreeMap::new();
+                        for height in time_win_heights(blk_height, win_size) {
+                            let blk_content = chain.read_block_content(height)?;
+                            id_set_proofs.insert(height, IdSetProof::new(&blk_content));
+                        }
                         let vo_blk_root = VOBlkRtNode {
                             blk_height: n.blk_height,
                             win_size,
                             acc,
+                            id_set_proofs,
                         };
                         vo_dag_content.insert(idx, VONode::BlkRt(vo_blk_root));
                         set_map.insert(idx, set);
@@ -462,6 +653,60 @@ fn query_final<K: Num, T: ReadInterface<K = K>>(
                         }
                     }
                 }
+                query_dag::DagNode::Identity(_) => {
+                    if let Some(QPNode::Identity(_)) = qp_dag_content.remove(&idx) {
+                        let qp_c_idx = identity_child(query_dag, idx)?;
+                        let vo_c = vo_dag_content
+                            .get(&qp_c_idx)
+                            .context("Cannot find the child vo node of identity")?;
+                        let c_set = set_map
+                            .get(&qp_c_idx)
+                            .context("Cannot find the set in set_map")?
+                            .clone();
+                        let vo_identity = VOIdentity {
+                            acc: *vo_c.get_acc()?,
+                        };
+                        vo_dag_content.insert(idx, VONode::Identity(vo_identity));
+                        set_map.insert(idx, c_set);
+                    }
+                }
+                query_dag::DagNode::HeightFilter(node) => {
+                    if let Some(QPNode::HeightFilter(_)) = qp_dag_content.remove(&idx) {
+                        let qp_c_idx = identity_child(query_dag, idx)?;
+                        let vo_c = vo_dag_content
+                            .get(&qp_c_idx)
+                            .context("Cannot find the child vo node of height filter")?;
+                        let c_set = set_map
+                            .get(&qp_c_idx)
+                            .context("Cannot find the set in set_map")?;
+                        time_win_map.insert(qp_end_blk_height, e_win_size);
+                        let mut filter_set = Set::new();
+                        let mut id_set_proofs = BTreeMap::new();
+                        for height in node.heights(qp_end_blk_height, e_win_size) {
+                            let blk_content = chain.read_block_content(height)?;
+                            filter_set = &filter_set | &blk_content.id_set();
+                            id_set_proofs.insert(height, IdSetProof::new(&blk_content));
+                        }
+                        let filter_acc = AccValue::from_set(&filter_set, pk);
+                        let (res_set, res_acc, inter_proof) = compute_set_operation_intermediate(
+                            Op::Intersection,
+                            c_set,
+                            vo_c.get_acc()?,
+                            &filter_set,
+                            &filter_acc,
+                            pk,
+                        );
+                        let vo_height_filter = VOHeightFilter {
+                            blk_height: qp_end_blk_height,
+                            win_size: e_win_size,
+                            id_set_proofs,
+                            acc: res_acc,
+                            proof: inter_proof,
+                        };
+                        vo_dag_content.insert(idx, VONode::HeightFilter(vo_height_filter));
+                        set_map.insert(idx, res_set);
+                    }
+                }
             }
         }
     }
@@ -494,15 +739,25 @@ fn query_final<K: Num, T: ReadInterface<K = K>>(
     let max_id_num = param.max_id_num;
     let id_tree_fanout = param.id_tree_fanout;
 
+    let mut output_sets = HashMap::<NodeIndex, Set>::new();
     for idx in outputs {
-        let set = set_map.get(&idx).context("Cannot find set in set_map")?;
+        let set = set_map.remove(&idx).context("Cannot find set in set_map")?;
+        let mut obj_ids = Vec::new();
         for i in set.iter() {
             let obj_id = ObjId(*i);
-            if let Some(obj_hash) = id_tree_ctx.query(obj_id, max_id_num, id_tree_fanout)? {
-                let obj = chain.read_object(obj_hash)?;
-                obj_map.insert(obj_id, obj);
+            if id_tree_ctx
+                .query(obj_id, max_id_num, id_tree_fanout)?
+                .is_some()
+                && !obj_map.contains_key(&obj_id)
+            {
+                obj_ids.push(obj_id);
             }
         }
+        for res in chain.read_objects_stream(qp_end_blk_height, &obj_ids) {
+            let (obj_id, obj) = res?;
+            obj_map.insert(obj_id, obj);
+        }
+        output_sets.insert(idx, set);
     }
 
     let id_tree_proof = id_tree_ctx.into_proof();
@@ -534,16 +789,16 @@ fn query_final<K: Num, T: ReadInterface<K = K>>(
         } else {
             Some(chain.read_block_content(height)?.id_tree_root.to_digest())
         };
-        let merkle_proof = MerkleProof {
+        let merkle_proof = MerkleProof::new(
             id_tree_root_hash,
             id_set_root_hash,
             ads_hashes,
             extra_bplus_rt_hashes,
-        };
+        )?;
         merkle_proofs.insert(height, merkle_proof);
     }
     let vo_dag_struct = VoDagContent {
-        output_sets: set_map,
+        output_sets,
         dag_content: vo_dag_content,
     };
     let vo = VO {
@@ -680,10 +935,10 @@ fn process_empty_sets<K: Num>(
             .node_weight(idx)
             .context("node not exist in dag")?;
         match query_node {
-            DagNode::Range(_) => {
+            DagNode::Range(_) | DagNode::MultiInterval(_) => {
                 new_qp_content.insert(idx, qp_content.remove(&idx).context("")?);
             }
-            DagNode::Keyword(_) => {
+            DagNode::Keyword(_) | DagNode::KeywordSet(_) | DagNode::KeywordPrefix(_) => {
                 new_qp_content.insert(idx, qp_content.remove(&idx).context("")?);
             }
             DagNode::BlkRt(_) => {
@@ -787,6 +1042,10 @@ fn process_empty_sets<K: Num>(
                 queue.push_back(*qp_c_idx2);
                 new_qp_content.insert(idx, qp_content.remove(&idx).context("")?);
             }
+            DagNode::Identity(_) | DagNode::HeightFilter(_) => {
+                queue.push_back(identity_child(query_dag, idx)?);
+                new_qp_content.insert(idx, qp_content.remove(&idx).context("")?);
+            }
         }
     }
 
@@ -842,15 +1101,54 @@ pub fn query<K: Num, T: ReadInterface<K = K> + std::marker::Sync + std::marker::
     Vec<(HashMap<ObjId, Object<K>>, VO<K>)>,
     Graph<DagNode<K>, bool>,
     QueryTime,
+)> {
+    let query_content = query_param.gen_query_content();
+    let dag = gen_parallel_query_dag(&query_content)?;
+    query_with_dag(empty_set, egg_opt, chain, query_param, dag, pk)
+}
+
+/// 与 `query` 相同，但每个时间窗口的结果与 VO、查询 DAG 一起封装为 `QueryResult`
+pub fn query_results<K: Num, T: ReadInterface<K = K> + std::marker::Sync + std::marker::Send>(
+    empty_set: bool,
+    egg_opt: bool,
+    chain: T,
+    query_param: QueryParam<K>,
+    pk: &AccPublicKey,
+) -> Result<(Vec<QueryResult<K>>, QueryTime)> {
+    let (results, dag, time) = query(empty_set, egg_opt, chain, query_param, pk)?;
+    let results = results
+        .into_iter()
+        .map(|(objects, vo)| QueryResult {
+            objects,
+            vo,
+            dag: dag.clone(),
+        })
+        .collect();
+    Ok((results, time))
+}
+
+/// 与 `query` 相同，但使用调用方给定的查询 DAG（例如经过 `optimize_dag` 重排后的 DAG），
+/// 查询条件仍以 `query_param` 中的时间窗口为准
+#[allow(clippy::type_complexity)]
+pub fn query_with_dag<K: Num, T: ReadInterface<K = K> + std::marker::Sync + std::marker::Send>(
+    empty_set: bool,
+    egg_opt: bool,
+    chain: T,
+    query_param: QueryParam<K>,
+    dag: Graph<DagNode<K>, bool>,
+    pk: &AccPublicKey,
+) -> Result<(
+    Vec<(HashMap<ObjId, Object<K>>, VO<K>)>,
+    Graph<DagNode<K>, bool>,
+    QueryTime,
 )> {
     let chain_param = &chain.get_parameter()?;
+    chain_param.schema().check_dag(&dag)?;
     let chain_win_sizes = &chain_param.time_win_sizes;
     let timer = howlong::ProcessCPUTimer::new();
     let query_time_win = query_param.gen_time_win();
-    let query_content = query_param.gen_query_content();
     let mut complete_wins = select_win_size(chain_win_sizes, query_time_win)?;
     let mut responses = Vec::with_capacity(complete_wins.len());
-    let dag = gen_parallel_query_dag(&query_content)?;
     let res_dag = parallel_processing(
         empty_set,
         egg_opt,
@@ -1,5 +1,5 @@
 use super::{
-    query_dag::DagNode,
+    query_dag::{identity_child, DagNode},
     query_plan::{QPNode, QueryPlan},
 };
 use crate::{
@@ -116,6 +116,14 @@ pub fn egg_optimize<K: Num>(
     dag: &Graph<DagNode<K>, bool>,
     qp: &mut QueryPlan<K>,
 ) -> Result<Graph<DagNode<K>, bool>> {
+    // 高度过滤节点依赖其子节点所在的区块窗口，不参与集合运算的重写
+    if dag
+        .raw_nodes()
+        .iter()
+        .any(|n| matches!(n.weight, DagNode::HeightFilter(_)))
+    {
+        return Ok(dag.clone());
+    }
     let dag_cont = qp.get_dag_cont();
     let (expr, root_id) = create_rec_exp(dag, dag_cont)?;
 
@@ -165,7 +173,12 @@ fn create_rec_exp<K: Num>(
     for idx in &dag_inputs {
         if let Some(dag_node) = dag.node_weight(*idx) {
             match dag_node {
-                DagNode::Range(_) | DagNode::Keyword(_) | DagNode::BlkRt(_) => {
+                DagNode::Range(_)
+                | DagNode::MultiInterval(_)
+                | DagNode::Keyword(_)
+                | DagNode::KeywordSet(_)
+                | DagNode::KeywordPrefix(_)
+                | DagNode::BlkRt(_) => {
                     let set = dag_cont
                         .get(idx)
                         .context("Cannot find node in dag_cont when creating expr")?
@@ -264,6 +277,20 @@ fn create_rec_exp<K: Num>(
                     }
                     idx_map.insert(*idx, dif_idx);
                 }
+                DagNode::Identity(_) => {
+                    // 透传节点不参与优化，直接使用子节点的表达式
+                    let qp_c_idx = identity_child(dag, *idx)?;
+                    let exp_c_idx = *idx_map
+                        .get(&qp_c_idx)
+                        .context("Cannot find id in idx_map")?;
+                    if root_idx == *idx {
+                        root_id = exp_c_idx;
+                    }
+                    idx_map.insert(*idx, exp_c_idx);
+                }
+                DagNode::HeightFilter(_) => {
+                    bail!("Height filter node cannot be optimized");
+                }
             }
         }
     }
@@ -0,0 +1,258 @@
+//! 查询执行前的代价估计（EXPLAIN）
+//!
+//! 只读取区块元数据（各区块的 `obj_id_nums`、参数中的扇出与维度），不做任何密码学运算，
+//! 按 `VOSize` 的分类估计每个时间窗口子查询产生的 VO 大小以及验证所需的配对运算次数。
+//!
+//! 估计模型（均为 bincode 编码后的字节数）：
+//! - 集合基数：窗口内对象数为 n，关键字 / 范围 / BlkRt 节点取 n/2，k 个关键字的合取取 n/2^k，
+//!   交集取较小子集合的一半，并集取两者之和（不超过 n），差集取左子集合的一半；
+//! - `vo_dag_s`：叶子节点携带一个累加器值，范围节点另加 `(depth + 1)` 层 B+ 树证明，
+//!   BlkRt 节点另加窗口内每个区块的对象 id 集合证明；高度过滤节点按窗口内全部区块落在区间中估计，
+//!   携带各区块的对象 id 集合证明、一个累加器值与 `IntermediateProof`，基数取子节点的一半；
+//!   运算节点若为输出节点则携带 `FinalProof`，否则携带累加器值与 `IntermediateProof`；
+//!   k 个关键字的合取节点携带 k 个累加器值与 k - 1 次求交集的证明，前缀节点按只匹配一个关键字估计；
+//! - `trie_proof_s`：每个关键字的证明路径长度取关键字长度加一；
+//! - `id_proof_s`：每个结果对象一条 id 树路径；
+//! - `merkle_s`：每个窗口一个 `MerkleProof`。
+//!
+//! 在测试数据上，估计的总大小与实际 `cal_vo_size` 的结果相差不超过 `EXPLAIN_ERROR_FACTOR` 倍。
+
+use super::{query_dag::DagNode, query_param::QueryParam, select_win_size, TimeWin};
+use crate::{
+    acc::{AccPublicKey, AccValue, Set},
+    chain::{
+        block::Height,
+        id_tree::write::id_tree_depth,
+        traits::{Num, ReadInterface},
+        verify::VOSize,
+    },
+    digest::DIGEST_LEN,
+};
+use anyhow::{bail, Context, Result};
+use ark_serialize::CanonicalSerialize;
+use petgraph::{
+    algo::toposort,
+    graph::NodeIndex,
+    EdgeDirection::{Incoming, Outgoing},
+    Graph,
+};
+use std::collections::{HashMap, HashSet};
+
+/// 估计值与实际 VO 大小之间允许的误差倍数
+pub const EXPLAIN_ERROR_FACTOR: usize = 3;
+
+/// bincode 对变长字节串、集合等写入的长度前缀
+const LEN_PREFIX: usize = 8;
+/// snappy 帧格式的流标识与块头开销
+const FRAME_OVERHEAD: usize = 18;
+/// `IntersectionProof` 中的 G1 元素个数
+const INTERSECTION_PROOF_G1_NUM: usize = 5;
+/// `IntermediateProof` 中的 G1 元素个数（两个 `IntersectionProof` 加四个 G1 元素）
+const INTERMEDIATE_PROOF_G1_NUM: usize = 2 * INTERSECTION_PROOF_G1_NUM + 4;
+/// 验证一个 `IntersectionProof` 所需的配对次数
+const INTERSECTION_PROOF_PAIRING_NUM: usize = 9;
+/// 验证一个 `IntermediateProof` 所需的配对次数
+const INTERMEDIATE_PROOF_PAIRING_NUM: usize = 2 * INTERSECTION_PROOF_PAIRING_NUM + 10;
+
+#[derive(Debug)]
+pub struct QueryExplain {
+    /// 查询被划分成的时间窗口及其窗口大小
+    pub windows: Vec<(TimeWin, u16)>,
+    /// 每个 DAG 节点在所有窗口中估计产生的 VO 大小
+    pub node_vo_sizes: HashMap<NodeIndex, VOSize>,
+    /// 估计的 VO 总大小
+    pub vo_size: VOSize,
+    /// 估计的验证配对运算次数
+    pub pairing_num: usize,
+}
+
+/// 估计在 `chain` 上执行 `query_param`（使用查询 DAG `graph`）所产生的 VO 大小与验证代价
+pub fn explain_query<K: Num, T: ReadInterface<K = K>>(
+    chain: &T,
+    query_param: &QueryParam<K>,
+    graph: &Graph<DagNode<K>, bool>,
+    pk: &AccPublicKey,
+) -> Result<QueryExplain> {
+    let param = chain.get_parameter()?;
+    let windows = select_win_size(&param.time_win_sizes, query_param.gen_time_win())?;
+    let order = match toposort(graph, None) {
+        Ok(v) => v,
+        Err(_) => bail!("Input query graph not valid"),
+    };
+
+    let acc_s = bincode::serialize(&AccValue::from_set(&Set::new(), pk))?.len();
+    let g1_s = pk.g.serialized_size() + LEN_PREFIX;
+    let inter_proof_s = 4 + INTERMEDIATE_PROOF_G1_NUM * g1_s;
+    let final_proof_s = 4 + INTERSECTION_PROOF_G1_NUM * g1_s;
+    let entry_s = 4 + 4;
+    let leaf_s = 4 + 2 + acc_s;
+    let path_s = DIGEST_LEN + LEN_PREFIX;
+    let id_depth = id_tree_depth(param.max_id_num, param.id_tree_fanout);
+
+    let mut node_vo_sizes = HashMap::<NodeIndex, VOSize>::new();
+    let mut vo_size = VOSize::default();
+    let mut pairing_num = 0;
+    for (time_win, win_size) in &windows {
+        let mut obj_num = 0;
+        for height in time_win.get_start()..=time_win.get_end() {
+            obj_num += chain.read_block_content(Height(height))?.obj_id_nums.len();
+        }
+        let bplus_depth = if obj_num > 1 {
+            (obj_num as f64).log(param.bplus_tree_fanout as f64).ceil() as usize
+        } else {
+            1
+        };
+
+        let mut cards = HashMap::<NodeIndex, usize>::new();
+        let mut keywords = HashSet::<&str>::new();
+        let mut win_vo_size = VOSize::default();
+        let mut output_s = LEN_PREFIX;
+        for &idx in order.iter().rev() {
+            let node = graph.node_weight(idx).context("Cannot find dag node")?;
+            let child_cards: Vec<usize> = graph
+                .neighbors_directed(idx, Outgoing)
+                .map(|c| cards.get(&c).copied().unwrap_or(obj_num))
+                .collect();
+            let is_output = graph.neighbors_directed(idx, Incoming).next().is_none();
+            let (card, vo_dag_s, trie_proof_s) = match node {
+                DagNode::Keyword(n) => {
+                    let trie_proof_s = if keywords.insert(&n.keyword) {
+                        (n.keyword.len() + 1) * path_s * 2
+                    } else {
+                        0
+                    };
+                    (obj_num / 2, leaf_s, trie_proof_s)
+                }
+                DagNode::KeywordSet(n) => {
+                    let trie_proof_s: usize = n
+                        .keywords
+                        .iter()
+                        .filter(|k| keywords.insert(k))
+                        .map(|k| (k.len() + 1) * path_s * 2)
+                        .sum();
+                    let op_num = n.keywords.len().saturating_sub(1);
+                    let vo_dag_s = if is_output && op_num > 0 {
+                        pairing_num += (op_num - 1) * INTERMEDIATE_PROOF_PAIRING_NUM
+                            + INTERSECTION_PROOF_PAIRING_NUM;
+                        (op_num - 1) * (acc_s + inter_proof_s) + 1 + final_proof_s
+                    } else {
+                        pairing_num += op_num * INTERMEDIATE_PROOF_PAIRING_NUM;
+                        op_num * (acc_s + inter_proof_s) + 1
+                    };
+                    (
+                        obj_num >> n.keywords.len().min(16),
+                        4 + 2 + LEN_PREFIX * 2 + n.keywords.len() * acc_s + vo_dag_s,
+                        trie_proof_s,
+                    )
+                }
+                DagNode::KeywordPrefix(n) => (
+                    obj_num / 2,
+                    4 + 2 + LEN_PREFIX * 2 + acc_s + 1,
+                    (n.prefix.len() + 1) * path_s * 2,
+                ),
+                DagNode::BlkRt(_) => {
+                    let id_set_proof_s = usize::from(*win_size) * (4 + 2 * path_s + LEN_PREFIX);
+                    (
+                        obj_num / 2,
+                        leaf_s + LEN_PREFIX + id_set_proof_s + 2 * obj_num,
+                        0,
+                    )
+                }
+                DagNode::Identity(_) => (
+                    child_cards.first().copied().unwrap_or(obj_num),
+                    4 + acc_s,
+                    0,
+                ),
+                DagNode::HeightFilter(_) => {
+                    let id_set_proof_s = usize::from(*win_size) * (4 + 2 * path_s + LEN_PREFIX);
+                    pairing_num += INTERMEDIATE_PROOF_PAIRING_NUM;
+                    (
+                        child_cards.first().copied().unwrap_or(obj_num) / 2,
+                        4 + 2 + LEN_PREFIX + id_set_proof_s + obj_num + acc_s + inter_proof_s,
+                        0,
+                    )
+                }
+                DagNode::Range(_) => (
+                    obj_num / 2,
+                    leaf_s + (bplus_depth + 1) * (acc_s + path_s),
+                    0,
+                ),
+                // 各区间共用自根向下的路径，每多一个区间只多出靠近叶子的一段边界
+                DagNode::MultiInterval(n) => (
+                    obj_num / 2,
+                    leaf_s + (bplus_depth + n.intervals().len()) * (acc_s + path_s),
+                    0,
+                ),
+                DagNode::Union(_) | DagNode::Intersec(_) | DagNode::Diff(_) => {
+                    let (lhs, rhs) = match child_cards.as_slice() {
+                        [a, b] => (*a, *b),
+                        _ => bail!("Invalid number of operands in query dag"),
+                    };
+                    let card = match node {
+                        DagNode::Union(_) => (lhs + rhs).min(obj_num),
+                        DagNode::Intersec(_) => lhs.min(rhs) / 2,
+                        _ => lhs / 2,
+                    };
+                    if is_output {
+                        pairing_num += INTERSECTION_PROOF_PAIRING_NUM;
+                        (card, final_proof_s, 0)
+                    } else {
+                        pairing_num += INTERMEDIATE_PROOF_PAIRING_NUM;
+                        (card, acc_s + 1 + inter_proof_s, 0)
+                    }
+                }
+            };
+            cards.insert(idx, card);
+            if is_output {
+                output_s += 4 + LEN_PREFIX + 2 * card;
+            }
+            let node_vo_size = VOSize::new(
+                entry_s + vo_dag_s,
+                trie_proof_s,
+                0,
+                0,
+                0,
+                entry_s + vo_dag_s + trie_proof_s,
+            );
+            win_vo_size += node_vo_size;
+            *node_vo_sizes.entry(idx).or_default() += node_vo_size;
+        }
+
+        let res_num: usize = graph
+            .node_indices()
+            .filter(|&idx| graph.neighbors_directed(idx, Incoming).next().is_none())
+            .filter_map(|idx| cards.get(&idx))
+            .sum();
+        let trie_base_s = LEN_PREFIX + 4 + path_s;
+        let id_proof_s = res_num.min(param.max_id_num as usize) * (id_depth + 1) * path_s;
+        let cur_id_s = 2;
+        let merkle_s = 2 * path_s
+            + LEN_PREFIX
+            + param.time_win_sizes.len().saturating_sub(1) * (2 + DIGEST_LEN)
+            + LEN_PREFIX
+            + param.num_dim.saturating_sub(1) as usize * (1 + DIGEST_LEN);
+        win_vo_size += VOSize::new(
+            output_s,
+            trie_base_s,
+            id_proof_s,
+            cur_id_s,
+            merkle_s,
+            output_s + trie_base_s + id_proof_s + cur_id_s + merkle_s,
+        );
+        vo_size += VOSize::new(
+            win_vo_size.vo_dag_s + FRAME_OVERHEAD,
+            win_vo_size.trie_proof_s + FRAME_OVERHEAD,
+            win_vo_size.id_proof_s + FRAME_OVERHEAD,
+            win_vo_size.cur_id_s + FRAME_OVERHEAD,
+            win_vo_size.merkle_s + FRAME_OVERHEAD,
+            win_vo_size.total_s + FRAME_OVERHEAD,
+        );
+    }
+
+    Ok(QueryExplain {
+        windows,
+        node_vo_sizes,
+        vo_size,
+        pairing_num,
+    })
+}
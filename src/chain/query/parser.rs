@@ -0,0 +1,393 @@
+//! 查询表达式解析：将文本形式的查询直接编译为查询 DAG
+//!
+//! 语法（运算符不区分大小写）：
+//!
+//! ```text
+//! expr    := and ("OR" and)*
+//! and     := diff ("AND" diff)*
+//! diff    := primary ("-" primary)*
+//! primary := keyword("..") | prefix("..") | range(dim=D, L..H) | range(dim=D, L..=H) | "(" expr ")"
+//! ```
+//!
+//! 优先级从低到高依次为 `OR`、`AND`、`-`，同级运算左结合。字符串中可用 `\"`、`\\` 转义。
+//! 生成的 DAG 与 `gen_parallel_query_dag` 的约定一致：Intersec / Union 的左操作数边权为 `true`；
+//! Diff 的被减数边权为 `false`、减数边权为 `true`。同一关键字只生成一个叶子节点。
+
+use super::query_dag::{
+    DagNode, DiffNode, IntersecNode, KeywordNode, KeywordPrefixNode, UnionNode,
+};
+use crate::chain::{range::RangeBound, traits::Num};
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use petgraph::{graph::NodeIndex, Graph};
+use std::{collections::HashMap, ops::Bound};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Number(String),
+    LParen,
+    RParen,
+    Comma,
+    Eq,
+    Minus,
+    DotDot,
+    DotDotEq,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' | ',' | '=' | '-' => {
+                chars.next();
+                tokens.push(match c {
+                    '(' => Token::LParen,
+                    ')' => Token::RParen,
+                    ',' => Token::Comma,
+                    '=' => Token::Eq,
+                    _ => Token::Minus,
+                });
+            }
+            '.' => {
+                chars.next();
+                ensure!(chars.next() == Some('.'), "Expected '..' in query");
+                if chars.peek() == Some(&'=') {
+                    chars.next();
+                    tokens.push(Token::DotDotEq);
+                } else {
+                    tokens.push(Token::DotDot);
+                }
+            }
+            '"' => {
+                chars.next();
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(e @ '"') | Some(e @ '\\') => s.push(e),
+                            other => bail!("Invalid escape sequence \\{:?} in query", other),
+                        },
+                        Some(ch) => s.push(ch),
+                        None => bail!("Unterminated string in query"),
+                    }
+                }
+                tokens.push(Token::Str(s));
+            }
+            c if c.is_ascii_digit() => {
+                let mut s = String::new();
+                while let Some(&d) = chars.peek() {
+                    if !d.is_ascii_digit() {
+                        break;
+                    }
+                    s.push(d);
+                    chars.next();
+                }
+                tokens.push(Token::Number(s));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut s = String::new();
+                while let Some(&d) = chars.peek() {
+                    if !(d.is_alphanumeric() || d == '_') {
+                        break;
+                    }
+                    s.push(d);
+                    chars.next();
+                }
+                tokens.push(Token::Ident(s));
+            }
+            c => bail!("Unexpected character {:?} in query", c),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser<K: Num> {
+    tokens: Vec<Token>,
+    pos: usize,
+    dag: Graph<DagNode<K>, bool>,
+    keywords: HashMap<String, NodeIndex>,
+}
+
+impl<K: Num> Parser<K> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<()> {
+        match self.next() {
+            Some(t) if t == expected => Ok(()),
+            other => bail!("Expected {:?} in query, found {:?}", expected, other),
+        }
+    }
+
+    fn eat_op(&mut self, op: &str) -> bool {
+        match self.peek() {
+            Some(Token::Ident(s)) if s.eq_ignore_ascii_case(op) => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn add_op(&mut self, node: DagNode<K>, lhs: NodeIndex, rhs: NodeIndex) -> NodeIndex {
+        let (lhs_w, rhs_w) = match node {
+            DagNode::Diff(_) => (false, true),
+            _ => (true, false),
+        };
+        let idx = self.dag.add_node(node);
+        self.dag.add_edge(idx, lhs, lhs_w);
+        self.dag.add_edge(idx, rhs, rhs_w);
+        idx
+    }
+
+    fn parse_or(&mut self) -> Result<NodeIndex> {
+        let mut lhs = self.parse_and()?;
+        while self.eat_op("or") {
+            let rhs = self.parse_and()?;
+            lhs = self.add_op(DagNode::Union(UnionNode {}), lhs, rhs);
+        }
+        Ok(lhs)
+    }
+
+    fn parse_and(&mut self) -> Result<NodeIndex> {
+        let mut lhs = self.parse_diff()?;
+        while self.eat_op("and") {
+            let rhs = self.parse_diff()?;
+            lhs = self.add_op(DagNode::Intersec(IntersecNode {}), lhs, rhs);
+        }
+        Ok(lhs)
+    }
+
+    fn parse_diff(&mut self) -> Result<NodeIndex> {
+        let mut lhs = self.parse_primary()?;
+        while self.peek() == Some(&Token::Minus) {
+            self.pos += 1;
+            let rhs = self.parse_primary()?;
+            lhs = self.add_op(DagNode::Diff(DiffNode {}), lhs, rhs);
+        }
+        Ok(lhs)
+    }
+
+    fn parse_primary(&mut self) -> Result<NodeIndex> {
+        match self.next() {
+            Some(Token::LParen) => {
+                let idx = self.parse_or()?;
+                self.expect(Token::RParen)?;
+                Ok(idx)
+            }
+            Some(Token::Ident(f)) if f.eq_ignore_ascii_case("keyword") => {
+                let keyword = self.parse_str_arg()?;
+                let dag = &mut self.dag;
+                Ok(*self.keywords.entry(keyword.clone()).or_insert_with(|| {
+                    dag.add_node(DagNode::Keyword(Box::new(KeywordNode { keyword })))
+                }))
+            }
+            Some(Token::Ident(f)) if f.eq_ignore_ascii_case("prefix") => {
+                let prefix = self.parse_str_arg()?;
+                Ok(self
+                    .dag
+                    .add_node(DagNode::KeywordPrefix(Box::new(KeywordPrefixNode {
+                        prefix,
+                    }))))
+            }
+            Some(Token::Ident(f)) if f.eq_ignore_ascii_case("range") => {
+                let range = self.parse_range_args()?;
+                Ok(self.dag.add_node(DagNode::Range(range)))
+            }
+            other => bail!("Unexpected token {:?} in query", other),
+        }
+    }
+
+    fn parse_str_arg(&mut self) -> Result<String> {
+        self.expect(Token::LParen)?;
+        let s = match self.next() {
+            Some(Token::Str(s)) => s,
+            other => bail!("Expected a string literal in query, found {:?}", other),
+        };
+        self.expect(Token::RParen)?;
+        Ok(s)
+    }
+
+    fn parse_num<T: Num>(&mut self) -> Result<T> {
+        let neg = self.peek() == Some(&Token::Minus);
+        if neg {
+            self.pos += 1;
+        }
+        match self.next() {
+            Some(Token::Number(s)) => {
+                let s = if neg { format!("-{}", s) } else { s };
+                s.parse::<T>()
+                    .map_err(|_| anyhow!("Invalid number {} in query", s))
+            }
+            other => bail!("Expected a number in query, found {:?}", other),
+        }
+    }
+
+    // range(dim=D, L..H) 或 range(dim=D, L..=H)
+    fn parse_range_args(&mut self) -> Result<RangeBound<K>> {
+        self.expect(Token::LParen)?;
+        ensure!(self.eat_op("dim"), "Expected 'dim=' in range");
+        self.expect(Token::Eq)?;
+        let dim: u8 = self.parse_num()?;
+        self.expect(Token::Comma)?;
+        let low: K = self.parse_num()?;
+        let inclusive = match self.next() {
+            Some(Token::DotDot) => false,
+            Some(Token::DotDotEq) => true,
+            other => bail!("Expected '..' or '..=' in range, found {:?}", other),
+        };
+        let high: K = self.parse_num()?;
+        self.expect(Token::RParen)?;
+        let high = if inclusive {
+            Bound::Included(high)
+        } else {
+            Bound::Excluded(high)
+        };
+        RangeBound::with_bounds(dim, Bound::Included(low), high)
+    }
+}
+
+/// 将查询表达式编译为查询 DAG，语法见模块文档
+pub fn parse_query<K: Num>(input: &str) -> Result<Graph<DagNode<K>, bool>> {
+    let mut parser = Parser {
+        tokens: tokenize(input)?,
+        pos: 0,
+        dag: Graph::new(),
+        keywords: HashMap::new(),
+    };
+    ensure!(!parser.tokens.is_empty(), "Empty query");
+    parser.parse_or().context("Failed to parse query")?;
+    if let Some(token) = parser.peek() {
+        bail!("Unexpected trailing token {:?} in query", token);
+    }
+    Ok(parser.dag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use petgraph::EdgeDirection::{Incoming, Outgoing};
+
+    fn root<K: Num>(dag: &Graph<DagNode<K>, bool>) -> NodeIndex {
+        let roots: Vec<_> = dag
+            .node_indices()
+            .filter(|&idx| dag.neighbors_directed(idx, Incoming).next().is_none())
+            .collect();
+        assert_eq!(roots.len(), 1);
+        roots[0]
+    }
+
+    // 按边权返回运算节点的 (权为 true 的子节点, 权为 false 的子节点)
+    fn children<K: Num>(dag: &Graph<DagNode<K>, bool>, idx: NodeIndex) -> (NodeIndex, NodeIndex) {
+        let mut t = None;
+        let mut f = None;
+        for c in dag.neighbors_directed(idx, Outgoing) {
+            let edge = dag.find_edge(idx, c).unwrap();
+            if dag[edge] {
+                t = Some(c);
+            } else {
+                f = Some(c);
+            }
+        }
+        (t.unwrap(), f.unwrap())
+    }
+
+    fn keyword<K: Num>(dag: &Graph<DagNode<K>, bool>, idx: NodeIndex) -> &str {
+        match &dag[idx] {
+            DagNode::Keyword(n) => &n.keyword,
+            n => panic!("not a keyword node: {:?}", n),
+        }
+    }
+
+    #[test]
+    fn test_parse_precedence() {
+        // AND 优先于 OR
+        let dag = parse_query::<u32>(r#"keyword("a") OR keyword("b") and keyword("c")"#).unwrap();
+        assert_eq!(dag.node_count(), 5);
+        let r = root(&dag);
+        assert!(matches!(dag[r], DagNode::Union(_)));
+        let (lhs, rhs) = children(&dag, r);
+        assert_eq!(keyword(&dag, lhs), "a");
+        assert!(matches!(dag[rhs], DagNode::Intersec(_)));
+        let (lhs, rhs) = children(&dag, rhs);
+        assert_eq!((keyword(&dag, lhs), keyword(&dag, rhs)), ("b", "c"));
+
+        // 差集优先于 AND
+        let dag = parse_query::<u32>(r#"keyword("a") AND keyword("b") - keyword("c")"#).unwrap();
+        let r = root(&dag);
+        assert!(matches!(dag[r], DagNode::Intersec(_)));
+        assert!(matches!(dag[children(&dag, r).1], DagNode::Diff(_)));
+
+        // 同一关键字共享叶子节点
+        let dag = parse_query::<u32>(r#"keyword("a") OR keyword("a")"#).unwrap();
+        assert_eq!(dag.node_count(), 2);
+    }
+
+    #[test]
+    fn test_parse_parentheses() {
+        let dag = parse_query::<u32>(
+            r#"keyword("apple") AND (range(dim=0, 10..20) OR keyword("banana"))"#,
+        )
+        .unwrap();
+        let r = root(&dag);
+        assert!(matches!(dag[r], DagNode::Intersec(_)));
+        let (lhs, rhs) = children(&dag, r);
+        assert_eq!(keyword(&dag, lhs), "apple");
+        assert!(matches!(dag[rhs], DagNode::Union(_)));
+        let (lhs, rhs) = children(&dag, rhs);
+        let expect = RangeBound::with_bounds(0, Bound::Included(10), Bound::Excluded(20)).unwrap();
+        assert!(matches!(dag[lhs], DagNode::Range(r) if r == expect));
+        assert_eq!(keyword(&dag, rhs), "banana");
+
+        let dag = parse_query::<i64>(r#"range(dim=1, -5..=5)"#).unwrap();
+        let expect = RangeBound::new(1, -5, 5).unwrap();
+        assert!(matches!(dag[root(&dag)], DagNode::Range(r) if r == expect));
+
+        let dag = parse_query::<u32>(r#"prefix("app\"le")"#).unwrap();
+        assert!(matches!(&dag[root(&dag)], DagNode::KeywordPrefix(n) if n.prefix == "app\"le"));
+    }
+
+    #[test]
+    fn test_parse_diff() {
+        // 被减数的边权为 false，减数的边权为 true
+        let dag = parse_query::<u32>(r#"keyword("a") - keyword("b") - keyword("c")"#).unwrap();
+        let r = root(&dag);
+        assert!(matches!(dag[r], DagNode::Diff(_)));
+        let (subtrahend, minuend) = children(&dag, r);
+        assert_eq!(keyword(&dag, subtrahend), "c");
+        assert!(matches!(dag[minuend], DagNode::Diff(_)));
+        let (subtrahend, minuend) = children(&dag, minuend);
+        assert_eq!(keyword(&dag, subtrahend), "b");
+        assert_eq!(keyword(&dag, minuend), "a");
+    }
+
+    #[test]
+    fn test_parse_error() {
+        for input in &[
+            "",
+            r#"keyword("a") AND"#,
+            r#"(keyword("a")"#,
+            r#"keyword("a"))"#,
+            r#"keyword(a)"#,
+            r#"keyword("a"#,
+            r#"range(dim=0, 20..10)"#,
+            r#"range(0, 1..2)"#,
+            r#"keyword("a") keyword("b")"#,
+        ] {
+            assert!(parse_query::<u32>(input).is_err(), "{}", input);
+        }
+    }
+}
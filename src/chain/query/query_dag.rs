@@ -1,41 +1,62 @@
-use super::{query_plan::QueryPlan, TimeWin};
+use super::{query_plan::QueryPlan, trie_ctx_for, TimeWin};
 use crate::{
-    acc::AccPublicKey,
+    acc::{AccPublicKey, AccValue, Set},
     chain::{
         block::Height,
         bplus_tree,
         query::{
             query_param::{AndNode, Node, NotNode, OrNode},
             query_plan::{
-                QPBlkRtNode, QPDiff, QPIntersec, QPKeywordNode, QPNode, QPRangeNode, QPUnion,
+                QPBlkRtNode, QPDiff, QPHeightFilter, QPIdentity, QPIntersec, QPKeywordNode, QPNode,
+                QPRangeNode, QPUnion,
             },
             QueryContent,
         },
-        range::Range,
+        range::RangeBound,
         traits::{Num, ReadInterface},
         trie_tree,
+        verify::vo::time_win_heights,
     },
+    utils::{binary_decode, binary_encode},
+};
+use anyhow::{bail, ensure, Context, Result};
+use petgraph::{
+    algo::toposort,
+    graph::NodeIndex,
+    EdgeDirection::{Incoming, Outgoing},
+    Graph,
 };
-use anyhow::{bail, Context, Result};
-use petgraph::{algo::toposort, graph::NodeIndex, EdgeDirection::Outgoing, Graph};
 use serde::{Deserialize, Serialize};
 use smol_str::SmolStr;
-use std::collections::{HashMap, VecDeque};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    ops::{Bound, Range},
+};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum DagNode<K: Num> {
-    Range(RangeNode<K>),
+    Range(RangeBound<K>),
     Keyword(Box<KeywordNode>),
+    KeywordSet(Box<KeywordSetNode>),
+    KeywordPrefix(Box<KeywordPrefixNode>),
     BlkRt(Box<BlkRtNode>),
     Union(UnionNode),
     Intersec(IntersecNode),
     Diff(DiffNode),
+    Identity(IdentityNode),
+    HeightFilter(HeightFilterNode),
+    MultiInterval(Box<MultiIntervalNode<K>>),
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct RangeNode<K: Num> {
-    pub(crate) range: Range<K>,
-    pub(crate) dim: u8,
+impl<K: Num> DagNode<K> {
+    /// Range / MultiInterval 节点在 B+ 树上查询的维度与区间
+    pub(crate) fn bplus_query(&self) -> Result<(u8, Vec<RangeBound<K>>)> {
+        match self {
+            DagNode::Range(n) => Ok((n.dim(), vec![*n])),
+            DagNode::MultiInterval(n) => Ok((n.dim(), n.bounds()?)),
+            _ => bail!("Not a range query node"),
+        }
+    }
 }
 
 #[derive(Clone, Default, Debug, Serialize, Deserialize)]
@@ -43,6 +64,18 @@ pub struct KeywordNode {
     pub(crate) keyword: String,
 }
 
+/// 多个关键字的合取（AND），共享同一 trie 证明，无需额外的 Intersec 节点
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+pub struct KeywordSetNode {
+    pub(crate) keywords: Vec<String>,
+}
+
+/// 以 `prefix` 为前缀的所有关键字的并集
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+pub struct KeywordPrefixNode {
+    pub(crate) prefix: String,
+}
+
 #[derive(Clone, Default, Debug, Serialize, Deserialize)]
 pub struct BlkRtNode {}
 
@@ -55,6 +88,100 @@ pub struct IntersecNode {}
 #[derive(Clone, Default, Debug, Serialize, Deserialize)]
 pub struct DiffNode {}
 
+/// 只有一个子节点的透传节点，结果集合与子节点相同
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+pub struct IdentityNode {}
+
+/// 只保留子节点结果中位于区块高度区间 `range`（左闭右开）内的对象，
+/// 过滤集合由区间内各区块经区块头认证的对象 id 集合求并得到
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+pub struct HeightFilterNode {
+    pub range: Range<Height>,
+}
+
+impl HeightFilterNode {
+    /// 以 `blk_height` 结尾、大小为 `win_size` 的时间窗口中落在 `range` 内的区块高度
+    pub(crate) fn heights(
+        &self,
+        blk_height: Height,
+        win_size: u16,
+    ) -> impl Iterator<Item = Height> + '_ {
+        time_win_heights(blk_height, win_size).filter(move |h| self.range.contains(h))
+    }
+
+    /// 过滤集合：窗口内落在 `range` 中的各区块对象 id 集合的并集
+    pub(crate) fn filter_set<K: Num, T: ReadInterface<K = K>>(
+        &self,
+        chain: &T,
+        blk_height: Height,
+        win_size: u16,
+    ) -> Result<Set> {
+        let mut set = Set::new();
+        for height in self.heights(blk_height, win_size) {
+            set = &set | &chain.read_block_content(height)?.id_set();
+        }
+        Ok(set)
+    }
+}
+
+/// 同一维度上多个闭区间的并集（OR），在该维度的 B+ 树上只生成一个证明，无需多个 Range 节点与 Union 节点。
+/// 区间按升序排列且互不相交
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MultiIntervalNode<K: Num> {
+    dim: u8,
+    intervals: Vec<(K, K)>,
+}
+
+impl<K: Num> MultiIntervalNode<K> {
+    pub fn new(dim: u8, intervals: Vec<(K, K)>) -> Result<Self> {
+        let node = Self { dim, intervals };
+        node.bounds()?;
+        Ok(node)
+    }
+
+    pub fn dim(&self) -> u8 {
+        self.dim
+    }
+
+    pub fn intervals(&self) -> &[(K, K)] {
+        &self.intervals
+    }
+
+    /// 各区间对应的查询条件；区间为空、逆序或相交时返回错误
+    pub(crate) fn bounds(&self) -> Result<Vec<RangeBound<K>>> {
+        ensure!(
+            !self.intervals.is_empty(),
+            "No interval on dimension {}",
+            self.dim
+        );
+        for pair in self.intervals.windows(2) {
+            ensure!(
+                pair[0].1 < pair[1].0,
+                "Intervals on dimension {} must be sorted and disjoint, got {:?} before {:?}",
+                self.dim,
+                pair[0],
+                pair[1]
+            );
+        }
+        self.intervals
+            .iter()
+            .map(|&(l, h)| RangeBound::new(self.dim, l, h))
+            .collect()
+    }
+}
+
+/// 返回 Identity / HeightFilter 节点唯一的子节点
+pub(crate) fn identity_child<K: Num>(
+    dag: &Graph<DagNode<K>, bool>,
+    idx: NodeIndex,
+) -> Result<NodeIndex> {
+    let mut children = dag.neighbors_directed(idx, Outgoing);
+    match (children.next(), children.next()) {
+        (Some(c_idx), None) => Ok(c_idx),
+        _ => bail!("Single-child node must have exactly one child"),
+    }
+}
+
 // return the root idx of added keyword expression
 fn query_dag_add_keyword_exp<K: Num>(
     keyword_exp: &Node,
@@ -350,10 +477,11 @@ pub fn gen_parallel_query_dag<K: Num>(
         let mut range_lock = false;
         for (i, r) in query_content.range.iter().enumerate() {
             // add range
-            let range_idx = query_dag.add_node(DagNode::Range(RangeNode {
-                range: *r,
-                dim: i as u8,
-            }));
+            let range_idx = query_dag.add_node(DagNode::Range(RangeBound::new(
+                i as u8,
+                r.get_low(),
+                r.get_high(),
+            )?));
             if range_lock {
                 // add intersec
                 let intersec_idx = query_dag.add_node(DagNode::Intersec(IntersecNode {}));
@@ -379,6 +507,78 @@ pub fn gen_parallel_query_dag<K: Num>(
     Ok(query_dag)
 }
 
+/// 按估计基数重排 DAG 中可结合、可交换的 Intersec / Union 子树。
+///
+/// 对每个 Intersec（Union）节点，沿只有一个父节点的同类子节点向下展开，得到一组操作数；
+/// 按 `cardinality_hint` 从小到大排序后重建为左深树，使基数最小的两个集合最先参与运算，
+/// 从而尽早缩小中间结果。只改动这些运算节点的出边，节点编号保持不变；
+/// Intersec / Union 与子节点顺序无关，因此验证方同样接受重排后的 DAG。
+pub fn optimize_dag<K: Num>(
+    graph: &mut Graph<DagNode<K>, bool>,
+    cardinality_hint: impl Fn(NodeIndex) -> u64,
+) -> Result<()> {
+    fn same_op<K: Num>(a: &DagNode<K>, b: &DagNode<K>) -> bool {
+        matches!(
+            (a, b),
+            (DagNode::Intersec(_), DagNode::Intersec(_)) | (DagNode::Union(_), DagNode::Union(_))
+        )
+    }
+
+    let order = match toposort(&*graph, None) {
+        Ok(v) => v,
+        Err(_) => bail!("Input query graph not valid"),
+    };
+    let mut visited = HashSet::<NodeIndex>::new();
+    for idx in order {
+        if visited.contains(&idx) {
+            continue;
+        }
+        let node = graph.node_weight(idx).context("Cannot find dag node")?;
+        if !matches!(node, DagNode::Intersec(_) | DagNode::Union(_)) {
+            continue;
+        }
+
+        let mut inner_idxs = vec![idx];
+        let mut operands = Vec::<NodeIndex>::new();
+        let mut stack: Vec<NodeIndex> = graph.neighbors_directed(idx, Outgoing).collect();
+        while let Some(c_idx) = stack.pop() {
+            let c_node = graph.node_weight(c_idx).context("Cannot find dag node")?;
+            if same_op(node, c_node) && graph.neighbors_directed(c_idx, Incoming).count() == 1 {
+                inner_idxs.push(c_idx);
+                stack.extend(graph.neighbors_directed(c_idx, Outgoing));
+            } else {
+                operands.push(c_idx);
+            }
+        }
+        visited.extend(inner_idxs.iter().copied());
+        ensure!(
+            inner_idxs.len() + 1 == operands.len(),
+            "Invalid number of operands in query dag"
+        );
+        if operands.len() < 3 {
+            continue;
+        }
+
+        operands.sort_by_key(|&o| (cardinality_hint(o), o.index()));
+        for &i in &inner_idxs {
+            while let Some(edge_idx) = graph.first_edge(i, Outgoing) {
+                graph.remove_edge(edge_idx);
+            }
+        }
+        // inner_idxs[0] 为子树的根，inner_idxs 的最后一个节点连接基数最小的两个操作数
+        let n = inner_idxs.len();
+        for (k, &i) in inner_idxs.iter().enumerate() {
+            if k + 1 < n {
+                graph.add_edge(i, inner_idxs[k + 1], true);
+            } else {
+                graph.add_edge(i, operands[0], true);
+            }
+            graph.add_edge(i, operands[n - k], false);
+        }
+    }
+    Ok(())
+}
+
 #[allow(clippy::type_complexity)]
 pub fn gen_last_query_dag_with_cont_basic<K: Num, T: ReadInterface<K = K>>(
     time_win: &TimeWin,
@@ -406,15 +606,16 @@ pub fn gen_last_query_dag_with_cont_basic<K: Num, T: ReadInterface<K = K>>(
     for idx in &end_q_inputs {
         if let Some(dag_node) = query_dag.node_weight(*idx) {
             match dag_node {
-                DagNode::Range(node) => {
+                DagNode::Range(_) | DagNode::MultiInterval(_) => {
+                    let (dim, intervals) = dag_node.bplus_query()?;
                     let bplus_root = chain
                         .read_block_content(end_blk_height)?
                         .ads
-                        .read_bplus_root(e_win_size, node.dim)?;
-                    let (s, a, p) = bplus_tree::read::range_query(
+                        .read_bplus_root(e_win_size, dim)?;
+                    let (s, a, p) = bplus_tree::read::multi_range_query(
                         chain,
                         bplus_root.bplus_tree_root_id,
-                        node.range,
+                        &intervals,
                         pk,
                     )?;
                     let qp_range_node = QPRangeNode {
@@ -448,6 +649,37 @@ pub fn gen_last_query_dag_with_cont_basic<K: Num, T: ReadInterface<K = K>>(
                     };
                     dag_content.insert(*idx, QPNode::Keyword(Box::new(qp_keyword_node)));
                 }
+                DagNode::KeywordSet(n) => {
+                    ensure!(!n.keywords.is_empty(), "Empty keyword set in query dag");
+                    let trie_ctx =
+                        trie_ctx_for(chain, &mut trie_ctxes, end_blk_height, e_win_size)?;
+                    let mut sets = trie_ctx
+                        .query_keywords(&n.keywords, pk)?
+                        .into_iter()
+                        .map(|(s, _a)| s);
+                    let first = sets.next().context("Empty keyword set in query dag")?;
+                    let set = sets.fold(first, |acc_set, s| &acc_set & &s);
+                    let acc = AccValue::from_set(&set, pk);
+                    let qp_keyword_node = QPKeywordNode {
+                        blk_height: end_blk_height,
+                        set: Some((set, acc)),
+                    };
+                    dag_content.insert(*idx, QPNode::Keyword(Box::new(qp_keyword_node)));
+                }
+                DagNode::KeywordPrefix(n) => {
+                    let trie_ctx =
+                        trie_ctx_for(chain, &mut trie_ctxes, end_blk_height, e_win_size)?;
+                    let set = trie_ctx
+                        .query_prefix(&n.prefix, pk)?
+                        .into_iter()
+                        .fold(Set::new(), |acc_set, (s, _a)| &acc_set | &s);
+                    let acc = AccValue::from_set(&set, pk);
+                    let qp_keyword_node = QPKeywordNode {
+                        blk_height: end_blk_height,
+                        set: Some((set, acc)),
+                    };
+                    dag_content.insert(*idx, QPNode::Keyword(Box::new(qp_keyword_node)));
+                }
                 DagNode::BlkRt(_) => {
                     let blk_content = chain.read_block_content(end_blk_height)?;
                     let bplus_root = blk_content.ads.read_bplus_root(e_win_size, 0)?;
@@ -550,6 +782,28 @@ pub fn gen_last_query_dag_with_cont_basic<K: Num, T: ReadInterface<K = K>>(
                     let qp_diff_node = QPDiff { set: Some(c_diff) };
                     dag_content.insert(*idx, QPNode::Diff(qp_diff_node));
                 }
+                DagNode::Identity(_) => {
+                    let c_idx = identity_child(&query_dag, *idx)?;
+                    let c_set = dag_content
+                        .get(&c_idx)
+                        .context("Cannot find the child qp node of identity")?
+                        .get_set()?
+                        .clone();
+                    let qp_identity_node = QPIdentity { set: Some(c_set) };
+                    dag_content.insert(*idx, QPNode::Identity(qp_identity_node));
+                }
+                DagNode::HeightFilter(n) => {
+                    let c_idx = identity_child(&query_dag, *idx)?;
+                    let c_set = dag_content
+                        .get(&c_idx)
+                        .context("Cannot find the child qp node of height filter")?
+                        .get_set()?;
+                    let filter_set = n.filter_set(chain, end_blk_height, e_win_size)?;
+                    let qp_filter_node = QPHeightFilter {
+                        set: Some(c_set & &filter_set),
+                    };
+                    dag_content.insert(*idx, QPNode::HeightFilter(qp_filter_node));
+                }
             }
         }
     }
@@ -591,3 +845,121 @@ pub fn gen_last_query_dag_with_cont_basic<K: Num, T: ReadInterface<K = K>>(
 
     Ok((query_dag, qp))
 }
+
+// 区间端点的文本形式，如 `[10`、`20)`
+fn fmt_bound<K: Num>(bound: Bound<K>, is_low: bool) -> String {
+    match (bound, is_low) {
+        (Bound::Included(v), true) => format!("[{:?}", v),
+        (Bound::Excluded(v), true) => format!("({:?}", v),
+        (Bound::Unbounded, true) => "(-inf".to_string(),
+        (Bound::Included(v), false) => format!("{:?}]", v),
+        (Bound::Excluded(v), false) => format!("{:?})", v),
+        (Bound::Unbounded, false) => "+inf)".to_string(),
+    }
+}
+
+fn dot_label<K: Num>(node: &DagNode<K>) -> String {
+    match node {
+        DagNode::Range(r) => format!(
+            "Range\ndim={} {}, {}",
+            r.dim(),
+            fmt_bound(r.low(), true),
+            fmt_bound(r.high(), false)
+        ),
+        DagNode::Keyword(n) => format!("Keyword\n{:?}", n.keyword),
+        DagNode::KeywordSet(n) => format!("KeywordSet\n{:?}", n.keywords),
+        DagNode::KeywordPrefix(n) => format!("KeywordPrefix\n{:?}*", n.prefix),
+        DagNode::MultiInterval(n) => format!("MultiInterval\ndim={} {:?}", n.dim, n.intervals),
+        DagNode::BlkRt(_) => "BlkRt".to_string(),
+        DagNode::Union(_) => "Union".to_string(),
+        DagNode::Intersec(_) => "Intersec".to_string(),
+        DagNode::Diff(_) => "Diff".to_string(),
+        DagNode::Identity(_) => "Identity".to_string(),
+        DagNode::HeightFilter(n) => {
+            format!("HeightFilter\nheight [{}, {})", n.range.start, n.range.end)
+        }
+    }
+}
+
+/// 将查询 DAG 输出为 Graphviz DOT 格式（可用 `dot -Tpng` 渲染）。
+///
+/// 节点标签为节点类型与关键参数，边标签为边权；Diff 节点中边权为 `false` 的子节点是被减数，
+/// 为 `true` 的是减数
+pub fn dag_to_dot<K: Num>(graph: &Graph<DagNode<K>, bool>) -> String {
+    let mut out = String::from("digraph query_dag {\n    node [shape=box];\n");
+    for idx in graph.node_indices() {
+        let label = dot_label(&graph[idx])
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n");
+        out.push_str(&format!(
+            "    n{} [label=\"{}: {}\"];\n",
+            idx.index(),
+            idx.index(),
+            label
+        ));
+    }
+    for edge in graph.raw_edges() {
+        out.push_str(&format!(
+            "    n{} -> n{} [label=\"{}\"];\n",
+            edge.source().index(),
+            edge.target().index(),
+            edge.weight
+        ));
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// 查询 DAG 的传输格式：节点按下标顺序排列，边按下标顺序记录端点下标与边权，
+/// 不依赖 petgraph 的内部表示
+#[derive(Serialize, Deserialize)]
+struct WireDag<K: Num> {
+    nodes: Vec<DagNode<K>>,
+    edges: Vec<(u32, u32, bool)>,
+}
+
+/// 将查询 DAG 编码为紧凑的二进制格式，随 VO 一起发送给验证方
+pub fn serialize_dag<K: Num + Serialize>(graph: &Graph<DagNode<K>, bool>) -> Result<Vec<u8>> {
+    let wire = WireDag {
+        nodes: graph.raw_nodes().iter().map(|n| n.weight.clone()).collect(),
+        edges: graph
+            .raw_edges()
+            .iter()
+            .map(|e| {
+                (
+                    e.source().index() as u32,
+                    e.target().index() as u32,
+                    e.weight,
+                )
+            })
+            .collect(),
+    };
+    binary_encode(&wire)
+}
+
+/// 解码 `serialize_dag` 的输出。节点与边按原顺序加入，因此各节点的 `NodeIndex`
+/// 以及子节点的遍历顺序都与原 DAG 相同
+pub fn deserialize_dag<K: Num + for<'de> Deserialize<'de>>(
+    bytes: &[u8],
+) -> Result<Graph<DagNode<K>, bool>> {
+    let wire: WireDag<K> = binary_decode(bytes).context("Failed to decode query dag")?;
+    let mut graph = Graph::with_capacity(wire.nodes.len(), wire.edges.len());
+    for node in wire.nodes {
+        graph.add_node(node);
+    }
+    for (source, target, weight) in wire.edges {
+        ensure!(
+            (source as usize) < graph.node_count() && (target as usize) < graph.node_count(),
+            "Edge {} -> {} refers to a node outside the query dag",
+            source,
+            target
+        );
+        graph.add_edge(
+            NodeIndex::new(source as usize),
+            NodeIndex::new(target as usize),
+            weight,
+        );
+    }
+    Ok(graph)
+}
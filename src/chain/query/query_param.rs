@@ -1,18 +1,23 @@
-use super::{query_dag::DagNode, query_plan::QueryPlan, QueryContent, TimeWin};
+use super::{
+    query_dag::{identity_child, DagNode},
+    query_plan::QueryPlan,
+    trie_ctx_for, QueryContent, TimeWin,
+};
 use crate::{
-    acc::AccPublicKey,
+    acc::{AccPublicKey, AccValue, Set},
     chain::{
         block::Height,
         bplus_tree,
         query::query_plan::{
-            QPBlkRtNode, QPDiff, QPIntersec, QPKeywordNode, QPNode, QPRangeNode, QPUnion,
+            QPBlkRtNode, QPDiff, QPHeightFilter, QPIdentity, QPIntersec, QPKeywordNode, QPNode,
+            QPRangeNode, QPUnion,
         },
         range::Range,
         traits::{Num, ReadInterface},
         trie_tree,
     },
 };
-use anyhow::{bail, Context, Result};
+use anyhow::{bail, ensure, Context, Result};
 use petgraph::{algo::toposort, graph::NodeIndex, EdgeDirection::Outgoing, Graph};
 use serde::{Deserialize, Serialize};
 use smol_str::SmolStr;
@@ -163,15 +168,16 @@ pub fn param_to_qp<K: Num, T: ReadInterface<K = K>>(
     for idx in &q_inputs {
         if let Some(node) = query_dag.node_weight(*idx) {
             match node {
-                DagNode::Range(n) => {
+                DagNode::Range(_) | DagNode::MultiInterval(_) => {
+                    let (dim, intervals) = node.bplus_query()?;
                     let bplus_root = chain
                         .read_block_content(end_blk_height)?
                         .ads
-                        .read_bplus_root(e_win_size, n.dim)?;
-                    let (s, a, p) = bplus_tree::read::range_query(
+                        .read_bplus_root(e_win_size, dim)?;
+                    let (s, a, p) = bplus_tree::read::multi_range_query(
                         chain,
                         bplus_root.bplus_tree_root_id,
-                        n.range,
+                        &intervals,
                         pk,
                     )?;
                     let qp_range_node: QPRangeNode<K> = QPRangeNode {
@@ -205,6 +211,37 @@ pub fn param_to_qp<K: Num, T: ReadInterface<K = K>>(
                     };
                     dag_content.insert(*idx, QPNode::Keyword(Box::new(qp_keyword_node)));
                 }
+                DagNode::KeywordSet(n) => {
+                    ensure!(!n.keywords.is_empty(), "Empty keyword set in query dag");
+                    let trie_ctx =
+                        trie_ctx_for(chain, &mut trie_ctxes, end_blk_height, e_win_size)?;
+                    let mut sets = trie_ctx
+                        .query_keywords(&n.keywords, pk)?
+                        .into_iter()
+                        .map(|(s, _a)| s);
+                    let first = sets.next().context("Empty keyword set in query dag")?;
+                    let set = sets.fold(first, |acc_set, s| &acc_set & &s);
+                    let acc = AccValue::from_set(&set, pk);
+                    let qp_keyword_node = QPKeywordNode {
+                        blk_height: end_blk_height,
+                        set: Some((set, acc)),
+                    };
+                    dag_content.insert(*idx, QPNode::Keyword(Box::new(qp_keyword_node)));
+                }
+                DagNode::KeywordPrefix(n) => {
+                    let trie_ctx =
+                        trie_ctx_for(chain, &mut trie_ctxes, end_blk_height, e_win_size)?;
+                    let set = trie_ctx
+                        .query_prefix(&n.prefix, pk)?
+                        .into_iter()
+                        .fold(Set::new(), |acc_set, (s, _a)| &acc_set | &s);
+                    let acc = AccValue::from_set(&set, pk);
+                    let qp_keyword_node = QPKeywordNode {
+                        blk_height: end_blk_height,
+                        set: Some((set, acc)),
+                    };
+                    dag_content.insert(*idx, QPNode::Keyword(Box::new(qp_keyword_node)));
+                }
                 DagNode::BlkRt(_) => {
                     let blk_content = chain.read_block_content(end_blk_height)?;
                     let bplus_root = blk_content.ads.read_bplus_root(e_win_size, 0)?;
@@ -307,6 +344,28 @@ pub fn param_to_qp<K: Num, T: ReadInterface<K = K>>(
                     let qp_diff = QPDiff { set: Some(c_diff) };
                     dag_content.insert(*idx, QPNode::Diff(qp_diff));
                 }
+                DagNode::Identity(_) => {
+                    let c_idx = identity_child(query_dag, *idx)?;
+                    let c_set = dag_content
+                        .get(&c_idx)
+                        .context("Cannot find the child qp node of identity")?
+                        .get_set()?
+                        .clone();
+                    let qp_identity = QPIdentity { set: Some(c_set) };
+                    dag_content.insert(*idx, QPNode::Identity(qp_identity));
+                }
+                DagNode::HeightFilter(n) => {
+                    let c_idx = identity_child(query_dag, *idx)?;
+                    let c_set = dag_content
+                        .get(&c_idx)
+                        .context("Cannot find the child qp node of height filter")?
+                        .get_set()?;
+                    let filter_set = n.filter_set(chain, end_blk_height, e_win_size)?;
+                    let qp_filter = QPHeightFilter {
+                        set: Some(c_set & &filter_set),
+                    };
+                    dag_content.insert(*idx, QPNode::HeightFilter(qp_filter));
+                }
             }
         }
     }
@@ -15,6 +15,8 @@ pub enum QPNode<K: Num> {
     Union(QPUnion),
     Intersec(QPIntersec),
     Diff(QPDiff),
+    Identity(QPIdentity),
+    HeightFilter(QPHeightFilter),
 }
 
 impl<K: Num> QPNode<K> {
@@ -26,6 +28,8 @@ impl<K: Num> QPNode<K> {
             QPNode::Union(n) => Ok(n.set.as_ref().context("No set in the QPNode")?),
             QPNode::Intersec(n) => Ok(n.set.as_ref().context("No set in the QPNode")?),
             QPNode::Diff(n) => Ok(n.set.as_ref().context("No set in the QPNode")?),
+            QPNode::Identity(n) => Ok(n.set.as_ref().context("No set in the QPNode")?),
+            QPNode::HeightFilter(n) => Ok(n.set.as_ref().context("No set in the QPNode")?),
         }
     }
 }
@@ -63,6 +67,16 @@ pub struct QPDiff {
     pub(crate) set: Option<Set>,
 }
 
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+pub struct QPIdentity {
+    pub(crate) set: Option<Set>,
+}
+
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+pub struct QPHeightFilter {
+    pub(crate) set: Option<Set>,
+}
+
 #[derive(Debug)]
 pub struct QueryPlan<K: Num> {
     pub(crate) end_blk_height: Height,
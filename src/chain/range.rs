@@ -2,7 +2,9 @@ use crate::{
     chain::{hash::range_hash, traits::Num},
     digest::{Digest, Digestible},
 };
+use anyhow::{ensure, Result};
 use serde::{Deserialize, Serialize};
+use std::ops::Bound;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Range<K: Num>(K, K);
@@ -58,3 +60,163 @@ impl<K: Num> Digestible for Range<K> {
         range_hash(self)
     }
 }
+
+/// 某一维度上的区间查询条件，每个端点可以是闭、开或无界；构造时保证区间非空
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RangeBound<K: Num> {
+    dim: u8,
+    low: Bound<K>,
+    high: Bound<K>,
+}
+
+impl<K: Num> RangeBound<K> {
+    /// 闭区间 `[low, high]`
+    pub fn new(dim: u8, low: K, high: K) -> Result<Self> {
+        Self::with_bounds(dim, Bound::Included(low), Bound::Included(high))
+    }
+
+    pub fn with_bounds(dim: u8, low: Bound<K>, high: Bound<K>) -> Result<Self> {
+        match (low, high) {
+            (Bound::Included(l), Bound::Included(h)) => ensure!(
+                l <= h,
+                "Invalid range on dimension {}: low {:?} is greater than high {:?}",
+                dim,
+                l,
+                h
+            ),
+            (Bound::Included(l), Bound::Excluded(h))
+            | (Bound::Excluded(l), Bound::Included(h))
+            | (Bound::Excluded(l), Bound::Excluded(h)) => ensure!(
+                l < h,
+                "Invalid range on dimension {}: {:?}..{:?} is empty",
+                dim,
+                low,
+                high
+            ),
+            _ => {}
+        }
+        Ok(Self { dim, low, high })
+    }
+
+    pub fn dim(&self) -> u8 {
+        self.dim
+    }
+
+    pub fn low(&self) -> Bound<K> {
+        self.low
+    }
+
+    pub fn high(&self) -> Bound<K> {
+        self.high
+    }
+
+    fn above_low(&self, v: K) -> bool {
+        match self.low {
+            Bound::Included(l) => l <= v,
+            Bound::Excluded(l) => l < v,
+            Bound::Unbounded => true,
+        }
+    }
+
+    fn below_high(&self, v: K) -> bool {
+        match self.high {
+            Bound::Included(h) => v <= h,
+            Bound::Excluded(h) => v < h,
+            Bound::Unbounded => true,
+        }
+    }
+
+    pub fn contains(&self, v: K) -> bool {
+        self.above_low(v) && self.below_high(v)
+    }
+
+    /// `range` 中的所有值都满足查询条件
+    pub fn covers(&self, range: Range<K>) -> bool {
+        self.above_low(range.get_low()) && self.below_high(range.get_high())
+    }
+
+    /// `range` 中没有任何值满足查询条件
+    pub fn is_disjoint(&self, range: Range<K>) -> bool {
+        !self.below_high(range.get_low()) || !self.above_low(range.get_high())
+    }
+
+    /// 转换为等价的闭区间 `[low, high]`，无界的端点取类型的最小 / 最大值；
+    /// 开端点在整数上可能不含任何值（如 `(3, 4)`），此时返回 `None`
+    pub fn to_inclusive(&self) -> Option<Range<K>> {
+        let low = match self.low {
+            Bound::Included(l) => l,
+            Bound::Excluded(l) => l.successor()?,
+            Bound::Unbounded => K::min_value(),
+        };
+        let high = match self.high {
+            Bound::Included(h) => h,
+            Bound::Excluded(h) => h.predecessor()?,
+            Bound::Unbounded => K::max_value(),
+        };
+        if low <= high {
+            Some(Range::new(low, high))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_range_bound() {
+        let b = RangeBound::new(1, 2u32, 8).unwrap();
+        assert_eq!(b.dim(), 1);
+        assert!(b.contains(2) && b.contains(8));
+        assert!(!b.contains(1) && !b.contains(9));
+        assert!(b.covers(Range::new(2, 8)));
+        assert!(!b.covers(Range::new(2, 9)));
+        assert!(b.is_disjoint(Range::new(9, 10)));
+        assert!(!b.is_disjoint(Range::new(8, 10)));
+
+        assert!(RangeBound::new(0, 8u32, 2).is_err());
+
+        let p = RangeBound::new(0, 5u32, 5).unwrap();
+        assert!(p.contains(5));
+        assert!(!p.contains(4) && !p.contains(6));
+    }
+
+    #[test]
+    fn test_range_bound_open_ended() {
+        // >= 3
+        let b = RangeBound::with_bounds(0, Bound::Included(3u32), Bound::Unbounded).unwrap();
+        assert!(b.contains(3) && b.contains(u32::MAX));
+        assert!(!b.contains(2));
+        assert!(b.covers(Range::new(3, 100)));
+        assert!(b.is_disjoint(Range::new(0, 2)));
+
+        // < 3
+        let b = RangeBound::with_bounds(0, Bound::Unbounded, Bound::Excluded(3u32)).unwrap();
+        assert!(b.contains(0) && b.contains(2));
+        assert!(!b.contains(3));
+        assert!(!b.covers(Range::new(0, 3)));
+        assert!(b.is_disjoint(Range::new(3, 3)));
+        assert!(!b.is_disjoint(Range::new(2, 3)));
+
+        let b = RangeBound::<u32>::with_bounds(0, Bound::Unbounded, Bound::Unbounded).unwrap();
+        assert!(b.covers(Range::new(0, u32::MAX)));
+
+        assert!(RangeBound::with_bounds(0, Bound::Excluded(3u32), Bound::Included(3)).is_err());
+        assert!(RangeBound::with_bounds(0, Bound::Included(3u32), Bound::Excluded(3)).is_err());
+        assert!(RangeBound::with_bounds(0, Bound::Excluded(3u32), Bound::Excluded(4)).is_ok());
+    }
+
+    #[test]
+    fn test_range_bound_to_inclusive() {
+        let b = RangeBound::with_bounds(0, Bound::Excluded(3u32), Bound::Excluded(8)).unwrap();
+        assert_eq!(b.to_inclusive(), Some(Range::new(4, 7)));
+        let b = RangeBound::with_bounds(0, Bound::Unbounded, Bound::Included(-2i64)).unwrap();
+        assert_eq!(b.to_inclusive(), Some(Range::new(i64::MIN, -2)));
+        let b = RangeBound::with_bounds(0, Bound::Excluded(-2i64), Bound::Unbounded).unwrap();
+        assert_eq!(b.to_inclusive(), Some(Range::new(-1, i64::MAX)));
+        let b = RangeBound::with_bounds(0, Bound::Excluded(3u32), Bound::Excluded(4)).unwrap();
+        assert_eq!(b.to_inclusive(), None);
+    }
+}
@@ -0,0 +1,80 @@
+//! 链上对象的模式，用于在查询之前检查查询 DAG 引用的维度与索引确实存在
+
+use crate::chain::{query::query_dag::DagNode, traits::Num};
+use anyhow::Result;
+use petgraph::Graph;
+use serde::{Deserialize, Serialize};
+
+/// 一个数值维度；每个时间窗口中都为它建立一棵 B+ 树
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DimInfo {
+    pub dim: u8,
+}
+
+/// 由 `Parameter::schema` 得出：各数值维度，以及是否为关键字建立了 trie 索引
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Schema {
+    pub dims: Vec<DimInfo>,
+    pub keywords_indexed: bool,
+}
+
+/// 查询 DAG 与模式不符；调用方可通过 `anyhow::Error::downcast_ref` 识别
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaError {
+    /// 范围查询的维度不在模式中
+    UnknownDim { dim: u8, num_dims: usize },
+    /// 关键字查询，但模式中没有关键字索引
+    KeywordsNotIndexed,
+}
+
+impl std::fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchemaError::UnknownDim { dim, num_dims } => write!(
+                f,
+                "Range query on dimension {}, but the schema has {} dimensions",
+                dim, num_dims
+            ),
+            SchemaError::KeywordsNotIndexed => {
+                write!(f, "Keyword query, but keywords are not indexed")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+impl Schema {
+    pub fn has_dim(&self, dim: u8) -> bool {
+        self.dims.iter().any(|d| d.dim == dim)
+    }
+
+    /// 检查查询 DAG 中的范围查询维度都在模式中，且仅在有关键字索引时包含关键字查询
+    pub fn check_dag<K: Num>(&self, graph: &Graph<DagNode<K>, bool>) -> Result<()> {
+        for idx in graph.node_indices() {
+            match graph.node_weight(idx) {
+                Some(DagNode::Range(n)) if !self.has_dim(n.dim()) => {
+                    return Err(SchemaError::UnknownDim {
+                        dim: n.dim(),
+                        num_dims: self.dims.len(),
+                    }
+                    .into());
+                }
+                Some(DagNode::MultiInterval(n)) if !self.has_dim(n.dim()) => {
+                    return Err(SchemaError::UnknownDim {
+                        dim: n.dim(),
+                        num_dims: self.dims.len(),
+                    }
+                    .into());
+                }
+                Some(DagNode::Keyword(_) | DagNode::KeywordSet(_) | DagNode::KeywordPrefix(_))
+                    if !self.keywords_indexed =>
+                {
+                    return Err(SchemaError::KeywordsNotIndexed.into());
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
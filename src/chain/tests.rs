@@ -1,27 +1,54 @@
 use super::{
-    block::{build::build_block, BlockContent, BlockHead, Height},
+    block::{
+        block_ads_root::{BlockADSComponents, ComponentKind},
+        build::{build_block, build_block_with_progress, BuildProgress},
+        BlockContent, BlockHead, Height,
+    },
     bplus_tree::{BPlusTreeNode, BPlusTreeNodeId},
-    id_tree::{IdTreeNode, IdTreeNodeId},
-    object::Object,
-    range::Range,
-    traits::{ReadInterface, ScanQueryInterface, WriteInterface},
+    id_tree::{IdTreeNode, IdTreeNodeId, ObjId},
+    object::{FieldId, Object},
+    range::RangeBound,
+    testutil::{gen_test_chain, MemChain},
+    traits::{ReadError, ReadInterface, WriteInterface},
     trie_tree::{TrieNode, TrieNodeId},
     Parameter,
 };
 use crate::{
     acc::{AccPublicKey, AccSecretKey, AccSecretKeyWithPowCache},
     chain::{
-        query::{query, query_param::QueryParam},
-        verify::verify,
+        query::{
+            explain::{explain_query, EXPLAIN_ERROR_FACTOR},
+            query,
+            query_dag::{
+                gen_parallel_query_dag, optimize_dag, DagNode, HeightFilterNode, IdentityNode,
+                IntersecNode, KeywordNode, KeywordPrefixNode, KeywordSetNode, MultiIntervalNode,
+                UnionNode,
+            },
+            query_param::QueryParam,
+            query_results, query_with_dag,
+        },
+        verify::{
+            light::{gen_header_chain_proof, verify_with_heads, HeaderChainProof, LightVerifier},
+            retry::RetryingChain,
+            verify, verify_batch, verify_locate, VerifyJob,
+        },
     },
     digest::{Digest, Digestible},
     utils::{init_tracing_subscriber, load_raw_obj_from_str},
 };
 use anyhow::{Context, Result};
 use once_cell::sync::Lazy;
+use petgraph::Graph;
 use rand::{prelude::*, rngs::StdRng};
 use serde_json::json;
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
 
 const Q: u64 = 40;
 static SEC_KEY: Lazy<AccSecretKeyWithPowCache> = Lazy::new(|| {
@@ -31,353 +58,10 @@ static SEC_KEY: Lazy<AccSecretKeyWithPowCache> = Lazy::new(|| {
 });
 pub(crate) static PUB_KEY: Lazy<AccPublicKey> = Lazy::new(|| AccPublicKey::gen_key(&(*SEC_KEY), Q));
 
-#[derive(Debug, Default)]
-struct FakeChain {
-    param: Option<Parameter>,
-    block_head: HashMap<Height, BlockHead>,
-    block_content: HashMap<Height, BlockContent>,
-    id_tree_nodes: HashMap<IdTreeNodeId, IdTreeNode>,
-    bplus_tree_nodes: HashMap<BPlusTreeNodeId, BPlusTreeNode<u32>>,
-    trie_nodes: HashMap<TrieNodeId, TrieNode>,
-    objects: HashMap<Digest, Object<u32>>,
-}
-
-impl ReadInterface for &FakeChain {
-    type K = u32;
-    fn get_parameter(&self) -> Result<Parameter> {
-        self.param.clone().context("failed to read parameter")
-    }
-    fn read_block_head(&self, blk_height: Height) -> Result<BlockHead> {
-        self.block_head
-            .get(&blk_height)
-            .cloned()
-            .context("failed to read block header")
-    }
-    fn read_block_content(&self, block_id: Height) -> Result<BlockContent> {
-        self.block_content
-            .get(&block_id)
-            .cloned()
-            .context("failed to read block content")
-    }
-    fn read_id_tree_node(&self, id_tree_node_id: IdTreeNodeId) -> Result<IdTreeNode> {
-        self.id_tree_nodes
-            .get(&id_tree_node_id)
-            .cloned()
-            .context("failed to read id tree node")
-    }
-    fn read_bplus_tree_node(
-        &self,
-        bplus_tree_node_id: BPlusTreeNodeId,
-    ) -> Result<BPlusTreeNode<Self::K>> {
-        self.bplus_tree_nodes
-            .get(&bplus_tree_node_id)
-            .cloned()
-            .context(format!(
-                "failed to read bplus tree node with id {:?}",
-                bplus_tree_node_id
-            ))
-    }
-    fn read_trie_node(&self, trie_node_id: TrieNodeId) -> Result<TrieNode> {
-        self.trie_nodes
-            .get(&trie_node_id)
-            .cloned()
-            .context("failed to read trie tree node")
-    }
-    fn read_object(&self, obj_hash: Digest) -> Result<Object<Self::K>> {
-        self.objects
-            .get(&obj_hash)
-            .cloned()
-            .context("failed to read object")
-    }
-}
-
-impl ReadInterface for &mut FakeChain {
-    type K = u32;
-    fn get_parameter(&self) -> Result<Parameter> {
-        self.param.clone().context("failed to read parameter")
-    }
-    fn read_block_head(&self, blk_height: Height) -> Result<BlockHead> {
-        self.block_head
-            .get(&blk_height)
-            .cloned()
-            .context("failed to read block header")
-    }
-    fn read_block_content(&self, block_id: Height) -> Result<BlockContent> {
-        self.block_content
-            .get(&block_id)
-            .cloned()
-            .context("failed to read block content")
-    }
-    fn read_id_tree_node(&self, id_tree_node_id: IdTreeNodeId) -> Result<IdTreeNode> {
-        self.id_tree_nodes
-            .get(&id_tree_node_id)
-            .cloned()
-            .context("failed to read id tree node")
-    }
-    fn read_bplus_tree_node(
-        &self,
-        bplus_tree_node_id: BPlusTreeNodeId,
-    ) -> Result<BPlusTreeNode<Self::K>> {
-        self.bplus_tree_nodes
-            .get(&bplus_tree_node_id)
-            .cloned()
-            .context(format!(
-                "failed to read bplus tree node with id {:?}",
-                bplus_tree_node_id
-            ))
-    }
-    fn read_trie_node(&self, trie_node_id: TrieNodeId) -> Result<TrieNode> {
-        self.trie_nodes
-            .get(&trie_node_id)
-            .cloned()
-            .context("failed to read trie tree node")
-    }
-    fn read_object(&self, obj_hash: Digest) -> Result<Object<Self::K>> {
-        self.objects
-            .get(&obj_hash)
-            .cloned()
-            .context("failed to read object")
-    }
-}
-
-impl WriteInterface for FakeChain {
-    type K = u32;
-    fn set_parameter(&mut self, param: &Parameter) -> Result<()> {
-        self.param = Some(param.clone());
-        Ok(())
-    }
-    fn write_block_head(&mut self, blk_height: Height, blk_head: &BlockHead) -> Result<()> {
-        self.block_head.insert(blk_height, blk_head.clone());
-        Ok(())
-    }
-    fn write_block_content(
-        &mut self,
-        blk_height: Height,
-        blk_content: &BlockContent,
-    ) -> Result<()> {
-        self.block_content.insert(blk_height, blk_content.clone());
-        Ok(())
-    }
-    fn write_id_tree_node(&mut self, id: IdTreeNodeId, node: &IdTreeNode) -> Result<()> {
-        self.id_tree_nodes.insert(id, node.clone());
-        Ok(())
-    }
-    fn write_bplus_tree_node(
-        &mut self,
-        id: BPlusTreeNodeId,
-        node: &BPlusTreeNode<Self::K>,
-    ) -> Result<()> {
-        self.bplus_tree_nodes.insert(id, node.clone());
-        Ok(())
-    }
-    fn write_trie_node(&mut self, id: TrieNodeId, node: &TrieNode) -> Result<()> {
-        self.trie_nodes.insert(id, node.clone());
-        Ok(())
-    }
-    fn write_object(&mut self, obj_hash: Digest, obj: &Object<Self::K>) -> Result<()> {
-        self.objects.insert(obj_hash, obj.clone());
-        Ok(())
-    }
-}
-
-impl WriteInterface for &mut FakeChain {
-    type K = u32;
-    fn set_parameter(&mut self, param: &Parameter) -> Result<()> {
-        self.param = Some(param.clone());
-        Ok(())
-    }
-    fn write_block_head(&mut self, blk_height: Height, blk_head: &BlockHead) -> Result<()> {
-        self.block_head.insert(blk_height, blk_head.clone());
-        Ok(())
-    }
-    fn write_block_content(
-        &mut self,
-        blk_height: Height,
-        blk_content: &BlockContent,
-    ) -> Result<()> {
-        self.block_content.insert(blk_height, blk_content.clone());
-        Ok(())
-    }
-    fn write_id_tree_node(&mut self, id: IdTreeNodeId, node: &IdTreeNode) -> Result<()> {
-        self.id_tree_nodes.insert(id, node.clone());
-        Ok(())
-    }
-    fn write_bplus_tree_node(
-        &mut self,
-        id: BPlusTreeNodeId,
-        node: &BPlusTreeNode<Self::K>,
-    ) -> Result<()> {
-        self.bplus_tree_nodes.insert(id, node.clone());
-        Ok(())
-    }
-    fn write_trie_node(&mut self, id: TrieNodeId, node: &TrieNode) -> Result<()> {
-        self.trie_nodes.insert(id, node.clone());
-        Ok(())
-    }
-    fn write_object(&mut self, obj_hash: Digest, obj: &Object<Self::K>) -> Result<()> {
-        self.objects.insert(obj_hash, obj.clone());
-        Ok(())
-    }
-}
-
-impl ScanQueryInterface for &FakeChain {
-    type K = u32;
-    fn range_query(
-        &self,
-        query: Range<Self::K>,
-        start_blk_height: Height,
-        end_blk_height: Height,
-        dim: usize,
-    ) -> Result<HashSet<Digest>> {
-        let mut res = HashSet::<Digest>::new();
-        for (hash, o) in &self.objects {
-            if o.blk_height <= end_blk_height && o.blk_height >= start_blk_height {
-                let o_num_val = o.num_data.get(dim).with_context(|| {
-                    format!("Object does not have numerical value at dim {}", dim)
-                })?;
-                if query.is_in_range(*o_num_val) {
-                    res.insert(*hash);
-                }
-            }
-        }
-        Ok(res)
-    }
-
-    fn keyword_query(
-        &self,
-        keyword: &str,
-        start_blk_height: Height,
-        end_blk_height: Height,
-    ) -> Result<HashSet<Digest>> {
-        let mut res = HashSet::<Digest>::new();
-        for (hash, o) in &self.objects {
-            if o.blk_height <= end_blk_height && o.blk_height >= start_blk_height {
-                for k in o.keyword_data.iter() {
-                    if keyword == k {
-                        res.insert(*hash);
-                    }
-                }
-            }
-        }
-        Ok(res)
-    }
-
-    fn root_query(&self, height: Height, win_size: u16) -> Result<HashSet<Digest>> {
-        let mut res = HashSet::<Digest>::new();
-        for (hash, o) in &self.objects {
-            if o.blk_height <= height
-                && Height(o.blk_height.0 + win_size as u32) >= Height(height.0 + 1)
-            {
-                res.insert(*hash);
-            }
-        }
-        Ok(res)
-    }
-
-    fn get_range_info(
-        &self,
-        start_blk_height: Height,
-        end_blk_height: Height,
-        dim_num: usize,
-    ) -> Result<Vec<Range<Self::K>>> {
-        let mut num_ranges = Vec::<Range<Self::K>>::new();
-        let mut num_range_scope = Vec::<(Self::K, Self::K)>::new();
-        for _ in 0..dim_num {
-            num_range_scope.push((std::u32::MAX, 0));
-        }
-        for (_hash, o) in &self.objects {
-            if o.blk_height < end_blk_height && o.blk_height > start_blk_height {
-                let o_num_vals = &o.num_data;
-                for (i, num_val) in o_num_vals.iter().enumerate() {
-                    if i < dim_num {
-                        if num_val
-                            < &num_range_scope
-                                .get(i)
-                                .with_context(|| {
-                                    format!("Object does not have numerical value at dim {}", i)
-                                })?
-                                .0
-                        {
-                            num_range_scope
-                                .get_mut(i)
-                                .with_context(|| {
-                                    format!("Object does not have numerical value at dim {}", i)
-                                })?
-                                .0 = *num_val;
-                        } else if num_val
-                            > &num_range_scope
-                                .get(i)
-                                .with_context(|| {
-                                    format!("Object does not have numerical value at dim {}", i)
-                                })?
-                                .1
-                        {
-                            num_range_scope
-                                .get_mut(i)
-                                .with_context(|| {
-                                    format!("Object does not have numerical value at dim {}", i)
-                                })?
-                                .1 = *num_val;
-                        }
-                    }
-                }
-            }
-        }
-
-        for (min, max) in num_range_scope {
-            num_ranges.push(Range::new(min, max));
-        }
-
-        Ok(num_ranges)
-    }
-
-    fn get_keyword_info(
-        &self,
-        start_blk_height: Height,
-        end_blk_height: Height,
-    ) -> Result<HashSet<String>> {
-        let mut res = HashSet::<String>::new();
-        for (_, o) in &self.objects {
-            if o.blk_height < end_blk_height && o.blk_height > start_blk_height {
-                for k in o.keyword_data.iter() {
-                    res.insert(k.to_string());
-                }
-            }
-        }
-        Ok(res)
-    }
-
-    fn get_chain_info(&self) -> Result<(u32, u32)> {
-        let mut cur_height_num = 0;
-        let mut total_num = 0;
-        for (_, o) in &self.objects {
-            if cur_height_num < o.blk_height.0 {
-                cur_height_num = o.blk_height.0;
-            }
-            total_num += 1;
-        }
-        Ok((total_num, cur_height_num))
-    }
-}
-
-impl FakeChain {
-    fn new() -> Self {
-        Self {
-            param: None,
-            block_head: HashMap::<Height, BlockHead>::new(),
-            block_content: HashMap::<Height, BlockContent>::new(),
-            id_tree_nodes: HashMap::<IdTreeNodeId, IdTreeNode>::new(),
-            bplus_tree_nodes: HashMap::<BPlusTreeNodeId, BPlusTreeNode<u32>>::new(),
-            trie_nodes: HashMap::<TrieNodeId, TrieNode>::new(),
-            objects: HashMap::<Digest, Object<u32>>::new(),
-        }
-    }
-}
-
-fn build_chain(data: &str, param: &Parameter) -> Result<FakeChain> {
-    let mut chain = FakeChain::new();
+fn build_chain(data: &str, param: &Parameter) -> Result<MemChain> {
+    let mut chain = MemChain::new();
     chain.set_parameter(param)?;
-    let mut prev_hash = Digest::zero();
+    let mut prev_hash = BlockContent::genesis(param).0.to_digest();
     for (blk_height, objs) in load_raw_obj_from_str(data)? {
         let (blk_head, _duration) =
             build_block(blk_height, prev_hash, objs, &mut chain, &param, &PUB_KEY)?;
@@ -487,6 +171,160 @@ fn test_fake_chain_write() {
     assert_eq!(1, 1);
 }
 
+#[test]
+fn test_build_block_progress() {
+    let param = Parameter {
+        time_win_sizes: vec![2, 3],
+        id_tree_fanout: 2,
+        max_id_num: 32,
+        bplus_tree_fanout: 3,
+        num_dim: 2,
+    };
+    let mut chain = MemChain::new();
+    chain.set_parameter(&param).unwrap();
+    let mut prev_hash = BlockContent::genesis(&param).0.to_digest();
+    for (blk_height, objs) in load_raw_obj_from_str(TEST_DATA_2).unwrap() {
+        let obj_num = objs.len();
+        let events = Mutex::new(Vec::<BuildProgress>::new());
+        let record = |p: BuildProgress| events.lock().unwrap().push(p);
+        let (blk_head, _duration) = build_block_with_progress(
+            blk_height,
+            prev_hash,
+            objs,
+            &mut chain,
+            &param,
+            &PUB_KEY,
+            Some(&record),
+        )
+        .unwrap();
+        prev_hash = blk_head.to_digest();
+
+        let events = events.into_inner().unwrap();
+        assert_eq!(events.len(), 1 + 2 + 2 * 2 + 1);
+        assert!(matches!(
+            events.first(),
+            Some(BuildProgress::IdTreeBuilt { obj_num: n, .. }) if *n == obj_num
+        ));
+        for &k in &param.time_win_sizes {
+            let trie_num = events
+                .iter()
+                .filter(
+                    |e| matches!(e, BuildProgress::TrieBuilt { time_win, .. } if *time_win == k),
+                )
+                .count();
+            assert_eq!(trie_num, 1);
+            for d in 0..param.num_dim {
+                let bplus_num = events
+                    .iter()
+                    .filter(|e| {
+                        matches!(e, BuildProgress::BPlusTreeBuilt { time_win, dim, .. } if *time_win == k && *dim == d)
+                    })
+                    .count();
+                assert_eq!(bplus_num, 1);
+            }
+        }
+        assert_eq!(
+            events.last(),
+            Some(&BuildProgress::AdsRootComputed { blk_height })
+        );
+    }
+}
+
+#[test]
+fn test_build_block_exceed_max_id_num() {
+    let param = Parameter {
+        time_win_sizes: vec![2],
+        id_tree_fanout: 2,
+        max_id_num: 4,
+        bplus_tree_fanout: 3,
+        num_dim: 1,
+    };
+    let data = "1 [ 1 ] { a }\n1 [ 2 ] { b }\n1 [ 3 ] { a }\n1 [ 4 ] { b }\n1 [ 5 ] { c }\n";
+    let err = build_chain(data, &param).unwrap_err();
+    assert!(err.to_string().contains("max_id_num"));
+    let chain = build_chain(TEST_DATA_1, &param).unwrap();
+    assert_eq!(
+        (&chain)
+            .read_block_content(Height(1))
+            .unwrap()
+            .obj_id_nums
+            .len(),
+        4
+    );
+
+    let mut content = BlockContent::new(Height(1), Digest::zero());
+    let ids = |v: &[u16]| -> Vec<std::num::NonZeroU16> {
+        v.iter()
+            .map(|&i| std::num::NonZeroU16::new(i).unwrap())
+            .collect()
+    };
+    assert!(content.try_set_obj_id_nums(ids(&[1, 2, 5]), 4).is_err());
+    assert!(content
+        .try_set_obj_id_nums(ids(&[1, 2, 3, 4, 1]), 4)
+        .is_err());
+    assert!(content.obj_id_nums.is_empty());
+    content.try_set_obj_id_nums(ids(&[3, 4, 1]), 4).unwrap();
+    assert_eq!(content.obj_id_nums, ids(&[3, 4, 1]));
+}
+
+#[test]
+fn test_update_component() {
+    let param = Parameter::default_for_testing();
+    let chain = build_chain(TEST_DATA_1, &param).unwrap();
+    let blk_head = (&chain).read_block_head(Height(1)).unwrap();
+    let mut content = (&chain).read_block_content(Height(1)).unwrap();
+    let old = content.ads_components.clone();
+    assert_eq!(old.compute_root(), blk_head.get_ads_root());
+
+    let new_hash = "reindexed_multi_ads".to_digest();
+    let new_root = content.update_component(ComponentKind::MultiAds, new_hash);
+    assert_ne!(new_root, blk_head.get_ads_root());
+    assert_eq!(content.ads_components.multi_ads_hash, new_hash);
+    assert_eq!(
+        content.ads_components.id_set_root_hash,
+        old.id_set_root_hash
+    );
+    assert_eq!(
+        content.ads_components.id_tree_root_hash,
+        old.id_tree_root_hash
+    );
+    assert_eq!(new_root, content.ads_components.compute_root());
+    let recomputed = BlockADSComponents::new(old.id_set_root_hash, old.id_tree_root_hash, new_hash)
+        .compute_root();
+    assert_eq!(new_root, recomputed);
+
+    // 换回原来的哈希后恢复原来的根
+    let restored = content.update_component(ComponentKind::MultiAds, old.multi_ads_hash);
+    assert_eq!(restored, blk_head.get_ads_root());
+}
+
+#[test]
+fn test_height_next_prev() {
+    assert_eq!(Height(0).prev(), None);
+    assert_eq!(Height(0).next(), Some(Height(1)));
+    assert_eq!(Height(1).prev(), Some(Height(0)));
+    assert_eq!(Height(5).next(), Some(Height(6)));
+    assert_eq!(Height(u32::MAX).next(), None);
+    assert_eq!(Height(u32::MAX).prev(), Some(Height(u32::MAX - 1)));
+}
+
+#[test]
+fn test_height_digest() {
+    let h1 = Height(1);
+    let h2 = Height(2);
+    assert_eq!(h1.to_digest(), Height(1).to_digest());
+    assert_ne!(h1.to_digest(), h2.to_digest());
+    // 带域分隔标签，与同值 u32 的摘要不同
+    assert_ne!(h1.to_digest(), 1u32.to_digest());
+
+    let head = |blk_height| BlockHead {
+        blk_height,
+        ..BlockHead::default()
+    };
+    assert_eq!(head(h1).to_digest(), head(h1).to_digest());
+    assert_ne!(head(h1).to_digest(), head(h2).to_digest());
+}
+
 #[test]
 fn test_fake_chain_read_basic() -> Result<()> {
     init_tracing_subscriber("info")?;
@@ -540,3 +378,3092 @@ fn test_fake_chain_read_basic() -> Result<()> {
     assert_eq!(1, 1);
     Ok(())
 }
+
+#[test]
+fn test_inverted_range_rejected() {
+    let param = Parameter {
+        time_win_sizes: vec![4],
+        id_tree_fanout: 4,
+        max_id_num: 32,
+        bplus_tree_fanout: 4,
+        num_dim: 2,
+    };
+    let test_chain = build_chain(TEST_DATA_3, &param).unwrap();
+    let query_param: QueryParam<u32> = serde_json::from_value(json!({
+        "start_blk": 1,
+        "end_blk": 4,
+        "range": [(1, 7), (9, 2)],
+        "keyword_exp": null,
+    }))
+    .unwrap();
+    match query(false, false, &test_chain, query_param, &PUB_KEY) {
+        Ok(_) => panic!("inverted range should be rejected"),
+        Err(e) => assert!(e.to_string().contains("dimension 1")),
+    }
+
+    let query_param: QueryParam<u32> = serde_json::from_value(json!({
+        "start_blk": 1,
+        "end_blk": 4,
+        "range": [(4, 4), (2, 9)],
+        "keyword_exp": null,
+    }))
+    .unwrap();
+    let (results, dag_map, _time) =
+        query(false, false, &test_chain, query_param, &PUB_KEY).unwrap();
+    verify(&test_chain, &results, &dag_map, &PUB_KEY).unwrap();
+}
+
+#[test]
+fn test_open_ended_range() -> Result<()> {
+    use std::ops::Bound;
+
+    let param = Parameter {
+        time_win_sizes: vec![2, 4],
+        id_tree_fanout: 4,
+        max_id_num: 32,
+        bplus_tree_fanout: 4,
+        num_dim: 2,
+    };
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    // 第 0 维的取值都在 [1, 15] 内，开区间与等价的闭区间查询结果应当一致
+    let cases = vec![
+        ((Bound::Included(8), Bound::Unbounded), (8, 100)),
+        ((Bound::Unbounded, Bound::Excluded(6)), (0, 5)),
+        ((Bound::Unbounded, Bound::Unbounded), (0, 100)),
+    ];
+    for ((low, high), closed) in cases {
+        let query_param: QueryParam<u32> = serde_json::from_value(json!({
+            "start_blk": 1,
+            "end_blk": 6,
+            "range": [closed],
+            "keyword_exp": null,
+        }))?;
+        let mut range_dag = Graph::<DagNode<u32>, bool>::new();
+        range_dag.add_node(DagNode::Range(RangeBound::with_bounds(0, low, high)?));
+
+        let (results, dag, _time) =
+            query(false, false, &test_chain, query_param.clone(), &PUB_KEY)?;
+        let (range_results, range_dag, _time) =
+            query_with_dag(false, false, &test_chain, query_param, range_dag, &PUB_KEY)?;
+        verify(&test_chain, &results, &dag, &PUB_KEY)?;
+        verify(&test_chain, &range_results, &range_dag, &PUB_KEY)?;
+        assert_eq!(results.len(), range_results.len());
+        assert!(results.iter().any(|(res, _)| !res.is_empty()));
+        for ((res, _), (range_res, _)) in results.iter().zip(range_results.iter()) {
+            assert_eq!(res, range_res);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_light_verifier() -> Result<()> {
+    let param = Parameter {
+        time_win_sizes: vec![2],
+        id_tree_fanout: 4,
+        max_id_num: 32,
+        bplus_tree_fanout: 4,
+        num_dim: 2,
+    };
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_param_data = json!({
+        "start_blk": 2,
+        "end_blk": 3,
+        "range": [(1, 7), (2, 9)],
+        "keyword_exp": {
+            "or": [
+                {"input": "a"},
+                {"and": [{"input": "b"}, {"input": "c"}]},
+            ]
+        },
+    });
+    let query_param: QueryParam<u32> = serde_json::from_value(query_param_data)?;
+    let (results, dag, _time) = query(false, false, &test_chain, query_param, &PUB_KEY)?;
+    assert_eq!(results.len(), 1);
+    let (res, vo) = &results[0];
+    assert!(!res.is_empty());
+
+    // 轻节点只持有区块头，components 由全节点提供
+    let head = (&test_chain).read_block_head(Height(3))?;
+    let components = (&test_chain)
+        .read_block_content(Height(3))?
+        .get_ads_components()
+        .clone();
+    let verifier = LightVerifier::new(head, param.clone());
+    let info = verifier.verify(&components, vo, res, &dag, &PUB_KEY)?;
+    assert!(info.vo_size.total_s > 0);
+
+    let mut forged = components.clone();
+    forged.multi_ads_hash = Digest::zero();
+    assert!(verifier.verify(&forged, vo, res, &dag, &PUB_KEY).is_err());
+
+    let other_head = (&test_chain).read_block_head(Height(2))?;
+    let other = LightVerifier::new(other_head, param);
+    assert!(other.verify(&components, vo, res, &dag, &PUB_KEY).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_verify_with_heads() -> Result<()> {
+    let param = Parameter::default_for_testing();
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_param_data = json!({
+        "start_blk": 1,
+        "end_blk": 6,
+        "range": [(1, 7), (2, 9)],
+        "keyword_exp": {
+            "or": [
+                {"input": "a"},
+                {"and": [{"input": "b"}, {"input": "c"}]},
+            ]
+        },
+    });
+    let query_param: QueryParam<u32> = serde_json::from_value(query_param_data)?;
+    let (results, dag, _time) = query(false, false, &test_chain, query_param, &PUB_KEY)?;
+
+    // 预先取得区块头后不再访问链
+    let mut heads = HashMap::new();
+    for h in 1..=6 {
+        heads.insert(Height(h), (&test_chain).read_block_head(Height(h))?);
+    }
+    for (res, vo) in &results {
+        let info = verify_with_heads(&heads, &param, res, vo, &dag, &PUB_KEY)?;
+        assert!(info.vo_size.total_s > 0);
+    }
+
+    let (res, vo) = &results[0];
+    let height = *vo
+        .merkle_proofs
+        .keys()
+        .next()
+        .context("VO has no merkle proof")?;
+    let mut missing = heads.clone();
+    missing.remove(&height);
+    let err = verify_with_heads(&missing, &param, res, vo, &dag, &PUB_KEY).unwrap_err();
+    assert!(format!("{:#}", err).contains("Missing block head"));
+    Ok(())
+}
+
+#[test]
+fn test_header_chain_proof() -> Result<()> {
+    let param = Parameter {
+        time_win_sizes: vec![2, 4],
+        id_tree_fanout: 4,
+        max_id_num: 64,
+        bplus_tree_fanout: 4,
+        num_dim: 1,
+    };
+    let gen_data = |seed: u32| {
+        (1..=10)
+            .map(|h| format!("{} [ {} ] {{ a }}\n", h, h + seed))
+            .collect::<String>()
+    };
+    let test_chain = build_chain(&gen_data(0), &param)?;
+    let other_chain = build_chain(&gen_data(1), &param)?;
+
+    let trusted = (&test_chain).read_block_head(Height(1))?;
+    let proof = gen_header_chain_proof(&test_chain, Height(1), Height(10))?;
+    assert_eq!(proof.heads.len(), 9);
+    let head = proof.verify(&trusted)?;
+    assert_eq!(*head, (&test_chain).read_block_head(Height(10))?);
+    let empty_proof = gen_header_chain_proof(&test_chain, Height(4), Height(4))?;
+    assert_eq!(empty_proof.verify(&trusted)?, &trusted);
+    assert!(gen_header_chain_proof(&test_chain, Height(5), Height(4)).is_err());
+
+    // 交换两个中间区块头
+    let mut swapped = proof.clone();
+    swapped.heads.swap(3, 4);
+    assert!(swapped.verify(&trusted).is_err());
+    // 替换为另一条链上同一高度的区块头
+    let mut replaced = proof.clone();
+    replaced.heads[3] = (&other_chain).read_block_head(Height(5))?;
+    assert!(replaced.verify(&trusted).is_err());
+    // 可信区块头不匹配
+    let other_trusted = (&other_chain).read_block_head(Height(1))?;
+    assert!(proof.verify(&other_trusted).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_genesis_block() -> Result<()> {
+    let param = Parameter::default_for_testing();
+    let (genesis, content) = BlockContent::genesis(&param);
+    assert_eq!(genesis.prev_hash, Digest::default());
+    assert_eq!(genesis.blk_height, Height(0));
+    assert!(genesis.is_genesis());
+    assert!(genesis.verify_ads_components(&content.ads_components));
+    for &k in &param.time_win_sizes {
+        assert!(content.ads.bplus_root(k, param.num_dim - 1).is_some());
+    }
+    assert!(content.obj_hashes.is_empty());
+
+    // 第一个区块的 prev_hash 为创世区块头的摘要
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let first = (&test_chain).read_block_head(Height(1))?;
+    assert!(!first.is_genesis());
+    assert_eq!(first.prev_hash, genesis.to_digest());
+    let proof = gen_header_chain_proof(&test_chain, Height(0), Height(3))?;
+    assert_eq!(
+        proof.verify(&genesis)?,
+        &(&test_chain).read_block_head(Height(3))?
+    );
+    // 高度为 0 但 prev_hash 不是默认值的区块头不是创世区块头
+    let not_genesis = BlockHead {
+        prev_hash: first.to_digest(),
+        ..genesis.clone()
+    };
+    assert!(!not_genesis.is_genesis());
+    assert!(proof.verify(&not_genesis).is_err());
+    // 以默认值作为 prev_hash 伪造的第一个区块头不能链接到创世区块头
+    let forged = HeaderChainProof {
+        heads: vec![BlockHead {
+            prev_hash: Digest::default(),
+            ..first
+        }],
+    };
+    assert!(forged.verify(&genesis).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_optimize_dag() -> Result<()> {
+    let param = Parameter {
+        time_win_sizes: vec![2, 4],
+        id_tree_fanout: 4,
+        max_id_num: 32,
+        bplus_tree_fanout: 4,
+        num_dim: 2,
+    };
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    // b、c 在区块 1~4 中各出现 8 次，d 只出现 1 次
+    let query_param_data = json!({
+        "start_blk": 1,
+        "end_blk": 4,
+        "range": [],
+        "keyword_exp": {
+            "and": [
+                {"and": [{"input": "b"}, {"input": "c"}]},
+                {"input": "d"},
+            ]
+        },
+    });
+    let query_param: QueryParam<u32> = serde_json::from_value(query_param_data)?;
+    let dag = gen_parallel_query_dag(&query_param.gen_query_content())?;
+    let keyword_hint = |idx| match dag.node_weight(idx) {
+        Some(DagNode::Keyword(n)) if n.keyword == "d" => 1,
+        Some(DagNode::Keyword(_)) => 8,
+        _ => u64::MAX,
+    };
+    let mut opt_dag = dag.clone();
+    optimize_dag(&mut opt_dag, keyword_hint)?;
+
+    let (results, dag, _time) =
+        query_with_dag(true, false, &test_chain, query_param.clone(), dag, &PUB_KEY)?;
+    let (opt_results, opt_dag, _time) =
+        query_with_dag(true, false, &test_chain, query_param, opt_dag, &PUB_KEY)?;
+    let info = verify(&test_chain, &results, &dag, &PUB_KEY)?;
+    let opt_info = verify(&test_chain, &opt_results, &opt_dag, &PUB_KEY)?;
+    assert_eq!(results.len(), opt_results.len());
+    for ((res, _), (opt_res, _)) in results.iter().zip(opt_results.iter()) {
+        assert_eq!(res, opt_res);
+    }
+    assert!(opt_info.vo_size.total_s < info.vo_size.total_s);
+    Ok(())
+}
+
+#[test]
+fn test_keyword_conjunction() -> Result<()> {
+    use super::verify::vo::VONode;
+    use crate::{
+        acc::{compute_set_operation_final, compute_set_operation_intermediate, AccValue, Op},
+        set,
+    };
+
+    let (s1, s2) = (set! {1, 2}, set! {2, 3});
+    let (a1, a2) = (
+        AccValue::from_set(&s1, &PUB_KEY),
+        AccValue::from_set(&s2, &PUB_KEY),
+    );
+    let union_proofs = (
+        compute_set_operation_intermediate(Op::Union, &s1, &a1, &s2, &a2, &PUB_KEY).2,
+        compute_set_operation_final(Op::Union, &s1, &s2, &PUB_KEY).1,
+    );
+    let param = Parameter {
+        time_win_sizes: vec![2, 4],
+        id_tree_fanout: 4,
+        max_id_num: 32,
+        bplus_tree_fanout: 4,
+        num_dim: 2,
+    };
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let keyword_set = || {
+        DagNode::KeywordSet(Box::new(KeywordSetNode {
+            keywords: vec!["b".to_string(), "c".to_string()],
+        }))
+    };
+
+    // 输出节点为合取节点
+    let mut conj_dag = Graph::<DagNode<u32>, bool>::new();
+    conj_dag.add_node(keyword_set());
+    // 合取节点作为并集的子节点
+    let mut union_dag = Graph::<DagNode<u32>, bool>::new();
+    let union_idx = union_dag.add_node(DagNode::Union(UnionNode {}));
+    let conj_idx = union_dag.add_node(keyword_set());
+    let a_idx = union_dag.add_node(DagNode::Keyword(Box::new(KeywordNode {
+        keyword: "a".to_string(),
+    })));
+    union_dag.add_edge(union_idx, conj_idx, true);
+    union_dag.add_edge(union_idx, a_idx, false);
+
+    let cases = vec![
+        (json!({"and": [{"input": "b"}, {"input": "c"}]}), conj_dag),
+        (
+            json!({"or": [{"and": [{"input": "b"}, {"input": "c"}]}, {"input": "a"}]}),
+            union_dag,
+        ),
+    ];
+    for (keyword_exp, conj_dag) in cases {
+        let query_param: QueryParam<u32> = serde_json::from_value(json!({
+            "start_blk": 1,
+            "end_blk": 4,
+            "range": [],
+            "keyword_exp": keyword_exp,
+        }))?;
+        let (results, dag, _time) =
+            query(false, false, &test_chain, query_param.clone(), &PUB_KEY)?;
+        let (mut conj_results, conj_dag, _time) =
+            query_with_dag(false, false, &test_chain, query_param, conj_dag, &PUB_KEY)?;
+        verify(&test_chain, &results, &dag, &PUB_KEY)?;
+        verify(&test_chain, &conj_results, &conj_dag, &PUB_KEY)?;
+        assert_eq!(results.len(), conj_results.len());
+        for ((res, _), (conj_res, _)) in results.iter().zip(conj_results.iter()) {
+            assert!(!res.is_empty());
+            assert_eq!(res, conj_res);
+        }
+
+        // 合取要求交集，证明方给出并集的证明时验证失败
+        let mut tampered = false;
+        for (_, vo) in conj_results.iter_mut() {
+            for node in vo.vo_dag_content.dag_content.values_mut() {
+                if let VONode::KeywordConjunction(n) = node {
+                    for (_, proof) in n.proofs.iter_mut() {
+                        *proof = union_proofs.0;
+                        tampered = true;
+                    }
+                    if let Some(proof) = n.final_proof.as_mut() {
+                        *proof = union_proofs.1;
+                        tampered = true;
+                    }
+                }
+            }
+        }
+        assert!(tampered);
+        let err = verify(&test_chain, &conj_results, &conj_dag, &PUB_KEY).unwrap_err();
+        assert!(
+            format!("{:#}", err).contains("requires Intersection"),
+            "{:#}",
+            err
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_identity_node() -> Result<()> {
+    use super::verify::vo::VONode;
+
+    let param = Parameter {
+        time_win_sizes: vec![2, 4],
+        id_tree_fanout: 4,
+        max_id_num: 32,
+        bplus_tree_fanout: 4,
+        num_dim: 2,
+    };
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let keyword = |k: &str| {
+        DagNode::Keyword(Box::new(KeywordNode {
+            keyword: k.to_string(),
+        }))
+    };
+
+    let mut identity_dag = Graph::<DagNode<u32>, bool>::new();
+    let identity_idx = identity_dag.add_node(DagNode::Identity(IdentityNode {}));
+    let a_idx = identity_dag.add_node(keyword("a"));
+    identity_dag.add_edge(identity_idx, a_idx, true);
+    // Union(Identity(a), b)：Identity 作为并集的单侧子节点
+    let mut union_dag = Graph::<DagNode<u32>, bool>::new();
+    let union_idx = union_dag.add_node(DagNode::Union(UnionNode {}));
+    let identity_idx = union_dag.add_node(DagNode::Identity(IdentityNode {}));
+    let a_idx = union_dag.add_node(keyword("a"));
+    let b_idx = union_dag.add_node(keyword("b"));
+    union_dag.add_edge(identity_idx, a_idx, true);
+    union_dag.add_edge(union_idx, identity_idx, true);
+    union_dag.add_edge(union_idx, b_idx, false);
+
+    let cases = vec![
+        (json!({"input": "a"}), identity_dag),
+        (json!({"or": [{"input": "a"}, {"input": "b"}]}), union_dag),
+    ];
+    for (keyword_exp, identity_dag) in cases {
+        let query_param: QueryParam<u32> = serde_json::from_value(json!({
+            "start_blk": 1,
+            "end_blk": 6,
+            "range": [],
+            "keyword_exp": keyword_exp,
+        }))?;
+        let (results, dag, _time) =
+            query(false, false, &test_chain, query_param.clone(), &PUB_KEY)?;
+        verify(&test_chain, &results, &dag, &PUB_KEY)?;
+        for &(empty_set, egg_opt) in &[(false, false), (true, false), (false, true)] {
+            let (id_results, id_dag, _time) = query_with_dag(
+                empty_set,
+                egg_opt,
+                &test_chain,
+                query_param.clone(),
+                identity_dag.clone(),
+                &PUB_KEY,
+            )?;
+            verify(&test_chain, &id_results, &id_dag, &PUB_KEY)?;
+            assert_eq!(results.len(), id_results.len());
+            for ((res, _), (id_res, _)) in results.iter().zip(id_results.iter()) {
+                assert!(!res.is_empty());
+                assert_eq!(res, id_res);
+            }
+        }
+    }
+
+    // 篡改 Identity 节点的累加器值
+    let query_param: QueryParam<u32> = serde_json::from_value(json!({
+        "start_blk": 1,
+        "end_blk": 4,
+        "range": [],
+        "keyword_exp": {"input": "a"},
+    }))?;
+    let mut identity_dag = Graph::<DagNode<u32>, bool>::new();
+    let identity_idx = identity_dag.add_node(DagNode::Identity(IdentityNode {}));
+    let a_idx = identity_dag.add_node(keyword("a"));
+    identity_dag.add_edge(identity_idx, a_idx, true);
+    let (mut results, dag, _time) = query_with_dag(
+        false,
+        false,
+        &test_chain,
+        query_param,
+        identity_dag,
+        &PUB_KEY,
+    )?;
+    verify(&test_chain, &results, &dag, &PUB_KEY)?;
+    let wrong_acc = crate::acc::AccValue::from_set(&[1u16].iter().copied().collect(), &PUB_KEY);
+    for (_, vo) in results.iter_mut() {
+        for node in vo.vo_dag_content.dag_content.values_mut() {
+            if let VONode::Identity(n) = node {
+                n.acc = wrong_acc;
+            }
+        }
+    }
+    assert!(verify(&test_chain, &results, &dag, &PUB_KEY).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_verify_locate() -> Result<()> {
+    use super::verify::vo::VONode;
+
+    let param = Parameter {
+        time_win_sizes: vec![2, 4],
+        id_tree_fanout: 4,
+        max_id_num: 32,
+        bplus_tree_fanout: 4,
+        num_dim: 2,
+    };
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_param: QueryParam<u32> = serde_json::from_value(json!({
+        "start_blk": 1,
+        "end_blk": 6,
+        "range": [],
+        "keyword_exp": {"or": [{"input": "a"}, {"input": "b"}]},
+    }))?;
+    let (mut results, dag, _time) = query(false, false, &test_chain, query_param, &PUB_KEY)?;
+    assert!(verify_locate(&test_chain, &results, &dag, &PUB_KEY)?.is_ok());
+
+    let keyword_idx = |k: &str| {
+        dag.node_indices()
+            .find(|&idx| matches!(&dag[idx], DagNode::Keyword(n) if n.keyword == k))
+            .unwrap()
+    };
+    let (a_idx, b_idx) = (keyword_idx("a"), keyword_idx("b"));
+    // 在两个相互独立的关键字节点上分别篡改累加器值
+    let wrong_acc = crate::acc::AccValue::from_set(&[1u16].iter().copied().collect(), &PUB_KEY);
+    for (_, vo) in results.iter_mut() {
+        for idx in &[a_idx, b_idx] {
+            if let Some(VONode::Keyword(n)) = vo.vo_dag_content.dag_content.get_mut(idx) {
+                n.acc = wrong_acc;
+            }
+        }
+    }
+    assert!(verify(&test_chain, &results, &dag, &PUB_KEY).is_err());
+
+    let report = verify_locate(&test_chain, &results, &dag, &PUB_KEY)?;
+    assert!(!report.is_ok());
+    let failed_idxs: HashSet<_> = report.failed_nodes.iter().map(|(idx, _)| *idx).collect();
+    assert_eq!(failed_idxs, [a_idx, b_idx].iter().copied().collect());
+    assert!(report.failed_heights.is_empty());
+    assert!(report.others.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_query_result_verify() -> Result<()> {
+    let param = Parameter {
+        time_win_sizes: vec![2, 4],
+        id_tree_fanout: 4,
+        max_id_num: 32,
+        bplus_tree_fanout: 4,
+        num_dim: 2,
+    };
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_param = |k: &str| -> Result<QueryParam<u32>> {
+        Ok(serde_json::from_value(json!({
+            "start_blk": 1,
+            "end_blk": 4,
+            "range": [],
+            "keyword_exp": {"input": k},
+        }))?)
+    };
+    let (mut a_results, _time) =
+        query_results(false, false, &test_chain, query_param("a")?, &PUB_KEY)?;
+    let (b_results, _time) = query_results(false, false, &test_chain, query_param("b")?, &PUB_KEY)?;
+    for res in a_results.iter().chain(b_results.iter()) {
+        res.verify(&test_chain, &PUB_KEY)?;
+    }
+
+    // 换成另一个查询的结果对象后验证失败
+    let a_res = &mut a_results[0];
+    let b_res = &b_results[0];
+    assert_ne!(a_res.objects, b_res.objects);
+    a_res.objects = b_res.objects.clone();
+    assert!(a_res.verify(&test_chain, &PUB_KEY).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_object_projection() -> Result<()> {
+    let param = Parameter {
+        time_win_sizes: vec![2, 4],
+        id_tree_fanout: 4,
+        max_id_num: 32,
+        bplus_tree_fanout: 4,
+        num_dim: 2,
+    };
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_param: QueryParam<u32> = serde_json::from_value(json!({
+        "start_blk": 1,
+        "end_blk": 4,
+        "range": [],
+        "keyword_exp": {"input": "a"},
+    }))?;
+    let (results, _time) = query_results(false, false, &test_chain, query_param, &PUB_KEY)?;
+    let fields = [FieldId::Num(1), FieldId::Keywords];
+    for res in &results {
+        let projected = res.projected_objects(&fields);
+        assert!(!projected.is_empty());
+        assert_eq!(
+            projected.keys().collect::<HashSet<_>>(),
+            res.objects.keys().collect::<HashSet<_>>()
+        );
+        for (id, p) in &projected {
+            let obj = &res.objects[id];
+            assert_eq!(p.blk_height, None);
+            assert_eq!(p.num_data.len(), 1);
+            assert_eq!(p.num_data[&1], obj.num_data[1]);
+            assert_eq!(p.keyword_data.as_ref(), Some(&obj.keyword_data));
+        }
+        // 投影只影响展示，完整对象仍能通过验证
+        res.verify(&test_chain, &PUB_KEY)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_verify_id_tree_members_parallel() -> Result<()> {
+    use super::{
+        id_tree::{proof::Proof, read::ReadContext, write::WriteContext, IdTreeRoot},
+        verify::{hash::obj_hash, verify_id_tree_members},
+    };
+
+    fn assert_sync<T: Sync>() {}
+    assert_sync::<Proof>();
+
+    const OBJ_NUM: u16 = 1000;
+    const MAX_ID_NUM: u16 = 1024;
+    const FANOUT: u8 = 4;
+    let mut chain = MemChain::default();
+    let mut objs = HashMap::new();
+    let changes = {
+        let loader = &chain;
+        let mut ctx = WriteContext::new(&loader, IdTreeRoot::default());
+        for i in 0..OBJ_NUM {
+            let obj: Object<u32> = Object::new(Height(1), vec![i as u32], ["a".to_string()].into());
+            let id = ctx.insert(obj.to_digest(), MAX_ID_NUM, FANOUT)?;
+            objs.insert(id, obj);
+        }
+        ctx.changes()
+    };
+    chain.id_tree_nodes.extend(changes.nodes);
+    let loader = &chain;
+    let mut ctx = ReadContext::new(&loader, changes.root.get_id_tree_root_id());
+    for id in objs.keys() {
+        ctx.query(*id, MAX_ID_NUM, FANOUT)?;
+    }
+    let p = ctx.into_proof();
+
+    // 篡改其中一个对象，并行与串行验证应给出相同的结果
+    let bad_id = *objs.keys().next().context("empty objects")?;
+    objs.get_mut(&bad_id).context("missing object")?.num_data = vec![u32::MAX];
+    let serial: Vec<bool> = objs
+        .iter()
+        .map(|(id, obj)| {
+            p.verify_value(obj_hash(obj, id), *id, MAX_ID_NUM, FANOUT)
+                .is_ok()
+        })
+        .collect();
+    let parallel: Vec<bool> = verify_id_tree_members(&p, &objs, MAX_ID_NUM, FANOUT)
+        .iter()
+        .map(|r| r.is_ok())
+        .collect();
+    assert_eq!(serial.len(), OBJ_NUM as usize);
+    assert_eq!(serial, parallel);
+    assert_eq!(parallel.iter().filter(|ok| !**ok).count(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_empty_result() -> Result<()> {
+    use super::verify::vo::VONode;
+
+    let param = Parameter {
+        time_win_sizes: vec![2, 4],
+        id_tree_fanout: 4,
+        max_id_num: 32,
+        bplus_tree_fanout: 4,
+        num_dim: 2,
+    };
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_param = |keyword_exp: serde_json::Value| -> Result<QueryParam<u32>> {
+        Ok(serde_json::from_value(json!({
+            "start_blk": 1,
+            "end_blk": 6,
+            "range": [],
+            "keyword_exp": keyword_exp,
+        }))?)
+    };
+    let exps = vec![
+        json!({"input": "zzz"}),
+        json!({"and": [{"input": "a"}, {"input": "zzz"}]}),
+        json!({"and": [{"input": "zzz"}, {"not": {"input": "a"}}]}),
+    ];
+    for keyword_exp in &exps {
+        for &(empty_set, egg_opt) in &[(false, false), (true, false), (false, true)] {
+            let (results, dag, _time) = query(
+                empty_set,
+                egg_opt,
+                &test_chain,
+                query_param(keyword_exp.clone())?,
+                &PUB_KEY,
+            )?;
+            for (res, vo) in &results {
+                assert!(res.is_empty());
+                let output_sets = &vo.vo_dag_content.output_sets;
+                assert!(!output_sets.is_empty());
+                assert!(output_sets.values().all(|set| set.is_empty()));
+            }
+            verify(&test_chain, &results, &dag, &PUB_KEY)?;
+        }
+    }
+
+    // 不使用空集处理时，空结果由 FinalIntersec 的证明给出
+    let (results, _dag, _time) = query(
+        false,
+        false,
+        &test_chain,
+        query_param(exps[1].clone())?,
+        &PUB_KEY,
+    )?;
+    for (_, vo) in &results {
+        for idx in vo.vo_dag_content.output_sets.keys() {
+            assert!(matches!(
+                vo.vo_dag_content.dag_content.get(idx),
+                Some(VONode::FinalIntersec(_))
+            ));
+        }
+    }
+
+    // 空集处理后的输出节点没有证明，其累加器值必须是空集累加器
+    let (mut results, dag, _time) = query(
+        true,
+        false,
+        &test_chain,
+        query_param(exps[1].clone())?,
+        &PUB_KEY,
+    )?;
+    let wrong_acc = crate::acc::AccValue::from_set(&[1u16].iter().copied().collect(), &PUB_KEY);
+    let mut tampered = false;
+    for (_, vo) in results.iter_mut() {
+        let output_idxs: Vec<_> = vo.vo_dag_content.output_sets.keys().copied().collect();
+        for idx in output_idxs {
+            if let Some(VONode::InterIntersec(n)) = vo.vo_dag_content.dag_content.get_mut(&idx) {
+                n.acc = wrong_acc;
+                tampered = true;
+            }
+        }
+    }
+    assert!(tampered);
+    assert!(verify(&test_chain, &results, &dag, &PUB_KEY).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_id_tree_fanout() -> Result<()> {
+    let mut param = Parameter {
+        time_win_sizes: vec![2, 4],
+        id_tree_fanout: 8,
+        max_id_num: 32,
+        bplus_tree_fanout: 4,
+        num_dim: 2,
+    };
+    let mut test_chain = build_chain(TEST_DATA_3, &param)?;
+    let mut results = Vec::new();
+    for keyword in &["a", "zzz"] {
+        let query_param: QueryParam<u32> = serde_json::from_value(json!({
+            "start_blk": 1,
+            "end_blk": 6,
+            "range": [],
+            "keyword_exp": {"input": keyword},
+        }))?;
+        let (res, dag, _time) = query(false, false, &test_chain, query_param, &PUB_KEY)?;
+        verify(&test_chain, &res, &dag, &PUB_KEY)?;
+        results.push((res, dag));
+    }
+
+    // 参数声明的扇出与构建时不一致，结果为空时也能发现
+    param.id_tree_fanout = 4;
+    test_chain.set_parameter(&param)?;
+    for (res, dag) in &results {
+        assert!(verify(&test_chain, res, dag, &PUB_KEY).is_err());
+    }
+
+    // 不能以不同的扇出继续构建已有的 id 树
+    let last_height = *test_chain.block_head.keys().max().context("empty chain")?;
+    let prev_hash = test_chain.block_head[&last_height].to_digest();
+    let blk_height = Height(last_height.0 + 1);
+    let objs = load_raw_obj_from_str(&format!("{} [ 1, 1 ] {{ a }}\n", blk_height.0))?
+        .remove(&blk_height)
+        .context("failed to load objects")?;
+    assert!(build_block(
+        blk_height,
+        prev_hash,
+        objs,
+        &mut test_chain,
+        &param,
+        &PUB_KEY
+    )
+    .is_err());
+    Ok(())
+}
+
+#[test]
+fn test_block_snapshot() -> Result<()> {
+    let param = Parameter {
+        time_win_sizes: vec![2, 4],
+        id_tree_fanout: 4,
+        max_id_num: 32,
+        bplus_tree_fanout: 4,
+        num_dim: 2,
+    };
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let blk_content = (&test_chain).read_block_content(Height(3))?;
+    let mut snapshot = Vec::new();
+    blk_content.export(&mut snapshot)?;
+    let imported = BlockContent::import(&snapshot[..])?;
+    assert_eq!(imported, blk_content);
+    assert!((&test_chain)
+        .read_block_head(Height(3))?
+        .verify_ads_components(imported.get_ads_components()));
+
+    // 任意位置的损坏与截断都能发现
+    for &pos in &[0, snapshot.len() / 2, snapshot.len() - 1] {
+        let mut corrupted = snapshot.clone();
+        corrupted[pos] ^= 1;
+        assert!(BlockContent::import(&corrupted[..]).is_err());
+    }
+    assert!(BlockContent::import(&snapshot[..snapshot.len() - 1]).is_err());
+
+    // 校验和正确但组件与内容不一致
+    let mut tampered = blk_content;
+    tampered.obj_id_nums.pop();
+    let mut snapshot = Vec::new();
+    tampered.export(&mut snapshot)?;
+    assert!(BlockContent::import(&snapshot[..]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_block_validate_shape() -> Result<()> {
+    use super::block::hash::obj_id_nums_hash;
+
+    let param = Parameter {
+        time_win_sizes: vec![2, 4],
+        id_tree_fanout: 4,
+        max_id_num: 32,
+        bplus_tree_fanout: 4,
+        num_dim: 2,
+    };
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    for height in test_chain.block_content.keys() {
+        (&test_chain)
+            .read_block_content(*height)?
+            .validate_shape()?;
+    }
+
+    // 对象哈希与对象 id 的个数不一致
+    let blk_content = (&test_chain).read_block_content(Height(3))?;
+    let mut tampered = blk_content.clone();
+    tampered.obj_hashes.pop();
+    assert!(tampered.validate_shape().is_err());
+    let mut snapshot = Vec::new();
+    tampered.export(&mut snapshot)?;
+    assert!(BlockContent::import(&snapshot[..]).is_err());
+
+    // 重复的对象 id，即使 ads 组件按篡改后的 id 重新计算
+    let mut tampered = blk_content.clone();
+    tampered.obj_id_nums[1] = tampered.obj_id_nums[0];
+    let mut components = tampered.get_ads_components().clone();
+    components.id_set_root_hash = obj_id_nums_hash(tampered.obj_id_nums.iter());
+    tampered.set_ads_components(components);
+    assert!(tampered.validate_shape().is_err());
+
+    // id 树根与 ads 组件不一致
+    let mut tampered = blk_content;
+    tampered.set_id_tree_root(Default::default());
+    assert!(tampered.validate_shape().is_err());
+    Ok(())
+}
+
+#[test]
+fn test_id_set_acc() -> Result<()> {
+    use super::verify::vo::VONode;
+    use crate::acc::{compute_set_operation_intermediate, ops::Op, AccValue, Set};
+
+    let param = Parameter {
+        time_win_sizes: vec![2, 4],
+        id_tree_fanout: 4,
+        max_id_num: 32,
+        bplus_tree_fanout: 4,
+        num_dim: 2,
+    };
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_param = |keyword_exp: serde_json::Value| -> Result<QueryParam<u32>> {
+        Ok(serde_json::from_value(json!({
+            "start_blk": 3,
+            "end_blk": 4,
+            "range": [],
+            "keyword_exp": keyword_exp,
+        }))?)
+    };
+    let res_ids = |results: &[(HashMap<ObjId, Object<u32>>, _)]| -> Set {
+        results
+            .iter()
+            .flat_map(|(res, _)| res.keys().map(|id| id.0))
+            .collect()
+    };
+
+    // 窗口 [3, 4] 的全集为两个区块对象 id 集合的并
+    let mut universe = Set::new();
+    for height in 3..=4 {
+        let blk_content = (&test_chain).read_block_content(Height(height))?;
+        assert_eq!(
+            blk_content.id_set_acc(&PUB_KEY),
+            AccValue::from_set(&blk_content.id_set(), &PUB_KEY)
+        );
+        universe = &universe | &blk_content.id_set();
+    }
+    let universe_acc = AccValue::from_set(&universe, &PUB_KEY);
+
+    let (a_results, _, _) = query(
+        false,
+        false,
+        &test_chain,
+        query_param(json!({"input": "a"}))?,
+        &PUB_KEY,
+    )?;
+    let a_set = res_ids(&a_results);
+    let a_acc = AccValue::from_set(&a_set, &PUB_KEY);
+    let (diff_set, diff_acc, diff_proof) = compute_set_operation_intermediate(
+        Op::Difference,
+        &universe,
+        &universe_acc,
+        &a_set,
+        &a_acc,
+        &PUB_KEY,
+    );
+    diff_proof.verify(&universe_acc, &a_acc, &diff_acc, &PUB_KEY)?;
+
+    let (mut results, dag, _) = query(
+        false,
+        false,
+        &test_chain,
+        query_param(json!({"not": {"input": "a"}}))?,
+        &PUB_KEY,
+    )?;
+    assert_eq!(res_ids(&results), diff_set);
+    verify(&test_chain, &results, &dag, &PUB_KEY)?;
+
+    // BlkRt 节点的累加器值由窗口内各区块的对象 id 集合证明约束
+    for (_, vo) in results.iter_mut() {
+        for node in vo.vo_dag_content.dag_content.values_mut() {
+            if let VONode::BlkRt(n) = node {
+                assert_eq!(n.acc, universe_acc);
+                n.acc = a_acc;
+            }
+        }
+    }
+    assert!(verify(&test_chain, &results, &dag, &PUB_KEY).is_err());
+    // 即使不经过差集证明，BlkRt 节点本身也会因累加器与 id 集合证明不符而被拒绝
+    let report = verify_locate(&test_chain, &results, &dag, &PUB_KEY)?;
+    assert!(report.failed_nodes.iter().any(|(idx, e)| {
+        matches!(dag.node_weight(*idx), Some(DagNode::BlkRt(_)))
+            && format!("{:#}", e.error).contains("BlkRt acc does not match")
+    }));
+
+    let (mut results, dag, _) = query(
+        false,
+        false,
+        &test_chain,
+        query_param(json!({"not": {"input": "a"}}))?,
+        &PUB_KEY,
+    )?;
+    for (_, vo) in results.iter_mut() {
+        for node in vo.vo_dag_content.dag_content.values_mut() {
+            if let VONode::BlkRt(n) = node {
+                for proof in n.id_set_proofs.values_mut() {
+                    proof.obj_id_nums.pop();
+                }
+            }
+        }
+    }
+    assert!(verify(&test_chain, &results, &dag, &PUB_KEY).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_height_filter() -> Result<()> {
+    use super::verify::vo::VONode;
+    use crate::{
+        acc::{compute_set_operation_intermediate, AccValue, Op, Set},
+        set,
+    };
+
+    let param = Parameter {
+        time_win_sizes: vec![2, 4],
+        id_tree_fanout: 4,
+        max_id_num: 32,
+        bplus_tree_fanout: 4,
+        num_dim: 2,
+    };
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_param: QueryParam<u32> = serde_json::from_value(json!({
+        "start_blk": 1,
+        "end_blk": 6,
+        "range": [],
+        "keyword_exp": {"input": "a"},
+    }))?;
+    let res_ids = |results: &[(HashMap<ObjId, Object<u32>>, _)]| -> Set {
+        results
+            .iter()
+            .flat_map(|(res, _)| res.keys().map(|id| id.0))
+            .collect()
+    };
+    let filter_dag = || {
+        let mut dag = Graph::<DagNode<u32>, bool>::new();
+        let filter_idx = dag.add_node(DagNode::HeightFilter(HeightFilterNode {
+            range: Height(3)..Height(5),
+        }));
+        let a_idx = dag.add_node(DagNode::Keyword(Box::new(KeywordNode {
+            keyword: "a".to_string(),
+        })));
+        dag.add_edge(filter_idx, a_idx, true);
+        dag
+    };
+
+    let mut range_ids = Set::new();
+    for height in 3..5 {
+        range_ids = &range_ids | &(&test_chain).read_block_content(Height(height))?.id_set();
+    }
+    let (a_results, _, _) = query(false, false, &test_chain, query_param.clone(), &PUB_KEY)?;
+    let expected = &res_ids(&a_results) & &range_ids;
+    assert!(!expected.is_empty());
+    assert_ne!(expected, res_ids(&a_results));
+
+    for &(empty_set, egg_opt) in &[(false, false), (true, false), (false, true)] {
+        let (results, dag, _time) = query_with_dag(
+            empty_set,
+            egg_opt,
+            &test_chain,
+            query_param.clone(),
+            filter_dag(),
+            &PUB_KEY,
+        )?;
+        assert_eq!(res_ids(&results), expected);
+        verify(&test_chain, &results, &dag, &PUB_KEY)?;
+    }
+
+    // 过滤集合由区块头认证，篡改区块的对象 id 集合证明后验证失败
+    let (mut results, dag, _time) = query_with_dag(
+        false,
+        false,
+        &test_chain,
+        query_param.clone(),
+        filter_dag(),
+        &PUB_KEY,
+    )?;
+    let mut tampered = false;
+    for (_, vo) in results.iter_mut() {
+        for node in vo.vo_dag_content.dag_content.values_mut() {
+            if let VONode::HeightFilter(n) = node {
+                for proof in n.id_set_proofs.values_mut() {
+                    proof.obj_id_nums.pop();
+                    tampered = true;
+                }
+            }
+        }
+    }
+    assert!(tampered);
+    assert!(verify(&test_chain, &results, &dag, &PUB_KEY).is_err());
+
+    // 过滤要求交集，证明方给出并集的证明时验证失败
+    let (mut results, dag, _time) = query_with_dag(
+        false,
+        false,
+        &test_chain,
+        query_param,
+        filter_dag(),
+        &PUB_KEY,
+    )?;
+    let (s1, s2) = (set! {1, 2}, set! {2, 3});
+    let (a1, a2) = (
+        AccValue::from_set(&s1, &PUB_KEY),
+        AccValue::from_set(&s2, &PUB_KEY),
+    );
+    let (_, _, union_proof) =
+        compute_set_operation_intermediate(Op::Union, &s1, &a1, &s2, &a2, &PUB_KEY);
+    for (_, vo) in results.iter_mut() {
+        for node in vo.vo_dag_content.dag_content.values_mut() {
+            if let VONode::HeightFilter(n) = node {
+                n.proof = union_proof;
+            }
+        }
+    }
+    let err = verify(&test_chain, &results, &dag, &PUB_KEY).unwrap_err();
+    assert!(
+        format!("{:#}", err).contains("requires Intersection"),
+        "{:#}",
+        err
+    );
+    Ok(())
+}
+
+/// 读取区块头时先返回若干次暂时性错误的后端，缺失的高度返回 `ReadError::NotFound`
+struct FlakyChain<'a> {
+    inner: &'a MemChain,
+    failures: AtomicU32,
+    head_reads: AtomicU32,
+}
+
+impl<'a> FlakyChain<'a> {
+    fn new(inner: &'a MemChain, failures: u32) -> Self {
+        Self {
+            inner,
+            failures: AtomicU32::new(failures),
+            head_reads: AtomicU32::new(0),
+        }
+    }
+}
+
+impl ReadInterface for &FlakyChain<'_> {
+    type K = u32;
+    fn get_parameter(&self) -> Result<Parameter> {
+        self.inner.get_parameter()
+    }
+    fn read_block_head(&self, blk_height: Height) -> Result<BlockHead> {
+        self.head_reads.fetch_add(1, Ordering::SeqCst);
+        let failures = self.failures.load(Ordering::SeqCst);
+        if failures > 0 {
+            self.failures.store(failures - 1, Ordering::SeqCst);
+            return Err(ReadError::Transient("connection reset".to_owned()).into());
+        }
+        self.inner
+            .read_block_head(blk_height)
+            .map_err(|e| ReadError::NotFound(e.to_string()).into())
+    }
+    fn read_block_content(&self, blk_height: Height) -> Result<BlockContent> {
+        self.inner.read_block_content(blk_height)
+    }
+    fn read_id_tree_node(&self, id_tree_node_id: IdTreeNodeId) -> Result<IdTreeNode> {
+        self.inner.read_id_tree_node(id_tree_node_id)
+    }
+    fn read_bplus_tree_node(
+        &self,
+        bplus_tree_node_id: BPlusTreeNodeId,
+    ) -> Result<BPlusTreeNode<Self::K>> {
+        self.inner.read_bplus_tree_node(bplus_tree_node_id)
+    }
+    fn read_trie_node(&self, trie_node_id: TrieNodeId) -> Result<TrieNode> {
+        self.inner.read_trie_node(trie_node_id)
+    }
+    fn read_object(&self, obj_hash: Digest) -> Result<Object<Self::K>> {
+        self.inner.read_object(obj_hash)
+    }
+}
+
+#[test]
+fn test_retrying_chain() -> Result<()> {
+    let param = Parameter {
+        time_win_sizes: vec![2, 4],
+        id_tree_fanout: 4,
+        max_id_num: 32,
+        bplus_tree_fanout: 4,
+        num_dim: 2,
+    };
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_param: QueryParam<u32> = serde_json::from_value(json!({
+        "start_blk": 1,
+        "end_blk": 6,
+        "range": [],
+        "keyword_exp": {"input": "a"},
+    }))?;
+    let (results, dag, _time) = query(false, false, &test_chain, query_param, &PUB_KEY)?;
+    let backoff = Duration::from_millis(1);
+
+    // 没有重试时一次暂时性错误就会中止验证
+    let flaky = FlakyChain::new(&test_chain, 2);
+    assert!(verify(&flaky, &results, &dag, &PUB_KEY).is_err());
+
+    // 连续失败两次后成功
+    let flaky = FlakyChain::new(&test_chain, 2);
+    verify(
+        RetryingChain::new(&flaky, 3, backoff),
+        &results,
+        &dag,
+        &PUB_KEY,
+    )?;
+    assert_eq!(flaky.failures.load(Ordering::SeqCst), 0);
+
+    // 重试次数用尽
+    let flaky = FlakyChain::new(&test_chain, 2);
+    let res = verify(
+        RetryingChain::new(&flaky, 1, backoff),
+        &results,
+        &dag,
+        &PUB_KEY,
+    );
+    assert!(res.is_err());
+
+    // 不存在的高度不重试
+    let flaky = FlakyChain::new(&test_chain, 0);
+    let err = RetryingChain::new(&flaky, 3, backoff)
+        .read_block_head(Height(1000))
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ReadError>(),
+        Some(ReadError::NotFound(_))
+    ));
+    assert_eq!(flaky.head_reads.load(Ordering::SeqCst), 1);
+    Ok(())
+}
+
+#[test]
+fn test_keyword_prefix() -> Result<()> {
+    use super::verify::vo::VONode;
+    use crate::{
+        acc::{compute_set_operation_final, compute_set_operation_intermediate, AccValue, Op},
+        set,
+    };
+
+    let (s1, s2) = (set! {1, 2}, set! {2, 3});
+    let (a1, a2) = (
+        AccValue::from_set(&s1, &PUB_KEY),
+        AccValue::from_set(&s2, &PUB_KEY),
+    );
+    let intersec_proofs = (
+        compute_set_operation_intermediate(Op::Intersection, &s1, &a1, &s2, &a2, &PUB_KEY).2,
+        compute_set_operation_final(Op::Intersection, &s1, &s2, &PUB_KEY).1,
+    );
+    let param = Parameter {
+        time_win_sizes: vec![2, 4],
+        id_tree_fanout: 4,
+        max_id_num: 32,
+        bplus_tree_fanout: 4,
+        num_dim: 2,
+    };
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let keyword_prefix = |prefix: &str| {
+        DagNode::KeywordPrefix(Box::new(KeywordPrefixNode {
+            prefix: prefix.to_string(),
+        }))
+    };
+    // 区块 1~4 中以 "a" 为前缀的关键字为 a、ab、acd、ae、aed
+    let a_exp = json!({"or": [
+        {"or": [{"input": "a"}, {"input": "ab"}]},
+        {"or": [{"input": "acd"}, {"or": [{"input": "ae"}, {"input": "aed"}]}]},
+    ]});
+
+    let mut prefix_dag = Graph::<DagNode<u32>, bool>::new();
+    prefix_dag.add_node(keyword_prefix("a"));
+    let mut intersec_dag = Graph::<DagNode<u32>, bool>::new();
+    let intersec_idx = intersec_dag.add_node(DagNode::Intersec(IntersecNode {}));
+    let prefix_idx = intersec_dag.add_node(keyword_prefix("a"));
+    let c_idx = intersec_dag.add_node(DagNode::Keyword(Box::new(KeywordNode {
+        keyword: "c".to_string(),
+    })));
+    intersec_dag.add_edge(intersec_idx, prefix_idx, true);
+    intersec_dag.add_edge(intersec_idx, c_idx, false);
+
+    let cases = vec![
+        (a_exp.clone(), prefix_dag),
+        (json!({"and": [a_exp, {"input": "c"}]}), intersec_dag),
+    ];
+    for (keyword_exp, prefix_dag) in cases {
+        let query_param: QueryParam<u32> = serde_json::from_value(json!({
+            "start_blk": 1,
+            "end_blk": 4,
+            "range": [],
+            "keyword_exp": keyword_exp,
+        }))?;
+        let (results, dag, _time) =
+            query(false, false, &test_chain, query_param.clone(), &PUB_KEY)?;
+        let (mut prefix_results, prefix_dag, _time) =
+            query_with_dag(false, false, &test_chain, query_param, prefix_dag, &PUB_KEY)?;
+        verify(&test_chain, &results, &dag, &PUB_KEY)?;
+        verify(&test_chain, &prefix_results, &prefix_dag, &PUB_KEY)?;
+        assert_eq!(results.len(), prefix_results.len());
+        for ((res, _), (prefix_res, _)) in results.iter().zip(prefix_results.iter()) {
+            assert!(!res.is_empty());
+            assert_eq!(res, prefix_res);
+        }
+
+        // 前缀匹配要求并集，证明方给出交集的证明时验证失败
+        let mut tampered = false;
+        for (_, vo) in prefix_results.iter_mut() {
+            for node in vo.vo_dag_content.dag_content.values_mut() {
+                if let VONode::KeywordPrefix(n) = node {
+                    for (_, proof) in n.proofs.iter_mut() {
+                        *proof = intersec_proofs.0;
+                        tampered = true;
+                    }
+                    if let Some(proof) = n.final_proof.as_mut() {
+                        *proof = intersec_proofs.1;
+                        tampered = true;
+                    }
+                }
+            }
+        }
+        assert!(tampered);
+        let err = verify(&test_chain, &prefix_results, &prefix_dag, &PUB_KEY).unwrap_err();
+        assert!(format!("{:#}", err).contains("requires Union"), "{:#}", err);
+    }
+
+    // 不存在的前缀
+    let query_param: QueryParam<u32> = serde_json::from_value(json!({
+        "start_blk": 1,
+        "end_blk": 4,
+        "range": [],
+        "keyword_exp": {"input": "x"},
+    }))?;
+    let mut prefix_dag = Graph::<DagNode<u32>, bool>::new();
+    prefix_dag.add_node(keyword_prefix("x"));
+    let (results, prefix_dag, _time) =
+        query_with_dag(false, false, &test_chain, query_param, prefix_dag, &PUB_KEY)?;
+    verify(&test_chain, &results, &prefix_dag, &PUB_KEY)?;
+    assert!(results.iter().all(|(res, _)| res.is_empty()));
+    Ok(())
+}
+
+#[test]
+fn test_verify_batch() -> Result<()> {
+    let param = Parameter {
+        time_win_sizes: vec![2, 4],
+        id_tree_fanout: 4,
+        max_id_num: 32,
+        bplus_tree_fanout: 4,
+        num_dim: 2,
+    };
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_params = vec![
+        json!({
+            "start_blk": 1,
+            "end_blk": 4,
+            "range": [],
+            "keyword_exp": {"and": [{"input": "b"}, {"input": "c"}]},
+        }),
+        json!({
+            "start_blk": 2,
+            "end_blk": 3,
+            "range": [(1, 7), (2, 9)],
+            "keyword_exp": {"or": [{"input": "a"}, {"input": "b"}]},
+        }),
+        json!({
+            "start_blk": 1,
+            "end_blk": 4,
+            "range": [(1, 7)],
+            "keyword_exp": null,
+        }),
+    ];
+    let mut jobs = Vec::new();
+    for _ in 0..2 {
+        for query_param_data in &query_params {
+            let query_param: QueryParam<u32> = serde_json::from_value(query_param_data.clone())?;
+            let (res_contents, graph, _time) =
+                query(false, false, &test_chain, query_param, &PUB_KEY)?;
+            jobs.push(VerifyJob {
+                res_contents,
+                graph,
+            });
+        }
+    }
+    // 删除一个结果对象
+    let res_content = &mut jobs[1].res_contents[0].0;
+    let obj_id = *res_content.keys().next().context("empty result")?;
+    res_content.remove(&obj_id);
+    // 同时从 VO 的输出集合与结果中删除同一个对象，只有集合运算证明能发现
+    let (res_content, vo) = &mut jobs[3].res_contents[0];
+    let output_set = vo
+        .vo_dag_content
+        .output_sets
+        .values_mut()
+        .next()
+        .context("empty output sets")?;
+    let id = *output_set.iter().next().context("empty output set")?;
+    output_set.remove(&id);
+    res_content.remove(&ObjId(id));
+
+    let results = verify_batch(&&test_chain, jobs, &PUB_KEY);
+    let failed: Vec<usize> = results
+        .iter()
+        .enumerate()
+        .filter(|(_, res)| res.is_err())
+        .map(|(i, _)| i)
+        .collect();
+    assert_eq!(failed, vec![1, 3]);
+    Ok(())
+}
+
+// 记录每个高度的区块头被读取的次数
+struct CountingChain<'a> {
+    chain: &'a MemChain,
+    head_reads: Mutex<HashMap<Height, usize>>,
+}
+
+impl ReadInterface for &CountingChain<'_> {
+    type K = u32;
+    fn get_parameter(&self) -> Result<Parameter> {
+        self.chain.get_parameter()
+    }
+    fn read_block_head(&self, blk_height: Height) -> Result<BlockHead> {
+        *self
+            .head_reads
+            .lock()
+            .unwrap()
+            .entry(blk_height)
+            .or_default() += 1;
+        self.chain.read_block_head(blk_height)
+    }
+    fn read_block_content(&self, blk_height: Height) -> Result<BlockContent> {
+        self.chain.read_block_content(blk_height)
+    }
+    fn read_id_tree_node(&self, id_tree_node_id: IdTreeNodeId) -> Result<IdTreeNode> {
+        self.chain.read_id_tree_node(id_tree_node_id)
+    }
+    fn read_bplus_tree_node(
+        &self,
+        bplus_tree_node_id: BPlusTreeNodeId,
+    ) -> Result<BPlusTreeNode<Self::K>> {
+        self.chain.read_bplus_tree_node(bplus_tree_node_id)
+    }
+    fn read_trie_node(&self, trie_node_id: TrieNodeId) -> Result<TrieNode> {
+        self.chain.read_trie_node(trie_node_id)
+    }
+    fn read_object(&self, obj_hash: Digest) -> Result<Object<Self::K>> {
+        self.chain.read_object(obj_hash)
+    }
+}
+
+#[test]
+fn test_verify_head_cache() -> Result<()> {
+    let param = Parameter {
+        time_win_sizes: vec![2, 4],
+        id_tree_fanout: 4,
+        max_id_num: 32,
+        bplus_tree_fanout: 4,
+        num_dim: 2,
+    };
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_param: QueryParam<u32> = serde_json::from_value(json!({
+        "start_blk": 1,
+        "end_blk": 10,
+        "range": [(1, 7), (2, 9)],
+        "keyword_exp": {"or": [{"input": "a"}, {"input": "b"}]},
+    }))?;
+    let (results, dag, _time) = query(false, false, &test_chain, query_param, &PUB_KEY)?;
+    let counting_chain = CountingChain {
+        chain: &test_chain,
+        head_reads: Mutex::new(HashMap::new()),
+    };
+    verify(&counting_chain, &results, &dag, &PUB_KEY)?;
+    let head_reads = counting_chain.head_reads.into_inner().unwrap();
+    let touched_heights: HashSet<Height> = results
+        .iter()
+        .flat_map(|(_, vo)| vo.merkle_proofs.keys().copied())
+        .collect();
+    assert!(touched_heights.len() > 1);
+    assert_eq!(
+        head_reads.keys().copied().collect::<HashSet<_>>(),
+        touched_heights
+    );
+    assert!(head_reads.values().all(|&n| n == 1));
+    Ok(())
+}
+
+#[test]
+fn test_explain_query() -> Result<()> {
+    let param = Parameter {
+        time_win_sizes: vec![2, 4],
+        id_tree_fanout: 4,
+        max_id_num: 32,
+        bplus_tree_fanout: 4,
+        num_dim: 2,
+    };
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_params = vec![
+        json!({
+            "start_blk": 1,
+            "end_blk": 4,
+            "range": [],
+            "keyword_exp": {"and": [{"input": "b"}, {"input": "c"}]},
+        }),
+        json!({
+            "start_blk": 2,
+            "end_blk": 3,
+            "range": [(1, 7), (2, 9)],
+            "keyword_exp": {
+                "or": [
+                    {"input": "a"},
+                    {"and": [{"input": "b"}, {"input": "c"}]},
+                ]
+            },
+        }),
+        json!({
+            "start_blk": 1,
+            "end_blk": 10,
+            "range": [(1, 7)],
+            "keyword_exp": null,
+        }),
+        json!({
+            "start_blk": 3,
+            "end_blk": 9,
+            "range": [(1, 7), (2, 9)],
+            "keyword_exp": {"not": {"input": "a"}},
+        }),
+    ];
+    for query_param_data in query_params {
+        let query_param: QueryParam<u32> = serde_json::from_value(query_param_data)?;
+        let dag = gen_parallel_query_dag(&query_param.gen_query_content())?;
+        let explain = explain_query(&&test_chain, &query_param, &dag, &PUB_KEY)?;
+        let (results, dag, _time) = query(false, false, &test_chain, query_param, &PUB_KEY)?;
+        let info = verify(&test_chain, &results, &dag, &PUB_KEY)?;
+        assert_eq!(explain.windows.len(), results.len());
+        let est = explain.vo_size.total_s;
+        let actual = info.vo_size.total_s;
+        assert!(est <= actual * EXPLAIN_ERROR_FACTOR && actual <= est * EXPLAIN_ERROR_FACTOR);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_batched_pairing_verify() -> Result<()> {
+    use super::verify::vo::VONode;
+
+    let param = Parameter {
+        time_win_sizes: vec![2, 4],
+        id_tree_fanout: 4,
+        max_id_num: 32,
+        bplus_tree_fanout: 4,
+        num_dim: 2,
+    };
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_param: QueryParam<u32> = serde_json::from_value(json!({
+        "start_blk": 1,
+        "end_blk": 6,
+        "range": [],
+        "keyword_exp": {"or": [
+            {"and": [{"input": "a"}, {"input": "b"}]},
+            {"input": "c"},
+        ]},
+    }))?;
+    let (mut results, dag, _time) = query(false, false, &test_chain, query_param, &PUB_KEY)?;
+    verify(&test_chain, &results, &dag, &PUB_KEY)?;
+    assert!(verify_locate(&test_chain, &results, &dag, &PUB_KEY)?.is_ok());
+
+    // 只篡改一个中间节点累加器的 G2 部分：G1 部分的检查仍能通过，只有配对等式会失败
+    let mut tampered = None;
+    for (_, vo) in results.iter_mut() {
+        for (idx, node) in vo.vo_dag_content.dag_content.iter_mut() {
+            if let VONode::InterIntersec(n) = node {
+                n.acc.h_r_s = n.acc.h_s_r;
+                tampered = Some(*idx);
+                break;
+            }
+        }
+        if tampered.is_some() {
+            break;
+        }
+    }
+    let tampered = tampered.expect("no intermediate intersection node in VO");
+    assert!(verify(&test_chain, &results, &dag, &PUB_KEY).is_err());
+    let report = verify_locate(&test_chain, &results, &dag, &PUB_KEY)?;
+    assert!(report.failed_nodes.iter().any(|(idx, _)| *idx == tampered));
+    Ok(())
+}
+
+#[test]
+fn test_merkle_proof_new() -> Result<()> {
+    use super::verify::{hash::compute_multi_ads_hash, vo::MerkleProof};
+
+    let param = Parameter {
+        time_win_sizes: vec![2, 4],
+        id_tree_fanout: 4,
+        max_id_num: 32,
+        bplus_tree_fanout: 4,
+        num_dim: 2,
+    };
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let height = Height(3);
+    let blk_content = (&test_chain).read_block_content(height)?;
+    let blk_head = (&test_chain).read_block_head(height)?;
+    let id_tree_root_hash = blk_content.id_tree_root.to_digest();
+    let ads_hashes: Vec<(u16, Digest)> = blk_content
+        .ads
+        .read_adses()
+        .iter()
+        .map(|(win_size, ads)| (*win_size, ads.to_digest()))
+        .collect();
+    assert_eq!(ads_hashes.len(), 2);
+
+    let proof = MerkleProof::new(
+        Some(id_tree_root_hash),
+        blk_content.get_ads_components().id_set_root_hash,
+        ads_hashes.clone(),
+        HashMap::new(),
+    )?;
+    let multi_ads_hash = compute_multi_ads_hash(proof.ads_hashes.iter());
+    proof.verify_with_components(&id_tree_root_hash, multi_ads_hash, &blk_head.get_ads_root())?;
+
+    // 缺少一个时间窗口的 ads 哈希时无法重建 ads_root
+    let partial = MerkleProof::new(
+        Some(id_tree_root_hash),
+        blk_content.get_ads_components().id_set_root_hash,
+        ads_hashes[..1].to_vec(),
+        HashMap::new(),
+    )?;
+    let multi_ads_hash = compute_multi_ads_hash(partial.ads_hashes.iter());
+    assert!(partial
+        .verify_with_components(&id_tree_root_hash, multi_ads_hash, &blk_head.get_ads_root())
+        .is_err());
+
+    // 同一窗口大小出现两次
+    let duplicated = vec![ads_hashes[0], ads_hashes[0]];
+    assert!(MerkleProof::new(None, Digest::zero(), duplicated, HashMap::new()).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_obj_merkle_builder() {
+    use super::block::hash::{obj_root_hash, ObjMerkleBuilder};
+
+    let hashes: Vec<Digest> = (0..100u32).map(|i| i.to_digest()).collect();
+    let mut builder = ObjMerkleBuilder::new();
+    assert!(builder.is_empty());
+    assert_eq!(builder.root(), obj_root_hash(hashes[..0].iter()));
+    for (i, hash) in hashes.iter().enumerate() {
+        builder.push(hash);
+        assert_eq!(builder.len(), i + 1);
+        if [1, 2, 3, 7, 8, 64, 100].contains(&(i + 1)) {
+            assert_eq!(builder.root(), obj_root_hash(hashes[..=i].iter()));
+        }
+    }
+}
+
+#[test]
+fn test_parse_query_dag() -> Result<()> {
+    use super::query::parser::parse_query;
+
+    let param = Parameter {
+        time_win_sizes: vec![2, 4],
+        id_tree_fanout: 4,
+        max_id_num: 32,
+        bplus_tree_fanout: 4,
+        num_dim: 2,
+    };
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_param = |keyword_exp: serde_json::Value| -> Result<QueryParam<u32>> {
+        Ok(serde_json::from_value(json!({
+            "start_blk": 1,
+            "end_blk": 6,
+            "range": [],
+            "keyword_exp": keyword_exp,
+        }))?)
+    };
+    let res_ids = |results: &[(HashMap<ObjId, Object<u32>>, _)]| -> HashSet<ObjId> {
+        results
+            .iter()
+            .flat_map(|(res, _)| res.keys().copied())
+            .collect()
+    };
+
+    // 解析得到的 A - B 与 A AND NOT B 的结果相同
+    let dag = parse_query::<u32>(r#"keyword("a") - keyword("b")"#)?;
+    let (results, dag, _time) = query_with_dag(
+        false,
+        false,
+        &test_chain,
+        query_param(json!({"input": "a"}))?,
+        dag,
+        &PUB_KEY,
+    )?;
+    verify(&test_chain, &results, &dag, &PUB_KEY)?;
+    let (expect, _, _) = query(
+        false,
+        false,
+        &test_chain,
+        query_param(json!({"and": [{"input": "a"}, {"not": {"input": "b"}}]}))?,
+        &PUB_KEY,
+    )?;
+    assert!(!res_ids(&results).is_empty());
+    assert_eq!(res_ids(&results), res_ids(&expect));
+    Ok(())
+}
+
+#[test]
+fn test_dag_to_dot() -> Result<()> {
+    use super::query::{parser::parse_query, query_dag::dag_to_dot};
+
+    let mut dag = parse_query::<u32>(
+        r#"keyword("a\"b") AND (range(dim=0, 10..20) OR keyword("c")) - prefix("d")"#,
+    )?;
+    let a_idx = dag
+        .node_indices()
+        .find(|&idx| matches!(dag[idx], DagNode::Keyword(_)))
+        .expect("keyword node");
+    let filter_idx = dag.add_node(DagNode::HeightFilter(HeightFilterNode {
+        range: Height(3)..Height(5),
+    }));
+    dag.add_edge(filter_idx, a_idx, true);
+
+    let dot = dag_to_dot(&dag);
+    assert!(dot.starts_with("digraph"));
+    assert!(dot.trim_end().ends_with('}'));
+    let node_lines: Vec<&str> = dot.lines().filter(|l| l.contains(" [label=")).collect();
+    for idx in dag.node_indices() {
+        let prefix = format!("    n{} [label=\"{}: ", idx.index(), idx.index());
+        assert_eq!(
+            node_lines.iter().filter(|l| l.starts_with(&prefix)).count(),
+            1
+        );
+    }
+    let edge_lines: Vec<&str> = dot.lines().filter(|l| l.contains(" -> ")).collect();
+    assert_eq!(edge_lines.len(), dag.edge_count());
+    for edge in dag.raw_edges() {
+        let line = format!(
+            "    n{} -> n{} [label=\"{}\"];",
+            edge.source().index(),
+            edge.target().index(),
+            edge.weight
+        );
+        assert!(edge_lines.contains(&line.as_str()), "{}", line);
+    }
+    assert!(dot.contains(r#"Keyword\n\"a\\\"b\""#));
+    assert!(dot.contains(r"Range\ndim=0 [10, 20)"));
+    assert!(dot.contains(r"HeightFilter\nheight [3, 5)"));
+    Ok(())
+}
+
+#[test]
+fn test_parameter_defaults() -> Result<()> {
+    use super::ParameterBuilder;
+
+    for param in &[Parameter::default_for_testing(), Parameter::recommended()] {
+        param.validate()?;
+        let built = Parameter::builder()
+            .time_win_sizes(param.time_win_sizes.clone())
+            .id_tree_fanout(param.id_tree_fanout)
+            .max_id_num(param.max_id_num)
+            .bplus_tree_fanout(param.bplus_tree_fanout)
+            .num_dim(param.num_dim)
+            .build()?;
+        assert_eq!(&built, param);
+    }
+
+    let param = Parameter::default_for_testing();
+    let json = serde_json::to_string(&param)?;
+    assert_eq!(serde_json::from_str::<Parameter>(&json)?, param);
+    let bytes = bincode::serialize(&param)?;
+    assert_eq!(bincode::deserialize::<Parameter>(&bytes)?, param);
+
+    assert!(Parameter::builder().build().is_err());
+    let invalid = |f: fn(&mut Parameter)| {
+        let mut param = Parameter::default_for_testing();
+        f(&mut param);
+        ParameterBuilder::from(param).build().is_err()
+    };
+    assert!(invalid(|p| p.time_win_sizes.clear()));
+    assert!(invalid(|p| p.time_win_sizes = vec![0, 4]));
+    assert!(invalid(|p| p.time_win_sizes = vec![4, 2]));
+    assert!(invalid(|p| p.time_win_sizes = vec![2, 2]));
+    assert!(invalid(|p| p.id_tree_fanout = 1));
+    assert!(invalid(|p| p.bplus_tree_fanout = 0));
+    assert!(invalid(|p| p.max_id_num = 0));
+    assert!(invalid(|p| p.num_dim = 0));
+    Ok(())
+}
+
+#[test]
+fn test_verify_with_trace() -> Result<()> {
+    use super::verify::{
+        trace::{verify_with_trace, VerifyTrace},
+        vo::VONode,
+    };
+
+    let param = Parameter::default_for_testing();
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_param: QueryParam<u32> = serde_json::from_value(json!({
+        "start_blk": 1,
+        "end_blk": 6,
+        "range": [(1, 7), (2, 9)],
+        "keyword_exp": {"or": [
+            {"and": [{"input": "a"}, {"input": "b"}]},
+            {"input": "c"},
+        ]},
+    }))?;
+    let (mut results, dag, _time) = query(false, false, &test_chain, query_param, &PUB_KEY)?;
+    let (_info, trace) = verify_with_trace(&test_chain, &results, &dag, &PUB_KEY)?;
+    assert!(trace.accepted);
+    assert_eq!(trace.vos.len(), results.len());
+    for (vo_trace, (_, vo)) in trace.vos.iter().zip(&results) {
+        let idxs: HashSet<usize> = vo_trace.nodes.iter().map(|n| n.idx).collect();
+        assert_eq!(idxs, dag.node_indices().map(|i| i.index()).collect());
+        let heights: HashSet<Height> = vo_trace.heights.iter().map(|h| h.height).collect();
+        assert_eq!(heights, vo.merkle_proofs.keys().copied().collect());
+        for (idx, node) in &vo.vo_dag_content.dag_content {
+            let n = &vo_trace.nodes[idx.index()];
+            assert_eq!(n.kind.as_deref(), Some(node.node_kind()));
+            if let Ok(acc) = node.get_acc() {
+                assert!(n.accs.contains(&acc.fingerprint()));
+            }
+        }
+    }
+    let replayed: VerifyTrace = serde_json::from_str(&serde_json::to_string(&trace)?)?;
+    assert_eq!(replayed, trace);
+    assert!(replayed.replay());
+
+    // 篡改一个中间节点的累加器，记录应定位到该节点，重放得出同样的拒绝结论
+    let (vo_idx, tampered) = results
+        .iter_mut()
+        .enumerate()
+        .find_map(|(i, (_, vo))| {
+            vo.vo_dag_content
+                .dag_content
+                .iter_mut()
+                .find_map(|(idx, node)| match node {
+                    VONode::InterIntersec(n) => {
+                        n.acc.h_r_s = n.acc.h_s_r;
+                        Some((i, *idx))
+                    }
+                    _ => None,
+                })
+        })
+        .context("no intermediate intersection node in VO")?;
+    assert!(verify(&test_chain, &results, &dag, &PUB_KEY).is_err());
+    let (_info, trace) = verify_with_trace(&test_chain, &results, &dag, &PUB_KEY)?;
+    assert!(!trace.accepted);
+    assert!(!trace.vos[vo_idx].nodes[tampered.index()].errors.is_empty());
+    let replayed: VerifyTrace = serde_json::from_str(&serde_json::to_string(&trace)?)?;
+    assert!(!replayed.replay());
+
+    // 篡改 merkle proof，对应高度的 ads_root 比较不通过
+    let (_, vo) = &mut results[vo_idx];
+    let height = *vo
+        .merkle_proofs
+        .keys()
+        .next()
+        .context("VO has no merkle proof")?;
+    if let Some(proof) = vo.merkle_proofs.get_mut(&height) {
+        proof.id_set_root_hash = Digest::zero();
+    }
+    let (_info, trace) = verify_with_trace(&test_chain, &results, &dag, &PUB_KEY)?;
+    let h = trace.vos[vo_idx]
+        .heights
+        .iter()
+        .find(|h| h.height == height)
+        .context("height not in trace")?;
+    assert!(h.computed.is_some() && h.computed != h.expected);
+    assert!(!trace.accepted);
+    assert!(!trace.replay());
+    Ok(())
+}
+
+#[test]
+fn test_ads_root_early_mismatch() -> Result<()> {
+    use super::verify::{AdsRootMismatch, MULTI_ADS_FOLDS};
+
+    let param = Parameter::default_for_testing();
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_param: QueryParam<u32> = serde_json::from_value(json!({
+        "start_blk": 1,
+        "end_blk": 6,
+        "range": [],
+        "keyword_exp": {"not": {"input": "a"}},
+    }))?;
+    let (mut results, dag, _time) = query(false, false, &test_chain, query_param, &PUB_KEY)?;
+    MULTI_ADS_FOLDS.with(|n| n.set(0));
+    verify(&test_chain, &results, &dag, &PUB_KEY)?;
+    assert!(MULTI_ADS_FOLDS.with(|n| n.get()) > 0);
+
+    // BlkRt 节点的 id 集合证明已确认各高度的 id_set_root_hash，篡改 merkle proof 中的该值后
+    // 在合并 multi_ads_hash 之前即失败
+    for (_, vo) in results.iter_mut() {
+        for proof in vo.merkle_proofs.values_mut() {
+            proof.id_set_root_hash = Digest::zero();
+        }
+    }
+    MULTI_ADS_FOLDS.with(|n| n.set(0));
+    let err = verify(&test_chain, &results, &dag, &PUB_KEY).unwrap_err();
+    assert_eq!(MULTI_ADS_FOLDS.with(|n| n.get()), 0);
+    let mismatch = err
+        .downcast_ref::<AdsRootMismatch>()
+        .context("expect AdsRootMismatch")?;
+    assert_eq!(mismatch.component, "id_set_root_hash");
+    assert!(results[0].1.merkle_proofs.contains_key(&mismatch.height));
+    Ok(())
+}
+
+#[test]
+fn test_vo_check_structure() -> Result<()> {
+    use super::verify::vo::{VONode, VO};
+    use petgraph::EdgeDirection::Outgoing;
+
+    let param = Parameter::default_for_testing();
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_param = |keyword_exp: serde_json::Value| -> Result<QueryParam<u32>> {
+        Ok(serde_json::from_value(json!({
+            "start_blk": 1,
+            "end_blk": 6,
+            "range": [(1, 7), (2, 9)],
+            "keyword_exp": keyword_exp,
+        }))?)
+    };
+    for keyword_exp in [
+        json!({"or": [{"input": "a"}, {"and": [{"input": "b"}, {"input": "c"}]}]}),
+        json!({"not": {"input": "a"}}),
+        json!(null),
+    ] {
+        let (results, dag, _) = query(
+            false,
+            false,
+            &test_chain,
+            query_param(keyword_exp)?,
+            &PUB_KEY,
+        )?;
+        for (_, vo) in &results {
+            vo.check_structure(&dag)?;
+        }
+    }
+
+    let expect_err = |vo: &VO<u32>, dag: &Graph<DagNode<u32>, bool>, msg: &str| {
+        let err = vo.check_structure(dag).unwrap_err();
+        assert!(format!("{:#}", err).contains(msg), "{:#}", err);
+    };
+    let keyword_exp = json!({"or": [{"input": "a"}, {"and": [{"input": "b"}, {"input": "c"}]}]});
+
+    // 运算节点的子节点缺失
+    let (mut results, dag, _) = query(
+        false,
+        false,
+        &test_chain,
+        query_param(keyword_exp.clone())?,
+        &PUB_KEY,
+    )?;
+    let (_, vo) = &mut results[0];
+    let op_idx = *vo
+        .vo_dag_content
+        .dag_content
+        .iter()
+        .find(|(_, n)| matches!(n, VONode::FinalUnion(_) | VONode::FinalIntersec(_)))
+        .context("no final set operation node")?
+        .0;
+    for child in dag.neighbors_directed(op_idx, Outgoing) {
+        vo.vo_dag_content.dag_content.remove(&child);
+    }
+    expect_err(vo, &dag, "Cannot find the children of set operation node");
+
+    // VO 节点类型与 DAG 节点不匹配
+    let (mut results, dag, _) = query(
+        false,
+        false,
+        &test_chain,
+        query_param(keyword_exp.clone())?,
+        &PUB_KEY,
+    )?;
+    let (_, vo) = &mut results[0];
+    let keyword_idx = *vo
+        .vo_dag_content
+        .dag_content
+        .iter()
+        .find(|(_, n)| matches!(n, VONode::Keyword(_)))
+        .context("no keyword node")?
+        .0;
+    let range_node = vo
+        .vo_dag_content
+        .dag_content
+        .values()
+        .find(|n| matches!(n, VONode::Range(_)))
+        .context("no range node")?;
+    let range_node: VONode<u32> = bincode::deserialize(&bincode::serialize(range_node)?)?;
+    vo.vo_dag_content
+        .dag_content
+        .insert(keyword_idx, range_node);
+    expect_err(vo, &dag, "does not match the query graph node");
+
+    // 缺少 merkle proof
+    let (mut results, dag, _) = query(
+        false,
+        false,
+        &test_chain,
+        query_param(keyword_exp.clone())?,
+        &PUB_KEY,
+    )?;
+    let (_, vo) = &mut results[0];
+    vo.merkle_proofs.clear();
+    expect_err(vo, &dag, "Cannot find merkle proof");
+
+    // 缺少输出集合
+    let (mut results, dag, _) = query(
+        false,
+        false,
+        &test_chain,
+        query_param(keyword_exp.clone())?,
+        &PUB_KEY,
+    )?;
+    let (_, vo) = &mut results[0];
+    vo.vo_dag_content.output_sets.clear();
+    expect_err(vo, &dag, "Cannot find output set");
+
+    // DAG 中有环
+    let (results, mut dag, _) = query(
+        false,
+        false,
+        &test_chain,
+        query_param(keyword_exp)?,
+        &PUB_KEY,
+    )?;
+    let child = dag
+        .neighbors_directed(op_idx, Outgoing)
+        .next()
+        .context("no child")?;
+    dag.add_edge(child, op_idx, false);
+    expect_err(&results[0].1, &dag, "cycle");
+    Ok(())
+}
+
+#[test]
+fn test_validate_dag_bad_arity() -> Result<()> {
+    use super::verify::{validate_dag, DagError};
+    use petgraph::EdgeDirection::Outgoing;
+
+    let param = Parameter::default_for_testing();
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_param: QueryParam<u32> = serde_json::from_value(json!({
+        "start_blk": 1,
+        "end_blk": 6,
+        "range": [],
+        "keyword_exp": {"or": [{"input": "a"}, {"input": "b"}]},
+    }))?;
+    let (results, dag, _time) = query(false, false, &test_chain, query_param, &PUB_KEY)?;
+    validate_dag(&dag)?;
+    verify(&test_chain, &results, &dag, &PUB_KEY)?;
+    let union_idx = dag
+        .node_indices()
+        .find(|&idx| matches!(dag[idx], DagNode::Union(_)))
+        .context("expect a union node")?;
+    let bad_arity = |graph: &Graph<DagNode<u32>, bool>, found: usize| -> Result<()> {
+        let expected = DagError::BadArity {
+            idx: union_idx,
+            expected: 2,
+            found,
+        };
+        let err = validate_dag(graph).unwrap_err();
+        assert_eq!(err.downcast_ref::<DagError>(), Some(&expected));
+        let err = verify(&test_chain, &results, graph, &PUB_KEY).unwrap_err();
+        assert_eq!(err.downcast_ref::<DagError>(), Some(&expected));
+        Ok(())
+    };
+
+    // 只有一个子节点的并集
+    let mut one_child = dag.clone();
+    let child = one_child
+        .neighbors_directed(union_idx, Outgoing)
+        .next()
+        .context("expect a child")?;
+    let edge = one_child
+        .find_edge(union_idx, child)
+        .context("expect an edge")?;
+    one_child.remove_edge(edge);
+    bad_arity(&one_child, 1)?;
+
+    // 有三个子节点的并集
+    let mut three_children = dag.clone();
+    let extra = three_children.add_node(DagNode::Keyword(Box::new(KeywordNode {
+        keyword: "c".to_string(),
+    })));
+    three_children.add_edge(union_idx, extra, false);
+    bad_arity(&three_children, 3)?;
+    Ok(())
+}
+
+#[test]
+fn test_serialize_dag_roundtrip() -> Result<()> {
+    use super::query::{
+        parser::parse_query,
+        query_dag::{deserialize_dag, serialize_dag},
+    };
+    use petgraph::EdgeDirection::Outgoing;
+
+    let mut dag = parse_query::<u32>(
+        r#"keyword("a") AND (range(dim=0, 10..20) OR keyword("c")) - prefix("d")"#,
+    )?;
+    let a_idx = dag
+        .node_indices()
+        .find(|&idx| matches!(dag[idx], DagNode::Keyword(_)))
+        .context("expect a keyword node")?;
+    let filter_idx = dag.add_node(DagNode::HeightFilter(HeightFilterNode {
+        range: Height(3)..Height(5),
+    }));
+    dag.add_edge(filter_idx, a_idx, true);
+
+    let bytes = serialize_dag(&dag)?;
+    let decoded = deserialize_dag::<u32>(&bytes)?;
+    assert_eq!(decoded.node_count(), dag.node_count());
+    assert_eq!(decoded.edge_count(), dag.edge_count());
+    for idx in dag.node_indices() {
+        assert_eq!(format!("{:?}", decoded[idx]), format!("{:?}", dag[idx]));
+        let children: Vec<_> = dag.neighbors_directed(idx, Outgoing).collect();
+        let decoded_children: Vec<_> = decoded.neighbors_directed(idx, Outgoing).collect();
+        assert_eq!(decoded_children, children);
+    }
+    for (e1, e2) in dag.raw_edges().iter().zip(decoded.raw_edges()) {
+        assert_eq!(e1.source(), e2.source());
+        assert_eq!(e1.target(), e2.target());
+        assert_eq!(e1.weight, e2.weight);
+    }
+
+    // 解码后的 DAG 可直接用于验证
+    let param = Parameter::default_for_testing();
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_param: QueryParam<u32> = serde_json::from_value(json!({
+        "start_blk": 1,
+        "end_blk": 6,
+        "range": [(1, 7), (2, 9)],
+        "keyword_exp": {"or": [{"input": "a"}, {"not": {"input": "b"}}]},
+    }))?;
+    let (results, dag, _time) = query(false, false, &test_chain, query_param, &PUB_KEY)?;
+    let decoded = deserialize_dag::<u32>(&serialize_dag(&dag)?)?;
+    verify(&test_chain, &results, &decoded, &PUB_KEY)?;
+
+    assert!(deserialize_dag::<u32>(&bytes[..bytes.len() / 2]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_verify_object_membership() -> Result<()> {
+    use super::verify::verify_object_membership;
+
+    let param = Parameter::default_for_testing();
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_param: QueryParam<u32> = serde_json::from_value(json!({
+        "start_blk": 1,
+        "end_blk": 6,
+        "range": [],
+        "keyword_exp": {"input": "a"},
+    }))?;
+    let (results, _dag, _time) = query(false, false, &test_chain, query_param, &PUB_KEY)?;
+    let (res_content, vo) = results
+        .iter()
+        .find(|(res_content, _)| !res_content.is_empty())
+        .context("expect a non-empty result")?;
+    // VO 中的 id 树证明取自该子查询最后一个区块的 id 树
+    let end_height = *vo
+        .merkle_proofs
+        .keys()
+        .max()
+        .context("expect merkle proofs")?;
+    let id_root = (&test_chain).read_block_content(end_height)?.id_tree_root;
+    let expected_root = id_root.get_id_tree_root_hash();
+    for (id, obj) in res_content {
+        verify_object_membership(obj, *id, &vo.id_tree_proof, &param, expected_root)?;
+    }
+    let (id, obj) = res_content.iter().next().context("expect an object")?;
+    let err =
+        verify_object_membership(obj, *id, &vo.id_tree_proof, &param, Digest::zero()).unwrap_err();
+    assert!(err.to_string().contains("Id tree root hash not matched"));
+
+    // 对象内容被篡改
+    let mut forged = obj.clone();
+    forged.num_data[0] += 1;
+    assert!(
+        verify_object_membership(&forged, *id, &vo.id_tree_proof, &param, expected_root).is_err()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_verify_with_keys() -> Result<()> {
+    use super::verify::verify_with_keys;
+    use std::collections::BTreeMap;
+
+    let mut rng = StdRng::seed_from_u64(987_654_321u64);
+    let other_sk: AccSecretKeyWithPowCache = AccSecretKey::rand(&mut rng).into();
+    let other_pk = AccPublicKey::gen_key(&other_sk, Q);
+
+    let param = Parameter::default_for_testing();
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_blocks = |start_blk: u32, end_blk: u32| -> Result<_> {
+        let query_param: QueryParam<u32> = serde_json::from_value(json!({
+            "start_blk": start_blk,
+            "end_blk": end_blk,
+            "range": [],
+            "keyword_exp": {"or": [{"input": "a"}, {"input": "b"}]},
+        }))?;
+        let (results, dag, _time) = query(false, false, &test_chain, query_param, &PUB_KEY)?;
+        Ok((results, dag))
+    };
+    let (early_results, early_dag) = query_blocks(1, 2)?;
+    let (late_results, late_dag) = query_blocks(5, 6)?;
+
+    // 高度 4 起轮换为 other_pk，但链实际始终使用 PUB_KEY 构建
+    let keys: BTreeMap<Height, AccPublicKey> =
+        vec![(Height(1), PUB_KEY.clone()), (Height(4), other_pk.clone())]
+            .into_iter()
+            .collect();
+    verify_with_keys(&test_chain, &early_results, &early_dag, &keys)?;
+    assert!(verify_with_keys(&test_chain, &late_results, &late_dag, &keys).is_err());
+
+    let keys: BTreeMap<Height, AccPublicKey> =
+        vec![(Height(1), other_pk.clone()), (Height(4), PUB_KEY.clone())]
+            .into_iter()
+            .collect();
+    assert!(verify_with_keys(&test_chain, &early_results, &early_dag, &keys).is_err());
+    verify_with_keys(&test_chain, &late_results, &late_dag, &keys)?;
+
+    // 高度 5 之前没有生效的公钥
+    let keys: BTreeMap<Height, AccPublicKey> =
+        vec![(Height(5), PUB_KEY.clone())].into_iter().collect();
+    let err = verify_with_keys(&test_chain, &early_results, &early_dag, &keys).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("No accumulator public key is effective"));
+
+    // 一个 VO 跨越了两个公钥生效的高度：把高度 2 的 merkle proof 移入高度 6 的 VO
+    let (mut early_results, _) = query_blocks(1, 2)?;
+    let (mut results, dag) = query_blocks(5, 6)?;
+    let proof = early_results[0]
+        .1
+        .merkle_proofs
+        .remove(&Height(2))
+        .context("expect merkle proof at height 2")?;
+    results[0].1.merkle_proofs.insert(Height(2), proof);
+    let keys: BTreeMap<Height, AccPublicKey> =
+        vec![(Height(1), PUB_KEY.clone()), (Height(4), other_pk)]
+            .into_iter()
+            .collect();
+    let err = verify_with_keys(&test_chain, &results, &dag, &keys).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("whose accumulator public keys differ"));
+    Ok(())
+}
+
+#[test]
+fn test_gen_test_chain() -> Result<()> {
+    let (chain, pk) = gen_test_chain(6, 10, 7);
+    let param = (&chain).get_parameter()?;
+    assert!(param.max_id_num >= 40);
+    let mut prev_hash = BlockContent::genesis(&param).0.to_digest();
+    for height in 1..=6 {
+        let blk_head = (&chain).read_block_head(Height(height))?;
+        let blk_content = (&chain).read_block_content(Height(height))?;
+        assert_eq!(blk_content.obj_hashes.len(), 10);
+        assert_eq!(blk_head.prev_hash, prev_hash);
+        blk_content.verify_ads_root_against(&blk_head)?;
+        prev_hash = blk_head.to_digest();
+    }
+    // 区块内容与区块头不对应
+    let blk_head = (&chain).read_block_head(Height(2))?;
+    let blk_content = (&chain).read_block_content(Height(3))?;
+    assert!(blk_content.verify_ads_root_against(&blk_head).is_err());
+
+    // 相同的种子生成相同的链，不同的种子生成不同的链
+    let (same_chain, same_pk) = gen_test_chain(6, 10, 7);
+    assert_eq!(same_pk, pk);
+    assert_eq!(
+        (&same_chain).read_block_head(Height(6))?,
+        (&chain).read_block_head(Height(6))?
+    );
+    let (other_chain, _) = gen_test_chain(6, 10, 8);
+    assert_ne!(
+        (&other_chain).read_block_head(Height(6))?,
+        (&chain).read_block_head(Height(6))?
+    );
+
+    // 生成的链可直接用于查询与验证
+    let query_param: QueryParam<u32> = serde_json::from_value(json!({
+        "start_blk": 3,
+        "end_blk": 6,
+        "range": [(10, 60), (0, 100)],
+        "keyword_exp": {"or": [{"input": "a"}, {"input": "b"}]},
+    }))?;
+    let (results, dag, _time) = query(false, false, &chain, query_param, &pk)?;
+    verify(&chain, &results, &dag, &pk)?;
+    Ok(())
+}
+
+#[test]
+fn test_vo_decode_malformed() -> Result<()> {
+    use super::verify::vo::VO;
+    use std::io::Write;
+
+    let param = Parameter::default_for_testing();
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_param: QueryParam<u32> = serde_json::from_value(json!({
+        "start_blk": 1,
+        "end_blk": 6,
+        "range": [(1, 7), (2, 9)],
+        "keyword_exp": {"or": [{"input": "a"}, {"input": "b"}]},
+    }))?;
+    let (results, dag, _time) = query(false, false, &test_chain, query_param, &PUB_KEY)?;
+    let bytes = results[0].1.encode()?;
+    let decoded = VO::<u32>::decode(&bytes)?;
+    decoded.check_structure(&dag)?;
+    verify(
+        &test_chain,
+        &[(results[0].0.clone(), decoded)],
+        &dag,
+        &PUB_KEY,
+    )?;
+    for len in [0, 1, bytes.len() / 2, bytes.len() - 1] {
+        assert!(VO::<u32>::decode(&bytes[..len]).is_err());
+    }
+
+    // 一个 Keyword 节点，其累加器的第一个字段带有极大的长度前缀
+    let mut raw = Vec::new();
+    raw.extend_from_slice(&0u64.to_le_bytes()); // output_sets
+    raw.extend_from_slice(&1u64.to_le_bytes()); // dag_content
+    raw.extend_from_slice(&0u32.to_le_bytes()); // NodeIndex
+    raw.extend_from_slice(&1u32.to_le_bytes()); // VONode::Keyword
+    raw.extend_from_slice(&1u32.to_le_bytes()); // blk_height
+    raw.extend_from_slice(&2u16.to_le_bytes()); // win_size
+    raw.extend_from_slice(&0xffff_ffff_ffff_fff0u64.to_le_bytes());
+    let compress = |raw: &[u8]| -> Result<Vec<u8>> {
+        let mut encoder = snap::write::FrameEncoder::new(Vec::new());
+        encoder.write_all(raw)?;
+        Ok(encoder.into_inner()?)
+    };
+    let malformed = compress(&raw)?;
+    assert_eq!(
+        &malformed[..],
+        &include_bytes!("../../fuzz/corpus/fuzz_vo_decode/acc-len-capacity-overflow")[..]
+    );
+    assert!(VO::<u32>::decode(&malformed).is_err());
+
+    // 一个 trie 证明，其根为 `depth` 层只有一个子节点的 NonLeaf
+    let nested_trie = |depth: usize| -> Vec<u8> {
+        let mut raw = Vec::new();
+        raw.extend_from_slice(&0u64.to_le_bytes()); // output_sets
+        raw.extend_from_slice(&0u64.to_le_bytes()); // dag_content
+        raw.extend_from_slice(&1u64.to_le_bytes()); // trie_proofs
+        raw.extend_from_slice(&1u32.to_le_bytes()); // Height
+        raw.push(1); // Some(root)
+        for _ in 0..depth {
+            raw.extend_from_slice(&2u32.to_le_bytes()); // SubProof::NonLeaf
+            raw.extend_from_slice(&0u64.to_le_bytes()); // nibble
+            raw.extend_from_slice(&1u64.to_le_bytes()); // children
+            raw.push(b'a');
+        }
+        raw.extend_from_slice(&0u32.to_le_bytes()); // SubProof::Hash
+        raw.push(0); // node_id
+        raw.extend_from_slice(&0u64.to_le_bytes()); // nibble
+        raw.extend_from_slice(&[0u8; 32]); // node_hash
+        raw.push(0); // id_tree_proof
+        raw.extend_from_slice(&1u16.to_le_bytes()); // cur_obj_id
+        raw.extend_from_slice(&0u64.to_le_bytes()); // merkle_proofs
+        raw
+    };
+    VO::<u32>::decode(&compress(&nested_trie(200))?)?;
+    // 过深的嵌套在耗尽栈空间之前返回错误
+    assert!(VO::<u32>::decode(&compress(&nested_trie(300))?).is_err());
+    assert!(VO::<u32>::decode(&compress(&nested_trie(100_000))?).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_verify_count() -> Result<()> {
+    use super::verify::verify_count;
+
+    let param = Parameter::default_for_testing();
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_param: QueryParam<u32> = serde_json::from_value(json!({
+        "start_blk": 1,
+        "end_blk": 6,
+        "range": [],
+        "keyword_exp": {"or": [{"input": "a"}, {"input": "b"}]},
+    }))?;
+    let (mut results, dag, _time) = query(false, false, &test_chain, query_param, &PUB_KEY)?;
+    let mut total = 0;
+    for (res, vo) in &results {
+        let count = res.len() as u64;
+        verify_count(&test_chain, vo, &dag, &PUB_KEY, count)?;
+        assert!(verify_count(&test_chain, vo, &dag, &PUB_KEY, count + 1).is_err());
+        if count > 0 {
+            assert!(verify_count(&test_chain, vo, &dag, &PUB_KEY, count - 1).is_err());
+        }
+        total += count;
+    }
+    assert!(total > 0);
+
+    // 从输出集合中删去一个对象以伪造较小的个数
+    let (res, vo) = results
+        .iter_mut()
+        .find(|(res, _)| !res.is_empty())
+        .context("no results")?;
+    let count = res.len() as u64;
+    let set = vo
+        .vo_dag_content
+        .output_sets
+        .values_mut()
+        .find(|set| !set.is_empty())
+        .context("no output set")?;
+    let id = *set.iter().next().context("empty set")?;
+    set.remove(&id);
+    assert!(verify_count(&test_chain, vo, &dag, &PUB_KEY, count - 1).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_multi_ads_add_window() -> Result<()> {
+    use super::{block::block_ads::BlockMultiADS, verify::hash::compute_multi_ads_hash};
+
+    let param = Parameter::default_for_testing();
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    for height in 4..=6 {
+        let blk_content = (&test_chain).read_block_content(Height(height))?;
+        let adses = blk_content.ads.read_adses();
+        assert!(adses.len() > 1);
+
+        // 按窗口从大到小加入，与按构建顺序得到的哈希一致
+        let mut multi_ads = BlockMultiADS::default();
+        for (&window, ads) in adses.iter().rev() {
+            multi_ads.add_window(window, ads.clone())?;
+            let hashes: Vec<(u16, Digest)> = multi_ads
+                .read_adses()
+                .iter()
+                .map(|(w, ads)| (*w, ads.to_digest()))
+                .collect();
+            assert_eq!(
+                multi_ads.multi_ads_hash(),
+                compute_multi_ads_hash(hashes.iter().map(|(w, h)| (w, h)))
+            );
+        }
+        assert_eq!(multi_ads, blk_content.ads);
+        assert_eq!(
+            multi_ads.multi_ads_hash(),
+            blk_content.ads_components.multi_ads_hash
+        );
+
+        let (&window, ads) = adses.iter().next().context("no window")?;
+        assert!(multi_ads.add_window(window, ads.clone()).is_err());
+    }
+    Ok(())
+}
+
+#[test]
+fn test_verify_span_fields() -> Result<()> {
+    use super::verify::{verify_with_options, VerifyOptions};
+    use std::sync::{Arc, Mutex};
+    use tracing::{
+        field::{Field, Visit},
+        span::{Attributes, Id, Record},
+        Subscriber,
+    };
+    use tracing_subscriber::{layer::Context, prelude::*, registry::Registry, Layer};
+
+    /// 记录名为 verify 的 span 的字段
+    #[derive(Clone, Default)]
+    struct SpanFields(
+        Arc<Mutex<Vec<HashMap<String, String>>>>,
+        Arc<Mutex<Vec<Id>>>,
+    );
+
+    struct FieldVisitor<'a>(&'a mut HashMap<String, String>);
+
+    impl Visit for FieldVisitor<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0
+                .insert(field.name().to_string(), format!("{:?}", value));
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for SpanFields {
+        fn new_span(&self, attrs: &Attributes<'_>, id: &Id, _ctx: Context<'_, S>) {
+            if attrs.metadata().name() != "verify" {
+                return;
+            }
+            let mut fields = HashMap::new();
+            attrs.record(&mut FieldVisitor(&mut fields));
+            self.0.lock().unwrap().push(fields);
+            self.1.lock().unwrap().push(id.clone());
+        }
+
+        fn on_record(&self, id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+            let ids = self.1.lock().unwrap();
+            if let Some(pos) = ids.iter().position(|i| i == id) {
+                values.record(&mut FieldVisitor(&mut self.0.lock().unwrap()[pos]));
+            }
+        }
+    }
+
+    let param = Parameter::default_for_testing();
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_param: QueryParam<u32> = serde_json::from_value(json!({
+        "start_blk": 1,
+        "end_blk": 6,
+        "range": [],
+        "keyword_exp": {"or": [{"input": "a"}, {"input": "b"}]},
+    }))?;
+    let (results, dag, _time) = query(false, false, &test_chain, query_param, &PUB_KEY)?;
+    let opts = VerifyOptions {
+        query_id: Some([0xab; 32]),
+        ..VerifyOptions::default()
+    };
+    let layer = SpanFields::default();
+    let subscriber = Registry::default().with(layer.clone());
+    let info = tracing::subscriber::with_default(subscriber, || {
+        verify_with_options(&test_chain, &results, &dag, &PUB_KEY, &opts)
+    })?;
+
+    let spans = layer.0.lock().unwrap();
+    assert_eq!(spans.len(), 1);
+    let fields = &spans[0];
+    let result_count: usize = results.iter().map(|(res, _)| res.len()).sum();
+    assert_eq!(fields["query_id"], format!("{:?}", hex::encode([0xab; 32])));
+    let heights: Vec<u32> = results
+        .iter()
+        .flat_map(|(_, vo)| vo.merkle_proofs.keys().map(|h| h.0))
+        .collect();
+    let min_height = heights.iter().min().context("no merkle proof")?;
+    let max_height = heights.iter().max().context("no merkle proof")?;
+    assert_eq!(fields["min_height"], min_height.to_string());
+    assert_eq!(fields["max_height"], max_height.to_string());
+    assert_eq!(max_height, &6);
+    assert_eq!(fields["result_count"], result_count.to_string());
+    assert_eq!(fields["vo.total_size"], info.vo_size.total_s.to_string());
+    assert!(fields.contains_key("verify_time_ms"));
+    Ok(())
+}
+
+#[test]
+fn test_verify_options_limits() -> Result<()> {
+    use super::verify::{verify_with_options, TooLarge, VerifyOptions};
+    use std::collections::BTreeSet;
+
+    let param = Parameter::default_for_testing();
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_param: QueryParam<u32> = serde_json::from_value(json!({
+        "start_blk": 1,
+        "end_blk": 6,
+        "range": [(1, 10), (1, 10)],
+        "keyword_exp": {"or": [{"input": "a"}, {"input": "b"}]},
+    }))?;
+    let (results, dag, _time) = query(false, false, &test_chain, query_param, &PUB_KEY)?;
+    let max_nodes = results
+        .iter()
+        .map(|(_, vo)| vo.vo_dag_content.dag_content.len())
+        .chain(std::iter::once(dag.node_count()))
+        .max()
+        .context("no nodes")?;
+    let heights: BTreeSet<Height> = results
+        .iter()
+        .flat_map(|(_, vo)| vo.merkle_proofs.keys().chain(vo.trie_proofs.keys()))
+        .copied()
+        .collect();
+    let at_limit = VerifyOptions {
+        max_nodes,
+        max_heights: heights.len(),
+        ..VerifyOptions::default()
+    };
+    verify_with_options(&test_chain, &results, &dag, &PUB_KEY, &at_limit)?;
+
+    // 超出限制时在密码学验证之前返回：即使公钥不对，也得到 TooLarge 而不是验证失败
+    let mut rng = StdRng::seed_from_u64(1);
+    let other_sk: AccSecretKeyWithPowCache = AccSecretKey::rand(&mut rng).into();
+    let other_pk = AccPublicKey::gen_key(&other_sk, Q);
+    assert!(verify_with_options(&test_chain, &results, &dag, &other_pk, &at_limit).is_err());
+    for (opts, kind) in [
+        (
+            VerifyOptions {
+                max_nodes: max_nodes - 1,
+                ..at_limit
+            },
+            "nodes",
+        ),
+        (
+            VerifyOptions {
+                max_heights: heights.len() - 1,
+                ..at_limit
+            },
+            "heights",
+        ),
+    ] {
+        let err = verify_with_options(&test_chain, &results, &dag, &other_pk, &opts).unwrap_err();
+        let too_large = err.downcast_ref::<TooLarge>().context("not TooLarge")?;
+        assert_eq!(too_large.kind, kind);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_deferred_subgroup_check() -> Result<()> {
+    use super::verify::{verify_with_options, vo::VONode, VerifyOptions};
+    use crate::acc::batch::off_subgroup_g2;
+
+    let param = Parameter::default_for_testing();
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_param: QueryParam<u32> = serde_json::from_value(json!({
+        "start_blk": 1,
+        "end_blk": 6,
+        "range": [],
+        "keyword_exp": {"and": [{"input": "a"}, {"input": "b"}]},
+    }))?;
+    let (mut results, dag, _time) = query(false, false, &test_chain, query_param, &PUB_KEY)?;
+    let eager = VerifyOptions::default();
+    assert!(!eager.deferred_subgroup_check);
+    let deferred = VerifyOptions {
+        deferred_subgroup_check: true,
+        ..VerifyOptions::default()
+    };
+    verify_with_options(&test_chain, &results, &dag, &PUB_KEY, &eager)?;
+    verify_with_options(&test_chain, &results, &dag, &PUB_KEY, &deferred)?;
+
+    // 给一个累加器值加上子群之外的点，两种方式都在密码学验证之前拒绝
+    let node = results
+        .iter_mut()
+        .flat_map(|(_, vo)| vo.vo_dag_content.dag_content.values_mut())
+        .find(|node| matches!(node, VONode::Keyword(_)))
+        .context("no keyword node")?;
+    if let VONode::Keyword(n) = node {
+        n.acc.h_s_r = n.acc.h_s_r + off_subgroup_g2();
+    }
+    for opts in [eager, deferred] {
+        let err = verify_with_options(&test_chain, &results, &dag, &PUB_KEY, &opts).unwrap_err();
+        assert!(
+            err.to_string().contains("prime order subgroup"),
+            "{:#}",
+            err
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_pre_verify_on_all_paths() -> Result<()> {
+    use super::verify::{verify_count, verify_with_keys, vo::VONode};
+    use crate::acc::batch::off_subgroup_g2;
+    use std::collections::BTreeMap;
+
+    let param = Parameter::default_for_testing();
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_param: QueryParam<u32> = serde_json::from_value(json!({
+        "start_blk": 1,
+        "end_blk": 6,
+        "range": [],
+        "keyword_exp": {"and": [{"input": "a"}, {"input": "b"}]},
+    }))?;
+    let (mut results, dag, _time) = query(false, false, &test_chain, query_param, &PUB_KEY)?;
+    if let Some(VONode::Keyword(n)) = results
+        .iter_mut()
+        .flat_map(|(_, vo)| vo.vo_dag_content.dag_content.values_mut())
+        .find(|node| matches!(node, VONode::Keyword(_)))
+    {
+        n.acc.h_s_r = n.acc.h_s_r + off_subgroup_g2();
+    }
+    let mut heads = HashMap::new();
+    for h in 1..=6 {
+        heads.insert(Height(h), (&test_chain).read_block_head(Height(h))?);
+    }
+    let keys: BTreeMap<Height, AccPublicKey> =
+        vec![(Height(1), PUB_KEY.clone())].into_iter().collect();
+    let (res, vo) = results
+        .iter()
+        .find(|(_, vo)| {
+            vo.vo_dag_content
+                .dag_content
+                .values()
+                .any(|n| n.acc_values().iter().any(|acc| !acc.in_prime_subgroup()))
+        })
+        .context("no tampered VO")?;
+    let count = res.len() as u64;
+
+    // 每个入口都在密码学验证之前拒绝子群之外的累加器值
+    let errs = vec![
+        verify_count(&test_chain, vo, &dag, &PUB_KEY, count).unwrap_err(),
+        verify_with_keys(&test_chain, &results, &dag, &keys).unwrap_err(),
+        verify_locate(&test_chain, &results, &dag, &PUB_KEY).unwrap_err(),
+        verify_with_heads(&heads, &param, res, vo, &dag, &PUB_KEY).unwrap_err(),
+    ];
+    for err in errs {
+        assert!(
+            err.to_string().contains("prime order subgroup"),
+            "{:#}",
+            err
+        );
+    }
+    let jobs = vec![VerifyJob {
+        res_contents: results,
+        graph: dag,
+    }];
+    assert!(verify_batch(&&test_chain, jobs, &PUB_KEY)[0].is_err());
+    Ok(())
+}
+
+#[test]
+fn test_multi_ads_bplus_root() -> Result<()> {
+    let param = Parameter::default_for_testing();
+    assert_eq!(param.num_dim, 2);
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let blk_content = (&test_chain).read_block_content(Height(4))?;
+    let ads = &blk_content.ads;
+    for &window in &param.time_win_sizes {
+        let roots: Vec<Digest> = (0..param.num_dim)
+            .map(|dim| ads.bplus_root(window, dim).context("missing bplus root"))
+            .collect::<Result<_>>()?;
+        for (dim, root) in roots.iter().enumerate() {
+            let expect = ads.read_bplus_root(window, dim as u8)?;
+            assert_eq!(*root, expect.bplus_tree_root_hash);
+        }
+        assert_ne!(roots[0], roots[1]);
+        assert_eq!(ads.bplus_root(window, param.num_dim), None);
+    }
+    assert_eq!(ads.bplus_root(3, 0), None);
+    Ok(())
+}
+
+#[test]
+fn test_verify_cache() -> Result<()> {
+    use super::verify::{cache::VerifyCache, verify_with_cache, VerifyOptions};
+    use crate::acc::{AccValue, Set};
+
+    let param = Parameter::default_for_testing();
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_param: QueryParam<u32> = serde_json::from_value(json!({
+        "start_blk": 1,
+        "end_blk": 6,
+        "range": [],
+        "keyword_exp": {"input": "a"},
+    }))?;
+    let (results, dag, _time) = query(false, false, &test_chain, query_param, &PUB_KEY)?;
+    let opts = VerifyOptions::default();
+    let cache = VerifyCache::new(&PUB_KEY);
+    assert!(cache.is_empty());
+    let first = verify_with_cache(&test_chain, &results, &dag, &PUB_KEY, &opts, &cache)?;
+    let cached = cache.len();
+    assert!(cached > 0);
+    // 第二次验证命中缓存，不再加入新的值，结果不变
+    let second = verify_with_cache(&test_chain, &results, &dag, &PUB_KEY, &opts, &cache)?;
+    assert_eq!(cache.len(), cached);
+    assert_eq!(first.vo_size, second.vo_size);
+
+    // 其他公钥不读写缓存
+    let mut rng = StdRng::seed_from_u64(1);
+    let other_sk: AccSecretKeyWithPowCache = AccSecretKey::rand(&mut rng).into();
+    let other_pk = AccPublicKey::gen_key(&other_sk, Q);
+    let set = [1u16, 2, 3].iter().copied().collect::<Set>();
+    assert_eq!(
+        cache.acc_of(&set, &other_pk),
+        AccValue::from_set(&set, &other_pk)
+    );
+    assert_eq!(cache.len(), cached);
+
+    // 达到容量上限后不再加入
+    let small = VerifyCache::with_capacity(&PUB_KEY, 1);
+    small.acc_of(&[1u16].iter().copied().collect(), &PUB_KEY);
+    small.acc_of(&[2u16].iter().copied().collect(), &PUB_KEY);
+    assert_eq!(small.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_keyword_absence() -> Result<()> {
+    use super::{
+        trie_tree::proof::Proof as TrieProof,
+        verify::{
+            absence::{prove_keyword_absence, verify_keyword_absence},
+            vo::VONode,
+        },
+    };
+
+    let param = Parameter::default_for_testing();
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    // 最小的时间窗口为 2，高度 4 的 trie 包含区块 3、4 中的关键字
+    for keyword in &["f", "ab", "e", "ac", "zzz"] {
+        let vo = prove_keyword_absence(&test_chain, keyword, Height(4), &PUB_KEY)?;
+        let (height, _info) = verify_keyword_absence(&test_chain, &vo, keyword, &PUB_KEY)?;
+        assert_eq!(height, Height(4));
+    }
+    assert!(prove_keyword_absence::<u32, _>(&test_chain, "a", Height(4), &PUB_KEY).is_err());
+    // "ab" 出现在区块 1 中，位于高度 2 的窗口内
+    assert!(prove_keyword_absence::<u32, _>(&test_chain, "ab", Height(2), &PUB_KEY).is_err());
+
+    // 用其他关键字的证明冒充已有关键字的不存在证明
+    for keyword in &["a", "dc", "aed"] {
+        let vo = prove_keyword_absence(&test_chain, "ab", Height(4), &PUB_KEY)?;
+        assert!(verify_keyword_absence(&test_chain, &vo, keyword, &PUB_KEY).is_err());
+    }
+    // 只保留 trie 根哈希：ads_root 与累加器都能对上，但查找路径没有展开
+    let mut forged = prove_keyword_absence(&test_chain, "ab", Height(4), &PUB_KEY)?;
+    let root_hash = forged.trie_proofs[&Height(4)].root_hash();
+    forged
+        .trie_proofs
+        .insert(Height(4), TrieProof::from_root_hash(None, "", root_hash));
+    assert!(verify_keyword_absence(&test_chain, &forged, "a", &PUB_KEY).is_err());
+    // 关键字节点的累加器不是空集的累加器
+    let mut forged = prove_keyword_absence(&test_chain, "ab", Height(4), &PUB_KEY)?;
+    for node in forged.vo_dag_content.dag_content.values_mut() {
+        if let VONode::Keyword(n) = node {
+            n.acc = crate::acc::AccValue::from_set(&[1u16].iter().copied().collect(), &PUB_KEY);
+        }
+    }
+    assert!(verify_keyword_absence(&test_chain, &forged, "ab", &PUB_KEY).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_schema_check() -> Result<()> {
+    use super::schema::SchemaError;
+
+    let param = Parameter::default_for_testing();
+    let schema = param.schema();
+    assert_eq!(schema.dims.len(), 2);
+    assert!(schema.has_dim(1) && !schema.has_dim(2));
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_param: QueryParam<u32> = serde_json::from_value(json!({
+        "start_blk": 1,
+        "end_blk": 6,
+        "range": [(1, 10)],
+        "keyword_exp": null,
+    }))?;
+    let mut range_dag = Graph::<DagNode<u32>, bool>::new();
+    range_dag.add_node(DagNode::Range(RangeBound::new(5, 1, 10)?));
+    let err = query_with_dag(false, false, &test_chain, query_param, range_dag, &PUB_KEY)
+        .err()
+        .context("query on an unknown dimension succeeded")?;
+    assert_eq!(
+        err.downcast_ref::<SchemaError>(),
+        Some(&SchemaError::UnknownDim {
+            dim: 5,
+            num_dims: 2
+        })
+    );
+
+    let mut keyword_dag = Graph::<DagNode<u32>, bool>::new();
+    keyword_dag.add_node(DagNode::Keyword(Box::new(KeywordNode {
+        keyword: "a".to_string(),
+    })));
+    schema.check_dag(&keyword_dag)?;
+    let no_keywords = super::schema::Schema {
+        keywords_indexed: false,
+        ..schema
+    };
+    let err = no_keywords.check_dag(&keyword_dag).unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<SchemaError>(),
+        Some(&SchemaError::KeywordsNotIndexed)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_verify_case_replay() -> Result<()> {
+    use super::verify::case::VerifyCase;
+
+    let param = Parameter::default_for_testing();
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_param: QueryParam<u32> = serde_json::from_value(json!({
+        "start_blk": 1,
+        "end_blk": 6,
+        "range": [(1, 10), (1, 10)],
+        "keyword_exp": {"or": [{"input": "a"}, {"input": "b"}]},
+    }))?;
+    let (mut results, dag, _time) = query(false, false, &test_chain, query_param, &PUB_KEY)?;
+    let dir = tempfile::tempdir()?;
+
+    let (res_content, vo) = &results[0];
+    let path = dir.path().join("ok.case");
+    VerifyCase::capture(&path, &test_chain, res_content, vo, &dag, &PUB_KEY)?;
+    let case = VerifyCase::<u32>::load(&path)?;
+    assert_eq!(case.heads.len(), vo.referenced_heights().len());
+    case.run()?;
+
+    // 少返回一个结果对象，验证失败；重放得到同样的错误
+    let res_content = &mut results[0].0;
+    let removed = *res_content.keys().next().context("no results")?;
+    res_content.remove(&removed);
+    let err = verify(&test_chain, &results[..1], &dag, &PUB_KEY).unwrap_err();
+    let (res_content, vo) = &results[0];
+    let path = dir.path().join("failing.case");
+    VerifyCase::capture(&path, &test_chain, res_content, vo, &dag, &PUB_KEY)?;
+    let replayed = VerifyCase::<u32>::load(&path)?.run().unwrap_err();
+    assert_eq!(replayed.to_string(), err.to_string());
+
+    std::fs::write(&path, b"not a case")?;
+    assert!(VerifyCase::<u32>::load(&path).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_vo_referenced_heights() -> Result<()> {
+    use std::collections::BTreeSet;
+
+    let param = Parameter::default_for_testing();
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_param: QueryParam<u32> = serde_json::from_value(json!({
+        "start_blk": 2,
+        "end_blk": 9,
+        "range": [(1, 10), (1, 10)],
+        "keyword_exp": {"or": [{"input": "a"}, {"input": "b"}]},
+    }))?;
+    let (results, _dag, _time) = query(false, false, &test_chain, query_param, &PUB_KEY)?;
+    let mut keywords = BTreeSet::new();
+    for (_, vo) in &results {
+        let heights = vo.referenced_heights();
+        let expect: BTreeSet<Height> = vo
+            .merkle_proofs
+            .keys()
+            .chain(vo.trie_proofs.keys())
+            .copied()
+            .collect();
+        assert_eq!(heights, expect);
+        assert!(!heights.is_empty());
+        let vo_keywords = vo.referenced_keywords();
+        let mut sorted = vo_keywords.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(vo_keywords, sorted);
+        keywords.extend(vo_keywords);
+    }
+    assert_eq!(keywords, ["a", "b"].iter().map(|k| k.to_string()).collect());
+    Ok(())
+}
+
+#[test]
+fn test_missing_trie_proof() -> Result<()> {
+    use super::verify::vo::VoStructureError;
+
+    let param = Parameter::default_for_testing();
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_param: QueryParam<u32> = serde_json::from_value(json!({
+        "start_blk": 1,
+        "end_blk": 6,
+        "range": [],
+        "keyword_exp": {"or": [{"input": "a"}, {"input": "b"}]},
+    }))?;
+    let (mut results, dag, _) = query(false, false, &test_chain, query_param, &PUB_KEY)?;
+    let (_, vo) = results
+        .iter_mut()
+        .max_by_key(|(_, vo)| vo.trie_proofs.len())
+        .context("no VO")?;
+    let height = *vo.trie_proofs.keys().max().context("no trie proof")?;
+    vo.trie_proofs.remove(&height);
+    let expected = VoStructureError::MissingTrieProof { height };
+
+    let err = vo.check_structure(&dag).unwrap_err();
+    assert_eq!(err.downcast_ref::<VoStructureError>(), Some(&expected));
+    let err = verify(&test_chain, &results, &dag, &PUB_KEY).unwrap_err();
+    assert_eq!(err.downcast_ref::<VoStructureError>(), Some(&expected));
+    Ok(())
+}
+
+#[test]
+fn test_multi_interval() -> Result<()> {
+    let param = Parameter::default_for_testing();
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let query_param: QueryParam<u32> = serde_json::from_value(json!({
+        "start_blk": 1,
+        "end_blk": 6,
+        "range": [(1, 12)],
+        "keyword_exp": null,
+    }))?;
+
+    let mut union_dag = Graph::<DagNode<u32>, bool>::new();
+    let union_idx = union_dag.add_node(DagNode::Union(UnionNode {}));
+    let lhs = union_dag.add_node(DagNode::Range(RangeBound::new(0, 2, 4)?));
+    let rhs = union_dag.add_node(DagNode::Range(RangeBound::new(0, 8, 10)?));
+    union_dag.add_edge(union_idx, lhs, false);
+    union_dag.add_edge(union_idx, rhs, true);
+    let mut multi_dag = Graph::<DagNode<u32>, bool>::new();
+    multi_dag.add_node(DagNode::MultiInterval(Box::new(MultiIntervalNode::new(
+        0,
+        vec![(2, 4), (8, 10)],
+    )?)));
+
+    let (union_results, union_dag, _) = query_with_dag(
+        false,
+        false,
+        &test_chain,
+        query_param.clone(),
+        union_dag,
+        &PUB_KEY,
+    )?;
+    let (multi_results, multi_dag, _) =
+        query_with_dag(false, false, &test_chain, query_param, multi_dag, &PUB_KEY)?;
+    let union_info = verify(&test_chain, &union_results, &union_dag, &PUB_KEY)?;
+    let multi_info = verify(&test_chain, &multi_results, &multi_dag, &PUB_KEY)?;
+    assert_eq!(union_results.len(), multi_results.len());
+    assert!(multi_results.iter().any(|(res, _)| !res.is_empty()));
+    for ((union_res, _), (multi_res, _)) in union_results.iter().zip(multi_results.iter()) {
+        assert_eq!(union_res, multi_res);
+    }
+    // 单个 B+ 树证明比两个区间查询的证明更小
+    assert!(multi_info.vo_size.total_s < union_info.vo_size.total_s);
+
+    // 区间必须升序且互不相交
+    assert!(MultiIntervalNode::<u32>::new(0, vec![(8, 10), (2, 4)]).is_err());
+    assert!(MultiIntervalNode::<u32>::new(0, vec![(2, 8), (8, 10)]).is_err());
+    assert!(MultiIntervalNode::<u32>::new(0, vec![]).is_err());
+    Ok(())
+}
+
+// 覆盖 read_objects_stream 的后端，记录每次请求的对象 id
+struct BatchChain<'a> {
+    chain: &'a MemChain,
+    requests: Mutex<Vec<Vec<ObjId>>>,
+}
+
+impl ReadInterface for &BatchChain<'_> {
+    type K = u32;
+    fn get_parameter(&self) -> Result<Parameter> {
+        self.chain.get_parameter()
+    }
+    fn read_block_head(&self, blk_height: Height) -> Result<BlockHead> {
+        self.chain.read_block_head(blk_height)
+    }
+    fn read_block_content(&self, blk_height: Height) -> Result<BlockContent> {
+        self.chain.read_block_content(blk_height)
+    }
+    fn read_id_tree_node(&self, id_tree_node_id: IdTreeNodeId) -> Result<IdTreeNode> {
+        self.chain.read_id_tree_node(id_tree_node_id)
+    }
+    fn read_bplus_tree_node(
+        &self,
+        bplus_tree_node_id: BPlusTreeNodeId,
+    ) -> Result<BPlusTreeNode<Self::K>> {
+        self.chain.read_bplus_tree_node(bplus_tree_node_id)
+    }
+    fn read_trie_node(&self, trie_node_id: TrieNodeId) -> Result<TrieNode> {
+        self.chain.read_trie_node(trie_node_id)
+    }
+    fn read_object(&self, obj_hash: Digest) -> Result<Object<Self::K>> {
+        self.chain.read_object(obj_hash)
+    }
+    fn read_objects_stream<'b>(
+        &'b self,
+        blk_height: Height,
+        ids: &'b [ObjId],
+    ) -> Box<dyn Iterator<Item = Result<(ObjId, Object<Self::K>)>> + 'b> {
+        self.requests.lock().unwrap().push(ids.to_vec());
+        self.chain.read_objects_stream(blk_height, ids)
+    }
+}
+
+#[test]
+fn test_read_objects_stream() -> Result<()> {
+    let param = Parameter::default_for_testing();
+    let test_chain = build_chain(TEST_DATA_3, &param)?;
+    let height = Height(3);
+    let mut ids = Vec::new();
+    let mut hashes = HashMap::new();
+    for h in [2, 1] {
+        let blk_content = (&test_chain).read_block_content(Height(h))?;
+        for (id, hash) in blk_content
+            .read_obj_id_nums()
+            .into_iter()
+            .zip(blk_content.obj_hashes.iter())
+            .rev()
+        {
+            ids.push(ObjId(id));
+            hashes.insert(ObjId(id), *hash);
+        }
+    }
+    let objs = (&test_chain)
+        .read_objects_stream(height, &ids)
+        .collect::<Result<Vec<_>>>()?;
+    assert_eq!(objs.iter().map(|(id, _)| *id).collect::<Vec<_>>(), ids);
+    for (id, obj) in &objs {
+        assert_eq!(obj.to_digest(), hashes[id]);
+    }
+
+    // id 树中不存在的 id 产生错误，不影响后续的 id
+    let absent = ObjId(core::num::NonZeroU16::new(20).unwrap());
+    let with_absent = [ids[0], absent, ids[1]];
+    let res: Vec<_> = (&test_chain)
+        .read_objects_stream(height, &with_absent)
+        .collect();
+    assert_eq!(res.len(), 3);
+    assert_eq!(res[0].as_ref().unwrap().0, ids[0]);
+    assert!(res[1].is_err());
+    assert_eq!(res[2].as_ref().unwrap().0, ids[1]);
+
+    // 查询通过 read_objects_stream 读取结果对象
+    let query_param: QueryParam<u32> = serde_json::from_value(json!({
+        "start_blk": 1,
+        "end_blk": 6,
+        "range": [],
+        "keyword_exp": {"or": [{"input": "a"}, {"input": "b"}]},
+    }))?;
+    let (expect, _dag, _time) = query(false, false, &test_chain, query_param.clone(), &PUB_KEY)?;
+    let batch_chain = BatchChain {
+        chain: &test_chain,
+        requests: Mutex::new(Vec::new()),
+    };
+    let (results, _dag, _time) = query(false, false, &batch_chain, query_param, &PUB_KEY)?;
+    assert_eq!(results.len(), expect.len());
+    for ((res, _), (expect_res, _)) in results.iter().zip(expect.iter()) {
+        assert_eq!(res, expect_res);
+    }
+    let requested: usize = batch_chain
+        .requests
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|r| r.len())
+        .sum();
+    assert_eq!(
+        requested,
+        results.iter().map(|(res, _)| res.len()).sum::<usize>()
+    );
+    Ok(())
+}
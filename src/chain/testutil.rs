@@ -0,0 +1,412 @@
+//! 单元测试共用的内存链与确定性的测试链生成器
+
+use super::{
+    block::{build::build_block, BlockContent, BlockHead, Height},
+    bplus_tree::{BPlusTreeNode, BPlusTreeNodeId},
+    id_tree::{IdTreeNode, IdTreeNodeId},
+    object::Object,
+    range::Range,
+    traits::{ReadInterface, ScanQueryInterface, WriteInterface},
+    trie_tree::{TrieNode, TrieNodeId},
+    Parameter,
+};
+use crate::{
+    acc::{AccPublicKey, AccSecretKey, AccSecretKeyWithPowCache},
+    digest::{Digest, Digestible},
+};
+use anyhow::{Context, Result};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryFrom,
+};
+
+/// `gen_test_chain` 生成对象时使用的关键字
+const TEST_KEYWORDS: &[&str] = &["a", "b", "c", "d", "e"];
+
+/// 全部数据保存在内存中的链，供单元测试使用
+#[derive(Debug, Default)]
+pub(crate) struct MemChain {
+    pub(crate) param: Option<Parameter>,
+    pub(crate) block_head: HashMap<Height, BlockHead>,
+    pub(crate) block_content: HashMap<Height, BlockContent>,
+    pub(crate) id_tree_nodes: HashMap<IdTreeNodeId, IdTreeNode>,
+    pub(crate) bplus_tree_nodes: HashMap<BPlusTreeNodeId, BPlusTreeNode<u32>>,
+    pub(crate) trie_nodes: HashMap<TrieNodeId, TrieNode>,
+    pub(crate) objects: HashMap<Digest, Object<u32>>,
+}
+
+impl ReadInterface for &MemChain {
+    type K = u32;
+    fn get_parameter(&self) -> Result<Parameter> {
+        self.param.clone().context("failed to read parameter")
+    }
+    fn read_block_head(&self, blk_height: Height) -> Result<BlockHead> {
+        self.block_head
+            .get(&blk_height)
+            .cloned()
+            .context("failed to read block header")
+    }
+    fn read_block_content(&self, block_id: Height) -> Result<BlockContent> {
+        self.block_content
+            .get(&block_id)
+            .cloned()
+            .context("failed to read block content")
+    }
+    fn read_id_tree_node(&self, id_tree_node_id: IdTreeNodeId) -> Result<IdTreeNode> {
+        self.id_tree_nodes
+            .get(&id_tree_node_id)
+            .cloned()
+            .context("failed to read id tree node")
+    }
+    fn read_bplus_tree_node(
+        &self,
+        bplus_tree_node_id: BPlusTreeNodeId,
+    ) -> Result<BPlusTreeNode<Self::K>> {
+        self.bplus_tree_nodes
+            .get(&bplus_tree_node_id)
+            .cloned()
+            .context(format!(
+                "failed to read bplus tree node with id {:?}",
+                bplus_tree_node_id
+            ))
+    }
+    fn read_trie_node(&self, trie_node_id: TrieNodeId) -> Result<TrieNode> {
+        self.trie_nodes
+            .get(&trie_node_id)
+            .cloned()
+            .context("failed to read trie tree node")
+    }
+    fn read_object(&self, obj_hash: Digest) -> Result<Object<Self::K>> {
+        self.objects
+            .get(&obj_hash)
+            .cloned()
+            .context("failed to read object")
+    }
+}
+
+impl ReadInterface for &mut MemChain {
+    type K = u32;
+    fn get_parameter(&self) -> Result<Parameter> {
+        self.param.clone().context("failed to read parameter")
+    }
+    fn read_block_head(&self, blk_height: Height) -> Result<BlockHead> {
+        self.block_head
+            .get(&blk_height)
+            .cloned()
+            .context("failed to read block header")
+    }
+    fn read_block_content(&self, block_id: Height) -> Result<BlockContent> {
+        self.block_content
+            .get(&block_id)
+            .cloned()
+            .context("failed to read block content")
+    }
+    fn read_id_tree_node(&self, id_tree_node_id: IdTreeNodeId) -> Result<IdTreeNode> {
+        self.id_tree_nodes
+            .get(&id_tree_node_id)
+            .cloned()
+            .context("failed to read id tree node")
+    }
+    fn read_bplus_tree_node(
+        &self,
+        bplus_tree_node_id: BPlusTreeNodeId,
+    ) -> Result<BPlusTreeNode<Self::K>> {
+        self.bplus_tree_nodes
+            .get(&bplus_tree_node_id)
+            .cloned()
+            .context(format!(
+                "failed to read bplus tree node with id {:?}",
+                bplus_tree_node_id
+            ))
+    }
+    fn read_trie_node(&self, trie_node_id: TrieNodeId) -> Result<TrieNode> {
+        self.trie_nodes
+            .get(&trie_node_id)
+            .cloned()
+            .context("failed to read trie tree node")
+    }
+    fn read_object(&self, obj_hash: Digest) -> Result<Object<Self::K>> {
+        self.objects
+            .get(&obj_hash)
+            .cloned()
+            .context("failed to read object")
+    }
+}
+
+impl WriteInterface for MemChain {
+    type K = u32;
+    fn set_parameter(&mut self, param: &Parameter) -> Result<()> {
+        self.param = Some(param.clone());
+        Ok(())
+    }
+    fn write_block_head(&mut self, blk_height: Height, blk_head: &BlockHead) -> Result<()> {
+        self.block_head.insert(blk_height, blk_head.clone());
+        Ok(())
+    }
+    fn write_block_content(
+        &mut self,
+        blk_height: Height,
+        blk_content: &BlockContent,
+    ) -> Result<()> {
+        self.block_content.insert(blk_height, blk_content.clone());
+        Ok(())
+    }
+    fn write_id_tree_node(&mut self, id: IdTreeNodeId, node: &IdTreeNode) -> Result<()> {
+        self.id_tree_nodes.insert(id, node.clone());
+        Ok(())
+    }
+    fn write_bplus_tree_node(
+        &mut self,
+        id: BPlusTreeNodeId,
+        node: &BPlusTreeNode<Self::K>,
+    ) -> Result<()> {
+        self.bplus_tree_nodes.insert(id, node.clone());
+        Ok(())
+    }
+    fn write_trie_node(&mut self, id: TrieNodeId, node: &TrieNode) -> Result<()> {
+        self.trie_nodes.insert(id, node.clone());
+        Ok(())
+    }
+    fn write_object(&mut self, obj_hash: Digest, obj: &Object<Self::K>) -> Result<()> {
+        self.objects.insert(obj_hash, obj.clone());
+        Ok(())
+    }
+}
+
+impl WriteInterface for &mut MemChain {
+    type K = u32;
+    fn set_parameter(&mut self, param: &Parameter) -> Result<()> {
+        self.param = Some(param.clone());
+        Ok(())
+    }
+    fn write_block_head(&mut self, blk_height: Height, blk_head: &BlockHead) -> Result<()> {
+        self.block_head.insert(blk_height, blk_head.clone());
+        Ok(())
+    }
+    fn write_block_content(
+        &mut self,
+        blk_height: Height,
+        blk_content: &BlockContent,
+    ) -> Result<()> {
+        self.block_content.insert(blk_height, blk_content.clone());
+        Ok(())
+    }
+    fn write_id_tree_node(&mut self, id: IdTreeNodeId, node: &IdTreeNode) -> Result<()> {
+        self.id_tree_nodes.insert(id, node.clone());
+        Ok(())
+    }
+    fn write_bplus_tree_node(
+        &mut self,
+        id: BPlusTreeNodeId,
+        node: &BPlusTreeNode<Self::K>,
+    ) -> Result<()> {
+        self.bplus_tree_nodes.insert(id, node.clone());
+        Ok(())
+    }
+    fn write_trie_node(&mut self, id: TrieNodeId, node: &TrieNode) -> Result<()> {
+        self.trie_nodes.insert(id, node.clone());
+        Ok(())
+    }
+    fn write_object(&mut self, obj_hash: Digest, obj: &Object<Self::K>) -> Result<()> {
+        self.objects.insert(obj_hash, obj.clone());
+        Ok(())
+    }
+}
+
+impl ScanQueryInterface for &MemChain {
+    type K = u32;
+    fn range_query(
+        &self,
+        query: Range<Self::K>,
+        start_blk_height: Height,
+        end_blk_height: Height,
+        dim: usize,
+    ) -> Result<HashSet<Digest>> {
+        let mut res = HashSet::<Digest>::new();
+        for (hash, o) in &self.objects {
+            if o.blk_height <= end_blk_height && o.blk_height >= start_blk_height {
+                let o_num_val = o.num_data.get(dim).with_context(|| {
+                    format!("Object does not have numerical value at dim {}", dim)
+                })?;
+                if query.is_in_range(*o_num_val) {
+                    res.insert(*hash);
+                }
+            }
+        }
+        Ok(res)
+    }
+
+    fn keyword_query(
+        &self,
+        keyword: &str,
+        start_blk_height: Height,
+        end_blk_height: Height,
+    ) -> Result<HashSet<Digest>> {
+        let mut res = HashSet::<Digest>::new();
+        for (hash, o) in &self.objects {
+            if o.blk_height <= end_blk_height && o.blk_height >= start_blk_height {
+                for k in o.keyword_data.iter() {
+                    if keyword == k {
+                        res.insert(*hash);
+                    }
+                }
+            }
+        }
+        Ok(res)
+    }
+
+    fn root_query(&self, height: Height, win_size: u16) -> Result<HashSet<Digest>> {
+        let mut res = HashSet::<Digest>::new();
+        for (hash, o) in &self.objects {
+            if o.blk_height <= height
+                && Height(o.blk_height.0 + win_size as u32) >= Height(height.0 + 1)
+            {
+                res.insert(*hash);
+            }
+        }
+        Ok(res)
+    }
+
+    fn get_range_info(
+        &self,
+        start_blk_height: Height,
+        end_blk_height: Height,
+        dim_num: usize,
+    ) -> Result<Vec<Range<Self::K>>> {
+        let mut num_ranges = Vec::<Range<Self::K>>::new();
+        let mut num_range_scope = Vec::<(Self::K, Self::K)>::new();
+        for _ in 0..dim_num {
+            num_range_scope.push((std::u32::MAX, 0));
+        }
+        for (_hash, o) in &self.objects {
+            if o.blk_height < end_blk_height && o.blk_height > start_blk_height {
+                let o_num_vals = &o.num_data;
+                for (i, num_val) in o_num_vals.iter().enumerate() {
+                    if i < dim_num {
+                        if num_val
+                            < &num_range_scope
+                                .get(i)
+                                .with_context(|| {
+                                    format!("Object does not have numerical value at dim {}", i)
+                                })?
+                                .0
+                        {
+                            num_range_scope
+                                .get_mut(i)
+                                .with_context(|| {
+                                    format!("Object does not have numerical value at dim {}", i)
+                                })?
+                                .0 = *num_val;
+                        } else if num_val
+                            > &num_range_scope
+                                .get(i)
+                                .with_context(|| {
+                                    format!("Object does not have numerical value at dim {}", i)
+                                })?
+                                .1
+                        {
+                            num_range_scope
+                                .get_mut(i)
+                                .with_context(|| {
+                                    format!("Object does not have numerical value at dim {}", i)
+                                })?
+                                .1 = *num_val;
+                        }
+                    }
+                }
+            }
+        }
+
+        for (min, max) in num_range_scope {
+            num_ranges.push(Range::new(min, max));
+        }
+
+        Ok(num_ranges)
+    }
+
+    fn get_keyword_info(
+        &self,
+        start_blk_height: Height,
+        end_blk_height: Height,
+    ) -> Result<HashSet<String>> {
+        let mut res = HashSet::<String>::new();
+        for (_, o) in &self.objects {
+            if o.blk_height < end_blk_height && o.blk_height > start_blk_height {
+                for k in o.keyword_data.iter() {
+                    res.insert(k.to_string());
+                }
+            }
+        }
+        Ok(res)
+    }
+
+    fn get_chain_info(&self) -> Result<(u32, u32)> {
+        let mut cur_height_num = 0;
+        let mut total_num = 0;
+        for (_, o) in &self.objects {
+            if cur_height_num < o.blk_height.0 {
+                cur_height_num = o.blk_height.0;
+            }
+            total_num += 1;
+        }
+        Ok((total_num, cur_height_num))
+    }
+}
+
+impl MemChain {
+    pub(crate) fn new() -> Self {
+        Self {
+            param: None,
+            block_head: HashMap::<Height, BlockHead>::new(),
+            block_content: HashMap::<Height, BlockContent>::new(),
+            id_tree_nodes: HashMap::<IdTreeNodeId, IdTreeNode>::new(),
+            bplus_tree_nodes: HashMap::<BPlusTreeNodeId, BPlusTreeNode<u32>>::new(),
+            trie_nodes: HashMap::<TrieNodeId, TrieNode>::new(),
+            objects: HashMap::<Digest, Object<u32>>::new(),
+        }
+    }
+}
+
+/// 由 `seed` 确定性地生成一条 `num_blocks` 个区块、每个区块 `objs_per_block` 个对象的链：
+/// 对象带有两个维度的数值与若干关键字，各区块的 ADS 与 ads_root 均由 `build_block` 正常构建。
+/// 公钥同样由 `seed` 生成，其大小足以容纳最大时间窗口内的全部对象 id
+pub(crate) fn gen_test_chain(
+    num_blocks: usize,
+    objs_per_block: usize,
+    seed: u64,
+) -> (MemChain, AccPublicKey) {
+    let mut param = Parameter::default_for_testing();
+    let max_win_size = param.time_win_sizes.iter().copied().max().unwrap_or(1) as usize;
+    let max_id_num = (objs_per_block * max_win_size).max(param.max_id_num as usize);
+    param.max_id_num = u16::try_from(max_id_num).expect("too many objects for the id tree");
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let sk: AccSecretKeyWithPowCache = AccSecretKey::rand(&mut rng).into();
+    let pk = AccPublicKey::gen_key(&sk, param.max_id_num as u64 + 1);
+
+    let mut chain = MemChain::new();
+    chain.set_parameter(&param).expect("set parameter");
+    let mut prev_hash = BlockContent::genesis(&param).0.to_digest();
+    for height in 1..=num_blocks as u32 {
+        let objs = (0..objs_per_block)
+            .map(|_| {
+                let num_data = vec![rng.gen_range(0..100u32), rng.gen_range(0..100u32)];
+                let mut keywords: HashSet<String> = TEST_KEYWORDS
+                    .iter()
+                    .filter(|_| rng.gen_bool(0.4))
+                    .map(|k| k.to_string())
+                    .collect();
+                if keywords.is_empty() {
+                    keywords
+                        .insert(TEST_KEYWORDS[rng.gen_range(0..TEST_KEYWORDS.len())].to_string());
+                }
+                Object::new(Height(height), num_data, keywords)
+            })
+            .collect();
+        let (blk_head, _) = build_block(Height(height), prev_hash, objs, &mut chain, &param, &pk)
+            .expect("build block");
+        prev_hash = blk_head.to_digest();
+    }
+    (chain, pk)
+}
@@ -2,7 +2,7 @@ use crate::{
     chain::{
         block::{BlockContent, BlockHead, Height},
         bplus_tree::{BPlusTreeNode, BPlusTreeNodeId, BPlusTreeNodeLoader},
-        id_tree::{IdTreeNode, IdTreeNodeId, IdTreeNodeLoader},
+        id_tree::{self, IdTreeNode, IdTreeNodeId, IdTreeNodeLoader, ObjId},
         object::Object,
         range::Range,
         trie_tree::TrieNodeLoader,
@@ -11,13 +11,16 @@ use crate::{
     },
     digest::{Digest, Digestible},
 };
-use anyhow::Result;
+use anyhow::{Context, Result};
 use core::str::FromStr;
 use rand::distributions::uniform::SampleUniform;
 use std::{collections::HashSet, fmt};
 
+/// 对象数值维度的类型。B+ 树的键顺序与区间证明只依赖 `Ord`（而非字节序），
+/// 因此有符号类型（如 `i64`）跨越负数与正数的区间同样可以查询与验证
 pub trait Num:
     num_traits::Num
+    + num_traits::PrimInt
     + Ord
     + Eq
     + Clone
@@ -29,10 +32,26 @@ pub trait Num:
     + std::marker::Sync
     + std::marker::Send
 {
+    /// 后继值，已是最大值时返回 `None`；用于把开区间的下界转换为闭区间的下界
+    fn successor(&self) -> Option<Self> {
+        self.checked_add(&Self::one())
+    }
+
+    /// 前驱值，已是最小值时返回 `None`；用于把开区间的上界转换为闭区间的上界
+    fn predecessor(&self) -> Option<Self> {
+        self.checked_sub(&Self::one())
+    }
+
+    /// 两数的中点，向负无穷取整，不会溢出。
+    /// 在具体整数类型上标准库的同名方法优先（有符号类型向零取整），需写作 `Num::midpoint(&a, &b)`
+    fn midpoint(&self, other: &Self) -> Self {
+        (*self & *other) + ((*self ^ *other) >> 1)
+    }
 }
 
 impl<T> Num for T where
     T: num_traits::Num
+        + num_traits::PrimInt
         + Ord
         + Eq
         + Clone
@@ -46,6 +65,37 @@ impl<T> Num for T where
 {
 }
 
+/// `ReadInterface` 的实现可以返回的错误类型，调用方通过 `anyhow::Error::downcast_ref` 区分；
+/// 未使用该类型的错误一律视为不可重试
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReadError {
+    /// 数据确实不存在（如高度超出链长），重试没有意义
+    NotFound(String),
+    /// 暂时性错误（如远程后端的网络抖动），可以重试
+    Transient(String),
+}
+
+impl ReadError {
+    /// `err` 是否为可以重试的暂时性错误
+    pub fn is_transient(err: &anyhow::Error) -> bool {
+        matches!(
+            err.downcast_ref::<ReadError>(),
+            Some(ReadError::Transient(_))
+        )
+    }
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadError::NotFound(msg) => write!(f, "Not found: {}", msg),
+            ReadError::Transient(msg) => write!(f, "Transient read error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ReadError {}
+
 pub trait ReadInterface {
     type K: Num;
     fn get_parameter(&self) -> Result<Parameter>;
@@ -58,6 +108,48 @@ pub trait ReadInterface {
     ) -> Result<BPlusTreeNode<Self::K>>;
     fn read_trie_node(&self, trie_node_id: TrieNodeId) -> Result<TrieNode>;
     fn read_object(&self, obj_hash: Digest) -> Result<Object<Self::K>>;
+
+    /// 按 `ids` 的顺序逐个读取区块 `blk_height` 的 id 树中的对象，不把所有对象同时载入内存。
+    /// 默认实现对每个 id 查询 id 树后调用 `read_object`；支持批量读取的后端可以覆盖该方法。
+    /// id 树中不存在的 id 产生一个错误，不影响后续的 id
+    #[allow(clippy::type_complexity)]
+    fn read_objects_stream<'a>(
+        &'a self,
+        blk_height: Height,
+        ids: &'a [ObjId],
+    ) -> Box<dyn Iterator<Item = Result<(ObjId, Object<Self::K>)>> + 'a>
+    where
+        Self: Sized,
+    {
+        let setup = self.get_parameter().and_then(|param| {
+            let id_root = self.read_block_content(blk_height)?.id_tree_root;
+            let root_id = id_root
+                .get_id_tree_root_id()
+                .with_context(|| format!("The id tree of height {:?} is empty", blk_height))?;
+            Ok((param, root_id))
+        });
+        let (param, root_id) = match setup {
+            Ok(v) => v,
+            Err(e) => return Box::new(std::iter::once(Err(e))),
+        };
+        Box::new(ids.iter().map(move |&obj_id| {
+            let obj_hash = id_tree::read::query_without_proof(
+                param.max_id_num,
+                self,
+                root_id,
+                obj_id.to_internal_id(),
+                param.id_tree_fanout,
+            )
+            .and_then(|hash| hash.context("No leaf for the object id"))
+            .with_context(|| {
+                format!(
+                    "Cannot find object {:?} in the id tree of height {:?}",
+                    obj_id, blk_height
+                )
+            })?;
+            Ok((obj_id, self.read_object(obj_hash)?))
+        }))
+    }
 }
 
 impl<Interface: ReadInterface> IdTreeNodeLoader for Interface {
@@ -127,3 +219,33 @@ pub trait ScanQueryInterface {
     ) -> Result<HashSet<String>>;
     fn get_chain_info(&self) -> Result<(u32, u32)>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Num;
+
+    #[test]
+    fn test_successor() {
+        assert_eq!(3u32.successor(), Some(4));
+        assert_eq!(u32::MAX.successor(), None);
+        assert_eq!((-1i64).successor(), Some(0));
+        assert_eq!(i64::MAX.successor(), None);
+        assert_eq!(i64::MIN.predecessor(), None);
+        assert_eq!(0u32.predecessor(), None);
+        assert_eq!(0i32.predecessor(), Some(-1));
+    }
+
+    #[test]
+    fn test_midpoint() {
+        assert_eq!(Num::midpoint(&2u32, &6), 4);
+        assert_eq!(Num::midpoint(&2u32, &7), 4);
+        assert_eq!(Num::midpoint(&7u32, &2), 4);
+        assert_eq!(Num::midpoint(&u32::MAX, &(u32::MAX - 1)), u32::MAX - 1);
+        // 负数同样向负无穷取整
+        assert_eq!(Num::midpoint(&-3i32, &0), -2);
+        assert_eq!(Num::midpoint(&-1i64, &0), -1);
+        assert_eq!(Num::midpoint(&i64::MIN, &i64::MAX), -1);
+        assert_eq!(Num::midpoint(&i64::MAX, &i64::MAX), i64::MAX);
+        assert_eq!(Num::midpoint(&i64::MIN, &i64::MIN), i64::MIN);
+    }
+}
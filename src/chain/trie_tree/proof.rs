@@ -5,6 +5,7 @@ use crate::{
 };
 use anyhow::{ensure, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use sub_proof::SubProof;
 
 pub(crate) mod leaf;
@@ -43,8 +44,12 @@ impl Proof {
     }
 
     fn value_acc_hash(&self, keyword: &str, pk: &AccPublicKey) -> Digest {
-        match self.root.as_ref() {
-            Some(root) => root.value_acc_hash(keyword, pk),
+        match self
+            .root
+            .as_ref()
+            .and_then(|root| root.value_acc_hash(keyword))
+        {
+            Some(acc_hash) => acc_hash,
             None => {
                 let empty_set = Set::new();
                 AccValue::from_set(&empty_set, pk).to_digest()
@@ -61,6 +66,100 @@ impl Proof {
         Ok(())
     }
 
+    pub fn verify_accs(
+        &self,
+        target_accs: &[AccValue],
+        keywords: &[String],
+        pk: &AccPublicKey,
+    ) -> Result<()> {
+        ensure!(
+            target_accs.len() == keywords.len(),
+            "Trie verification: number of acc values and keywords not matched!"
+        );
+        let items: Vec<(&str, AccValue)> = keywords
+            .iter()
+            .map(|k| k.as_str())
+            .zip(target_accs.iter().copied())
+            .collect();
+        self.verify_acc_batch(&items, pk)
+    }
+
+    /// 批量验证同一区块中的多个 `(keyword, acc)`：重复的关键字只查找一次，
+    /// 空集合的累加器哈希也只计算一次
+    pub fn verify_acc_batch(&self, items: &[(&str, AccValue)], pk: &AccPublicKey) -> Result<()> {
+        let mut acc_hashes = HashMap::<&str, Option<Digest>>::new();
+        let mut empty_acc_hash = None;
+        for (keyword, target_acc) in items {
+            let acc_hash = *acc_hashes.entry(keyword).or_insert_with(|| {
+                self.root
+                    .as_ref()
+                    .and_then(|root| root.value_acc_hash(keyword))
+            });
+            let computed_acc = match acc_hash {
+                Some(h) => h,
+                None => *empty_acc_hash
+                    .get_or_insert_with(|| AccValue::from_set(&Set::new(), pk).to_digest()),
+            };
+            ensure!(
+                target_acc.to_digest() == computed_acc,
+                "Trie verification: acc value of keyword {} not matched!",
+                keyword
+            );
+        }
+        Ok(())
+    }
+
+    /// 验证 trie 中不存在关键字 `keyword`。与 `verify_acc` 不同，查找路径上的节点必须全部展开，
+    /// 未展开的节点不能当作关键字不存在
+    pub fn verify_absence(&self, keyword: &str) -> Result<()> {
+        if let Some(root) = self.root.as_ref() {
+            ensure!(
+                root.is_absent(keyword)?,
+                "Trie verification: keyword {} exists",
+                keyword
+            );
+        }
+        Ok(())
+    }
+
+    /// 验证 `target_accs` 依次为以 `prefix` 为前缀的所有关键字的累加器；
+    /// 若不存在这样的关键字，`target_accs` 应只包含空集合的累加器
+    pub fn verify_prefix_accs(
+        &self,
+        target_accs: &[AccValue],
+        prefix: &str,
+        pk: &AccPublicKey,
+    ) -> Result<()> {
+        let mut acc_hashes = Vec::new();
+        if let Some(root) = self.root.as_ref() {
+            root.prefix_acc_hashes(prefix, &mut acc_hashes)?;
+        }
+        if acc_hashes.is_empty() {
+            let empty_set = Set::new();
+            acc_hashes.push(AccValue::from_set(&empty_set, pk).to_digest());
+        }
+        ensure!(
+            target_accs.len() == acc_hashes.len(),
+            "Trie verification: number of prefix acc values not matched!"
+        );
+        for (acc, acc_hash) in target_accs.iter().zip(acc_hashes) {
+            ensure!(
+                acc.to_digest() == acc_hash,
+                "Trie verification: prefix acc value not matched!"
+            );
+        }
+        Ok(())
+    }
+
+    /// 证明中展开到叶子的所有关键字，按 trie 中的顺序
+    pub fn revealed_keywords(&self) -> Vec<String> {
+        let mut keywords = Vec::new();
+        if let Some(root) = self.root.as_ref() {
+            root.collect_keywords(String::new(), &mut keywords);
+        }
+        keywords
+    }
+
     pub(crate) fn remove_node_id(&mut self) {
         if let Some(sub_proof) = &mut self.root {
             sub_proof.remove_node_id();
@@ -1,5 +1,4 @@
 use crate::{
-    acc::{AccPublicKey, AccValue, Set},
     chain::trie_tree::{hash::trie_leaf_proof_hash, TrieNodeId},
     digest::{Digest, Digestible},
 };
@@ -27,12 +26,11 @@ impl TrieLeaf {
         }
     }
 
-    pub(crate) fn value_acc_hash(&self, cur_key: &str, pk: &AccPublicKey) -> Digest {
+    pub(crate) fn value_acc_hash(&self, cur_key: &str) -> Option<Digest> {
         if cur_key == self.rest {
-            self.acc_hash
+            Some(self.acc_hash)
         } else {
-            let empty_set = Set::new();
-            AccValue::from_set(&empty_set, pk).to_digest()
+            None
         }
     }
 }
@@ -1,5 +1,4 @@
 use crate::{
-    acc::{AccPublicKey, AccValue, Set},
     chain::trie_tree::{
         hash::trie_non_leaf_proof_hash,
         proof::{sub_proof::SubProof, TrieNodeId},
@@ -7,6 +6,7 @@ use crate::{
     },
     digest::{Digest, Digestible},
 };
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use smol_str::SmolStr;
 use std::collections::BTreeMap;
@@ -14,6 +14,7 @@ use std::collections::BTreeMap;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct TrieNonLeaf {
     pub(crate) nibble: String,
+    #[serde(deserialize_with = "crate::utils::deserialize_nested")]
     pub(crate) children: BTreeMap<char, Box<SubProof>>,
 }
 
@@ -31,15 +32,24 @@ impl TrieNonLeaf {
         }
     }
 
-    pub(crate) fn value_acc_hash(&self, cur_key: &str, pk: &AccPublicKey) -> Digest {
+    pub(crate) fn value_acc_hash(&self, cur_key: &str) -> Option<Digest> {
         let (_common_key, cur_idx, rest_cur_key, _node_idx, _rest_node_key) =
             split_at_common_prefix2(cur_key, &self.nibble);
+        self.children
+            .get(&cur_idx)
+            .and_then(|c| c.value_acc_hash(&rest_cur_key))
+    }
+
+    /// `cur_key` 不以本节点的 nibble 开头，或没有对应的分支时，关键字不存在
+    pub(crate) fn is_absent(&self, cur_key: &str) -> Result<bool> {
+        let (_common_key, cur_idx, rest_cur_key, node_idx, _rest_node_key) =
+            split_at_common_prefix2(cur_key, &self.nibble);
+        if node_idx != '\0' {
+            return Ok(true);
+        }
         match self.children.get(&cur_idx) {
-            Some(c) => c.value_acc_hash(&rest_cur_key, pk),
-            None => {
-                let empty_set = Set::new();
-                AccValue::from_set(&empty_set, pk).to_digest()
-            }
+            Some(c) => c.is_absent(&rest_cur_key),
+            None => Ok(true),
         }
     }
 
@@ -55,6 +65,20 @@ impl TrieNonLeaf {
         }
     }
 
+    pub(crate) fn prefix_acc_hashes(&self, prefix: &str, hashes: &mut Vec<Digest>) -> Result<()> {
+        if self.nibble.starts_with(prefix) {
+            for c in self.children.values() {
+                c.subtree_acc_hashes(hashes)?;
+            }
+        } else if let Some(rest_prefix) = prefix.strip_prefix(self.nibble.as_str()) {
+            let mut chars = rest_prefix.chars();
+            if let Some(c) = chars.next().and_then(|idx| self.children.get(&idx)) {
+                c.prefix_acc_hashes(chars.as_str(), hashes)?;
+            }
+        }
+        Ok(())
+    }
+
     pub(crate) fn remove_node_id(&mut self) {
         let children = &mut self.children;
         for c in children.values_mut() {
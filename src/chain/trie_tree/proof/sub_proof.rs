@@ -1,11 +1,11 @@
 use crate::{
-    acc::{AccPublicKey, AccValue, Set},
     chain::trie_tree::{
         proof::{leaf::TrieLeaf, non_leaf::TrieNonLeaf, sub_tree::TrieSubTree},
         TrieNodeId,
     },
     digest::{Digest, Digestible},
 };
+use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
 use smol_str::SmolStr;
 
@@ -53,15 +53,23 @@ impl SubProof {
         Self::Leaf(Box::new(l))
     }
 
-    pub(crate) fn value_acc_hash(&self, cur_key: &str, pk: &AccPublicKey) -> Digest {
+    /// 返回 `cur_key` 对应叶子中的累加器哈希；若证明表明该关键字不存在则返回 `None`
+    pub(crate) fn value_acc_hash(&self, cur_key: &str) -> Option<Digest> {
         match self {
-            SubProof::Hash(_) => {
-                let empty_set = Set::new();
-                AccValue::from_set(&empty_set, pk).to_digest()
-            }
-            SubProof::Leaf(n) => n.value_acc_hash(cur_key, pk),
-            SubProof::NonLeaf(n) => n.value_acc_hash(cur_key, pk),
-            SubProof::NonLeafRoot(n) => n.value_acc_hash(cur_key, pk),
+            SubProof::Hash(_) => None,
+            SubProof::Leaf(n) => n.value_acc_hash(cur_key),
+            SubProof::NonLeaf(n) => n.value_acc_hash(cur_key),
+            SubProof::NonLeafRoot(n) => n.value_acc_hash(cur_key),
+        }
+    }
+
+    /// 证明是否表明 `cur_key` 不存在；查找路径上遇到未展开的节点时无法判断，返回错误
+    pub(crate) fn is_absent(&self, cur_key: &str) -> Result<bool> {
+        match self {
+            SubProof::Hash(_) => bail!("Trie verification: lookup path is not fully expanded"),
+            SubProof::Leaf(n) => Ok(n.rest != cur_key),
+            SubProof::NonLeaf(n) => n.is_absent(cur_key),
+            SubProof::NonLeafRoot(n) => n.is_absent(cur_key),
         }
     }
 
@@ -87,6 +95,65 @@ impl SubProof {
         }
     }
 
+    /// 收集以 `prefix` 为前缀的所有叶子的累加器哈希（按 trie 中的顺序）。
+    /// 到达前缀的路径须展开到关键字分叉处，前缀子树内必须完全展开。
+    pub(crate) fn prefix_acc_hashes(&self, prefix: &str, hashes: &mut Vec<Digest>) -> Result<()> {
+        match self {
+            SubProof::Hash(_) => bail!("Trie verification: prefix path is not fully expanded"),
+            SubProof::Leaf(n) => {
+                if n.rest.starts_with(prefix) {
+                    hashes.push(n.acc_hash);
+                }
+                Ok(())
+            }
+            SubProof::NonLeaf(n) => n.prefix_acc_hashes(prefix, hashes),
+            SubProof::NonLeafRoot(n) => n.prefix_acc_hashes(prefix, hashes),
+        }
+    }
+
+    pub(crate) fn subtree_acc_hashes(&self, hashes: &mut Vec<Digest>) -> Result<()> {
+        match self {
+            SubProof::Hash(_) => bail!("Trie verification: prefix subtree is not fully expanded"),
+            SubProof::Leaf(n) => {
+                hashes.push(n.acc_hash);
+                Ok(())
+            }
+            SubProof::NonLeaf(n) => {
+                for c in n.children.values() {
+                    c.subtree_acc_hashes(hashes)?;
+                }
+                Ok(())
+            }
+            SubProof::NonLeafRoot(n) => {
+                for c in n.children.values() {
+                    c.subtree_acc_hashes(hashes)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// 收集子树中已展开的叶子对应的关键字，`path` 为到达该节点之前的关键字前缀
+    pub(crate) fn collect_keywords(&self, path: String, keywords: &mut Vec<String>) {
+        let (nibble, children) = match self {
+            SubProof::Hash(_) => return,
+            SubProof::Leaf(n) => {
+                keywords.push(path + &n.rest);
+                return;
+            }
+            SubProof::NonLeaf(n) => (&n.nibble, &n.children),
+            SubProof::NonLeafRoot(n) => (&n.nibble, &n.children),
+        };
+        let path = path + nibble;
+        for (c, child) in children {
+            let mut child_path = path.clone();
+            if *c != '\0' {
+                child_path.push(*c);
+            }
+            child.collect_keywords(child_path, keywords);
+        }
+    }
+
     pub(crate) fn remove_node_id(&mut self) {
         match self {
             SubProof::Hash(n) => {
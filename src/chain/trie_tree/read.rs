@@ -6,7 +6,7 @@ use crate::{
     },
     digest::{Digest, Digestible},
 };
-use anyhow::{anyhow, bail, Context, Result};
+use anyhow::{anyhow, bail, ensure, Context, Result};
 use smol_str::SmolStr;
 use std::collections::BTreeMap;
 
@@ -22,16 +22,19 @@ pub fn query_trie(
     };
 
     let root_node = node_loader.load_node(trie_root_id)?;
-    let (res, acc, p) = inner_query_trie(node_loader, trie_root_id, root_node, keyword, pk)?;
+    let (res, acc, p) = inner_query_trie(node_loader, trie_root_id, root_node, keyword, pk, false)?;
     Ok((res, acc, Proof::from_subproof(p)))
 }
 
+// `reveal_end` 为真时展开查找路径的终点：不匹配的叶子给出其关键字与累加器哈希，
+// 没有对应分支的非叶子节点给出全部分支的哈希，使证明足以说明关键字不存在
 fn inner_query_trie(
     node_loader: &impl TrieNodeLoader,
     root_id: TrieNodeId,
     root_node: TrieNode,
     keyword: &SmolStr,
     pk: &AccPublicKey,
+    reveal_end: bool,
 ) -> Result<(Set, AccValue, SubProof)> {
     use super::proof::{leaf::TrieLeaf, non_leaf::TrieNonLeaf};
 
@@ -59,8 +62,17 @@ fn inner_query_trie(
                 } else {
                     query_val = Set::new();
                     res_acc = AccValue::from_set(&query_val, pk);
+                    let end_proof = if reveal_end {
+                        SubProof::from_leaf(TrieLeaf::new(
+                            Some(n.id),
+                            &n.rest,
+                            n.data_set_acc.to_digest(),
+                        ))
+                    } else {
+                        SubProof::from_hash(Some(n.id), &n.rest, n.to_digest())
+                    };
                     unsafe {
-                        *cur_proof = SubProof::from_hash(Some(n.id), &n.rest, n.to_digest());
+                        *cur_proof = end_proof;
                     }
                 }
                 break;
@@ -75,18 +87,7 @@ fn inner_query_trie(
                         let mut sub_proof =
                             Box::new(SubProof::from_hash(Some(*id), &rest_cur_key, *hash));
                         let sub_proof_ptr = &mut *sub_proof as *mut _;
-                        let mut children = BTreeMap::new();
-                        for (c, (i, h)) in &n.children {
-                            let child_node = node_loader.load_node(*i)?;
-                            children.insert(
-                                *c,
-                                Box::new(SubProof::from_hash(
-                                    Some(child_node.get_id()),
-                                    child_node.get_string(),
-                                    *h,
-                                )),
-                            );
-                        }
+                        let children = child_hashes(node_loader, &n.children)?;
                         let mut non_leaf = TrieNonLeaf::from_hashes(&n.nibble, children);
                         *non_leaf
                             .children
@@ -103,8 +104,14 @@ fn inner_query_trie(
                     None => {
                         query_val = Set::new();
                         res_acc = AccValue::from_set(&query_val, pk);
+                        let end_proof = if reveal_end {
+                            let children = child_hashes(node_loader, &n.children)?;
+                            SubProof::from_non_leaf(TrieNonLeaf::from_hashes(&n.nibble, children))
+                        } else {
+                            SubProof::from_hash(Some(n.id), &n.nibble, n.to_digest())
+                        };
                         unsafe {
-                            *cur_proof = SubProof::from_hash(Some(n.id), &n.nibble, n.to_digest());
+                            *cur_proof = end_proof;
                         }
                         break;
                     }
@@ -120,18 +127,7 @@ fn inner_query_trie(
                         let mut sub_proof =
                             Box::new(SubProof::from_hash(Some(*id), &rest_cur_key, *hash));
                         let sub_proof_ptr = &mut *sub_proof as *mut _;
-                        let mut children = BTreeMap::new();
-                        for (c, (i, h)) in &n.children {
-                            let child_node = node_loader.load_node(*i)?;
-                            children.insert(
-                                *c,
-                                Box::new(SubProof::from_hash(
-                                    Some(child_node.get_id()),
-                                    child_node.get_string(),
-                                    *h,
-                                )),
-                            );
-                        }
+                        let children = child_hashes(node_loader, &n.children)?;
                         let mut root_proof = TrieNonLeafRoot::from_hashes(
                             &n.nibble,
                             &n.data_set_acc.to_digest(),
@@ -152,8 +148,18 @@ fn inner_query_trie(
                     None => {
                         query_val = Set::new();
                         res_acc = AccValue::from_set(&query_val, pk);
+                        let end_proof = if reveal_end {
+                            let children = child_hashes(node_loader, &n.children)?;
+                            SubProof::from_non_leaf_root(TrieNonLeafRoot::from_hashes(
+                                &n.nibble,
+                                &n.data_set_acc.to_digest(),
+                                children,
+                            ))
+                        } else {
+                            SubProof::from_hash(Some(n.id), &n.nibble, n.to_digest())
+                        };
                         unsafe {
-                            *cur_proof = SubProof::from_hash(Some(n.id), &n.nibble, n.to_digest());
+                            *cur_proof = end_proof;
                         }
                         break;
                     }
@@ -165,6 +171,72 @@ fn inner_query_trie(
     Ok((query_val, res_acc, query_proof))
 }
 
+// 非叶子节点全部分支的哈希证明
+fn child_hashes(
+    node_loader: &impl TrieNodeLoader,
+    children: &BTreeMap<char, (TrieNodeId, Digest)>,
+) -> Result<BTreeMap<char, Box<SubProof>>> {
+    let mut hashes = BTreeMap::new();
+    for (c, (i, h)) in children {
+        let child_node = node_loader.load_node(*i)?;
+        hashes.insert(
+            *c,
+            Box::new(SubProof::from_hash(
+                Some(child_node.get_id()),
+                child_node.get_string(),
+                *h,
+            )),
+        );
+    }
+    Ok(hashes)
+}
+
+// 收集 `id` 子树下以 `prefix` 为前缀的所有关键字，`path` 为到达该节点之前的关键字前缀
+fn collect_prefix_keywords(
+    node_loader: &impl TrieNodeLoader,
+    id: TrieNodeId,
+    prefix: &str,
+    path: String,
+    keywords: &mut Vec<String>,
+) -> Result<()> {
+    let (nibble, children) = match node_loader.load_node(id)? {
+        TrieNode::Leaf(n) => {
+            if n.rest.starts_with(prefix) {
+                keywords.push(path + &n.rest);
+            }
+            return Ok(());
+        }
+        TrieNode::NonLeaf(n) => (n.nibble, n.children),
+        TrieNode::NonLeafRoot(n) => (n.nibble, n.children),
+    };
+    let path = path + &nibble;
+    if nibble.starts_with(prefix) {
+        for (c, (child_id, _)) in &children {
+            let mut child_path = path.clone();
+            if *c != '\0' {
+                child_path.push(*c);
+            }
+            collect_prefix_keywords(node_loader, *child_id, "", child_path, keywords)?;
+        }
+    } else if let Some(rest_prefix) = prefix.strip_prefix(nibble.as_str()) {
+        let mut chars = rest_prefix.chars();
+        if let Some(c) = chars.next() {
+            if let Some((child_id, _)) = children.get(&c) {
+                let mut child_path = path;
+                child_path.push(c);
+                collect_prefix_keywords(
+                    node_loader,
+                    *child_id,
+                    chars.as_str(),
+                    child_path,
+                    keywords,
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
 pub struct ReadContext<'a, L: TrieNodeLoader> {
     node_loader: &'a L,
     root_id: Option<TrieNodeId>,
@@ -209,6 +281,26 @@ impl<'a, L: TrieNodeLoader> ReadContext<'a, L> {
     }
 
     pub fn query(&mut self, keyword: &SmolStr, pk: &AccPublicKey) -> Result<(Set, AccValue)> {
+        self.inner_query(keyword, pk, false)
+    }
+
+    /// 证明 trie 中不存在关键字 `keyword`：查找路径的终点在证明中展开，
+    /// 可由 `Proof::verify_absence` 验证。关键字存在时返回错误
+    pub fn prove_absence(&mut self, keyword: &SmolStr, pk: &AccPublicKey) -> Result<()> {
+        if self.root_id.is_none() {
+            return Ok(());
+        }
+        let (set, _acc) = self.inner_query(keyword, pk, true)?;
+        ensure!(set.is_empty(), "Keyword {} exists in the trie", keyword);
+        Ok(())
+    }
+
+    fn inner_query(
+        &mut self,
+        keyword: &SmolStr,
+        pk: &AccPublicKey,
+        reveal_end: bool,
+    ) -> Result<(Set, AccValue)> {
         let query_val: Set;
         let res_acc: AccValue;
         match self.proof.root.as_mut() {
@@ -222,6 +314,7 @@ impl<'a, L: TrieNodeLoader> ReadContext<'a, L> {
                         sub_root_node,
                         &cur_key,
                         pk,
+                        reveal_end,
                     )?;
                     unsafe {
                         *sub_proof = p;
@@ -235,12 +328,60 @@ impl<'a, L: TrieNodeLoader> ReadContext<'a, L> {
                 }
             },
             None => {
-                let (v, a, p) = query_trie(self.node_loader, self.root_id, keyword, pk)?;
-                self.proof = p;
+                let root_id = self.root_id.context("The trie is empty")?;
+                let root_node = self.node_loader.load_node(root_id)?;
+                let (v, a, p) = inner_query_trie(
+                    self.node_loader,
+                    root_id,
+                    root_node,
+                    keyword,
+                    pk,
+                    reveal_end,
+                )?;
+                self.proof = Proof::from_subproof(p);
                 query_val = v;
                 res_acc = a;
             }
         }
         Ok((query_val, res_acc))
     }
+
+    /// 查询以 `prefix` 为前缀的所有关键字，按 trie 中的顺序返回各关键字的集合与累加器。
+    /// 证明中会完整展开前缀对应的子树，使验证者可以确认没有遗漏的叶子。
+    pub fn query_prefix(
+        &mut self,
+        prefix: &str,
+        pk: &AccPublicKey,
+    ) -> Result<Vec<(Set, AccValue)>> {
+        let root_id = match self.root_id {
+            Some(id) => id,
+            None => return Ok(Vec::new()),
+        };
+        let mut keywords = Vec::new();
+        collect_prefix_keywords(
+            self.node_loader,
+            root_id,
+            prefix,
+            String::new(),
+            &mut keywords,
+        )?;
+        if keywords.is_empty() {
+            // 展开前缀所在的路径直到分叉处以证明不存在
+            self.inner_query(&SmolStr::from(prefix), pk, true)?;
+            return Ok(Vec::new());
+        }
+        self.query_keywords(&keywords, pk)
+    }
+
+    /// 在同一个证明中依次查询多个关键字，共享的前缀路径只出现一次
+    pub fn query_keywords(
+        &mut self,
+        keywords: &[String],
+        pk: &AccPublicKey,
+    ) -> Result<Vec<(Set, AccValue)>> {
+        keywords
+            .iter()
+            .map(|keyword| self.query(&SmolStr::from(keyword), pk))
+            .collect()
+    }
 }
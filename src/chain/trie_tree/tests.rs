@@ -389,6 +389,52 @@ fn test_read_ctx() {
     p.verify_acc(expect_acc9, "abcde", &PUB_KEY).unwrap();
 }
 
+#[test]
+fn test_verify_acc_batch() {
+    let data = get_dataset();
+    let mut test_trie = TestTrie::new();
+    let mut test_trie_root = TrieRoot::default();
+    set_root_id(&mut test_trie_root, test_trie.root_id);
+    let mut ctx = WriteContext::new(&test_trie, test_trie_root);
+    for (k, id) in &data {
+        ctx.insert(SmolStr::from(k.clone()), ObjId(*id), &PUB_KEY)
+            .unwrap();
+    }
+    let change = ctx.changes();
+    test_trie.apply(change);
+
+    let empty_acc = AccValue::from_set(&Set::new(), &PUB_KEY);
+    let acc1 = AccValue::from_set(&set! {1, 4}, &PUB_KEY);
+    let acc2 = AccValue::from_set(&set! {6}, &PUB_KEY);
+    let acc3 = AccValue::from_set(&set! {10}, &PUB_KEY);
+    let mut ctx = ReadContext::new(&test_trie, test_trie.root_id);
+    for k in &["abcd", "abcb", "bce", "befg"] {
+        ctx.query(&SmolStr::from(*k), &PUB_KEY).unwrap();
+    }
+    let p = ctx.into_proof();
+
+    let items = vec![
+        ("abcd", acc1),
+        ("abcb", acc2),
+        ("bce", acc3),
+        ("befg", empty_acc),
+        ("abcd", acc1),
+    ];
+    for (k, acc) in &items {
+        p.verify_acc(*acc, k, &PUB_KEY).unwrap();
+    }
+    p.verify_acc_batch(&items, &PUB_KEY).unwrap();
+
+    for i in 0..items.len() {
+        let mut bad_items = items.clone();
+        bad_items[i].1 = if bad_items[i].1 == acc2 { acc3 } else { acc2 };
+        assert!(p
+            .verify_acc(bad_items[i].1, bad_items[i].0, &PUB_KEY)
+            .is_err());
+        assert!(p.verify_acc_batch(&bad_items, &PUB_KEY).is_err());
+    }
+}
+
 #[test]
 fn test_common_prefix_len() {
     use super::common_prefix_len;
@@ -457,3 +503,151 @@ fn test_box() {
     assert_eq!(a.to_digest(), b.to_digest());
     assert_eq!(a.to_digest(), b.as_ref().to_digest());
 }
+
+#[test]
+fn test_read_prefix() {
+    let mut test_trie = TestTrie::new();
+    let mut test_trie_root = TrieRoot::default();
+    set_root_id(&mut test_trie_root, test_trie.root_id);
+    let mut ctx = WriteContext::new(&test_trie, test_trie_root);
+    for (keyword, id) in &[("apple", 1), ("apply", 2), ("banana", 3)] {
+        ctx.insert(
+            SmolStr::from(*keyword),
+            ObjId(NonZeroU16::new(*id).unwrap()),
+            &PUB_KEY,
+        )
+        .unwrap();
+    }
+    let change = ctx.changes();
+    test_trie.apply(change);
+
+    let empty_acc = AccValue::from_set(&Set::new(), &PUB_KEY);
+    let mut ctx = ReadContext::new(&test_trie, test_trie.root_id);
+    let res = ctx.query_prefix("app", &PUB_KEY).unwrap();
+    let sets: Vec<Set> = res.iter().map(|(s, _)| s.clone()).collect();
+    let accs: Vec<AccValue> = res.iter().map(|(_, a)| *a).collect();
+    assert_eq!(sets, vec![set! {1}, set! {2}]);
+    let res = ctx.query_prefix("c", &PUB_KEY).unwrap();
+    assert!(res.is_empty());
+
+    let p = ctx.into_proof();
+    p.verify_prefix_accs(&accs, "app", &PUB_KEY).unwrap();
+    p.verify_prefix_accs(&accs, "appl", &PUB_KEY).unwrap();
+    p.verify_prefix_accs(&[empty_acc], "c", &PUB_KEY).unwrap();
+    // 遗漏或多出叶子都无法通过验证
+    assert!(p.verify_prefix_accs(&accs[..1], "app", &PUB_KEY).is_err());
+    let banana_acc = AccValue::from_set(&set! {3}, &PUB_KEY);
+    let mut extra_accs = accs.clone();
+    extra_accs.push(banana_acc);
+    assert!(p.verify_prefix_accs(&extra_accs, "app", &PUB_KEY).is_err());
+    // 证明中未展开 "banana" 所在的子树
+    assert!(p.verify_prefix_accs(&[banana_acc], "", &PUB_KEY).is_err());
+}
+
+#[test]
+fn test_read_prefix_collapsed_path() {
+    use super::proof::{sub_proof::SubProof, Proof};
+
+    let mut test_trie = TestTrie::new();
+    let mut test_trie_root = TrieRoot::default();
+    set_root_id(&mut test_trie_root, test_trie.root_id);
+    let mut ctx = WriteContext::new(&test_trie, test_trie_root);
+    for (keyword, id) in &[("apple", 1), ("apply", 2), ("banana", 3)] {
+        ctx.insert(
+            SmolStr::from(*keyword),
+            ObjId(NonZeroU16::new(*id).unwrap()),
+            &PUB_KEY,
+        )
+        .unwrap();
+    }
+    let change = ctx.changes();
+    test_trie.apply(change);
+
+    let empty_acc = AccValue::from_set(&Set::new(), &PUB_KEY);
+    let mut ctx = ReadContext::new(&test_trie, test_trie.root_id);
+    let accs: Vec<AccValue> = ctx
+        .query_prefix("app", &PUB_KEY)
+        .unwrap()
+        .into_iter()
+        .map(|(_, a)| a)
+        .collect();
+    let p = ctx.into_proof();
+    p.verify_prefix_accs(&accs, "app", &PUB_KEY).unwrap();
+    let root_hash = p.root_hash();
+
+    // 路径在前缀中间、nibble 中间或叶子处分叉时，证明均足以说明前缀不存在
+    for prefix in &["c", "apz", "az", "bz", "applez"] {
+        let mut ctx = ReadContext::new(&test_trie, test_trie.root_id);
+        assert!(ctx.query_prefix(prefix, &PUB_KEY).unwrap().is_empty());
+        let absent = ctx.into_proof();
+        assert_eq!(absent.root_hash(), root_hash);
+        absent
+            .verify_prefix_accs(&[empty_acc], prefix, &PUB_KEY)
+            .unwrap();
+    }
+
+    // 整条路径折叠为根节点的哈希，不能借此声称前缀不存在
+    let collapsed = Proof::from_root_hash(None, "", root_hash);
+    assert_eq!(collapsed.root_hash(), root_hash);
+    assert!(collapsed
+        .verify_prefix_accs(&[empty_acc], "app", &PUB_KEY)
+        .is_err());
+
+    // 只折叠路径中间的节点同样无法通过验证
+    let mut collapsed = p;
+    match collapsed.root.as_mut() {
+        Some(SubProof::NonLeafRoot(n)) => {
+            let child = n.children.get_mut(&'a').unwrap();
+            let hash = child.to_digest();
+            **child = SubProof::from_hash(None, "", hash);
+        }
+        _ => panic!("unexpected trie root"),
+    }
+    assert_eq!(collapsed.root_hash(), root_hash);
+    assert!(collapsed
+        .verify_prefix_accs(&[empty_acc], "app", &PUB_KEY)
+        .is_err());
+}
+
+#[test]
+fn test_prove_absence() {
+    let data = get_dataset();
+    let mut test_trie = TestTrie::new();
+    let mut test_trie_root = TrieRoot::default();
+    set_root_id(&mut test_trie_root, test_trie.root_id);
+    let mut ctx = WriteContext::new(&test_trie, test_trie_root);
+    for (k, id) in &data {
+        ctx.insert(SmolStr::from(k.clone()), ObjId(*id), &PUB_KEY)
+            .unwrap();
+    }
+    let change = ctx.changes();
+    test_trie.apply(change);
+    let root_hash = test_trie
+        .load_node(test_trie.root_id.unwrap())
+        .unwrap()
+        .to_digest();
+
+    // 分别终止于不匹配的叶子、没有对应分支的非叶子节点以及 nibble 不匹配的节点
+    for k in &["fge", "abdeg", "abcf", "ab", "bcf", "b"] {
+        let mut ctx = ReadContext::new(&test_trie, test_trie.root_id);
+        ctx.prove_absence(&SmolStr::from(*k), &PUB_KEY).unwrap();
+        let p = ctx.into_proof();
+        assert_eq!(p.root_hash(), root_hash);
+        p.verify_absence(k).unwrap();
+    }
+    for (k, _) in &data {
+        let mut ctx = ReadContext::new(&test_trie, test_trie.root_id);
+        assert!(ctx
+            .prove_absence(&SmolStr::from(k.clone()), &PUB_KEY)
+            .is_err());
+        // 为其他关键字生成的证明不能说明已有的关键字不存在
+        let mut ctx = ReadContext::new(&test_trie, test_trie.root_id);
+        ctx.prove_absence(&SmolStr::from(format!("{}z", k)), &PUB_KEY)
+            .unwrap();
+        assert!(ctx.into_proof().verify_absence(k).is_err());
+    }
+    // 普通查询的证明不展开终点，不足以说明关键字不存在
+    let mut ctx = ReadContext::new(&test_trie, test_trie.root_id);
+    ctx.query(&SmolStr::from("abdeg"), &PUB_KEY).unwrap();
+    assert!(ctx.into_proof().verify_absence("abdeg").is_err());
+}
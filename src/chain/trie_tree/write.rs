@@ -1,4 +1,4 @@
-use crate::acc::AccPublicKey;
+use crate::acc::{AccCache, AccPublicKey};
 use crate::chain::{
     id_tree::ObjId,
     trie_tree::{
@@ -28,6 +28,7 @@ pub struct WriteContext<'a, L: TrieNodeLoader> {
     node_loader: &'a L,
     apply: Apply,
     outdated: HashSet<TrieNodeId>,
+    acc_cache: Option<&'a AccCache>,
 }
 
 impl<'a, L: TrieNodeLoader> WriteContext<'a, L> {
@@ -39,6 +40,19 @@ impl<'a, L: TrieNodeLoader> WriteContext<'a, L> {
                 nodes: HashMap::new(),
             },
             outdated: HashSet::new(),
+            acc_cache: None,
+        }
+    }
+
+    /// 插入、删除时由集合计算的累加器值从 `cache` 中读取，多个上下文可共享同一个 `cache`
+    pub fn set_acc_cache(&mut self, cache: &'a AccCache) {
+        self.acc_cache = Some(cache);
+    }
+
+    fn acc_of(&self, set: &Set, pk: &AccPublicKey) -> AccValue {
+        match self.acc_cache {
+            Some(cache) => cache.acc_of(set, pk),
+            None => AccValue::from_set(set, pk),
         }
     }
 
@@ -82,7 +96,7 @@ impl<'a, L: TrieNodeLoader> WriteContext<'a, L> {
 
     pub fn insert(&mut self, key: SmolStr, obj_id: ObjId, pk: &AccPublicKey) -> Result<()> {
         let set = Set::from_single_element(obj_id.0);
-        let new_acc = AccValue::from_set(&set, pk);
+        let new_acc = self.acc_of(&set, pk);
         let mut cur_id_opt = self.apply.root.trie_root_id;
         let root_id_opt = cur_id_opt;
         let mut cur_key = key;
@@ -118,7 +132,7 @@ impl<'a, L: TrieNodeLoader> WriteContext<'a, L> {
                                 let leaf_set = &set | &n.data_set;
                                 let sets_inter = (&set) & (&n.data_set);
                                 let leaf_acc =
-                                    new_acc + n.data_set_acc - AccValue::from_set(&sets_inter, pk);
+                                    new_acc + n.data_set_acc - self.acc_of(&sets_inter, pk);
                                 let (leaf_id, leaf_hash) =
                                     self.write_leaf(cur_key, leaf_set, leaf_acc);
                                 temp_nodes.push(TempNode::Leaf(Box::new(Leaf {
@@ -143,7 +157,7 @@ impl<'a, L: TrieNodeLoader> WriteContext<'a, L> {
                                     let non_leaf_root_set = &set | &node_data_set;
                                     let sets_inter = (&set) & (&node_data_set);
                                     let non_leaf_root_acc =
-                                        new_acc + node_acc - AccValue::from_set(&sets_inter, pk);
+                                        new_acc + node_acc - self.acc_of(&sets_inter, pk);
                                     let new_root = TrieNonLeafRootNode::new(
                                         SmolStr::from(&common_key),
                                         non_leaf_root_set,
@@ -243,7 +257,7 @@ impl<'a, L: TrieNodeLoader> WriteContext<'a, L> {
                             let non_leaf_root_set = &set | &n.data_set;
                             let sets_inter = (&set) & (&n.data_set);
                             let non_leaf_root_acc =
-                                new_acc + n.data_set_acc - AccValue::from_set(&sets_inter, pk);
+                                new_acc + n.data_set_acc - self.acc_of(&sets_inter, pk);
                             if common_key == n.nibble {
                                 match n.children.get(&cur_idx) {
                                     Some((id, _digest)) => {
@@ -367,7 +381,7 @@ impl<'a, L: TrieNodeLoader> WriteContext<'a, L> {
 
     pub fn delete(&mut self, key: SmolStr, obj_id: ObjId, pk: &AccPublicKey) -> Result<()> {
         let set = Set::from_single_element(obj_id.0);
-        let delta_acc = AccValue::from_set(&set, pk);
+        let delta_acc = self.acc_of(&set, pk);
         let mut cur_id_opt = self.apply.root.trie_root_id;
         let mut cur_key = key;
 
@@ -1,27 +1,44 @@
+pub mod absence;
+pub mod cache;
+pub mod case;
 pub mod hash;
+pub mod light;
+pub mod retry;
+pub mod trace;
 pub mod vo;
 
 use crate::{
-    acc::{AccPublicKey, AccValue, Set},
+    acc::{
+        AccPublicKey, AccValue, FinalProof, IntermediateProof, Op, PairingBatch, Set, SubgroupBatch,
+    },
     chain::{
+        block::{block_ads_root::BlockADSComponents, BlockHead},
+        id_tree,
         traits::Num,
+        Parameter,
         {block::Height, id_tree::ObjId, object::Object, traits::ReadInterface},
     },
     digest::{Digest, Digestible},
-    utils::{binary_encode, Time},
+    utils::{binary_encoded_len, Time},
 };
-use anyhow::{bail, ensure, Context, Result};
-use hash::{ads_hash, bplus_roots_hash};
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use cache::VerifyCache;
+use hash::{ads_hash, bplus_roots_hash, compute_multi_ads_hash};
 use hash::{id_tree_root_hash, obj_hash};
 use petgraph::{graph::NodeIndex, EdgeDirection::Outgoing, Graph};
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::{BTreeMap, HashMap, HashSet},
+    collections::{hash_map::Entry, BTreeMap, BTreeSet, HashMap, HashSet},
+    iter::{FromIterator, Sum},
     ops::AddAssign,
 };
-use vo::VO;
+use vo::{VoDagContent, VO};
+
+use crate::chain::query::query_dag::{identity_child, DagNode};
 
-use crate::chain::query::query_dag::DagNode;
+/// 一个 VO 及其证明的结果对象
+pub type VoResult<K> = (HashMap<ObjId, Object<K>>, VO<K>);
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct VerifyInfo {
@@ -29,7 +46,7 @@ pub struct VerifyInfo {
     pub verify_time: Time,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct VOSize {
     pub vo_dag_s: usize,
     pub trie_proof_s: usize,
@@ -52,6 +69,15 @@ impl AddAssign for VOSize {
     }
 }
 
+impl Sum for VOSize {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::zero(), |mut acc, s| {
+            acc += s;
+            acc
+        })
+    }
+}
+
 impl VOSize {
     pub fn new(
         vo_dag_s: usize,
@@ -70,39 +96,935 @@ impl VOSize {
             total_s,
         }
     }
+
+    pub fn zero() -> Self {
+        Self::default()
+    }
+
+    /// 逐个字段合并两个 `VOSize`
+    fn zip_with(self, other: Self, f: impl Fn(usize, usize) -> usize) -> Self {
+        Self {
+            vo_dag_s: f(self.vo_dag_s, other.vo_dag_s),
+            trie_proof_s: f(self.trie_proof_s, other.trie_proof_s),
+            id_proof_s: f(self.id_proof_s, other.id_proof_s),
+            cur_id_s: f(self.cur_id_s, other.cur_id_s),
+            merkle_s: f(self.merkle_s, other.merkle_s),
+            total_s: f(self.total_s, other.total_s),
+        }
+    }
+
+    fn categories(&self) -> [(&'static str, usize); 5] {
+        [
+            ("vo_dag", self.vo_dag_s),
+            ("trie_proof", self.trie_proof_s),
+            ("id_proof", self.id_proof_s),
+            ("cur_id", self.cur_id_s),
+            ("merkle", self.merkle_s),
+        ]
+    }
+
+    /// 占用最大的类别名，用于快速判断 VO 的主要开销来源；大小相同时取靠前的类别
+    pub fn largest_category(&self) -> &'static str {
+        let mut largest = ("vo_dag", self.vo_dag_s);
+        for (name, size) in self.categories().iter().skip(1) {
+            if *size > largest.1 {
+                largest = (name, *size);
+            }
+        }
+        largest.0
+    }
+}
+
+/// 一批查询的 VO 大小统计，各字段分别统计（`min` 与 `max` 的不同字段可能来自不同的 VO），
+/// 平均值向下取整；没有样本时全部为零
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VOSizeStats {
+    pub count: usize,
+    pub min: VOSize,
+    pub max: VOSize,
+    pub mean: VOSize,
+    #[serde(skip)]
+    sum: VOSize,
+}
+
+impl VOSizeStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, size: VOSize) {
+        if self.count == 0 {
+            self.min = size;
+            self.max = size;
+        } else {
+            self.min = self.min.zip_with(size, usize::min);
+            self.max = self.max.zip_with(size, usize::max);
+        }
+        self.count += 1;
+        self.sum += size;
+        self.mean = self.sum.zip_with(VOSize::zero(), |s, _| s / self.count);
+    }
+}
+
+impl Extend<VOSize> for VOSizeStats {
+    fn extend<I: IntoIterator<Item = VOSize>>(&mut self, iter: I) {
+        for size in iter {
+            self.push(size);
+        }
+    }
+}
+
+impl FromIterator<VOSize> for VOSizeStats {
+    fn from_iter<I: IntoIterator<Item = VOSize>>(iter: I) -> Self {
+        let mut stats = Self::new();
+        stats.extend(iter);
+        stats
+    }
+}
+
+fn fmt_bytes(bytes: usize) -> String {
+    const KIB: f64 = 1024.0;
+    const MIB: f64 = 1024.0 * 1024.0;
+    let b = bytes as f64;
+    if b >= MIB {
+        format!("{:.1} MiB", b / MIB)
+    } else if b >= KIB {
+        format!("{:.1} KiB", b / KIB)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+/// 按类别输出大小及其占总大小的百分比，如 `vo_dag: 12.3 KiB (45%)`
+impl std::fmt::Display for VOSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (name, size) in self.categories().iter() {
+            let percent = if self.total_s == 0 {
+                0.0
+            } else {
+                *size as f64 * 100.0 / self.total_s as f64
+            };
+            write!(f, "{}: {} ({:.0}%), ", name, fmt_bytes(*size), percent)?;
+        }
+        write!(f, "total: {}", fmt_bytes(self.total_s))
+    }
+}
+
+/// 验证选项，主要是验证之前对输入规模的限制，防止畸形的查询 DAG 或 VO 耗尽验证方的资源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerifyOptions {
+    /// 查询 DAG 与每个 VO 中节点个数的上限
+    pub max_nodes: usize,
+    /// 所有 VO 涉及的不同区块高度个数的上限，即最多读取的区块头个数
+    pub max_heights: usize,
+    /// VO 中累加器值的子群检查以随机组合一次完成（`SubgroupBatch`），而不是逐个检查。
+    /// BN254 上每次检查约有 1/10069 的概率漏过子群之外的 G2 元素，默认关闭
+    pub deferred_subgroup_check: bool,
+    /// 调用方为本次查询指定的编号，记录在验证的 tracing span 中以关联日志，不参与验证
+    pub query_id: Option<[u8; 32]>,
+}
+
+impl Default for VerifyOptions {
+    fn default() -> Self {
+        Self {
+            max_nodes: 1 << 16,
+            max_heights: 1 << 16,
+            deferred_subgroup_check: false,
+            query_id: None,
+        }
+    }
+}
+
+impl VerifyOptions {
+    /// 只计数，不做任何密码学运算；超出限制时返回 `TooLarge`
+    pub fn check<K: Num>(
+        &self,
+        res_contents: &[VoResult<K>],
+        graph: &Graph<DagNode<K>, bool>,
+    ) -> Result<()> {
+        self.check_vos(res_contents.iter().map(|(_, vo)| vo), graph)
+    }
+
+    fn check_vos<'a, K: Num + 'a>(
+        &self,
+        vos: impl Iterator<Item = &'a VO<K>>,
+        graph: &Graph<DagNode<K>, bool>,
+    ) -> Result<()> {
+        let check_nodes = |found: usize| -> Result<()> {
+            if found > self.max_nodes {
+                return Err(TooLarge {
+                    kind: "nodes",
+                    limit: self.max_nodes,
+                    found,
+                }
+                .into());
+            }
+            Ok(())
+        };
+        check_nodes(graph.node_count())?;
+        let mut heights = BTreeSet::new();
+        for vo in vos {
+            let VoDagContent {
+                dag_content,
+                output_sets,
+            } = &vo.vo_dag_content;
+            check_nodes(dag_content.len().max(output_sets.len()))?;
+            heights.extend(vo.referenced_heights());
+            if heights.len() > self.max_heights {
+                return Err(TooLarge {
+                    kind: "heights",
+                    limit: self.max_heights,
+                    found: heights.len(),
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 所有验证路径在 `inner_verify` 之前都要做的检查：按 `opts` 限制规模，
+/// 每个 VO 都带有关键字节点所需的 trie 证明，且累加器值都位于素数阶子群
+pub(crate) fn pre_verify<K: Num>(
+    vos: &[&VO<K>],
+    graph: &Graph<DagNode<K>, bool>,
+    opts: &VerifyOptions,
+) -> Result<()> {
+    opts.check_vos(vos.iter().copied(), graph)?;
+    for vo in vos {
+        vo.check_trie_proofs()?;
+    }
+    check_acc_subgroups(vos.iter().copied(), opts.deferred_subgroup_check)
+}
+
+/// 反序列化 VO 时不检查累加器值的群元素，验证之前确认它们都位于素数阶子群
+fn check_acc_subgroups<'a, K: Num + 'a>(
+    vos: impl Iterator<Item = &'a VO<K>>,
+    deferred: bool,
+) -> Result<()> {
+    let accs = vos
+        .flat_map(|vo| vo.vo_dag_content.dag_content.values())
+        .flat_map(vo::VONode::acc_values);
+    if deferred {
+        let mut batch = SubgroupBatch::new();
+        for acc in accs {
+            batch.add(acc);
+        }
+        batch.verify()
+    } else {
+        for acc in accs {
+            ensure!(
+                acc.in_prime_subgroup(),
+                "Acc value is not in the prime order subgroup"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// 查询 DAG 或 VO 的规模超出 `VerifyOptions` 的限制；调用方可通过 `anyhow::Error::downcast_ref` 识别
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TooLarge {
+    /// 超出限制的项目，`nodes` 或 `heights`
+    pub kind: &'static str,
+    pub limit: usize,
+    pub found: usize,
+}
+
+impl std::fmt::Display for TooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Verification input too large: {} {} exceeds the limit {}",
+            self.found, self.kind, self.limit
+        )
+    }
+}
+
+impl std::error::Error for TooLarge {}
+
+/// 以默认的 `VerifyOptions` 验证
+pub fn verify<K: Num + Serialize, T: ReadInterface<K = K>>(
+    chain: T,
+    res_contents: &[VoResult<K>],
+    graph: &Graph<DagNode<K>, bool>,
+    pk: &AccPublicKey,
+) -> Result<VerifyInfo> {
+    verify_with_options(chain, res_contents, graph, pk, &VerifyOptions::default())
+}
+
+/// 先按 `opts` 检查查询 DAG 与 VO 的规模，超出限制时在任何密码学运算之前返回 `TooLarge`
+pub fn verify_with_options<K: Num + Serialize, T: ReadInterface<K = K>>(
+    chain: T,
+    res_contents: &[VoResult<K>],
+    graph: &Graph<DagNode<K>, bool>,
+    pk: &AccPublicKey,
+    opts: &VerifyOptions,
+) -> Result<VerifyInfo> {
+    verify_with_cache(chain, res_contents, graph, pk, opts, &VerifyCache::new(pk))
+}
+
+/// 与 `verify_with_options` 相同，由集合计算的累加器值从 `cache` 中读取，并保存新计算的值。
+/// 反复验证相互重叠的查询时，多次调用（包括多个线程同时调用）可共享同一个 `cache`
+pub fn verify_with_cache<K: Num + Serialize, T: ReadInterface<K = K>>(
+    chain: T,
+    res_contents: &[VoResult<K>],
+    graph: &Graph<DagNode<K>, bool>,
+    pk: &AccPublicKey,
+    opts: &VerifyOptions,
+    cache: &VerifyCache,
+) -> Result<VerifyInfo> {
+    pre_verify(&vos_of(res_contents), graph, opts)?;
+    verify_contents(
+        &chain,
+        res_contents,
+        graph,
+        cache,
+        pk,
+        opts.query_id.as_ref(),
+    )
+}
+
+fn vos_of<K: Num>(res_contents: &[VoResult<K>]) -> Vec<&VO<K>> {
+    res_contents.iter().map(|(_, vo)| vo).collect()
+}
+
+/// 只验证查询结果的个数而不需要结果对象：与 `verify` 相同地验证 DAG 与各高度的 ads_root，
+/// 由累加器认证过的输出集合得出结果个数并与 `claimed_count` 比较。
+/// 不验证结果对象在 id 树中的成员关系。一个查询拆分为多个 VO 时，各 VO 分别验证，个数相加
+pub fn verify_count<K: Num + Serialize, T: ReadInterface<K = K>>(
+    chain: T,
+    vo: &VO<K>,
+    graph: &Graph<DagNode<K>, bool>,
+    pk: &AccPublicKey,
+    claimed_count: u64,
+) -> Result<VerifyInfo> {
+    let timer = howlong::ProcessCPUTimer::new();
+    pre_verify(&[vo], graph, &VerifyOptions::default())?;
+    vo.check_structure(graph)?;
+    let cache = VerifyCache::new(pk);
+    inner_verify(
+        &chain,
+        None,
+        vo,
+        graph,
+        &cache,
+        &mut HashMap::new(),
+        None,
+        None,
+        pk,
+    )?;
+    let count = vo_output_union(vo).len() as u64;
+    ensure!(
+        count == claimed_count,
+        "Result count not matched! VO outputs {} objects, claimed {}",
+        count,
+        claimed_count
+    );
+    let time = Time::from(timer.elapsed());
+    Ok(VerifyInfo {
+        vo_size: cal_vo_size(vo)?,
+        verify_time: time,
+    })
+}
+
+/// 累加器公钥在某些高度轮换时的验证：`keys` 以公钥开始生效的高度为键，
+/// 每个 VO 使用其各节点所在高度上生效的公钥。同一个 VO 中的集合运算证明只能在一个公钥下验证，
+/// 因此 VO 跨越了不同公钥生效的高度，或某个高度之前没有生效的公钥时直接返回错误
+pub fn verify_with_keys<K: Num + Serialize, T: ReadInterface<K = K>>(
+    chain: T,
+    res_contents: &[VoResult<K>],
+    graph: &Graph<DagNode<K>, bool>,
+    keys: &BTreeMap<Height, AccPublicKey>,
+) -> Result<VerifyInfo> {
+    let timer = howlong::ProcessCPUTimer::new();
+    pre_verify(&vos_of(res_contents), graph, &VerifyOptions::default())?;
+    let mut blk_heads = HashMap::<Height, BlockHead>::new();
+    let mut caches = HashMap::<Height, VerifyCache>::new();
+    for (res_content, vo_content) in res_contents {
+        let (key_height, pk) = select_key(vo_content, keys)?;
+        let cache = caches
+            .entry(key_height)
+            .or_insert_with(|| VerifyCache::new(pk));
+        inner_verify(
+            &chain,
+            Some(res_content),
+            vo_content,
+            graph,
+            cache,
+            &mut blk_heads,
+            None,
+            None,
+            pk,
+        )?;
+    }
+    let time = Time::from(timer.elapsed());
+
+    let vo_size = res_contents
+        .iter()
+        .map(|(_, vo)| cal_vo_size(vo))
+        .sum::<Result<VOSize>>()?;
+    Ok(VerifyInfo {
+        vo_size,
+        verify_time: time,
+    })
+}
+
+/// VO 中各节点与 merkle proof 所在高度上生效的公钥，返回该公钥的生效高度与公钥
+fn select_key<'a, K: Num>(
+    vo: &VO<K>,
+    keys: &'a BTreeMap<Height, AccPublicKey>,
+) -> Result<(Height, &'a AccPublicKey)> {
+    let heights: BTreeSet<Height> = vo
+        .vo_dag_content
+        .dag_content
+        .values()
+        .filter_map(|n| n.blk_height())
+        .chain(vo.merkle_proofs.keys().copied())
+        .collect();
+    let mut selected: Option<(Height, Height, &AccPublicKey)> = None;
+    for height in heights {
+        let (&key_height, pk) = keys.range(..=height).next_back().with_context(|| {
+            format!(
+                "No accumulator public key is effective at height {:?}",
+                height
+            )
+        })?;
+        match selected {
+            None => selected = Some((height, key_height, pk)),
+            Some((first, first_key_height, _)) => ensure!(
+                first_key_height == key_height,
+                "VO spans heights {:?} and {:?} whose accumulator public keys differ \
+                 (effective from {:?} and {:?})",
+                first,
+                height,
+                first_key_height,
+                key_height
+            ),
+        }
+    }
+    selected
+        .map(|(_, key_height, pk)| (key_height, pk))
+        .context("Cannot determine the block heights covered by VO")
+}
+
+/// 一个独立的验证任务：一次查询返回的结果与 VO，以及该查询使用的 DAG
+pub struct VerifyJob<K: Num> {
+    pub res_contents: Vec<VoResult<K>>,
+    pub graph: Graph<DagNode<K>, bool>,
 }
 
-fn inner_verify<K: Num, T: ReadInterface<K = K>>(
+/// 在 rayon 线程池上并发验证多个相互独立的任务，各任务共享 `chain` 与空集合的累加器；
+/// 单个任务验证失败不会影响其他任务，返回结果与 `jobs` 一一对应
+pub fn verify_batch<K: Num + Serialize, T: ReadInterface<K = K> + Sync>(
     chain: &T,
+    jobs: Vec<VerifyJob<K>>,
+    pk: &AccPublicKey,
+) -> Vec<Result<VerifyInfo>> {
+    let cache = VerifyCache::new(pk);
+    jobs.par_iter()
+        .map(|job| {
+            pre_verify(
+                &vos_of(&job.res_contents),
+                &job.graph,
+                &VerifyOptions::default(),
+            )?;
+            verify_contents(chain, &job.res_contents, &job.graph, &cache, pk, None)
+        })
+        .collect()
+}
+
+fn verify_contents<K: Num + Serialize, T: ReadInterface<K = K>>(
+    chain: &T,
+    res_contents: &[VoResult<K>],
+    graph: &Graph<DagNode<K>, bool>,
+    cache: &VerifyCache,
+    pk: &AccPublicKey,
+    query_id: Option<&[u8; 32]>,
+) -> Result<VerifyInfo> {
+    // 高度范围为各 VO 对照区块头验证的区块高度
+    let heights = res_contents
+        .iter()
+        .flat_map(|(_, vo)| vo.merkle_proofs.keys())
+        .map(|h| h.0);
+    let span = info_span!(
+        "verify",
+        query_id = tracing::field::Empty,
+        min_height = tracing::field::Empty,
+        max_height = tracing::field::Empty,
+        vo.total_size = tracing::field::Empty,
+        result_count = tracing::field::Empty,
+        verify_time_ms = tracing::field::Empty,
+    );
+    if let Some(id) = query_id {
+        span.record("query_id", hex::encode(id).as_str());
+    }
+    if let (Some(min), Some(max)) = (heights.clone().min(), heights.max()) {
+        span.record("min_height", min);
+        span.record("max_height", max);
+    }
+    let _guard = span.enter();
+
+    let timer = howlong::ProcessCPUTimer::new();
+    let mut res_obj_hashes = HashSet::<Digest>::new();
+    let mut blk_heads = HashMap::<Height, BlockHead>::new();
+    for (res_content, vo_content) in res_contents {
+        inner_verify(
+            chain,
+            Some(res_content),
+            vo_content,
+            graph,
+            cache,
+            &mut blk_heads,
+            None,
+            None,
+            pk,
+        )?;
+        for obj in res_content.values() {
+            res_obj_hashes.insert(obj.to_digest());
+        }
+    }
+    let elapsed = timer.elapsed();
+    let time = Time::from(elapsed);
+
+    let mut total_vo_size = VOSize::zero();
+    for (_, vo) in res_contents {
+        total_vo_size += cal_vo_size(vo)?;
+    }
+    span.record("vo.total_size", total_vo_size.total_s);
+    span.record("result_count", res_obj_hashes.len());
+    span.record("verify_time_ms", elapsed.real.as_millis() as u64);
+    info!("Query results verified");
+
+    Ok(VerifyInfo {
+        vo_size: total_vo_size,
+        verify_time: time,
+    })
+}
+
+/// 定位模式下记录的一处验证失败
+#[derive(Debug)]
+pub struct VerifyError {
+    /// 出错的 VO 在 `res_contents` 中的下标
+    pub vo_idx: usize,
+    pub error: anyhow::Error,
+}
+
+impl std::fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "VO {}: {:#}", self.vo_idx, self.error)
+    }
+}
+
+/// `verify_locate` 的结果，各列表为空时表示验证通过
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    /// 验证失败的 DAG 节点；因子节点失败而连带失败的父节点不会重复报告
+    pub failed_nodes: Vec<(NodeIndex, VerifyError)>,
+    /// ads_root 不匹配（或缺少相应证明）的区块高度
+    pub failed_heights: Vec<(Height, VerifyError)>,
+    /// 无法归属到节点或高度的失败，如 id 树证明与输出集合不匹配
+    pub others: Vec<VerifyError>,
+}
+
+impl VerifyReport {
+    pub fn is_ok(&self) -> bool {
+        self.failed_nodes.is_empty() && self.failed_heights.is_empty() && self.others.is_empty()
+    }
+}
+
+/// 验证方已由区块头确认的 ads_root 组件与 VO 给出的不一致，在合并计算完整的 ads_root 之前即可判定；
+/// 调用方可通过 `anyhow::Error::downcast_ref` 识别
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdsRootMismatch {
+    pub height: Height,
+    /// 不一致的组件名
+    pub component: &'static str,
+}
+
+impl std::fmt::Display for AdsRootMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "BlockADSRoot verification failed for height {:?}: {} does not match the block head",
+            self.height, self.component
+        )
+    }
+}
+
+impl std::error::Error for AdsRootMismatch {}
+
+/// 查询 DAG 本身的结构错误，在验证任何节点之前由 `validate_dag` 给出；
+/// 调用方可通过 `anyhow::Error::downcast_ref` 识别
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DagError {
+    /// 集合运算节点的子节点个数不正确
+    BadArity {
+        idx: NodeIndex,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl std::fmt::Display for DagError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DagError::BadArity {
+                idx,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Set operation node {:?} must have {} children, found {}",
+                idx, expected, found
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DagError {}
+
+/// 检查查询 DAG 的结构：并集 / 交集 / 差集节点必须恰好有两个子节点。
+/// 验证时按边权确定子节点的顺序，子节点个数不对时后续只能得到难以理解的错误
+pub fn validate_dag<K: Num>(graph: &Graph<DagNode<K>, bool>) -> Result<()> {
+    for idx in graph.node_indices() {
+        if let Some(DagNode::Union(_) | DagNode::Intersec(_) | DagNode::Diff(_)) =
+            graph.node_weight(idx)
+        {
+            let found = graph.neighbors_directed(idx, Outgoing).count();
+            if found != 2 {
+                return Err(DagError::BadArity {
+                    idx,
+                    expected: 2,
+                    found,
+                }
+                .into());
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+thread_local! {
+    /// 测试用：当前线程中为验证 ads_root 合并 multi_ads_hash 的次数
+    pub(crate) static MULTI_ADS_FOLDS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// 验证失败的位置
+pub(crate) enum FailSite {
+    Node(NodeIndex),
+    Height(Height),
+    Other,
+}
+
+/// 定位模式下记录失败并继续验证，否则直接返回错误
+fn record_failure(
+    failures: &mut Option<&mut Vec<(FailSite, anyhow::Error)>>,
+    site: FailSite,
+    res: Result<()>,
+) -> Result<()> {
+    match (res, failures) {
+        (Ok(()), _) => Ok(()),
+        (Err(e), Some(failures)) => {
+            failures.push((site, e));
+            Ok(())
+        }
+        (Err(e), None) => Err(e),
+    }
+}
+
+/// 调试用的验证：遇到错误时不立即返回，而是继续检查其余独立的节点，
+/// 报告所有验证失败的节点与区块高度。比 `verify` 慢，只用于定位被篡改的位置
+pub fn verify_locate<K: Num + Serialize, T: ReadInterface<K = K>>(
+    chain: T,
+    res_contents: &[VoResult<K>],
+    graph: &Graph<DagNode<K>, bool>,
+    pk: &AccPublicKey,
+) -> Result<VerifyReport> {
+    pre_verify(&vos_of(res_contents), graph, &VerifyOptions::default())?;
+    let cache = VerifyCache::new(pk);
+    let mut blk_heads = HashMap::<Height, BlockHead>::new();
+    let mut report = VerifyReport::default();
+    for (vo_idx, (res_content, vo_content)) in res_contents.iter().enumerate() {
+        let mut failures = Vec::new();
+        inner_verify(
+            &chain,
+            Some(res_content),
+            vo_content,
+            graph,
+            &cache,
+            &mut blk_heads,
+            Some(&mut failures),
+            None,
+            pk,
+        )?;
+        let failed_idxs: HashSet<NodeIndex> = failures
+            .iter()
+            .filter_map(|(site, _)| match site {
+                FailSite::Node(idx) => Some(*idx),
+                _ => None,
+            })
+            .collect();
+        for (site, error) in failures {
+            let error = VerifyError { vo_idx, error };
+            match site {
+                FailSite::Node(idx) => {
+                    // 子节点已失败时，父节点的失败只是其连带结果
+                    if !graph
+                        .neighbors_directed(idx, Outgoing)
+                        .any(|c| failed_idxs.contains(&c))
+                    {
+                        report.failed_nodes.push((idx, error));
+                    }
+                }
+                FailSite::Height(height) => report.failed_heights.push((height, error)),
+                FailSite::Other => report.others.push(error),
+            }
+        }
+    }
+    Ok(report)
+}
+
+pub(crate) fn cal_vo_size<K: Num + Serialize>(vo: &VO<K>) -> Result<VOSize> {
+    let vo_dag_s = binary_encoded_len(&vo.vo_dag_content)?;
+    let trie_proof_s = binary_encoded_len(&vo.trie_proofs)?;
+    let id_proof_s = binary_encoded_len(&vo.id_tree_proof)?;
+    let cur_id_s = binary_encoded_len(&vo.cur_obj_id)?;
+    let merkle_s = binary_encoded_len(&vo.merkle_proofs)?;
+    let total_s = vo.estimated_size()?;
+    Ok(VOSize::new(
+        vo_dag_s,
+        trie_proof_s,
+        id_proof_s,
+        cur_id_s,
+        merkle_s,
+        total_s,
+    ))
+}
+
+// 证明中的集合运算由证明方给出，须与查询 DAG 节点要求的运算一致
+fn ensure_op(found: Op, expected: Op) -> Result<()> {
+    ensure!(
+        found == expected,
+        "Set operation proof is for {:?}, but the query requires {:?}",
+        found,
+        expected
+    );
+    Ok(())
+}
+
+// 按顺序执行的证明链中的每一步都须为 `expected` 运算
+fn ensure_fold_ops(
+    proofs: &[(AccValue, IntermediateProof)],
+    final_proof: Option<&FinalProof>,
+    expected: Op,
+) -> Result<()> {
+    for (_, proof) in proofs {
+        ensure_op(proof.op(), expected)?;
+    }
+    if let Some(proof) = final_proof {
+        ensure_op(proof.op(), expected)?;
+    }
+    Ok(())
+}
+
+// `batch` 不为空时证明中的配对等式只加入批量验证，由 `inner_verify` 最后统一检查
+fn verify_inter_proof(
+    proof: &IntermediateProof,
+    lhs_acc: &AccValue,
+    rhs_acc: &AccValue,
+    result_acc: &AccValue,
+    pk: &AccPublicKey,
+    batch: Option<&mut PairingBatch>,
+) -> Result<()> {
+    match batch {
+        Some(batch) => proof.verify_batched(lhs_acc, rhs_acc, result_acc, pk, batch),
+        None => proof.verify(lhs_acc, rhs_acc, result_acc, pk),
+    }
+}
+
+fn verify_final_proof(
+    proof: &FinalProof,
+    lhs_acc: &AccValue,
+    rhs_acc: &AccValue,
+    result_set: &Set,
+    pk: &AccPublicKey,
+    batch: Option<&mut PairingBatch>,
+) -> Result<()> {
+    match batch {
+        Some(batch) => proof.verify_batched(lhs_acc, rhs_acc, result_set, pk, batch),
+        None => proof.verify(lhs_acc, rhs_acc, result_set, pk),
+    }
+}
+
+// 验证按顺序逐个执行集合运算得到的证明链，`output_set` 为该节点作为输出节点时的结果集合
+fn verify_fold_set_operation(
+    accs: &[AccValue],
+    proofs: &[(AccValue, IntermediateProof)],
+    final_proof: Option<&FinalProof>,
+    output_set: Option<&Set>,
+    cache: &VerifyCache,
+    pk: &AccPublicKey,
+    mut batch: Option<&mut PairingBatch>,
+) -> Result<()> {
+    let op_num = accs.len().saturating_sub(1);
+    let inter_num = if final_proof.is_some() {
+        op_num.saturating_sub(1)
+    } else {
+        op_num
+    };
+    ensure!(
+        proofs.len() == inter_num,
+        "Invalid number of intermediate proofs"
+    );
+    let mut acc = accs.first().context("Empty operand list")?;
+    for ((res_acc, inter_proof), rhs_acc) in proofs.iter().zip(accs.iter().skip(1)) {
+        verify_inter_proof(inter_proof, acc, rhs_acc, res_acc, pk, batch.as_deref_mut())?;
+        acc = res_acc;
+    }
+    match (final_proof, output_set) {
+        (Some(final_proof), Some(final_set)) => {
+            let rhs_acc = accs.get(inter_num + 1).context("Invalid operand list")?;
+            verify_final_proof(final_proof, acc, rhs_acc, final_set, pk, batch)?;
+        }
+        (Some(_), None) => bail!("Cannot find set in VO output sets"),
+        (None, Some(final_set)) => ensure!(
+            *acc == cache.acc_of(final_set, pk),
+            "Output set does not match its acc value"
+        ),
+        (None, None) => {}
+    }
+    Ok(())
+}
+
+/// 验证携带累加器值的输出节点（单个叶子节点，或空集处理后没有证明的运算节点）的输出集合；
+/// 输出集合为空时直接与空集累加器比较，证明查询结果确实为空
+fn verify_output_acc<K: Num>(
+    node: Option<&vo::VONode<K>>,
+    final_set: &Set,
+    cache: &VerifyCache,
+    pk: &AccPublicKey,
+) -> Result<()> {
+    let node = node.context("Cannot find output node in VO")?;
+    match node {
+        // 这些节点已在 DAG 验证中与输出集合比较过
+        vo::VONode::FinalUnion(_)
+        | vo::VONode::FinalIntersec(_)
+        | vo::VONode::FinalDiff(_)
+        | vo::VONode::KeywordConjunction(_)
+        | vo::VONode::KeywordPrefix(_)
+        | vo::VONode::Identity(_) => Ok(()),
+        _ => {
+            let acc = node.get_acc()?;
+            ensure!(
+                *acc == cache.acc_of(final_set, pk),
+                "Output set does not match its acc value"
+            );
+            Ok(())
+        }
+    }
+}
+
+/// VO 中所有输出集合的并集，即查询结果的对象 id
+fn vo_output_union<K: Num>(vo: &VO<K>) -> Set {
+    let mut outputs = Set::new();
+    for set in vo.vo_dag_content.output_sets.values() {
+        outputs = &outputs | set;
+    }
+    outputs
+}
+
+/// 并行验证各结果对象在 id 树中的成员关系；各对象共享同一个只读的 id 树证明，
+/// 返回结果的顺序与 `res_content` 的迭代顺序一致
+pub(crate) fn verify_id_tree_members<K: Num>(
+    id_tree_proof: &id_tree::proof::Proof,
     res_content: &HashMap<ObjId, Object<K>>,
+    max_id_num: u16,
+    id_tree_fanout: u8,
+) -> Vec<Result<()>> {
+    res_content
+        .par_iter()
+        .map(|(id, obj)| {
+            id.to_internal_id_checked(max_id_num.into())?;
+            let target_hash = obj_hash(obj, id);
+            id_tree_proof.verify_value(target_hash, *id, max_id_num, id_tree_fanout)
+        })
+        .collect()
+}
+
+/// 不经过完整的查询验证，单独验证一个对象是 id 树中编号为 `id` 的成员：
+/// 由对象计算 `obj_hash` 并沿 `proof` 重建 id 树根节点的哈希，与 `expected_root` 比较。
+/// `expected_root` 是 id 树根节点的哈希（`IdTreeRoot::get_id_tree_root_hash`），
+/// 区块承诺的 `id_tree_root_hash` 在其上还绑定了当前对象 id 与扇出
+pub fn verify_object_membership<K: Num>(
+    obj: &Object<K>,
+    id: ObjId,
+    proof: &id_tree::proof::Proof,
+    param: &Parameter,
+    expected_root: Digest,
+) -> Result<()> {
+    id.to_internal_id_checked(param.max_id_num.into())?;
+    let target_hash = obj_hash(obj, &id);
+    proof.verify_value(target_hash, id, param.max_id_num, param.id_tree_fanout)?;
+    let root = proof.root_hash();
+    ensure!(
+        root.ct_eq(&expected_root),
+        "Id tree root hash not matched! Computed {:?}, expected {:?}",
+        root,
+        expected_root
+    );
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn inner_verify<K: Num, T: ReadInterface<K = K>>(
+    chain: &T,
+    res_content: Option<&HashMap<ObjId, Object<K>>>,
     vo_content: &VO<K>,
     graph: &Graph<DagNode<K>, bool>,
+    cache: &VerifyCache,
+    blk_heads: &mut HashMap<Height, BlockHead>,
+    mut failures: Option<&mut Vec<(FailSite, anyhow::Error)>>,
+    mut ads_root_checks: Option<&mut Vec<(Height, Digest, Digest)>>,
     pk: &AccPublicKey,
 ) -> Result<()> {
+    validate_dag(graph)?;
+    let empty_acc = cache.empty_acc();
     // verify dag, including range query and set operation
-    let empty_acc = AccValue::from_set(&Set::new(), pk);
     let vo_dag_idxs = graph.node_indices();
     let vo_dag_content = &vo_content.vo_dag_content.dag_content;
     let vo_output_sets = &vo_content.vo_dag_content.output_sets;
     let mut time_win_map = HashMap::<Height, u16>::new();
     let mut bplus_roots = HashMap::<Height, (u16, BTreeMap<u8, Digest>)>::new();
     let trie_proofs = &vo_content.trie_proofs;
-    for idx in vo_dag_idxs {
+    // 由 id 集合证明对照区块头确认过的各高度的 id_set_root_hash
+    let mut id_set_roots = HashMap::<Height, Digest>::new();
+    // 同一区块的关键字验证汇总后一次性批量验证
+    let mut trie_checks = HashMap::<Height, Vec<(NodeIndex, &str, AccValue)>>::new();
+    // 所有集合运算证明中的配对等式汇总后一次性随机批量验证；定位模式下仍逐个节点验证
+    let mut batch = if failures.is_none() {
+        Some(PairingBatch::new())
+    } else {
+        None
+    };
+    let mut verify_node = |idx: NodeIndex| -> Result<()> {
         if let Some(content) = vo_dag_content.get(&idx) {
             if let Some(node) = graph.node_weight(idx) {
                 match node {
-                    DagNode::Range(n) => match content {
+                    DagNode::Range(_) | DagNode::MultiInterval(_) => match content {
                         vo::VONode::Range(r_n) => {
+                            let (dim, intervals) = node.bplus_query()?;
                             let blk_height = r_n.blk_height;
                             time_win_map.insert(blk_height, r_n.win_size);
-                            let res_digest = r_n.proof.verify(n.range, r_n.acc, pk)?;
+                            let res_digest = r_n.proof.verify(&intervals, r_n.acc, pk)?;
                             match bplus_roots.get_mut(&blk_height) {
                                 Some((_win_size, btree_map)) => {
-                                    btree_map.insert(n.dim, res_digest);
+                                    btree_map.insert(dim, res_digest);
                                 }
                                 None => {
                                     let mut btree_map = BTreeMap::<u8, Digest>::new();
-                                    btree_map.insert(n.dim, res_digest);
+                                    btree_map.insert(dim, res_digest);
                                     bplus_roots.insert(blk_height, (r_n.win_size, btree_map));
                                 }
                             }
@@ -113,12 +1035,68 @@ fn inner_verify<K: Num, T: ReadInterface<K = K>>(
                     },
                     DagNode::Keyword(n) => match content {
                         vo::VONode::Keyword(k_n) => {
+                            let blk_height = k_n.blk_height;
+                            time_win_map.insert(blk_height, k_n.win_size);
+                            trie_checks
+                                .entry(blk_height)
+                                .or_default()
+                                .push((idx, &n.keyword, k_n.acc));
+                        }
+                        _ => {
+                            bail!("mismatched type");
+                        }
+                    },
+                    DagNode::KeywordSet(n) => match content {
+                        vo::VONode::KeywordConjunction(k_n) => {
+                            let blk_height = k_n.blk_height;
+                            time_win_map.insert(blk_height, k_n.win_size);
+                            ensure!(
+                                k_n.accs.len() == n.keywords.len(),
+                                "Trie verification: number of acc values and keywords not matched!"
+                            );
+                            trie_checks.entry(blk_height).or_default().extend(
+                                n.keywords
+                                    .iter()
+                                    .zip(k_n.accs.iter())
+                                    .map(|(k, acc)| (idx, k.as_str(), *acc)),
+                            );
+                            ensure_fold_ops(
+                                &k_n.proofs,
+                                k_n.final_proof.as_ref(),
+                                Op::Intersection,
+                            )?;
+                            verify_fold_set_operation(
+                                &k_n.accs,
+                                &k_n.proofs,
+                                k_n.final_proof.as_ref(),
+                                vo_output_sets.get(&idx),
+                                cache,
+                                pk,
+                                batch.as_mut(),
+                            )?;
+                        }
+                        _ => {
+                            bail!("mismatched type");
+                        }
+                    },
+                    DagNode::KeywordPrefix(n) => match content {
+                        vo::VONode::KeywordPrefix(k_n) => {
                             let blk_height = k_n.blk_height;
                             time_win_map.insert(blk_height, k_n.win_size);
                             let proof = trie_proofs
                                 .get(&blk_height)
                                 .context("Inside dag: cannot find trie proof in VO")?;
-                            proof.verify_acc(k_n.acc, &n.keyword, pk)?;
+                            proof.verify_prefix_accs(&k_n.accs, &n.prefix, pk)?;
+                            ensure_fold_ops(&k_n.proofs, k_n.final_proof.as_ref(), Op::Union)?;
+                            verify_fold_set_operation(
+                                &k_n.accs,
+                                &k_n.proofs,
+                                k_n.final_proof.as_ref(),
+                                vo_output_sets.get(&idx),
+                                cache,
+                                pk,
+                                batch.as_mut(),
+                            )?;
                         }
                         _ => {
                             bail!("mismatched type");
@@ -128,6 +1106,25 @@ fn inner_verify<K: Num, T: ReadInterface<K = K>>(
                         vo::VONode::BlkRt(br_n) => {
                             let blk_height = br_n.blk_height;
                             time_win_map.insert(blk_height, br_n.win_size);
+                            // 时间窗口的全集由窗口内各区块的对象 id 集合求并得到
+                            let mut universe = Set::new();
+                            for height in vo::time_win_heights(blk_height, br_n.win_size) {
+                                let proof = br_n
+                                    .id_set_proofs
+                                    .get(&height)
+                                    .context("Cannot find id set proof in VO")?;
+                                let blk_head = match blk_heads.entry(height) {
+                                    Entry::Occupied(e) => e.into_mut(),
+                                    Entry::Vacant(e) => e.insert(chain.read_block_head(height)?),
+                                };
+                                let (id_set, id_set_root) = proof.verify(blk_head)?;
+                                id_set_roots.insert(height, id_set_root);
+                                universe = &universe | &id_set;
+                            }
+                            ensure!(
+                                br_n.acc == cache.acc_of(&universe, pk),
+                                "BlkRt acc does not match the id sets of its time window"
+                            );
                         }
                         _ => {
                             bail!("mismatched type");
@@ -165,8 +1162,14 @@ fn inner_verify<K: Num, T: ReadInterface<K = K>>(
                             let child2 = vo_dag_content.get(child_idx2).context(
                                 "Cannot find the first child node of intermediate union",
                             )?;
-                            u_n.proof
-                                .verify(child1.get_acc()?, child2.get_acc()?, &u_n.acc, pk)?;
+                            verify_inter_proof(
+                                &u_n.proof,
+                                child1.get_acc()?,
+                                child2.get_acc()?,
+                                &u_n.acc,
+                                pk,
+                                batch.as_mut(),
+                            )?;
                         }
                         vo::VONode::FinalUnion(u_n) => {
                             let mut child_idxs = Vec::<NodeIndex>::new();
@@ -202,11 +1205,13 @@ fn inner_verify<K: Num, T: ReadInterface<K = K>>(
                             let final_set = vo_output_sets
                                 .get(&idx)
                                 .context("Cannot find set in VO output sets")?;
-                            u_n.proof.verify(
+                            verify_final_proof(
+                                &u_n.proof,
                                 child1.get_acc()?,
                                 child2.get_acc()?,
                                 final_set,
                                 pk,
+                                batch.as_mut(),
                             )?;
                         }
                         _ => {
@@ -246,10 +1251,14 @@ fn inner_verify<K: Num, T: ReadInterface<K = K>>(
                                 "Cannot find the second child node of intermediate intersection",
                             )?;
                                 ensure!(
-                                    *child2.get_acc()? == empty_acc,
+                                    *child2.get_acc()? == *empty_acc,
                                     "The child of intersec should be empty"
                                 );
-                                continue;
+                                ensure!(
+                                    i_n.acc == *empty_acc,
+                                    "The result of intersec should be empty"
+                                );
+                                return Ok(());
                             };
                             let acc2 = if let Some(child2) = vo_dag_content.get(child_idx2) {
                                 child2.get_acc()?
@@ -258,14 +1267,20 @@ fn inner_verify<K: Num, T: ReadInterface<K = K>>(
                                     "Cannot find the first child node of intermediate intersection",
                                 )?;
                                 ensure!(
-                                    *child1.get_acc()? == empty_acc,
+                                    *child1.get_acc()? == *empty_acc,
                                     "The child of intersec should be empty"
                                 );
-                                continue;
+                                ensure!(
+                                    i_n.acc == *empty_acc,
+                                    "The result of intersec should be empty"
+                                );
+                                return Ok(());
                             };
-                            i_n.proof
-                                .context("Intermediate intersection proof does not exist")?
-                                .verify(acc1, acc2, &i_n.acc, pk)?;
+                            let proof = i_n
+                                .proof
+                                .as_ref()
+                                .context("Intermediate intersection proof does not exist")?;
+                            verify_inter_proof(proof, acc1, acc2, &i_n.acc, pk, batch.as_mut())?;
                         }
                         vo::VONode::FinalIntersec(i_n) => {
                             let mut child_idxs = Vec::<NodeIndex>::new();
@@ -301,11 +1316,13 @@ fn inner_verify<K: Num, T: ReadInterface<K = K>>(
                             let final_set = vo_output_sets
                                 .get(&idx)
                                 .context("Cannot find set in VO output sets")?;
-                            i_n.proof.verify(
+                            verify_final_proof(
+                                &i_n.proof,
                                 child1.get_acc()?,
                                 child2.get_acc()?,
                                 final_set,
                                 pk,
+                                batch.as_mut(),
                             )?;
                         }
                         _ => {
@@ -345,18 +1362,24 @@ fn inner_verify<K: Num, T: ReadInterface<K = K>>(
                                     "Cannot find the first child node of intermediate difference",
                                 )?;
                                 ensure!(
-                                    *child1.get_acc()? == empty_acc,
+                                    *child1.get_acc()? == *empty_acc,
                                     "The child of diff should be empty"
                                 );
-                                continue;
+                                ensure!(
+                                    d_n.acc == *empty_acc,
+                                    "The result of diff should be empty"
+                                );
+                                return Ok(());
                             };
                             let child1 = vo_dag_content.get(child_idx1).context(
                                 "Cannot find the first child node of intermediate difference",
                             )?;
                             let acc1 = child1.get_acc()?;
-                            d_n.proof
-                                .context("Intermediate difference proof does not exist")?
-                                .verify(acc1, acc2, &d_n.acc, pk)?;
+                            let proof = d_n
+                                .proof
+                                .as_ref()
+                                .context("Intermediate difference proof does not exist")?;
+                            verify_inter_proof(proof, acc1, acc2, &d_n.acc, pk, batch.as_mut())?;
                         }
                         vo::VONode::FinalDiff(d_n) => {
                             let mut child_idxs = Vec::<NodeIndex>::new();
@@ -392,11 +1415,72 @@ fn inner_verify<K: Num, T: ReadInterface<K = K>>(
                             let final_set = vo_output_sets
                                 .get(&idx)
                                 .context("Cannot find set in VO output sets")?;
-                            d_n.proof.verify(
+                            verify_final_proof(
+                                &d_n.proof,
                                 child1.get_acc()?,
                                 child2.get_acc()?,
                                 final_set,
                                 pk,
+                                batch.as_mut(),
+                            )?;
+                        }
+                        _ => {
+                            bail!("mismatched type");
+                        }
+                    },
+                    DagNode::Identity(_) => match content {
+                        vo::VONode::Identity(id_n) => {
+                            let child_idx = identity_child(graph, idx)?;
+                            let child = vo_dag_content
+                                .get(&child_idx)
+                                .context("Cannot find the child node of identity")?;
+                            ensure!(
+                                *child.get_acc()? == id_n.acc,
+                                "Identity acc does not match its child"
+                            );
+                            if let Some(final_set) = vo_output_sets.get(&idx) {
+                                ensure!(
+                                    id_n.acc == cache.acc_of(final_set, pk),
+                                    "Output set does not match its acc value"
+                                );
+                            }
+                        }
+                        _ => {
+                            bail!("mismatched type");
+                        }
+                    },
+                    DagNode::HeightFilter(n) => match content {
+                        vo::VONode::HeightFilter(h_n) => {
+                            let blk_height = h_n.blk_height;
+                            time_win_map.insert(blk_height, h_n.win_size);
+                            let child_idx = identity_child(graph, idx)?;
+                            let child = vo_dag_content
+                                .get(&child_idx)
+                                .context("Cannot find the child node of height filter")?;
+                            // 过滤集合由区间内各区块的对象 id 集合求并得到，不信任查询方给出的集合
+                            let mut filter_set = Set::new();
+                            for height in n.heights(blk_height, h_n.win_size) {
+                                let proof = h_n
+                                    .id_set_proofs
+                                    .get(&height)
+                                    .context("Cannot find id set proof in VO")?;
+                                let blk_head = match blk_heads.entry(height) {
+                                    Entry::Occupied(e) => e.into_mut(),
+                                    Entry::Vacant(e) => e.insert(chain.read_block_head(height)?),
+                                };
+                                let (id_set, id_set_root) = proof.verify(blk_head)?;
+                                id_set_roots.insert(height, id_set_root);
+                                filter_set = &filter_set | &id_set;
+                            }
+                            let filter_acc = cache.acc_of(&filter_set, pk);
+                            ensure_op(h_n.proof.op(), Op::Intersection)?;
+                            verify_inter_proof(
+                                &h_n.proof,
+                                child.get_acc()?,
+                                &filter_acc,
+                                &h_n.acc,
+                                pk,
+                                batch.as_mut(),
                             )?;
                         }
                         _ => {
@@ -406,21 +1490,57 @@ fn inner_verify<K: Num, T: ReadInterface<K = K>>(
                 }
             }
         }
+        Ok(())
+    };
+    for idx in vo_dag_idxs {
+        let res = verify_node(idx);
+        record_failure(&mut failures, FailSite::Node(idx), res)?;
+    }
+    if let Some(batch) = batch {
+        batch.verify()?;
+    }
+    for (idx, final_set) in vo_output_sets {
+        let res = verify_output_acc(vo_dag_content.get(idx), final_set, cache, pk);
+        record_failure(&mut failures, FailSite::Node(*idx), res)?;
+    }
+    for (blk_height, checks) in &trie_checks {
+        let proof = match trie_proofs.get(blk_height) {
+            Some(proof) => proof,
+            None => {
+                let res = Err(anyhow!("Inside dag: cannot find trie proof in VO"));
+                record_failure(&mut failures, FailSite::Height(*blk_height), res)?;
+                continue;
+            }
+        };
+        let items: Vec<(&str, AccValue)> = checks.iter().map(|(_, k, acc)| (*k, *acc)).collect();
+        let res = proof.verify_acc_batch(&items, pk);
+        if res.is_err() && failures.is_some() {
+            // 定位模式下逐个验证，找出累加器不匹配的节点
+            for (idx, keyword, acc) in checks {
+                let res = proof.verify_acc(*acc, keyword, pk);
+                record_failure(&mut failures, FailSite::Node(*idx), res)?;
+            }
+        } else {
+            res?;
+        }
     }
 
     // verify id tree
-     // verify id tree
     let id_tree_proof = &vo_content.id_tree_proof;
     let param = chain.get_parameter()?;
     let max_id_num = param.max_id_num;
     let id_tree_fanout = param.id_tree_fanout;
-    for (id, obj) in res_content {
-        let target_hash = obj_hash(obj, id);
-        id_tree_proof.verify_value(target_hash, *id, max_id_num, id_tree_fanout)?;
+    if let Some(res_content) = res_content {
+        for res in verify_id_tree_members(id_tree_proof, res_content, max_id_num, id_tree_fanout) {
+            record_failure(&mut failures, FailSite::Other, res)?;
+        }
     }
     let id_tree_root_node_hash = id_tree_proof.root_hash();
-    let id_tree_root_hash =
-        id_tree_root_hash(vo_content.cur_obj_id.to_digest(), id_tree_root_node_hash);
+    let id_tree_root_hash = id_tree_root_hash(
+        vo_content.cur_obj_id.to_digest(),
+        id_tree_fanout,
+        id_tree_root_node_hash,
+    );
 
     // 【创新点1】使用 BlockADSComponents 进行结构化验证
     //
@@ -430,93 +1550,241 @@ fn inner_verify<K: Num, T: ReadInterface<K = K>>(
     // 3. 计算 compute_root() 得到统一承诺
     // 4. 与区块头中的 ads_root 比较
     let merkle_proofs = &vo_content.merkle_proofs;
-    for (height, time_win) in time_win_map {
-        if let Some((_win_size, bplus_hashes)) = bplus_roots.get_mut(&height) {
-            let merkle_proof = merkle_proofs
-                .get(&height)
-                .context("Cannot find merkle proof")?;
-
-            // Step 1: 收集并计算 B+树根哈希
-            let extra_bplus_hashes = &merkle_proof.extra_bplus_rt_hashes;
-            for (d, h) in extra_bplus_hashes {
-                bplus_hashes.insert(*d, *h);
+    let mut verify_ads_root = |height: Height, time_win: u16| -> Result<()> {
+        // 没有范围查询节点时，各维度的 B+ 树根哈希全部由 merkle proof 给出
+        let (_win_size, bplus_hashes) = bplus_roots
+            .entry(height)
+            .or_insert_with(|| (time_win, BTreeMap::new()));
+        let merkle_proof = merkle_proofs
+            .get(&height)
+            .context("Cannot find merkle proof")?;
+        // 先比较最廉价的组件：已确认的 id_set_root_hash 不一致时 ads_root 必然不匹配，
+        // 无需再合并各时间窗口的 ads 哈希
+        if let Some(id_set_root) = id_set_roots.get(&height) {
+            if !id_set_root.ct_eq(&merkle_proof.id_set_root_hash) {
+                return Err(AdsRootMismatch {
+                    height,
+                    component: "id_set_root_hash",
+                }
+                .into());
             }
-            let bplus_root_hash = bplus_roots_hash(bplus_hashes.iter());
+        }
+
+        // Step 1: 收集并计算 B+树根哈希
+        let extra_bplus_hashes = &merkle_proof.extra_bplus_rt_hashes;
+        for (d, h) in extra_bplus_hashes {
+            bplus_hashes.insert(*d, *h);
+        }
+        let bplus_root_hash = bplus_roots_hash(bplus_hashes.iter());
 
-            // Step 2: 获取 Trie 根哈希
-            let trie_proof = trie_proofs.get(&height).context("Cannot find trie proof")?;
-            let trie_root_hash = trie_proof.root_hash();
+        // Step 2: 获取 Trie 根哈希
+        let trie_proof = trie_proofs.get(&height).context("Cannot find trie proof")?;
+        let trie_root_hash = trie_proof.root_hash();
 
-            // Step 3: 计算单个时间窗口的 ads_hash
-            let single_ads_hash = ads_hash(bplus_root_hash, trie_root_hash);
+        // Step 3: 计算单个时间窗口的 ads_hash
+        let single_ads_hash = ads_hash(bplus_root_hash, trie_root_hash);
 
-            // Step 4: 计算完整的 multi_ads_hash（合并所有时间窗口）
-            let mut ads_hashes = merkle_proof.ads_hashes.clone();
-            ads_hashes.insert(time_win, single_ads_hash);
-            let multi_ads_hash = compute_multi_ads_hash(ads_hashes.iter());
+        // Step 4: 计算完整的 multi_ads_hash（合并所有时间窗口）
+        #[cfg(test)]
+        MULTI_ADS_FOLDS.with(|n| n.set(n.get() + 1));
+        let mut ads_hashes = merkle_proof.ads_hashes.clone();
+        ads_hashes.insert(time_win, single_ads_hash);
+        let multi_ads_hash = compute_multi_ads_hash(ads_hashes.iter());
 
-            // Step 5: 确定 id_tree_root_hash
-            let id_root_hash = match merkle_proof.id_tree_root_hash {
-                Some(d) => d,
-                None => id_tree_root_hash,
-            };
+        // Step 5: 确定 id_tree_root_hash
+        let id_root_hash = match merkle_proof.id_tree_root_hash {
+            Some(d) => d,
+            None => id_tree_root_hash,
+        };
 
-            // 【创新点1】Step 6: 使用 BlockADSComponents 进行结构化验证
-            let computed_components = BlockADSComponents::new(
-                merkle_proof.id_set_root_hash,
-                id_root_hash,
-                multi_ads_hash,
-            );
-            let computed_ads_root = computed_components.compute_root();
+        // 【创新点1】Step 6: 使用 BlockADSComponents 进行结构化验证
+        let computed_components =
+            BlockADSComponents::new(merkle_proof.id_set_root_hash, id_root_hash, multi_ads_hash);
+        let computed_ads_root = computed_components.compute_root();
 
-            // Step 7: 获取区块头中的 BlockADSRoot 并验证
-            let expect_ads_root = chain.read_block_head(height)?.get_ads_root();
-            ensure!(
-                computed_ads_root == expect_ads_root,
-                "BlockADSRoot verification failed for height {:?}!\n\
-                 Computed components: id_set={:?}, id_tree={:?}, multi_ads={:?}\n\
-                 Computed root: {:?}\n\
-                 Expected root: {:?}",
-                height,
-                merkle_proof.id_set_root_hash,
-                id_root_hash,
-                multi_ads_hash,
-                computed_ads_root,
-                expect_ads_root
-            );
+        // Step 7: 获取区块头中的 BlockADSRoot 并验证
+        // 同一次验证中每个高度的区块头只读取一次
+        let blk_head = match blk_heads.entry(height) {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(chain.read_block_head(height)?),
+        };
+        let expect_ads_root = blk_head.get_ads_root();
+        if let Some(checks) = ads_root_checks.as_mut() {
+            checks.push((height, computed_ads_root, expect_ads_root));
         }
+        ensure!(
+            computed_ads_root.ct_eq(&expect_ads_root),
+            "BlockADSRoot verification failed for height {:?}!\n\
+             Computed components: id_set={:?}, id_tree={:?}, multi_ads={:?}\n\
+             Computed root: {:?}\n\
+             Expected root: {:?}",
+            height,
+            merkle_proof.id_set_root_hash,
+            id_root_hash,
+            multi_ads_hash,
+            computed_ads_root,
+            expect_ads_root
+        );
+        Ok(())
+    };
+    for (height, time_win) in time_win_map {
+        let res = verify_ads_root(height, time_win);
+        record_failure(&mut failures, FailSite::Height(height), res)?;
     }
 
-    let mut vo_outputs = Set::new();
-    for set in vo_output_sets.values() {
-        vo_outputs = &vo_outputs | set;
-    }
-    let mut res_outputs: Set = Set::new();
-    for key in res_content.keys() {
-        res_outputs.insert(key.0);
-    }
-    ensure!(
-        vo_outputs == res_outputs,
-        "VO outputs do not match results!"
-    );
-    info!(
-        "Total number of result object returned: {}",
-        res_obj_hashes.len()
-    );
-
-    let mut total_vo_size = VOSize::new(0, 0, 0, 0, 0, 0);
-    for (_, vo) in res_contents {
-        total_vo_size += cal_vo_size(vo)?;
+    // 只验证结果个数时没有结果对象，输出集合的大小由调用方比较
+    if let Some(res_content) = res_content {
+        let vo_outputs = vo_output_union(vo_content);
+        let mut res_outputs: Set = Set::new();
+        for key in res_content.keys() {
+            res_outputs.insert(key.0);
+        }
+        let res = if vo_outputs == res_outputs {
+            Ok(())
+        } else {
+            Err(anyhow!("VO outputs do not match results!"))
+        };
+        record_failure(&mut failures, FailSite::Other, res)?;
     }
 
-    Ok(VerifyInfo {
-        vo_size: total_vo_size,
-        verify_time: time,
-    })
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
+    #[test]
+    fn test_vo_node_get_acc() {
+        use super::vo::*;
+        use crate::{
+            acc::{compute_set_operation_final, compute_set_operation_intermediate, AccValue, Op},
+            chain::{block::Height, tests::PUB_KEY},
+            set,
+        };
+
+        let pk = &*PUB_KEY;
+        let (s1, s2) = (set! {1, 2}, set! {2, 3});
+        let (a1, a2) = (AccValue::from_set(&s1, pk), AccValue::from_set(&s2, pk));
+        let (_, inter_acc, inter_proof) =
+            compute_set_operation_intermediate(Op::Union, &s1, &a1, &s2, &a2, pk);
+        let (_, final_proof) = compute_set_operation_final(Op::Union, &s1, &s2, pk);
+        let (blk_height, win_size) = (Height(1), 2);
+
+        let nodes: Vec<(VONode<u32>, Option<AccValue>)> = vec![
+            (
+                VONode::Range(VORangeNode {
+                    blk_height,
+                    win_size,
+                    acc: a1,
+                    proof: Default::default(),
+                }),
+                Some(a1),
+            ),
+            (
+                VONode::Keyword(VOKeywordNode {
+                    blk_height,
+                    win_size,
+                    acc: a1,
+                }),
+                Some(a1),
+            ),
+            (
+                VONode::KeywordConjunction(VOKeywordConjunction {
+                    blk_height,
+                    win_size,
+                    accs: vec![a1],
+                    proofs: vec![],
+                    final_proof: None,
+                }),
+                Some(a1),
+            ),
+            (
+                VONode::KeywordConjunction(VOKeywordConjunction {
+                    blk_height,
+                    win_size,
+                    accs: vec![],
+                    proofs: vec![],
+                    final_proof: None,
+                }),
+                None,
+            ),
+            (
+                VONode::KeywordPrefix(VOKeywordPrefix {
+                    blk_height,
+                    win_size,
+                    accs: vec![a1, a2],
+                    proofs: vec![(inter_acc, inter_proof)],
+                    final_proof: None,
+                }),
+                Some(inter_acc),
+            ),
+            (
+                VONode::KeywordPrefix(VOKeywordPrefix {
+                    blk_height,
+                    win_size,
+                    accs: vec![a1, a2],
+                    proofs: vec![],
+                    final_proof: Some(final_proof),
+                }),
+                None,
+            ),
+            (
+                VONode::BlkRt(VOBlkRtNode {
+                    blk_height,
+                    win_size,
+                    acc: a2,
+                    id_set_proofs: Default::default(),
+                }),
+                Some(a2),
+            ),
+            (
+                VONode::InterUnion(VOInterUnion {
+                    acc: inter_acc,
+                    proof: inter_proof,
+                }),
+                Some(inter_acc),
+            ),
+            (
+                VONode::FinalUnion(VOFinalUnion {
+                    proof: final_proof,
+                }),
+                None,
+            ),
+            (
+                VONode::InterIntersec(VOInterIntersec {
+                    acc: inter_acc,
+                    proof: Some(inter_proof),
+                }),
+                Some(inter_acc),
+            ),
+            (
+                VONode::FinalIntersec(VOFinalIntersec {
+                    proof: final_proof,
+                }),
+                None,
+            ),
+            (
+                VONode::InterDiff(VOInterDiff {
+                    acc: a2,
+                    proof: None,
+                }),
+                Some(a2),
+            ),
+            (
+                VONode::FinalDiff(VOFinalDiff {
+                    proof: final_proof,
+                }),
+                None,
+            ),
+        ];
+        for (node, expect) in &nodes {
+            match (node.get_acc(), expect) {
+                (Ok(acc), Some(expect)) => assert_eq!(acc, expect, "{}", node.node_kind()),
+                (Err(e), None) => assert!(e.to_string().contains(node.node_kind())),
+                (res, _) => panic!("unexpected get_acc of {}: {:?}", node.node_kind(), res),
+            }
+        }
+    }
+
     #[test]
     fn test_graph() {
         use petgraph::{EdgeDirection::Outgoing, Graph};
@@ -550,4 +1818,55 @@ mod tests {
         }
         assert_eq!(1, 1);
     }
+
+    #[test]
+    fn test_vo_size_display() {
+        use super::VOSize;
+
+        let vo_size = VOSize::new(2048, 300 * 1024, 3 * 1024 * 1024, 2, 512, 3_455_490);
+        assert_eq!(vo_size.largest_category(), "id_proof");
+        let s = vo_size.to_string();
+        assert!(s.contains("vo_dag: 2.0 KiB (0%)"), "{}", s);
+        assert!(s.contains("id_proof: 3.0 MiB (91%)"), "{}", s);
+        assert!(s.contains("cur_id: 2 B (0%)"), "{}", s);
+        assert!(s.ends_with("total: 3.3 MiB"), "{}", s);
+        let percent_sum: f64 = s
+            .split('(')
+            .skip(1)
+            .map(|p| p.split('%').next().unwrap().parse::<f64>().unwrap())
+            .sum();
+        assert!((percent_sum - 100.0).abs() <= 2.0, "{}", s);
+
+        let vo_size = VOSize::new(10, 4000, 20, 2, 30, 4062);
+        assert_eq!(vo_size.largest_category(), "trie_proof");
+        assert_eq!(VOSize::default().largest_category(), "vo_dag");
+        assert!(VOSize::default().to_string().contains("vo_dag: 0 B (0%)"));
+    }
+
+    #[test]
+    fn test_vo_size_stats() {
+        use super::{VOSize, VOSizeStats};
+
+        let sizes = vec![
+            VOSize::new(10, 200, 30, 2, 40, 282),
+            VOSize::new(30, 100, 50, 2, 20, 202),
+            VOSize::new(20, 300, 10, 2, 60, 392),
+        ];
+        let mut expect = VOSize::zero();
+        for s in &sizes {
+            expect += *s;
+        }
+        assert_eq!(sizes.iter().copied().sum::<VOSize>(), expect);
+        assert_eq!(
+            Vec::<VOSize>::new().into_iter().sum::<VOSize>(),
+            VOSize::zero()
+        );
+
+        let stats: VOSizeStats = sizes.into_iter().collect();
+        assert_eq!(stats.count, 3);
+        assert_eq!(stats.min, VOSize::new(10, 100, 10, 2, 20, 202));
+        assert_eq!(stats.max, VOSize::new(30, 300, 50, 2, 60, 392));
+        assert_eq!(stats.mean, VOSize::new(20, 200, 30, 2, 40, 292));
+        assert_eq!(VOSizeStats::new().mean, VOSize::zero());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,155 @@
+//! 关键字不存在的证明
+//!
+//! 时间窗口 trie 中不存在某个关键字时，该关键字在窗口内的任何区块（包括窗口末端的区块）中都没有匹配的对象。
+//! 证明由单个关键字节点组成的 VO 给出：节点的累加器为空集的累加器，trie 证明展开了关键字的查找路径，
+//! 验证方据此确认路径终止于不含该关键字的节点，并照常把 trie 根对照区块头中的 ads_root 验证。
+
+use super::{
+    cache::VerifyCache,
+    cal_vo_size, inner_verify, pre_verify,
+    vo::{MerkleProof, VOKeywordNode, VONode, VoDagContent, VO},
+    VerifyInfo, VerifyOptions,
+};
+use crate::{
+    acc::{AccPublicKey, AccValue, Set},
+    chain::{
+        block::{hash::obj_id_nums_hash, Height},
+        id_tree,
+        query::query_dag::{DagNode, KeywordNode},
+        traits::{Num, ReadInterface},
+        trie_tree,
+    },
+    digest::{Digest, Digestible},
+    utils::Time,
+};
+use anyhow::{bail, ensure, Context, Result};
+use petgraph::{graph::NodeIndex, Graph};
+use serde::Serialize;
+use smol_str::SmolStr;
+use std::collections::{BTreeMap, HashMap};
+
+/// 证明区块 `height` 中没有对象包含关键字 `keyword`。
+/// 使用最小的时间窗口；关键字出现在该窗口内的其他区块中时无法给出证明，返回错误
+pub fn prove_keyword_absence<K: Num, T: ReadInterface<K = K>>(
+    chain: T,
+    keyword: &str,
+    height: Height,
+    pk: &AccPublicKey,
+) -> Result<VO<K>> {
+    let param = chain.get_parameter()?;
+    let win_size = *param
+        .time_win_sizes
+        .first()
+        .context("No time window in the parameter")?;
+    let blk_content = chain.read_block_content(height)?;
+    let trie_root = blk_content.ads.read_trie_root(win_size)?;
+    let mut trie_ctx = trie_tree::read::ReadContext::new(&chain, trie_root.trie_root_id);
+    trie_ctx
+        .prove_absence(&SmolStr::from(keyword), pk)
+        .with_context(|| {
+            format!(
+                "Keyword {} occurs in the {}-block time window ending at height {:?}",
+                keyword, win_size, height
+            )
+        })?;
+    let mut trie_proofs = HashMap::new();
+    trie_proofs.insert(height, trie_ctx.into_proof());
+
+    let mut ads_hashes = BTreeMap::<u16, Digest>::new();
+    let mut extra_bplus_rt_hashes = HashMap::<u8, Digest>::new();
+    for (t_w, ads) in blk_content.ads.read_adses() {
+        if *t_w == win_size {
+            for (i, rt) in ads.bplus_tree_roots.iter().enumerate() {
+                extra_bplus_rt_hashes.insert(i as u8, rt.to_digest());
+            }
+        } else {
+            ads_hashes.insert(*t_w, ads.to_digest());
+        }
+    }
+    let merkle_proof = MerkleProof::new(
+        Some(blk_content.id_tree_root.to_digest()),
+        obj_id_nums_hash(blk_content.obj_id_nums.iter()),
+        ads_hashes,
+        extra_bplus_rt_hashes,
+    )?;
+    let mut merkle_proofs = HashMap::new();
+    merkle_proofs.insert(height, merkle_proof);
+
+    let idx = NodeIndex::new(0);
+    let mut dag_content = HashMap::new();
+    dag_content.insert(
+        idx,
+        VONode::Keyword(VOKeywordNode {
+            blk_height: height,
+            win_size,
+            acc: AccValue::from_set(&Set::new(), pk),
+        }),
+    );
+    let mut output_sets = HashMap::new();
+    output_sets.insert(idx, Set::new());
+    Ok(VO {
+        vo_dag_content: VoDagContent {
+            output_sets,
+            dag_content,
+        },
+        trie_proofs,
+        id_tree_proof: id_tree::proof::Proof::default(),
+        cur_obj_id: blk_content.id_tree_root.get_cur_obj_id(),
+        merkle_proofs,
+    })
+}
+
+/// 验证 `prove_keyword_absence` 生成的证明，返回证明所针对的区块高度
+pub fn verify_keyword_absence<K: Num + Serialize, T: ReadInterface<K = K>>(
+    chain: T,
+    vo: &VO<K>,
+    keyword: &str,
+    pk: &AccPublicKey,
+) -> Result<(Height, VerifyInfo)> {
+    let timer = howlong::ProcessCPUTimer::new();
+    let mut graph = Graph::<DagNode<K>, bool>::new();
+    let idx = graph.add_node(DagNode::Keyword(Box::new(KeywordNode {
+        keyword: keyword.to_string(),
+    })));
+    pre_verify(&[vo], &graph, &VerifyOptions::default())?;
+    vo.check_structure(&graph)?;
+    let dag_content = &vo.vo_dag_content.dag_content;
+    ensure!(
+        dag_content.len() == 1,
+        "Keyword absence VO must contain exactly one node"
+    );
+    let height = match dag_content.get(&idx) {
+        Some(VONode::Keyword(n)) => n.blk_height,
+        _ => bail!("Keyword absence VO must contain a keyword node"),
+    };
+    ensure!(
+        vo.vo_dag_content.output_sets.get(&idx) == Some(&Set::new()),
+        "Keyword absence VO must output an empty set"
+    );
+    // 验证关键字节点的累加器为空集的累加器，以及 trie 根与区块头中的 ads_root 一致
+    let cache = VerifyCache::new(pk);
+    inner_verify(
+        &chain,
+        Some(&HashMap::new()),
+        vo,
+        &graph,
+        &cache,
+        &mut HashMap::new(),
+        None,
+        None,
+        pk,
+    )?;
+    // 上面的累加器验证把未展开的路径视为关键字不存在，这里要求路径完整展开
+    vo.trie_proofs
+        .get(&height)
+        .context("Cannot find trie proof")?
+        .verify_absence(keyword)?;
+    let time = Time::from(timer.elapsed());
+    Ok((
+        height,
+        VerifyInfo {
+            vo_size: cal_vo_size(vo)?,
+            verify_time: time,
+        },
+    ))
+}
@@ -0,0 +1,73 @@
+//! 跨多次验证复用的累加器值
+//!
+//! 验证时需要由集合计算累加器值（空集、输出集合、时间窗口的全集等），其代价随集合大小线性增长。
+//! 反复验证相互重叠的查询（例如定时刷新的看板）时，这些集合大多相同。
+//! `VerifyCache` 以集合的摘要为键保存计算结果，可在多个线程的验证之间共享。
+
+use crate::{
+    acc::{keys::KeyFingerprint, AccPublicKey, AccValue, Set},
+    digest::{Digest, Digestible},
+};
+use std::{collections::HashMap, sync::RwLock};
+
+/// 默认最多保存的累加器值个数
+const DEFAULT_CAPACITY: usize = 4096;
+
+/// 只对创建时使用的公钥有效；以其他公钥调用时直接计算，不读写缓存
+#[derive(Debug)]
+pub struct VerifyCache {
+    key_fp: KeyFingerprint,
+    empty_acc: AccValue,
+    accs: RwLock<HashMap<Digest, AccValue>>,
+    capacity: usize,
+}
+
+impl VerifyCache {
+    pub fn new(pk: &AccPublicKey) -> Self {
+        Self::with_capacity(pk, DEFAULT_CAPACITY)
+    }
+
+    /// 最多保存 `capacity` 个非空集合的累加器值，达到上限后不再加入新的值
+    pub fn with_capacity(pk: &AccPublicKey, capacity: usize) -> Self {
+        Self {
+            key_fp: pk.fingerprint(),
+            empty_acc: AccValue::from_set(&Set::new(), pk),
+            accs: RwLock::new(HashMap::new()),
+            capacity,
+        }
+    }
+
+    /// 空集的累加器值
+    pub fn empty_acc(&self) -> &AccValue {
+        &self.empty_acc
+    }
+
+    /// 集合 `set` 在公钥 `pk` 下的累加器值
+    pub fn acc_of(&self, set: &Set, pk: &AccPublicKey) -> AccValue {
+        if pk.fingerprint() != self.key_fp {
+            return AccValue::from_set(set, pk);
+        }
+        if set.is_empty() {
+            return self.empty_acc;
+        }
+        let key = set.to_digest();
+        if let Some(acc) = self.accs.read().expect("poisoned lock").get(&key) {
+            return *acc;
+        }
+        let acc = AccValue::from_set(set, pk);
+        let mut accs = self.accs.write().expect("poisoned lock");
+        if accs.len() < self.capacity {
+            accs.insert(key, acc);
+        }
+        acc
+    }
+
+    /// 已保存的非空集合的累加器值个数
+    pub fn len(&self) -> usize {
+        self.accs.read().expect("poisoned lock").len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
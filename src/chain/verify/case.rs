@@ -0,0 +1,134 @@
+//! 保存与重放验证用例
+//!
+//! 验证失败时，`VerifyCase::capture` 把验证所需的全部输入（VO 涉及的区块头、参数、结果对象、VO、
+//! 查询 DAG 与公钥）写入一个文件；`VerifyCase::load` 读回后由 `run` 以 `verify_with_heads`
+//! 重新验证，不需要访问链，从而把线上的验证失败变成确定可复现的用例。
+
+use super::{light::verify_with_heads, vo::VO, VerifyInfo};
+use crate::{
+    acc::AccPublicKey,
+    chain::{
+        block::{BlockHead, Height},
+        id_tree::ObjId,
+        object::Object,
+        query::query_dag::DagNode,
+        traits::{Num, ReadInterface},
+        Parameter,
+    },
+    utils::{binary_decode, binary_encode},
+};
+use anyhow::{ensure, Context, Result};
+use petgraph::Graph;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+/// 用例文件的标识
+const CASE_MAGIC: [u8; 4] = *b"VCVC";
+/// 用例文件格式的版本
+const CASE_VERSION: u16 = 1;
+
+/// 用例文件的文件头，其后紧跟 `binary_encode` 编码的用例
+#[derive(Debug, Serialize, Deserialize)]
+struct CaseHeader {
+    magic: [u8; 4],
+    version: u16,
+}
+
+/// 一次验证的全部输入
+#[derive(Serialize, Deserialize)]
+pub struct VerifyCase<K: Num> {
+    pub heads: HashMap<Height, BlockHead>,
+    pub param: Parameter,
+    pub res_content: HashMap<ObjId, Object<K>>,
+    pub vo: VO<K>,
+    pub graph: Graph<DagNode<K>, bool>,
+    pub pk: AccPublicKey,
+}
+
+/// 与 `VerifyCase` 的字段及顺序相同，用于不复制输入地写出用例
+#[derive(Serialize)]
+struct VerifyCaseRef<'a, K: Num> {
+    heads: &'a HashMap<Height, BlockHead>,
+    param: &'a Parameter,
+    res_content: &'a HashMap<ObjId, Object<K>>,
+    vo: &'a VO<K>,
+    graph: &'a Graph<DagNode<K>, bool>,
+    pk: &'a AccPublicKey,
+}
+
+impl<K: Num + Serialize> VerifyCase<K> {
+    /// 从 `chain` 读取参数以及 VO 涉及的各高度的区块头，与其余输入一起写入 `path`。
+    /// 读取不到的区块头不写入，重放时同样得到缺少区块头的错误
+    pub fn capture<T: ReadInterface<K = K>>(
+        path: &Path,
+        chain: T,
+        res_content: &HashMap<ObjId, Object<K>>,
+        vo: &VO<K>,
+        graph: &Graph<DagNode<K>, bool>,
+        pk: &AccPublicKey,
+    ) -> Result<()> {
+        let heads = vo
+            .referenced_heights()
+            .into_iter()
+            .filter_map(|h| chain.read_block_head(h).ok().map(|head| (h, head)))
+            .collect();
+        let case = VerifyCaseRef {
+            heads: &heads,
+            param: &chain.get_parameter()?,
+            res_content,
+            vo,
+            graph,
+            pk,
+        };
+        let header = CaseHeader {
+            magic: CASE_MAGIC,
+            version: CASE_VERSION,
+        };
+        let mut writer = BufWriter::new(
+            File::create(path).with_context(|| format!("failed to create {}", path.display()))?,
+        );
+        bincode::serialize_into(&mut writer, &header)?;
+        writer.write_all(&binary_encode(&case)?)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// 以用例中的区块头重新验证
+    pub fn run(&self) -> Result<VerifyInfo> {
+        verify_with_heads(
+            &self.heads,
+            &self.param,
+            &self.res_content,
+            &self.vo,
+            &self.graph,
+            &self.pk,
+        )
+    }
+}
+
+impl<K: Num + for<'de> Deserialize<'de>> VerifyCase<K> {
+    /// 加载 `capture` 写出的用例
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+        let mut reader = &data[..];
+        let header: CaseHeader = bincode::deserialize_from(&mut reader)
+            .with_context(|| format!("{} is not a verify case file", path.display()))?;
+        ensure!(
+            header.magic == CASE_MAGIC,
+            "{} is not a verify case file",
+            path.display()
+        );
+        ensure!(
+            header.version == CASE_VERSION,
+            "Unsupported verify case version {}, expected {}",
+            header.version,
+            CASE_VERSION
+        );
+        binary_decode(reader).context("Failed to decode verify case")
+    }
+}
@@ -62,14 +62,19 @@ pub(crate) fn ads_hash(bplus_hash: Digest, trie_hash: Digest) -> Digest {
 pub(crate) fn obj_hash<K: Num>(obj: &Object<K>, id: &ObjId) -> Digest {
     let mut state = blake2().to_state();
     state.update(&id.to_internal_id().to_le_bytes());
-    state.update(obj.to_digest().as_bytes());
+    state.update(obj.content_digest().as_bytes());
     Digest::from(state.finalize())
 }
 
 #[inline]
-pub(crate) fn id_tree_root_hash(cur_obj_id_hash: Digest, id_tree_root_node_hash: Digest) -> Digest {
+pub(crate) fn id_tree_root_hash(
+    cur_obj_id_hash: Digest,
+    fanout: u8,
+    id_tree_root_node_hash: Digest,
+) -> Digest {
     let mut state = blake2().to_state();
     state.update(cur_obj_id_hash.as_bytes());
+    state.update(&fanout.to_le_bytes());
     state.update(id_tree_root_node_hash.as_bytes());
     Digest::from(state.finalize())
 }
\ No newline at end of file
@@ -0,0 +1,227 @@
+//! 轻节点验证器：只依赖同步到的区块头、全节点提供的 BlockADSComponents 以及查询 VO
+
+use crate::{
+    acc::AccPublicKey,
+    chain::{
+        block::{block_ads_root::BlockADSComponents, BlockContent, BlockHead, Height},
+        bplus_tree::{BPlusTreeNode, BPlusTreeNodeId},
+        id_tree::{IdTreeNode, IdTreeNodeId, ObjId},
+        object::Object,
+        query::query_dag::DagNode,
+        traits::{Num, ReadInterface},
+        trie_tree::{TrieNode, TrieNodeId},
+        verify::{
+            cache::VerifyCache, cal_vo_size, inner_verify, pre_verify, vo::VO, VerifyInfo,
+            VerifyOptions,
+        },
+        Parameter,
+    },
+    digest::{Digest, Digestible},
+    utils::Time,
+};
+use anyhow::{bail, ensure, Context, Result};
+use petgraph::Graph;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, marker::PhantomData};
+
+/// 【创新点1】轻节点验证器
+///
+/// 轻节点只保存区块头（其中的 ads_root 为 32 字节统一承诺），
+/// 验证时先用区块头校验全节点给出的 components，再用 VO 完成完整验证。
+#[derive(Debug, Clone)]
+pub struct LightVerifier {
+    pub head: BlockHead,
+    pub param: Parameter,
+}
+
+impl LightVerifier {
+    pub fn new(head: BlockHead, param: Parameter) -> Self {
+        Self { head, param }
+    }
+
+    pub fn verify<K: Num + Serialize>(
+        &self,
+        components: &BlockADSComponents,
+        vo: &VO<K>,
+        res: &HashMap<ObjId, Object<K>>,
+        graph: &Graph<DagNode<K>, bool>,
+        pk: &AccPublicKey,
+    ) -> Result<VerifyInfo> {
+        let timer = howlong::ProcessCPUTimer::new();
+        pre_verify(&[vo], graph, &VerifyOptions::default())?;
+        ensure!(
+            self.head.verify_ads_components(components),
+            "BlockADSComponents do not match ads_root in block head {:?}",
+            self.head.blk_height
+        );
+        if let Some(merkle_proof) = vo.merkle_proofs.get(&self.head.blk_height) {
+            ensure!(
+                merkle_proof
+                    .id_set_root_hash
+                    .ct_eq(&components.id_set_root_hash),
+                "Id set root hash in VO does not match BlockADSComponents"
+            );
+        }
+        let chain = HeadOnlyChain::<K> {
+            heads: PinnedHeads::Single(&self.head),
+            param: &self.param,
+            _k: PhantomData,
+        };
+        let cache = VerifyCache::new(pk);
+        inner_verify(
+            &chain,
+            Some(res),
+            vo,
+            graph,
+            &cache,
+            &mut HashMap::new(),
+            None,
+            None,
+            pk,
+        )?;
+        let time = Time::from(timer.elapsed());
+        Ok(VerifyInfo {
+            vo_size: cal_vo_size(vo)?,
+            verify_time: time,
+        })
+    }
+}
+
+/// 无状态验证：只使用调用方预先取得的区块头 `heads`（VO 涉及的每个高度都必须提供），
+/// 不需要 `ReadInterface`。调用方需自行保证这些区块头可信（例如经 `HeaderChainProof` 验证）
+pub fn verify_with_heads<K: Num + Serialize>(
+    heads: &HashMap<Height, BlockHead>,
+    param: &Parameter,
+    res: &HashMap<ObjId, Object<K>>,
+    vo: &VO<K>,
+    graph: &Graph<DagNode<K>, bool>,
+    pk: &AccPublicKey,
+) -> Result<VerifyInfo> {
+    let timer = howlong::ProcessCPUTimer::new();
+    pre_verify(&[vo], graph, &VerifyOptions::default())?;
+    let chain = HeadOnlyChain::<K> {
+        heads: PinnedHeads::Map(heads),
+        param,
+        _k: PhantomData,
+    };
+    let cache = VerifyCache::new(pk);
+    inner_verify(
+        &chain,
+        Some(res),
+        vo,
+        graph,
+        &cache,
+        &mut HashMap::new(),
+        None,
+        None,
+        pk,
+    )?;
+    let time = Time::from(timer.elapsed());
+    Ok(VerifyInfo {
+        vo_size: cal_vo_size(vo)?,
+        verify_time: time,
+    })
+}
+
+/// 区块头链证明：可信区块头之后、直到目标高度的所有区块头。
+/// 区块头很小，因此直接携带全部中间区块头，验证时逐个检查 `prev_hash` 链接
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeaderChainProof {
+    /// 高度为 `from + 1 ..= to` 的区块头
+    pub heads: Vec<BlockHead>,
+}
+
+/// 生成从高度 `from`（验证方已信任）到高度 `to` 的区块头链证明
+pub fn gen_header_chain_proof<T: ReadInterface>(
+    chain: T,
+    from: Height,
+    to: Height,
+) -> Result<HeaderChainProof> {
+    ensure!(
+        from <= to,
+        "Invalid header chain range: from {:?} is after to {:?}",
+        from,
+        to
+    );
+    let heads = (from.0 + 1..=to.0)
+        .map(|h| chain.read_block_head(Height(h)))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(HeaderChainProof { heads })
+}
+
+impl HeaderChainProof {
+    /// 验证证明中的区块头依次链接到可信区块头 `trusted`，返回链末端（目标高度）的区块头
+    pub fn verify<'a>(&'a self, trusted: &'a BlockHead) -> Result<&'a BlockHead> {
+        let mut prev = trusted;
+        for head in &self.heads {
+            ensure!(
+                prev.blk_height.next() == Some(head.blk_height),
+                "Block head at height {:?} does not follow height {:?}",
+                head.blk_height,
+                prev.blk_height
+            );
+            ensure!(
+                head.prev_hash.ct_eq(&prev.to_digest()),
+                "Block head at height {:?} is not linked to its previous head",
+                head.blk_height
+            );
+            prev = head;
+        }
+        Ok(prev)
+    }
+}
+
+/// 验证方持有的区块头
+enum PinnedHeads<'a> {
+    Single(&'a BlockHead),
+    Map(&'a HashMap<Height, BlockHead>),
+}
+
+/// 只能提供参数与给定区块头的链接口，用于复用完整验证逻辑
+struct HeadOnlyChain<'a, K> {
+    heads: PinnedHeads<'a>,
+    param: &'a Parameter,
+    _k: PhantomData<K>,
+}
+
+impl<K: Num> ReadInterface for HeadOnlyChain<'_, K> {
+    type K = K;
+    fn get_parameter(&self) -> Result<Parameter> {
+        Ok(self.param.clone())
+    }
+    fn read_block_head(&self, blk_height: Height) -> Result<BlockHead> {
+        match self.heads {
+            PinnedHeads::Single(head) => {
+                ensure!(
+                    blk_height == head.blk_height,
+                    "Light verifier only holds the block head at height {:?}, but VO requires {:?}",
+                    head.blk_height,
+                    blk_height
+                );
+                Ok(head.clone())
+            }
+            PinnedHeads::Map(heads) => heads
+                .get(&blk_height)
+                .cloned()
+                .with_context(|| format!("Missing block head at height {:?}", blk_height)),
+        }
+    }
+    fn read_block_content(&self, _blk_height: Height) -> Result<BlockContent> {
+        bail!("Light verifier cannot read block content")
+    }
+    fn read_id_tree_node(&self, _id_tree_node_id: IdTreeNodeId) -> Result<IdTreeNode> {
+        bail!("Light verifier cannot read id tree node")
+    }
+    fn read_bplus_tree_node(
+        &self,
+        _bplus_tree_node_id: BPlusTreeNodeId,
+    ) -> Result<BPlusTreeNode<Self::K>> {
+        bail!("Light verifier cannot read bplus tree node")
+    }
+    fn read_trie_node(&self, _trie_node_id: TrieNodeId) -> Result<TrieNode> {
+        bail!("Light verifier cannot read trie node")
+    }
+    fn read_object(&self, _obj_hash: Digest) -> Result<Object<Self::K>> {
+        bail!("Light verifier cannot read object")
+    }
+}
@@ -0,0 +1,83 @@
+//! 为远程 `ReadInterface` 后端提供重试，使验证不会因为一次暂时性的读取失败而中止
+
+use crate::{
+    chain::{
+        block::{BlockContent, BlockHead, Height},
+        bplus_tree::{BPlusTreeNode, BPlusTreeNodeId},
+        id_tree::{IdTreeNode, IdTreeNodeId},
+        object::Object,
+        traits::{ReadError, ReadInterface},
+        trie_tree::{TrieNode, TrieNodeId},
+        Parameter,
+    },
+    digest::Digest,
+};
+use anyhow::Result;
+use std::{thread, time::Duration};
+
+/// 包装任意 `ReadInterface`，对 `ReadError::Transient` 错误按指数退避重试，其余错误直接返回
+#[derive(Debug, Clone)]
+pub struct RetryingChain<T> {
+    inner: T,
+    max_retries: u32,
+    backoff: Duration,
+}
+
+impl<T: ReadInterface> RetryingChain<T> {
+    /// `max_retries` 为每次读取最多重试的次数，第 i 次重试前等待 `backoff * 2^i`
+    pub fn new(inner: T, max_retries: u32, backoff: Duration) -> Self {
+        Self {
+            inner,
+            max_retries,
+            backoff,
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    fn retry<R>(&self, f: impl Fn(&T) -> Result<R>) -> Result<R> {
+        let mut attempt = 0;
+        loop {
+            match f(&self.inner) {
+                Err(e) if attempt < self.max_retries && ReadError::is_transient(&e) => {
+                    let wait = self.backoff * 2u32.saturating_pow(attempt);
+                    warn!("read failed ({}), retrying in {:?}", e, wait);
+                    thread::sleep(wait);
+                    attempt += 1;
+                }
+                res => return res,
+            }
+        }
+    }
+}
+
+impl<T: ReadInterface> ReadInterface for RetryingChain<T> {
+    type K = T::K;
+
+    fn get_parameter(&self) -> Result<Parameter> {
+        self.retry(|c| c.get_parameter())
+    }
+    fn read_block_head(&self, blk_height: Height) -> Result<BlockHead> {
+        self.retry(|c| c.read_block_head(blk_height))
+    }
+    fn read_block_content(&self, blk_height: Height) -> Result<BlockContent> {
+        self.retry(|c| c.read_block_content(blk_height))
+    }
+    fn read_id_tree_node(&self, id_tree_node_id: IdTreeNodeId) -> Result<IdTreeNode> {
+        self.retry(|c| c.read_id_tree_node(id_tree_node_id))
+    }
+    fn read_bplus_tree_node(
+        &self,
+        bplus_tree_node_id: BPlusTreeNodeId,
+    ) -> Result<BPlusTreeNode<Self::K>> {
+        self.retry(|c| c.read_bplus_tree_node(bplus_tree_node_id))
+    }
+    fn read_trie_node(&self, trie_node_id: TrieNodeId) -> Result<TrieNode> {
+        self.retry(|c| c.read_trie_node(trie_node_id))
+    }
+    fn read_object(&self, obj_hash: Digest) -> Result<Object<Self::K>> {
+        self.retry(|c| c.read_object(obj_hash))
+    }
+}
@@ -0,0 +1,195 @@
+//! 供审计使用的验证记录
+//!
+//! `verify_with_trace` 以定位模式逐项验证，不在第一个错误处停止，并记录：
+//! - 每个 DAG 节点的 VO 节点类型、验证时检查的累加器指纹以及该节点的验证错误；
+//! - 每个涉及的区块高度上计算出的 ads_root 与区块头中 ads_root 的比较结果；
+//! - 无法归属到节点或高度的错误（如 id 树证明与结果不匹配）。
+//!
+//! 记录可用 serde 序列化保存，`VerifyTrace::replay` 只根据记录的内容重新得出接受或拒绝的结论。
+
+use super::{
+    cache::VerifyCache, cal_vo_size, inner_verify, pre_verify, vo::VONode, vos_of, FailSite,
+    VOSize, VerifyInfo, VerifyOptions, VoResult,
+};
+use crate::{
+    acc::{AccPublicKey, AccValue},
+    chain::{
+        block::{BlockHead, Height},
+        query::query_dag::DagNode,
+        traits::{Num, ReadInterface},
+    },
+    digest::Digest,
+    utils::Time,
+};
+use anyhow::Result;
+use petgraph::Graph;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// 一个 DAG 节点的验证记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeTrace {
+    /// 节点在查询 DAG 中的下标
+    pub idx: usize,
+    /// VO 节点类型；为空表示 VO 中没有该节点（如求交集时结果为空的子节点），验证时跳过
+    pub kind: Option<String>,
+    /// 验证时检查的累加器指纹，输出节点还包括由输出集合计算出的累加器
+    pub accs: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+/// 一个区块高度上 ads_root 的比较结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AdsRootTrace {
+    pub height: Height,
+    /// 由 VO 计算出的 ads_root；缺少相应证明时为空
+    pub computed: Option<Digest>,
+    /// 区块头中的 ads_root
+    pub expected: Option<Digest>,
+    pub errors: Vec<String>,
+}
+
+impl AdsRootTrace {
+    fn new(height: Height) -> Self {
+        Self {
+            height,
+            computed: None,
+            expected: None,
+            errors: Vec::new(),
+        }
+    }
+}
+
+/// 一个 VO 的验证记录
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VOTrace {
+    pub nodes: Vec<NodeTrace>,
+    /// 按高度升序排列
+    pub heights: Vec<AdsRootTrace>,
+    pub others: Vec<String>,
+}
+
+impl VOTrace {
+    fn replay(&self) -> bool {
+        self.nodes.iter().all(|n| n.errors.is_empty())
+            && self
+                .heights
+                .iter()
+                .all(|h| h.errors.is_empty() && h.computed.is_some() && h.computed == h.expected)
+            && self.others.is_empty()
+    }
+}
+
+/// `verify_with_trace` 的验证记录，`vos` 与 `res_contents` 一一对应
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifyTrace {
+    pub vos: Vec<VOTrace>,
+    /// 验证时得出的结论
+    pub accepted: bool,
+}
+
+impl VerifyTrace {
+    /// 只根据记录重新得出结论：所有节点没有错误、每个高度计算出的 ads_root 与区块头一致，
+    /// 且没有其他错误时接受
+    pub fn replay(&self) -> bool {
+        self.vos.iter().all(VOTrace::replay)
+    }
+}
+
+/// 与 `verify` 相同的验证，额外返回可序列化的验证记录。
+/// 验证不通过时仍返回 `Ok`，结论见 `VerifyTrace::accepted`；只有读取链数据等错误，
+/// 或输入未通过 `pre_verify` 的检查时才返回 `Err`
+pub fn verify_with_trace<K: Num + Serialize, T: ReadInterface<K = K>>(
+    chain: T,
+    res_contents: &[VoResult<K>],
+    graph: &Graph<DagNode<K>, bool>,
+    pk: &AccPublicKey,
+) -> Result<(VerifyInfo, VerifyTrace)> {
+    let timer = howlong::ProcessCPUTimer::new();
+    pre_verify(&vos_of(res_contents), graph, &VerifyOptions::default())?;
+    let cache = VerifyCache::new(pk);
+    let mut blk_heads = HashMap::<Height, BlockHead>::new();
+    let mut trace = VerifyTrace::default();
+    for (res_content, vo_content) in res_contents {
+        let mut failures = Vec::new();
+        let mut ads_root_checks = Vec::new();
+        inner_verify(
+            &chain,
+            Some(res_content),
+            vo_content,
+            graph,
+            &cache,
+            &mut blk_heads,
+            Some(&mut failures),
+            Some(&mut ads_root_checks),
+            pk,
+        )?;
+
+        let dag_content = &vo_content.vo_dag_content.dag_content;
+        let output_sets = &vo_content.vo_dag_content.output_sets;
+        let mut vo_trace = VOTrace::default();
+        let mut node_pos = HashMap::new();
+        for idx in graph.node_indices() {
+            let content = dag_content.get(&idx);
+            let mut accs: Vec<String> = content.map(checked_accs).unwrap_or_default();
+            if let Some(set) = output_sets.get(&idx) {
+                accs.push(AccValue::from_set(set, pk).fingerprint());
+            }
+            node_pos.insert(idx, vo_trace.nodes.len());
+            vo_trace.nodes.push(NodeTrace {
+                idx: idx.index(),
+                kind: content.map(|c| c.node_kind().to_string()),
+                accs,
+                errors: Vec::new(),
+            });
+        }
+
+        let mut heights = BTreeMap::<Height, AdsRootTrace>::new();
+        for (height, computed, expected) in ads_root_checks {
+            let h = heights
+                .entry(height)
+                .or_insert_with(|| AdsRootTrace::new(height));
+            h.computed = Some(computed);
+            h.expected = Some(expected);
+        }
+        for (site, e) in failures {
+            let e = format!("{:#}", e);
+            match site {
+                FailSite::Node(idx) => match node_pos.get(&idx) {
+                    Some(&pos) => vo_trace.nodes[pos].errors.push(e),
+                    None => vo_trace.others.push(e),
+                },
+                FailSite::Height(height) => heights
+                    .entry(height)
+                    .or_insert_with(|| AdsRootTrace::new(height))
+                    .errors
+                    .push(e),
+                FailSite::Other => vo_trace.others.push(e),
+            }
+        }
+        vo_trace.heights = heights.into_values().collect();
+        trace.vos.push(vo_trace);
+    }
+    trace.accepted = trace.replay();
+    let time = Time::from(timer.elapsed());
+
+    let mut vo_size = VOSize::default();
+    for (_, vo) in res_contents {
+        vo_size += cal_vo_size(vo)?;
+    }
+    Ok((
+        VerifyInfo {
+            vo_size,
+            verify_time: time,
+        },
+        trace,
+    ))
+}
+
+/// VO 节点中参与验证的累加器指纹
+fn checked_accs<K: Num>(node: &VONode<K>) -> Vec<String> {
+    node.acc_values()
+        .into_iter()
+        .map(AccValue::fingerprint)
+        .collect()
+}
@@ -1,24 +1,53 @@
 use crate::{
     acc::{AccValue, FinalProof, IntermediateProof, Set},
     chain::{
-        block::{block_ads_root::BlockADSComponents, Height},
+        block::{
+            block_ads_root::BlockADSComponents, hash::obj_id_nums_hash, BlockContent, BlockHead,
+            Height,
+        },
         bplus_tree,
         id_tree::{self, ObjId},
+        query::query_dag::{identity_child, DagNode},
         traits::Num,
         trie_tree,
-        verify::hash::merkle_proof_hash,
+        verify::{hash::merkle_proof_hash, validate_dag},
     },
     digest::Digest,
+    utils::{binary_decode, binary_encode, binary_encoded_len},
 };
-use anyhow::{bail, Result};
-use petgraph::graph::NodeIndex;
+use anyhow::{bail, ensure, Context, Result};
+use petgraph::{algo::toposort, graph::NodeIndex, EdgeDirection::Outgoing, Graph};
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, HashMap};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    num::NonZeroU16,
+};
+
+/// `VO::check_structure` 给出的结构错误；调用方可通过 `anyhow::Error::downcast_ref` 识别
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VoStructureError {
+    /// 关键字节点所在高度没有 trie 证明
+    MissingTrieProof { height: Height },
+}
+
+impl std::fmt::Display for VoStructureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VoStructureError::MissingTrieProof { height } => {
+                write!(f, "Cannot find trie proof of height {:?} in VO", height)
+            }
+        }
+    }
+}
+
+impl std::error::Error for VoStructureError {}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum VONode<K: Num> {
     Range(VORangeNode<K>),
     Keyword(VOKeywordNode),
+    KeywordConjunction(VOKeywordConjunction),
+    KeywordPrefix(VOKeywordPrefix),
     BlkRt(VOBlkRtNode),
     InterUnion(VOInterUnion),
     FinalUnion(VOFinalUnion),
@@ -26,24 +55,95 @@ pub enum VONode<K: Num> {
     FinalIntersec(VOFinalIntersec),
     InterDiff(VOInterDiff),
     FinalDiff(VOFinalDiff),
+    Identity(VOIdentity),
+    HeightFilter(VOHeightFilter),
 }
 
 impl<K: Num> VONode<K> {
-    pub(crate) fn get_acc(&self) -> Result<&AccValue> {
+    /// 节点的累加器值；输出节点只携带 `FinalProof`，没有累加器值
+    pub fn get_acc(&self) -> Result<&AccValue> {
         match self {
             VONode::Range(n) => Ok(&n.acc),
             VONode::Keyword(n) => Ok(&n.acc),
+            VONode::KeywordConjunction(n) => match (&n.final_proof, n.proofs.last()) {
+                (Some(_), _) => bail!("{} is a final node without acc", self.node_kind()),
+                (None, Some((acc, _))) => Ok(acc),
+                (None, None) => n
+                    .accs
+                    .first()
+                    .with_context(|| format!("{} has no keyword acc", self.node_kind())),
+            },
+            VONode::KeywordPrefix(n) => match (&n.final_proof, n.proofs.last()) {
+                (Some(_), _) => bail!("{} is a final node without acc", self.node_kind()),
+                (None, Some((acc, _))) => Ok(acc),
+                (None, None) => n
+                    .accs
+                    .first()
+                    .with_context(|| format!("{} has no keyword acc", self.node_kind())),
+            },
             VONode::BlkRt(n) => Ok(&n.acc),
             VONode::InterUnion(n) => Ok(&n.acc),
-            VONode::FinalUnion(_) => bail!("This is a final union operation"),
             VONode::InterIntersec(n) => Ok(&n.acc),
-            VONode::FinalIntersec(_) => bail!("This is a final intersec operation"),
             VONode::InterDiff(n) => Ok(&n.acc),
-            VONode::FinalDiff(_) => bail!("This is a final diff operation"),
+            VONode::Identity(n) => Ok(&n.acc),
+            VONode::HeightFilter(n) => Ok(&n.acc),
+            VONode::FinalUnion(_) | VONode::FinalIntersec(_) | VONode::FinalDiff(_) => {
+                bail!("{} is a final node without acc", self.node_kind())
+            }
+        }
+    }
+
+    /// 节点携带的所有累加器值，包括关键字合取节点中间步骤的累加器值
+    pub fn acc_values(&self) -> Vec<&AccValue> {
+        match self {
+            VONode::KeywordConjunction(n) => n
+                .accs
+                .iter()
+                .chain(n.proofs.iter().map(|(acc, _)| acc))
+                .collect(),
+            VONode::KeywordPrefix(n) => n
+                .accs
+                .iter()
+                .chain(n.proofs.iter().map(|(acc, _)| acc))
+                .collect(),
+            _ => self.get_acc().into_iter().collect(),
+        }
+    }
+
+    /// 节点所在的区块高度；集合运算节点不属于某个区块，返回 `None`
+    pub fn blk_height(&self) -> Option<Height> {
+        match self {
+            VONode::Range(n) => Some(n.blk_height),
+            VONode::Keyword(n) => Some(n.blk_height),
+            VONode::KeywordConjunction(n) => Some(n.blk_height),
+            VONode::KeywordPrefix(n) => Some(n.blk_height),
+            VONode::BlkRt(n) => Some(n.blk_height),
+            VONode::HeightFilter(n) => Some(n.blk_height),
+            _ => None,
+        }
+    }
+
+    /// 节点类型名，用于错误信息
+    pub fn node_kind(&self) -> &'static str {
+        match self {
+            VONode::Range(_) => "Range",
+            VONode::Keyword(_) => "Keyword",
+            VONode::KeywordConjunction(_) => "KeywordConjunction",
+            VONode::KeywordPrefix(_) => "KeywordPrefix",
+            VONode::BlkRt(_) => "BlkRt",
+            VONode::InterUnion(_) => "InterUnion",
+            VONode::FinalUnion(_) => "FinalUnion",
+            VONode::InterIntersec(_) => "InterIntersec",
+            VONode::FinalIntersec(_) => "FinalIntersec",
+            VONode::InterDiff(_) => "InterDiff",
+            VONode::FinalDiff(_) => "FinalDiff",
+            VONode::Identity(_) => "Identity",
+            VONode::HeightFilter(_) => "HeightFilter",
         }
     }
 }
 
+/// Range 与 MultiInterval 节点共用：MultiInterval 的各区间在同一个 B+ 树证明中验证
 #[derive(Debug, Serialize, Deserialize)]
 pub struct VORangeNode<K: Num> {
     pub(crate) blk_height: Height,
@@ -59,11 +159,82 @@ pub struct VOKeywordNode {
     pub(crate) acc: AccValue,
 }
 
+/// 多关键字合取节点：各关键字的累加器由同一高度上共享的 trie 证明验证，
+/// 再按关键字顺序逐个求交集，`proofs[i]` 为前 `i + 2` 个关键字交集的累加器及其证明。
+/// 若该节点为输出节点，则最后一次求交集使用 `final_proof`。
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VOKeywordConjunction {
+    pub(crate) blk_height: Height,
+    pub(crate) win_size: u16,
+    pub(crate) accs: Vec<AccValue>,
+    pub(crate) proofs: Vec<(AccValue, IntermediateProof)>,
+    pub(crate) final_proof: Option<FinalProof>,
+}
+
+/// 前缀查询节点：`accs` 依次为 trie 中前缀子树下各关键字的累加器（不存在时为空集合的累加器），
+/// 再按顺序逐个求并集，证明的组织方式与 `VOKeywordConjunction` 相同。
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VOKeywordPrefix {
+    pub(crate) blk_height: Height,
+    pub(crate) win_size: u16,
+    pub(crate) accs: Vec<AccValue>,
+    pub(crate) proofs: Vec<(AccValue, IntermediateProof)>,
+    pub(crate) final_proof: Option<FinalProof>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct VOBlkRtNode {
     pub(crate) blk_height: Height,
     pub(crate) win_size: u16,
     pub(crate) acc: AccValue,
+    /// 时间窗口内各区块的对象 id 集合证明，其并集即为 `acc` 对应的全集
+    pub(crate) id_set_proofs: BTreeMap<Height, IdSetProof>,
+}
+
+/// 区块对象 id 集合的证明：给出对象 id 以及区块承诺的其余两个组件，
+/// 验证者据此还原区块头中的 ads_root，从而确认 id 集合
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IdSetProof {
+    pub(crate) obj_id_nums: Vec<NonZeroU16>,
+    pub(crate) id_tree_root_hash: Digest,
+    pub(crate) multi_ads_hash: Digest,
+}
+
+impl IdSetProof {
+    pub(crate) fn new(blk_content: &BlockContent) -> Self {
+        let components = blk_content.get_ads_components();
+        Self {
+            obj_id_nums: blk_content.obj_id_nums.clone(),
+            id_tree_root_hash: components.id_tree_root_hash,
+            multi_ads_hash: components.multi_ads_hash,
+        }
+    }
+
+    /// 验证证明与区块头一致，返回该区块的对象 id 集合及其 `id_set_root_hash`
+    pub(crate) fn verify(&self, blk_head: &BlockHead) -> Result<(Set, Digest)> {
+        let components = BlockADSComponents::new(
+            obj_id_nums_hash(self.obj_id_nums.iter()),
+            self.id_tree_root_hash,
+            self.multi_ads_hash,
+        );
+        ensure!(
+            blk_head.verify_ads_components(&components),
+            "Id set proof does not match the block head of height {}",
+            blk_head.blk_height
+        );
+        Ok((
+            self.obj_id_nums.iter().copied().collect(),
+            components.id_set_root_hash,
+        ))
+    }
+}
+
+/// 以 `blk_height` 结尾、大小为 `win_size` 的时间窗口所包含的区块高度
+pub(crate) fn time_win_heights(blk_height: Height, win_size: u16) -> impl Iterator<Item = Height> {
+    let start = (blk_height.0 + 1)
+        .saturating_sub(u32::from(win_size))
+        .max(1);
+    (start..=blk_height.0).map(Height)
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -99,15 +270,63 @@ pub struct VOFinalDiff {
     pub(crate) proof: FinalProof,
 }
 
+/// 透传节点：`acc` 与唯一子节点的累加器值相同
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VOIdentity {
+    pub(crate) acc: AccValue,
+}
+
+/// 高度过滤节点：`id_set_proofs` 给出窗口内落在过滤区间中的各区块对象 id 集合，
+/// `acc` 为子节点结果与这些集合之并的交集，由 `proof` 证明
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VOHeightFilter {
+    pub(crate) blk_height: Height,
+    pub(crate) win_size: u16,
+    pub(crate) id_set_proofs: BTreeMap<Height, IdSetProof>,
+    pub(crate) acc: AccValue,
+    pub(crate) proof: IntermediateProof,
+}
+
+/// 从查询结果重建区块 `ads_root` 时缺少的哈希
+///
+/// 验证方由 VO 中的 B+ 树证明与 trie 证明算出查询所用时间窗口的 ads 哈希，
+/// 其余部分由该证明给出，合并后与区块头中的 `ads_root` 比较
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MerkleProof {
+    /// id 树根哈希；查询的最后一个区块由 VO 中的 id 树证明算出，此时为 `None`
     pub(crate) id_tree_root_hash: Option<Digest>,
+    /// 区块对象 id 集合的哈希
     pub(crate) id_set_root_hash: Digest,
+    /// 查询所用窗口以外各时间窗口的 ads 哈希，以窗口大小为键
     pub(crate) ads_hashes: BTreeMap<u16, Digest>,
+    /// 查询所用窗口中没有范围查询节点的维度的 B+ 树根哈希
     pub(crate) extra_bplus_rt_hashes: HashMap<u8, Digest>,
 }
 
 impl MerkleProof {
+    /// `ads_hashes` 中的窗口大小不能重复
+    pub fn new(
+        id_tree_root_hash: Option<Digest>,
+        id_set_root_hash: Digest,
+        ads_hashes: impl IntoIterator<Item = (u16, Digest)>,
+        extra_bplus_rt_hashes: HashMap<u8, Digest>,
+    ) -> Result<Self> {
+        let mut hashes = BTreeMap::new();
+        for (win_size, hash) in ads_hashes {
+            ensure!(
+                hashes.insert(win_size, hash).is_none(),
+                "Duplicated time window size {} in merkle proof",
+                win_size
+            );
+        }
+        Ok(Self {
+            id_tree_root_hash,
+            id_set_root_hash,
+            ads_hashes: hashes,
+            extra_bplus_rt_hashes,
+        })
+    }
+
     /// 【原有方法】计算 ads_root_hash（保持向后兼容）
     pub(crate) fn ads_root_hash(
         &self,
@@ -142,7 +361,7 @@ impl MerkleProof {
         let computed_root = computed_components.compute_root();
 
         // 验证是否匹配
-        if computed_root != *expected_ads_root {
+        if !computed_root.ct_eq(expected_ads_root) {
             bail!(
                 "BlockADSRoot verification failed: computed {:?}, expected {:?}",
                 computed_root,
@@ -180,4 +399,243 @@ pub struct VO<K: Num> {
     pub(crate) id_tree_proof: id_tree::proof::Proof,
     pub(crate) cur_obj_id: ObjId,
     pub(crate) merkle_proofs: HashMap<Height, MerkleProof>,
-}
\ No newline at end of file
+}
+impl<K: Num + Serialize> VO<K> {
+    /// VO 经 `binary_encode` 编码后的字节数（不分配编码缓冲区）
+    pub fn estimated_size(&self) -> Result<usize> {
+        binary_encoded_len(self)
+    }
+
+    /// 以 `binary_encode` 编码 VO，用于发送给验证方
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        binary_encode(self)
+    }
+}
+
+impl<K: Num + for<'de> Deserialize<'de>> VO<K> {
+    /// 解码 `encode` 的输出。输入来自不可信的一方，任何畸形的输入都只返回错误
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        binary_decode(bytes).context("Failed to decode VO")
+    }
+}
+
+impl<K: Num> VO<K> {
+    /// 验证时需要读取区块头的所有高度：merkle proof、trie 证明以及 id 集合证明所在的高度，
+    /// 可用于在验证之前预取区块头
+    pub fn referenced_heights(&self) -> BTreeSet<Height> {
+        let mut heights = BTreeSet::new();
+        heights.extend(self.merkle_proofs.keys().copied());
+        heights.extend(self.trie_proofs.keys().copied());
+        for node in self.vo_dag_content.dag_content.values() {
+            match node {
+                VONode::BlkRt(n) => heights.extend(n.id_set_proofs.keys().copied()),
+                VONode::HeightFilter(n) => heights.extend(n.id_set_proofs.keys().copied()),
+                _ => {}
+            }
+        }
+        heights
+    }
+
+    /// 各高度的 trie 证明中展开到叶子的关键字，按字典序去重。
+    /// 查询了但不存在的关键字没有对应的叶子，不包含在内
+    pub fn referenced_keywords(&self) -> Vec<String> {
+        let mut keywords = Vec::new();
+        for proof in self.trie_proofs.values() {
+            keywords.extend(proof.revealed_keywords());
+        }
+        keywords.sort_unstable();
+        keywords.dedup();
+        keywords
+    }
+
+    /// 检查每个关键字节点所在的高度都有 trie 证明，缺少时返回 `VoStructureError::MissingTrieProof`
+    pub fn check_trie_proofs(&self) -> Result<()> {
+        for vo_node in self.vo_dag_content.dag_content.values() {
+            let height = match vo_node {
+                VONode::Keyword(n) => n.blk_height,
+                VONode::KeywordConjunction(n) => n.blk_height,
+                VONode::KeywordPrefix(n) => n.blk_height,
+                _ => continue,
+            };
+            if !self.trie_proofs.contains_key(&height) {
+                return Err(VoStructureError::MissingTrieProof { height }.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// 只检查 VO 与查询 DAG 的结构一致性，不需要公钥，也不做任何密码学运算：
+    /// DAG 无环、VO 节点与 DAG 节点类型匹配、运算节点引用的子节点存在、
+    /// 各节点所需的 trie 证明 / merkle proof / id 集合证明与输出集合齐全。
+    /// 通过检查不代表 VO 可信，仍需 `verify` 完成验证；用于在密码学验证之前快速拒绝畸形输入
+    pub fn check_structure(&self, graph: &Graph<DagNode<K>, bool>) -> Result<()> {
+        ensure!(
+            toposort(graph, None).is_ok(),
+            "Query graph contains a cycle"
+        );
+        validate_dag(graph)?;
+        self.check_trie_proofs()?;
+        let dag_content = &self.vo_dag_content.dag_content;
+        let output_sets = &self.vo_dag_content.output_sets;
+        for (&idx, vo_node) in dag_content {
+            let dag_node = graph
+                .node_weight(idx)
+                .with_context(|| format!("VO node {:?} is not in the query graph", idx))?;
+            ensure!(
+                node_kind_matches(dag_node, vo_node),
+                "VO node {:?} of type {} does not match the query graph node {:?}",
+                idx,
+                vo_node.node_kind(),
+                dag_node
+            );
+            if let Some(blk_height) = vo_node.blk_height() {
+                ensure!(
+                    self.merkle_proofs.contains_key(&blk_height),
+                    "Cannot find merkle proof of height {:?} for VO node {:?}",
+                    blk_height,
+                    idx
+                );
+            }
+            match (dag_node, vo_node) {
+                (DagNode::KeywordSet(n), VONode::KeywordConjunction(k_n)) => {
+                    ensure!(
+                        k_n.accs.len() == n.keywords.len(),
+                        "VO node {:?} has {} acc values for {} keywords",
+                        idx,
+                        k_n.accs.len(),
+                        n.keywords.len()
+                    );
+                }
+                (_, VONode::BlkRt(br_n)) => {
+                    for height in time_win_heights(br_n.blk_height, br_n.win_size) {
+                        ensure!(
+                            br_n.id_set_proofs.contains_key(&height),
+                            "Cannot find id set proof of height {:?} for VO node {:?}",
+                            height,
+                            idx
+                        );
+                    }
+                }
+                (DagNode::HeightFilter(n), VONode::HeightFilter(h_n)) => {
+                    for height in n.heights(h_n.blk_height, h_n.win_size) {
+                        ensure!(
+                            h_n.id_set_proofs.contains_key(&height),
+                            "Cannot find id set proof of height {:?} for VO node {:?}",
+                            height,
+                            idx
+                        );
+                    }
+                }
+                _ => {}
+            }
+            match vo_node {
+                VONode::KeywordConjunction(VOKeywordConjunction {
+                    accs,
+                    proofs,
+                    final_proof,
+                    ..
+                })
+                | VONode::KeywordPrefix(VOKeywordPrefix {
+                    accs,
+                    proofs,
+                    final_proof,
+                    ..
+                }) => {
+                    let op_num = accs.len().saturating_sub(1);
+                    ensure!(
+                        !accs.is_empty()
+                            && proofs.len() + usize::from(final_proof.is_some()) == op_num,
+                        "VO node {:?} has {} proofs for {} operands",
+                        idx,
+                        proofs.len() + usize::from(final_proof.is_some()),
+                        accs.len()
+                    );
+                    if final_proof.is_some() {
+                        ensure!(
+                            output_sets.contains_key(&idx),
+                            "Cannot find output set for final VO node {:?}",
+                            idx
+                        );
+                    }
+                }
+                VONode::InterUnion(_)
+                | VONode::InterIntersec(_)
+                | VONode::InterDiff(_)
+                | VONode::FinalUnion(_)
+                | VONode::FinalIntersec(_)
+                | VONode::FinalDiff(_) => {
+                    let children: Vec<NodeIndex> =
+                        graph.neighbors_directed(idx, Outgoing).collect();
+                    let present = children
+                        .iter()
+                        .filter(|c| dag_content.contains_key(c))
+                        .count();
+                    // 求交集 / 差集的中间节点在某个子节点为空集时可以省略该子节点
+                    let required = match vo_node {
+                        VONode::InterIntersec(_) | VONode::InterDiff(_) => 1,
+                        _ => 2,
+                    };
+                    ensure!(
+                        present >= required,
+                        "Cannot find the children of set operation node {:?} in VO",
+                        idx
+                    );
+                    if let VONode::FinalUnion(_) | VONode::FinalIntersec(_) | VONode::FinalDiff(_) =
+                        vo_node
+                    {
+                        ensure!(
+                            output_sets.contains_key(&idx),
+                            "Cannot find output set for final VO node {:?}",
+                            idx
+                        );
+                    }
+                }
+                VONode::Identity(_) | VONode::HeightFilter(_) => {
+                    let child = identity_child(graph, idx)?;
+                    ensure!(
+                        dag_content.contains_key(&child),
+                        "Cannot find the child {:?} of VO node {:?}",
+                        child,
+                        idx
+                    );
+                }
+                _ => {}
+            }
+        }
+        for idx in output_sets.keys() {
+            ensure!(
+                dag_content.contains_key(idx),
+                "Cannot find VO node {:?} of an output set",
+                idx
+            );
+        }
+        Ok(())
+    }
+}
+
+/// VO 节点与查询 DAG 节点的类型是否对应
+fn node_kind_matches<K: Num>(dag_node: &DagNode<K>, vo_node: &VONode<K>) -> bool {
+    matches!(
+        (dag_node, vo_node),
+        (DagNode::Range(_), VONode::Range(_))
+            | (DagNode::MultiInterval(_), VONode::Range(_))
+            | (DagNode::Keyword(_), VONode::Keyword(_))
+            | (DagNode::KeywordSet(_), VONode::KeywordConjunction(_))
+            | (DagNode::KeywordPrefix(_), VONode::KeywordPrefix(_))
+            | (DagNode::BlkRt(_), VONode::BlkRt(_))
+            | (
+                DagNode::Union(_),
+                VONode::InterUnion(_) | VONode::FinalUnion(_)
+            )
+            | (
+                DagNode::Intersec(_),
+                VONode::InterIntersec(_) | VONode::FinalIntersec(_)
+            )
+            | (
+                DagNode::Diff(_),
+                VONode::InterDiff(_) | VONode::FinalDiff(_)
+            )
+            | (DagNode::Identity(_), VONode::Identity(_))
+            | (DagNode::HeightFilter(_), VONode::HeightFilter(_))
+    )
+}
@@ -1,9 +1,11 @@
+use anyhow::{ensure, Context, Result};
 use core::fmt;
 use serde::{
     de::{Deserializer, SeqAccess, Visitor},
     ser::{SerializeTupleStruct, Serializer},
     Deserialize, Serialize,
 };
+use subtle::ConstantTimeEq;
 
 pub const DIGEST_LEN: usize = 32;
 
@@ -16,6 +18,12 @@ impl fmt::Display for Digest {
     }
 }
 
+impl fmt::LowerHex for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
 impl fmt::Debug for Digest {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", hex::encode(&self.0))
@@ -37,6 +45,38 @@ impl Digest {
     pub fn is_zero(&self) -> bool {
         *self == Self::zero()
     }
+
+    /// 常数时间比较，耗时与两个摘要在何处不同无关；验证方比较来自不可信输入的摘要时应使用此方法
+    #[inline]
+    pub fn ct_eq(&self, other: &Self) -> bool {
+        self.0.ct_eq(&other.0).into()
+    }
+
+    /// 与字节切片常数时间比较，无需先构造 Digest；长度不是 `DIGEST_LEN` 时不相等
+    #[inline]
+    pub fn eq_bytes(&self, other: &[u8]) -> bool {
+        other.len() == DIGEST_LEN && bool::from(self.0[..].ct_eq(other))
+    }
+
+    /// 从 64 个十六进制字符解析 Digest，长度或字符非法时返回错误。
+    pub fn from_hex(s: &str) -> Result<Self> {
+        ensure!(
+            s.len() == DIGEST_LEN * 2,
+            "Invalid digest hex length: expect {}, got {}.",
+            DIGEST_LEN * 2,
+            s.len()
+        );
+        let data = hex::decode(s).context("Invalid digest hex string")?;
+        let mut out = Self::default();
+        out.0.copy_from_slice(&data[..DIGEST_LEN]);
+        Ok(out)
+    }
+}
+
+impl From<[u8; DIGEST_LEN]> for Digest {
+    fn from(input: [u8; DIGEST_LEN]) -> Self {
+        Self(input)
+    }
 }
 
 // Ref: https://github.com/slowli/hex-buffer-serde
@@ -122,19 +162,27 @@ impl From<blake2b_simd::Hash> for Digest {
     }
 }
 
+/// 链上所有摘要使用的哈希参数：输出长度为 `DIGEST_LEN` 的 Blake2b。
+/// 输出长度是 Blake2b 参数的一部分，结果与截断 Blake2b-512 的前 32 字节不同
 pub fn blake2() -> blake2b_simd::Params {
     let mut params = blake2b_simd::Params::new();
     params.hash_length(DIGEST_LEN);
     params
 }
 
+/// 以 `blake2()` 直接计算 `data` 的 32 字节摘要
+#[inline]
+pub fn blake2_256(data: &[u8]) -> Digest {
+    Digest::from(blake2().hash(data))
+}
+
 pub trait Digestible {
     fn to_digest(&self) -> Digest;
 }
 
 impl Digestible for [u8] {
     fn to_digest(&self) -> Digest {
-        Digest::from(blake2().hash(self))
+        blake2_256(self)
     }
 }
 
@@ -193,6 +241,21 @@ mod tests {
         assert_eq!("hello".to_owned().to_digest(), expect);
     }
 
+    #[test]
+    fn test_blake2_256() {
+        let d = blake2_256(b"hello");
+        assert_eq!(d.as_bytes().len(), DIGEST_LEN);
+        assert_eq!(d, b"hello"[..].to_digest());
+        assert_eq!(
+            d,
+            Digest::from_hex("324dcf027dd4a30a932c441f365a25e86b173defa4b8e58948253471b81b72cf")
+                .unwrap()
+        );
+        // 链使用输出长度为 32 的 Blake2b，与截断 Blake2b-512 的结果不同
+        let full = blake2b_simd::Params::new().hash_length(64).hash(b"hello");
+        assert_ne!(d.as_bytes(), &full.as_bytes()[..DIGEST_LEN]);
+    }
+
     #[test]
     fn test_zero() {
         let expect = Digest(*b"\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00");
@@ -205,6 +268,40 @@ mod tests {
         assert!(d.is_zero());
     }
 
+    #[test]
+    fn test_ct_eq() {
+        let a = "hello".to_digest();
+        let b = "world!".to_digest();
+        let mut c = a;
+        c.0[DIGEST_LEN - 1] ^= 1;
+        for (x, y) in &[
+            (a, a),
+            (a, b),
+            (a, c),
+            (c, a),
+            (Digest::zero(), Digest::zero()),
+        ] {
+            assert_eq!(x.ct_eq(y), x == y);
+        }
+        assert!(a.ct_eq(&a));
+        assert!(!a.ct_eq(&c));
+    }
+
+    #[test]
+    fn test_eq_bytes() {
+        let a = "hello".to_digest();
+        let mut c = a;
+        c.0[0] ^= 1;
+        assert!(a.eq_bytes(a.as_bytes()));
+        let stored: Vec<u8> = a.as_bytes().to_vec();
+        assert!(a.eq_bytes(&stored));
+        assert!(!a.eq_bytes(c.as_bytes()));
+        assert!(!a.eq_bytes("world!".to_digest().as_bytes()));
+        assert!(!a.eq_bytes(&a.0[..DIGEST_LEN - 1]));
+        assert!(!a.eq_bytes(&[a.0.to_vec(), vec![0]].concat()));
+        assert!(!Digest::zero().eq_bytes(&[]));
+    }
+
     #[test]
     fn test_digest_concat() {
         let input = vec!["hello".to_digest(), "world!".to_digest()];
@@ -235,4 +332,18 @@ mod tests {
         assert_eq!(serde_json::from_str::<Digest>(&json).unwrap(), digest);
         assert_eq!(bincode::deserialize::<Digest>(&bin[..]).unwrap(), digest);
     }
+
+    #[test]
+    fn test_hex() {
+        let digest = "hello".to_digest();
+        let hex_str = "324dcf027dd4a30a932c441f365a25e86b173defa4b8e58948253471b81b72cf";
+        assert_eq!(format!("{}", digest), hex_str);
+        assert_eq!(format!("{:x}", digest), hex_str);
+        assert_eq!(Digest::from_hex(hex_str).unwrap(), digest);
+        assert_eq!(Digest::from_hex(&digest.to_string()).unwrap(), digest);
+
+        assert!(Digest::from_hex(&hex_str[..63]).is_err());
+        assert!(Digest::from_hex(&hex_str.replace('c', "g")).is_err());
+        assert!(Digest::from_hex("").is_err());
+    }
 }
@@ -92,7 +92,9 @@ impl ReadInterface for &SimChain {
             .block_content_db
             .get(blk_height.to_le_bytes())?
             .context("failed to read block content")?;
-        Ok(bincode::deserialize::<BlockContent>(&data[..])?)
+        let content = bincode::deserialize::<BlockContent>(&data[..])?;
+        content.validate_shape()?;
+        Ok(content)
     }
     fn read_id_tree_node(&self, id_tree_node_id: IdTreeNodeId) -> Result<IdTreeNode> {
         let data = self
@@ -149,7 +151,9 @@ impl ReadInterface for &mut SimChain {
             .block_content_db
             .get(blk_height.to_le_bytes())?
             .context("failed to read block content")?;
-        Ok(bincode::deserialize::<BlockContent>(&data[..])?)
+        let content = bincode::deserialize::<BlockContent>(&data[..])?;
+        content.validate_shape()?;
+        Ok(content)
     }
     fn read_id_tree_node(&self, id_tree_node_id: IdTreeNodeId) -> Result<IdTreeNode> {
         let data = self
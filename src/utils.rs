@@ -3,12 +3,14 @@ use crate::{
     chain::{block::Height, object::Object, query::query_param::QueryParam, traits::Num},
 };
 use anyhow::{ensure, Context, Error, Result};
+use bincode::Options;
 use howlong::ProcessDuration;
 use memmap2::Mmap;
 use rand::{CryptoRng, RngCore};
-use serde::{Deserialize, Serialize};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
 use snap::{read::FrameDecoder, write::FrameEncoder};
 use std::{
+    cell::Cell,
     collections::{BTreeMap, HashSet},
     error::Error as StdError,
     fs,
@@ -236,9 +238,72 @@ pub fn binary_encode<T: Serialize>(value: &T) -> Result<Vec<u8>> {
     Ok(encoder.into_inner()?)
 }
 
+/// 计算 `binary_encode(value)` 输出的字节数，但只计数不保留编码结果。
+pub fn binary_encoded_len<T: Serialize>(value: &T) -> Result<usize> {
+    let mut encoder = FrameEncoder::new(CountingWriter::default());
+    bincode::serialize_into(&mut encoder, value).map_err(Error::msg)?;
+    Ok(encoder.into_inner()?.0)
+}
+
+#[derive(Debug, Default)]
+struct CountingWriter(usize);
+
+impl Write for CountingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// snappy 帧格式的压缩比不超过约 22 倍，解压后的字节数以输入字节数的该倍数为上限
+const MAX_DECOMPRESSION_RATIO: u64 = 32;
+/// 解码时允许读取的最少字节数，使很短的输入也能正常解码
+const MIN_DECODE_LIMIT: u64 = 1 << 20;
+
+/// 解码 `binary_encode` 的输出。解码读取的字节数受输入长度限制，bincode 在按长度前缀
+/// 分配缓冲区之前检查该上限，因此畸形输入中过大的长度前缀只会返回错误，而不会导致 panic 或耗尽内存
 pub fn binary_decode<T: for<'de> Deserialize<'de>>(bytes: &[u8]) -> Result<T> {
+    let limit = (bytes.len() as u64)
+        .saturating_mul(MAX_DECOMPRESSION_RATIO)
+        .max(MIN_DECODE_LIMIT);
     let decoder = FrameDecoder::new(bytes);
-    bincode::deserialize_from(decoder).map_err(Error::msg)
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .with_limit(limit)
+        .deserialize_from(decoder)
+        .map_err(Error::msg)
+}
+
+/// 解码时各树证明中子证明允许嵌套的最大深度，远大于实际树的深度
+const MAX_DECODE_DEPTH: usize = 256;
+
+thread_local! {
+    static DECODE_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// 递归结构中子节点字段的 `deserialize_with`：限制嵌套深度，
+/// 避免畸形输入中过深的嵌套在解码时耗尽栈空间
+pub(crate) fn deserialize_nested<'de, D, T>(deserializer: D) -> std::result::Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    let depth = DECODE_DEPTH.with(Cell::get);
+    if depth >= MAX_DECODE_DEPTH {
+        return Err(D::Error::custom(format!(
+            "nesting depth exceeds {}",
+            MAX_DECODE_DEPTH
+        )));
+    }
+    DECODE_DEPTH.with(|d| d.set(depth + 1));
+    let res = T::deserialize(deserializer);
+    DECODE_DEPTH.with(|d| d.set(depth));
+    res
 }
 
 #[cfg(test)]
@@ -253,7 +318,7 @@ mod tests {
         },
         digest::Digestible,
         set,
-        utils::{binary_decode, binary_encode, load_raw_obj_from_str},
+        utils::{binary_decode, binary_encode, binary_encoded_len, load_raw_obj_from_str},
     };
     use petgraph::Graph;
     use std::collections::BTreeMap;
@@ -367,6 +432,50 @@ mod tests {
         assert_eq!(binary_decode::<String>(bin.as_ref()).unwrap(), value);
     }
 
+    #[test]
+    fn test_binary_encoded_len() {
+        use crate::{
+            chain::id_tree::ObjId,
+            digest::Digest,
+        };
+        use std::{
+            collections::{HashMap, HashSet},
+            num::NonZeroU16,
+        };
+
+        let s = String::from("hello world");
+        assert_eq!(binary_encoded_len(&s).unwrap(), binary_encode(&s).unwrap().len());
+
+        let obj = Object::<u32>::new(
+            Height(3),
+            vec![1, 2, 3],
+            ["a", "b"].iter().map(|w| w.to_string()).collect::<HashSet<_>>(),
+        );
+        assert_eq!(
+            binary_encoded_len(&obj).unwrap(),
+            binary_encode(&obj).unwrap().len()
+        );
+
+        let digests: Vec<Digest> = (0..1000u32).map(|i| i.to_digest()).collect();
+        assert_eq!(
+            binary_encoded_len(&digests).unwrap(),
+            binary_encode(&digests).unwrap().len()
+        );
+
+        let obj_id = ObjId(NonZeroU16::new(7).unwrap());
+        assert_eq!(
+            binary_encoded_len(&obj_id).unwrap(),
+            binary_encode(&obj_id).unwrap().len()
+        );
+
+        let heights: HashMap<Height, Digest> =
+            (0..50u32).map(|i| (Height(i), i.to_digest())).collect();
+        assert_eq!(
+            binary_encoded_len(&heights).unwrap(),
+            binary_encode(&heights).unwrap().len()
+        );
+    }
+
     #[test]
     fn test_acc_size() {
         use crate::chain::tests::PUB_KEY;
@@ -18,7 +18,7 @@ use vchain_plus::chain::block::{
     block_ads_root::{BlockADSComponents, BlockADSRoot},
     BlockContent, BlockHead, Height,
 };
-use vchain_plus::digest::Digest;
+use vchain_plus::digest::{blake2_256, Digest};
 
 /// 测试1：模拟轻节点验证流程
 #[test]
@@ -372,7 +372,7 @@ fn test_build_style_workflow() {
     block_content.set_ads_components(block_ads_root.components().clone());
     
     println!("步骤5: 分别存储到 BlockHead 和 BlockContent ✓");
-    println!("  - BlockHead.ads_root: {:?}...", block_head.ads_root.as_bytes()[..4]);
+    println!("  - BlockHead.ads_root: {:?}...", &block_head.ads_root.as_bytes()[..4]);
     println!("  - BlockContent.ads_components: 已保存");
 
     // === 验证阶段（模拟轻节点） ===
@@ -395,24 +395,16 @@ fn test_build_style_workflow() {
     
     // 9. 轻节点可以使用各个组件根进行具体查询验证
     println!("\n步骤7: 使用组件进行查询验证");
-    println!("  - 可用于 ID Set 查询: {:?}...", full_node_components.id_set_root_hash.as_bytes()[..4]);
-    println!("  - 可用于 ID Tree 查询: {:?}...", full_node_components.id_tree_root_hash.as_bytes()[..4]);
-    println!("  - 可用于 MultiADS 查询: {:?}...", full_node_components.multi_ads_hash.as_bytes()[..4]);
+    println!("  - 可用于 ID Set 查询: {:?}...", &full_node_components.id_set_root_hash.as_bytes()[..4]);
+    println!("  - 可用于 ID Tree 查询: {:?}...", &full_node_components.id_tree_root_hash.as_bytes()[..4]);
+    println!("  - 可用于 MultiADS 查询: {:?}...", &full_node_components.multi_ads_hash.as_bytes()[..4]);
     
     println!("\n✓ 完整流程测试通过！\n");
 }
 
 // === 辅助函数 ===
 
-/// 模拟哈希函数（使用 Blake2b）
+/// 模拟哈希函数，与链上摘要相同使用 32 字节输出的 Blake2b
 fn mock_hash(data: &[u8]) -> Digest {
-    use blake2::{Blake2b512, Digest as Blake2Digest};
-
-    let mut hasher = Blake2b512::new();
-    hasher.update(data);
-    let result = hasher.finalize();
-    
-    let mut bytes = [0u8; 32];
-    bytes.copy_from_slice(&result[..32]);
-    Digest::from(bytes)
+    blake2_256(data)
 }
\ No newline at end of file